@@ -0,0 +1,376 @@
+use crate::StringEncoding;
+use anyhow::{Context, Result, bail};
+use indexmap::IndexMap;
+use wasmparser::{
+    CanonicalFunction, CanonicalOption, ComponentAlias, ComponentExternalKind, ComponentInstance,
+    ComponentOuterAliasKind, ComponentTypeRef, Parser, Payload,
+};
+
+/// The canonical ABI options that a component function was lifted with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CanonicalOptions {
+    /// The encoding used for strings in the function's signature.
+    pub string_encoding: StringEncoding,
+    /// Whether a memory was provided.
+    pub memory: bool,
+    /// Whether a `realloc` function was provided.
+    pub realloc: bool,
+    /// Whether a `post-return` function was provided.
+    pub post_return: bool,
+    /// Whether the function was lifted with the `async` ABI.
+    pub async_: bool,
+}
+
+impl CanonicalOptions {
+    fn new(options: &[CanonicalOption]) -> CanonicalOptions {
+        let mut ret = CanonicalOptions {
+            string_encoding: StringEncoding::UTF8,
+            memory: false,
+            realloc: false,
+            post_return: false,
+            async_: false,
+        };
+        for option in options {
+            match option {
+                CanonicalOption::UTF8 => ret.string_encoding = StringEncoding::UTF8,
+                CanonicalOption::UTF16 => ret.string_encoding = StringEncoding::UTF16,
+                CanonicalOption::CompactUTF16 => ret.string_encoding = StringEncoding::CompactUTF16,
+                CanonicalOption::Memory(_) => ret.memory = true,
+                CanonicalOption::Realloc(_) => ret.realloc = true,
+                CanonicalOption::PostReturn(_) => ret.post_return = true,
+                CanonicalOption::Async => ret.async_ = true,
+                CanonicalOption::Callback(_)
+                | CanonicalOption::CoreType(_)
+                | CanonicalOption::Gc => {}
+            }
+        }
+        ret
+    }
+}
+
+/// An exported function of a component along with the options it was lifted
+/// with, as returned by [`decode_export_options`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportOptions {
+    /// The name of the exported instance that contains this function, or
+    /// `None` if the function is exported from the component directly.
+    pub interface: Option<String>,
+    /// The name of the function.
+    pub name: String,
+    /// The options the function was lifted with.
+    pub options: CanonicalOptions,
+}
+
+/// Reads the canonical ABI options that each exported function of the
+/// component `bytes` was lifted with.
+///
+/// This complements [`decode`](crate::decode), which only describes the types
+/// of a component's exports, and can be used for example to verify that a
+/// component was built for an expected ABI. Functions are returned in export
+/// order, with functions of exported instances in the order they're exported
+/// from their instance.
+///
+/// Exports whose lift can't be traced within the component, for example
+/// re-exports of imports, are omitted.
+pub fn decode_export_options(bytes: &[u8]) -> Result<Vec<ExportOptions>> {
+    let mut stack = Vec::new();
+    let mut cur = Some(Scope::default());
+    let mut ret = None;
+
+    for payload in Parser::new(0).parse_all(bytes) {
+        let payload = payload.context("failed to parse component")?;
+
+        // Core modules don't affect the component-level index spaces, so
+        // skip over their contents.
+        let scope = match &mut cur {
+            Some(scope) => scope,
+            None => {
+                if let Payload::ModuleSection { .. } = payload {
+                    stack.push(None);
+                } else if let Payload::End(_) = payload {
+                    cur = stack.pop().unwrap();
+                }
+                continue;
+            }
+        };
+
+        match payload {
+            Payload::Version { encoding, .. } => {
+                if stack.is_empty() && encoding != wasmparser::Encoding::Component {
+                    bail!("expected a component, found a core module");
+                }
+            }
+            Payload::ModuleSection { .. } => {
+                stack.push(cur.take());
+            }
+            Payload::ComponentSection { .. } => {
+                stack.push(cur.replace(Scope::default()));
+            }
+            Payload::End(_) => {
+                let child = cur.take().unwrap();
+                match stack.pop() {
+                    Some(parent) => {
+                        cur = parent;
+                        let exports = child
+                            .exports
+                            .into_iter()
+                            .filter_map(|(name, item)| match item {
+                                Item::Func(func) => Some((name, func)),
+                                Item::Instance(_) => None,
+                            })
+                            .collect();
+                        cur.as_mut().unwrap().components.push(Some(exports));
+                    }
+                    None => ret = Some(child),
+                }
+            }
+            Payload::ComponentImportSection(s) => {
+                for import in s {
+                    let import = import?;
+                    match import.ty {
+                        ComponentTypeRef::Func(_) => {
+                            scope.funcs.push(Func::Import(import.name.0.to_string()))
+                        }
+                        ComponentTypeRef::Instance(_) => scope.instances.push(None),
+                        ComponentTypeRef::Component(_) => scope.components.push(None),
+                        _ => {}
+                    }
+                }
+            }
+            Payload::ComponentAliasSection(s) => {
+                for alias in s {
+                    match alias? {
+                        ComponentAlias::InstanceExport {
+                            kind,
+                            instance_index,
+                            name,
+                        } => {
+                            let instance = scope.instance(instance_index)?.as_ref();
+                            match kind {
+                                ComponentExternalKind::Func => scope.funcs.push(
+                                    instance
+                                        .and_then(|i| i.get(name).cloned())
+                                        .unwrap_or(Func::Unknown),
+                                ),
+                                ComponentExternalKind::Instance => scope.instances.push(None),
+                                ComponentExternalKind::Component => scope.components.push(None),
+                                _ => {}
+                            }
+                        }
+                        ComponentAlias::Outer {
+                            kind: ComponentOuterAliasKind::Component,
+                            ..
+                        } => scope.components.push(None),
+                        _ => {}
+                    }
+                }
+            }
+            Payload::ComponentCanonicalSection(s) => {
+                for func in s {
+                    if let CanonicalFunction::Lift { options, .. } = func? {
+                        scope
+                            .funcs
+                            .push(Func::Lifted(CanonicalOptions::new(&options)));
+                    }
+                }
+            }
+            Payload::ComponentInstanceSection(s) => {
+                for instance in s {
+                    let funcs = match instance? {
+                        ComponentInstance::Instantiate {
+                            component_index,
+                            args,
+                        } => scope
+                            .component(component_index)?
+                            .as_ref()
+                            .map(|exports| {
+                                let arg = |name: &str| match args.iter().find(|a| {
+                                    a.name == name && a.kind == ComponentExternalKind::Func
+                                }) {
+                                    Some(a) => scope.func(a.index).cloned(),
+                                    None => Ok(Func::Unknown),
+                                };
+                                exports
+                                    .iter()
+                                    .map(|(name, func)| {
+                                        let func = match func {
+                                            Func::Import(import) => arg(import)?,
+                                            other => other.clone(),
+                                        };
+                                        Ok((name.clone(), func))
+                                    })
+                                    .collect::<Result<_>>()
+                            })
+                            .transpose()?,
+                        ComponentInstance::FromExports(exports) => Some(
+                            exports
+                                .iter()
+                                .filter(|e| e.kind == ComponentExternalKind::Func)
+                                .map(|e| Ok((e.name.0.to_string(), scope.func(e.index)?.clone())))
+                                .collect::<Result<_>>()?,
+                        ),
+                    };
+                    scope.instances.push(funcs);
+                }
+            }
+            Payload::ComponentExportSection(s) => {
+                for export in s {
+                    let export = export?;
+                    let index = export.index;
+                    let item = match export.kind {
+                        ComponentExternalKind::Func => {
+                            let func = scope.func(index)?.clone();
+                            scope.funcs.push(func.clone());
+                            Item::Func(func)
+                        }
+                        ComponentExternalKind::Instance => {
+                            let instance = scope.instance(index)?.clone();
+                            scope.instances.push(instance.clone());
+                            Item::Instance(instance.unwrap_or_default())
+                        }
+                        ComponentExternalKind::Component => {
+                            let component = scope.component(index)?.clone();
+                            scope.components.push(component);
+                            continue;
+                        }
+                        _ => continue,
+                    };
+                    scope.exports.push((export.name.0.to_string(), item));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut ret_exports = Vec::new();
+    for (name, item) in ret.context("unexpected end of component")?.exports {
+        match item {
+            Item::Func(Func::Lifted(options)) => ret_exports.push(ExportOptions {
+                interface: None,
+                name,
+                options,
+            }),
+            Item::Func(_) => {}
+            Item::Instance(funcs) => {
+                for (func, item) in funcs {
+                    if let Func::Lifted(options) = item {
+                        ret_exports.push(ExportOptions {
+                            interface: Some(name.clone()),
+                            name: func,
+                            options,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    Ok(ret_exports)
+}
+
+/// The component-level index spaces of a component that are needed to trace
+/// an export back to the lift that created it.
+#[derive(Default)]
+struct Scope {
+    funcs: Vec<Func>,
+    /// Exported functions of each instance, or `None` if they're unknown.
+    instances: Vec<Option<IndexMap<String, Func>>>,
+    /// Exported functions of each nested component, or `None` if unknown.
+    components: Vec<Option<IndexMap<String, Func>>>,
+    exports: Vec<(String, Item)>,
+}
+
+impl Scope {
+    fn func(&self, index: u32) -> Result<&Func> {
+        self.funcs
+            .get(index as usize)
+            .with_context(|| format!("function index {index} is out of bounds"))
+    }
+
+    fn instance(&self, index: u32) -> Result<&Option<IndexMap<String, Func>>> {
+        self.instances
+            .get(index as usize)
+            .with_context(|| format!("instance index {index} is out of bounds"))
+    }
+
+    fn component(&self, index: u32) -> Result<&Option<IndexMap<String, Func>>> {
+        self.components
+            .get(index as usize)
+            .with_context(|| format!("component index {index} is out of bounds"))
+    }
+}
+
+#[derive(Clone)]
+enum Func {
+    Lifted(CanonicalOptions),
+    /// A function imported into the current component by this name.
+    Import(String),
+    Unknown,
+}
+
+enum Item {
+    Func(Func),
+    Instance(IndexMap<String, Func>),
+}
+
+#[cfg(all(test, feature = "dummy-module"))]
+mod test {
+    use super::*;
+    use crate::{ComponentEncoder, dummy_module, embed_component_metadata};
+    use wit_parser::{ManglingAndAbi, Resolve};
+
+    #[test]
+    fn decodes_export_options() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package test:wit;
+
+                interface i {
+                    f: func(s: string) -> string;
+                    g: func();
+                }
+
+                world test {
+                    export i;
+                    export run: func(x: u32);
+                }
+            "#,
+        )?;
+        let world = resolve.select_world(pkg, None)?;
+        let mut module = dummy_module(&resolve, world, ManglingAndAbi::Standard32);
+        embed_component_metadata(&mut module, &resolve, world, StringEncoding::UTF8)?;
+        let component = ComponentEncoder::default()
+            .module(&module)?
+            .validate(true)
+            .encode()?;
+
+        let exports = decode_export_options(&component)?;
+        let names = exports
+            .iter()
+            .map(|e| (e.interface.as_deref(), e.name.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                (None, "run"),
+                (Some("test:wit/i"), "f"),
+                (Some("test:wit/i"), "g")
+            ]
+        );
+        let f = &exports[1].options;
+        assert_eq!(f.string_encoding, StringEncoding::UTF8);
+        assert!(f.memory && f.realloc && f.post_return && !f.async_);
+        let run = &exports[0].options;
+        assert!(!run.memory && !run.realloc);
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_out_of_bounds_indices() -> Result<()> {
+        let component = wat::parse_str(r#"(component (export "f" (func 3)))"#)?;
+        let err = decode_export_options(&component).unwrap_err();
+        assert_eq!(err.to_string(), "function index 3 is out of bounds");
+        Ok(())
+    }
+}
@@ -6,10 +6,107 @@ use wit_parser::{
 
 /// Generate a dummy implementation core Wasm module for a given WIT document
 pub fn dummy_module(resolve: &Resolve, world: WorldId, mangling: ManglingAndAbi) -> Vec<u8> {
+    dummy_module_filtered(resolve, world, mangling, |_, _| true)
+}
+
+/// Same as [`dummy_module`] but only generates imports and exports for the
+/// items of `world` for which `filter` returns `true`.
+///
+/// This can be used to keep the generated module small when only part of a
+/// large world is being tested. The module returned is always valid, but note
+/// that it can only be turned into a component for a world which doesn't
+/// export the items which were filtered out.
+pub fn dummy_module_filtered(
+    resolve: &Resolve,
+    world: WorldId,
+    mangling: ManglingAndAbi,
+    filter: impl Fn(&WorldKey, &WorldItem) -> bool,
+) -> Vec<u8> {
+    generate(
+        resolve,
+        world,
+        mangling,
+        filter,
+        &DummyModuleConfig::default(),
+    )
+}
+
+/// Options for the ABI-related items of the module generated by
+/// [`dummy_module_with_config`].
+///
+/// The default configuration generates the same module as [`dummy_module`].
+#[derive(Debug, Clone)]
+pub struct DummyModuleConfig {
+    /// Whether a linear memory is defined and exported. Defaults to `true`.
+    pub memory: bool,
+    /// The name the memory is exported as, or `None` to use the name for the
+    /// mangling scheme, for example `memory` for the legacy mangling.
+    pub memory_name: Option<String>,
+    /// Whether the memory is a 64-bit memory. Defaults to `false`.
+    pub memory64: bool,
+    /// Whether a `realloc` function is exported. Defaults to `true`.
+    ///
+    /// The function's signature uses `i64` instead of `i32` if
+    /// [`DummyModuleConfig::memory64`] is set.
+    pub realloc: bool,
+    /// The name `realloc` is exported as, or `None` to use the name for the
+    /// mangling scheme, for example `cabi_realloc` for the legacy mangling.
+    pub realloc_name: Option<String>,
+    /// If set, a mutable global with this name is exported to act as the
+    /// stack pointer, such as `__stack_pointer`. Defaults to `None`.
+    pub stack_pointer: Option<String>,
+}
+
+impl Default for DummyModuleConfig {
+    fn default() -> DummyModuleConfig {
+        DummyModuleConfig {
+            memory: true,
+            memory_name: None,
+            memory64: false,
+            realloc: true,
+            realloc_name: None,
+            stack_pointer: None,
+        }
+    }
+}
+
+/// Same as [`dummy_module`] but with control over the memory, `realloc`, and
+/// stack pointer items of the module through `config`.
+///
+/// Note that a module without the items required by the canonical ABI for
+/// `world`, or with them under other names, can't be turned into a component
+/// by [`ComponentEncoder`](crate::ComponentEncoder), so this is primarily
+/// intended for testing tools with custom ABIs.
+pub fn dummy_module_with_config(
+    resolve: &Resolve,
+    world: WorldId,
+    mangling: ManglingAndAbi,
+    config: &DummyModuleConfig,
+) -> Vec<u8> {
+    generate(resolve, world, mangling, |_, _| true, config)
+}
+
+fn generate(
+    resolve: &Resolve,
+    world: WorldId,
+    mangling: ManglingAndAbi,
+    filter: impl Fn(&WorldKey, &WorldItem) -> bool,
+    config: &DummyModuleConfig,
+) -> Vec<u8> {
     let world = &resolve.worlds[world];
+    let imports = world
+        .imports
+        .iter()
+        .filter(|(name, item)| filter(name, item))
+        .collect::<Vec<_>>();
+    let exports = world
+        .exports
+        .iter()
+        .filter(|(name, item)| filter(name, item))
+        .collect::<Vec<_>>();
     let mut wat = String::new();
     wat.push_str("(module\n");
-    for (name, import) in world.imports.iter() {
+    for (name, import) in imports.iter().copied() {
         match import {
             WorldItem::Function(func) => {
                 push_imported_func(&mut wat, resolve, None, func, mangling);
@@ -34,7 +131,7 @@ pub fn dummy_module(resolve: &Resolve, world: WorldId, mangling: ManglingAndAbi)
 
     // Append any intrinsics which are imported but used in exported items
     // (e.g. resources)
-    for (name, export) in world.exports.iter() {
+    for (name, export) in exports.iter().copied() {
         match export {
             WorldItem::Function(func) => {
                 push_exported_func_intrinsics(&mut wat, resolve, None, func, mangling);
@@ -51,7 +148,7 @@ pub fn dummy_module(resolve: &Resolve, world: WorldId, mangling: ManglingAndAbi)
         }
     }
 
-    for (name, export) in world.exports.iter() {
+    for (name, export) in exports.iter().copied() {
         match export {
             WorldItem::Function(func) => {
                 push_func_export(&mut wat, resolve, None, func, mangling);
@@ -68,12 +165,28 @@ pub fn dummy_module(resolve: &Resolve, world: WorldId, mangling: ManglingAndAbi)
         }
     }
 
-    let memory = resolve.wasm_export_name(mangling, WasmExport::Memory);
-    wat.push_str(&format!("(memory (export {memory:?}) 0)\n"));
-    let realloc = resolve.wasm_export_name(mangling, WasmExport::Realloc);
-    wat.push_str(&format!(
-        "(func (export {realloc:?}) (param i32 i32 i32 i32) (result i32) unreachable)\n"
-    ));
+    let ptr = if config.memory64 { "i64" } else { "i32" };
+    if config.memory {
+        let memory = config
+            .memory_name
+            .clone()
+            .unwrap_or_else(|| resolve.wasm_export_name(mangling, WasmExport::Memory));
+        wat.push_str(&format!("(memory (export {memory:?}) {ptr} 0)\n"));
+    }
+    if config.realloc {
+        let realloc = config
+            .realloc_name
+            .clone()
+            .unwrap_or_else(|| resolve.wasm_export_name(mangling, WasmExport::Realloc));
+        wat.push_str(&format!(
+            "(func (export {realloc:?}) (param {ptr} {ptr} {ptr} {ptr}) (result {ptr}) unreachable)\n"
+        ));
+    }
+    if let Some(name) = &config.stack_pointer {
+        wat.push_str(&format!(
+            "(global (export {name:?}) (mut {ptr}) ({ptr}.const 0))\n"
+        ));
+    }
 
     let initialize = resolve.wasm_export_name(mangling, WasmExport::Initialize);
     wat.push_str(&format!("(func (export {initialize:?}))"));
@@ -318,7 +431,9 @@ fn push_func_export(
     wat.push_str(" unreachable)\n");
 
     match mangling {
-        ManglingAndAbi::Standard32 | ManglingAndAbi::Legacy(LiftLowerAbi::Sync) => {
+        ManglingAndAbi::Standard32
+        | ManglingAndAbi::Standard64
+        | ManglingAndAbi::Legacy(LiftLowerAbi::Sync) => {
             let name = resolve.wasm_export_name(
                 mangling,
                 WasmExport::Func {
@@ -399,3 +514,118 @@ fn push_root_async_intrinsics(dst: &mut String) {
 "#,
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{DummyModuleConfig, dummy_module, dummy_module_filtered, dummy_module_with_config};
+    use wasmparser::{Parser, Payload, Validator};
+    use wit_parser::{ManglingAndAbi, Resolve, WorldKey};
+
+    #[test]
+    fn filtered_exports() {
+        let mut resolve = Resolve::default();
+        let pkg = resolve
+            .push_str(
+                "test.wit",
+                r#"
+                    package foo:bar;
+
+                    world w {
+                        import f: func();
+                        export a: func();
+                        export b: func(x: u32) -> string;
+                        export c: func();
+                    }
+                "#,
+            )
+            .unwrap();
+        let world = resolve.select_world(pkg, None).unwrap();
+
+        let wasm = dummy_module_filtered(
+            &resolve,
+            world,
+            ManglingAndAbi::Standard32,
+            |key, _| matches!(key, WorldKey::Name(name) if name == "b"),
+        );
+        Validator::new().validate_all(&wasm).unwrap();
+
+        let mut imports = Vec::new();
+        let mut exports = Vec::new();
+        for payload in Parser::new(0).parse_all(&wasm) {
+            match payload.unwrap() {
+                Payload::ImportSection(s) => {
+                    for import in s {
+                        imports.push(import.unwrap().name.to_string());
+                    }
+                }
+                Payload::ExportSection(s) => {
+                    for export in s {
+                        exports.push(export.unwrap().name.to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+        assert!(imports.is_empty(), "{imports:?}");
+        assert_eq!(
+            exports,
+            [
+                "cm32p2||b",
+                "cm32p2||b_post",
+                "cm32p2_memory",
+                "cm32p2_realloc",
+                "cm32p2_initialize",
+            ]
+        );
+    }
+
+    #[test]
+    fn custom_config() {
+        let mut resolve = Resolve::default();
+        let pkg = resolve
+            .push_str(
+                "test.wit",
+                "package foo:bar; world w { export f: func(s: string); }",
+            )
+            .unwrap();
+        let world = resolve.select_world(pkg, None).unwrap();
+        let mangling = ManglingAndAbi::Legacy(wit_parser::LiftLowerAbi::Sync);
+
+        let default =
+            dummy_module_with_config(&resolve, world, mangling, &DummyModuleConfig::default());
+        assert_eq!(default, dummy_module(&resolve, world, mangling));
+
+        let config = DummyModuleConfig {
+            memory_name: Some("mem".to_string()),
+            memory64: true,
+            realloc: false,
+            stack_pointer: Some("__stack_pointer".to_string()),
+            ..DummyModuleConfig::default()
+        };
+        let wasm = dummy_module_with_config(&resolve, world, mangling, &config);
+        Validator::new().validate_all(&wasm).unwrap();
+
+        let mut exports = Vec::new();
+        let mut memory64 = false;
+        for payload in Parser::new(0).parse_all(&wasm) {
+            match payload.unwrap() {
+                Payload::MemorySection(s) => {
+                    for memory in s {
+                        memory64 = memory.unwrap().memory64;
+                    }
+                }
+                Payload::ExportSection(s) => {
+                    for export in s {
+                        exports.push(export.unwrap().name.to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+        assert!(memory64);
+        assert_eq!(
+            exports,
+            ["f", "cabi_post_f", "mem", "__stack_pointer", "_initialize"]
+        );
+    }
+}
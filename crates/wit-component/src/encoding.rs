@@ -443,12 +443,20 @@ impl<'a> EncodingState<'a> {
         }
     }
 
-    fn encode_imports(&mut self, name_map: &HashMap<String, String>) -> Result<()> {
+    fn encode_imports(
+        &mut self,
+        name_map: &HashMap<String, String>,
+        rename: Option<&(dyn Fn(&str) -> Option<String> + Send + Sync)>,
+    ) -> Result<()> {
         let mut has_funcs = false;
         for (name, info) in self.info.import_map.iter() {
             match name {
                 Some(name) => {
-                    self.encode_interface_import(name_map.get(name).unwrap_or(name), info)?
+                    let renamed = match name_map.get(name) {
+                        Some(renamed) => Some(renamed.clone()),
+                        None => rename.and_then(|rename| rename(name)),
+                    };
+                    self.encode_interface_import(renamed.as_deref().unwrap_or(name), info)?
                 }
                 None => has_funcs = true,
             }
@@ -2680,9 +2688,11 @@ pub struct ComponentEncoder {
     pub(super) main_module_exports: IndexSet<WorldKey>,
     pub(super) adapters: IndexMap<String, Adapter>,
     import_name_map: HashMap<String, String>,
+    rename_import: Option<Box<dyn Fn(&str) -> Option<String> + Send + Sync>>,
     realloc_via_memory_grow: bool,
     merge_imports_based_on_semver: Option<bool>,
     pub(super) reject_legacy_names: bool,
+    sort_world_items: bool,
 }
 
 impl ComponentEncoder {
@@ -2750,6 +2760,27 @@ impl ComponentEncoder {
         self
     }
 
+    /// Sets whether the imports and exports of the component's world are
+    /// sorted into a stable order before encoding.
+    ///
+    /// By default items are encoded in the order that they're listed in the
+    /// world, which can depend on how the world was constructed, for example
+    /// the order packages were parsed in or adapters were merged in. When
+    /// enabled the world is sorted with [`Resolve::sort_world`] first, so two
+    /// worlds which only list their imports and exports in a different order
+    /// produce identical bytes.
+    ///
+    /// This doesn't make encoding fully deterministic: only the order of the
+    /// world's own imports and exports is normalized. Other differences
+    /// between logically equivalent inputs, such as the order of functions
+    /// and types within an interface, still affect the output.
+    ///
+    /// This is disabled by default.
+    pub fn sort_world_items(mut self, sort: bool) -> Self {
+        self.sort_world_items = sort;
+        self
+    }
+
     /// Specifies a new adapter which is used to translate from a historical
     /// wasm ABI to the canonical ABI and the `interface` provided.
     ///
@@ -2865,6 +2896,24 @@ impl ComponentEncoder {
         self
     }
 
+    /// Sets a callback used to rename instance imports in the final
+    /// component.
+    ///
+    /// The callback is invoked with the name of each imported instance, such
+    /// as `foo:bar/baz`, and returns the name to use instead or `None` to keep
+    /// the original name. Names listed in [`ComponentEncoder::import_name_map`]
+    /// take precedence and aren't passed to the callback.
+    ///
+    /// Like [`ComponentEncoder::import_name_map`] the new names are not
+    /// validated unless the `validate` option is set to true.
+    pub fn rename_import(
+        mut self,
+        rename: impl Fn(&str) -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.rename_import = Some(Box::new(rename));
+        self
+    }
+
     /// Encode the component and return the bytes.
     pub fn encode(&mut self) -> Result<Vec<u8>> {
         if self.module.is_empty() {
@@ -2876,6 +2925,9 @@ impl ComponentEncoder {
                 .resolve
                 .merge_world_imports_based_on_semver(self.metadata.world)?;
         }
+        if self.sort_world_items {
+            self.metadata.resolve.sort_world(self.metadata.world);
+        }
 
         let world = ComponentWorld::new(self).context("failed to decode world from module")?;
         let mut state = EncodingState {
@@ -2895,7 +2947,7 @@ impl ComponentEncoder {
             aliased_core_items: Default::default(),
             info: &world,
         };
-        state.encode_imports(&self.import_name_map)?;
+        state.encode_imports(&self.import_name_map, self.rename_import.as_deref())?;
         state.encode_core_modules();
         state.encode_core_instantiation()?;
         state.encode_exports(CustomModule::Main)?;
@@ -2998,4 +3050,111 @@ world test {
         assert!(wat.contains("unlocked-dep=<foo:bar/foo@{>=1.0.0 <1.1.0}>"));
         assert!(wat.contains("locked-dep=<foo:bar/i@1.2.3>"));
     }
+
+    #[test]
+    fn it_renames_imports_with_a_callback() {
+        let mut resolve = Resolve::new();
+        let pkg = resolve
+            .push_str(
+                "test.wit",
+                r#"
+package test:wit;
+
+interface i {
+    f: func();
+}
+
+interface j {
+    g: func();
+}
+
+world test {
+    import i;
+    import j;
+}
+"#,
+            )
+            .unwrap();
+        let world = resolve.select_world(pkg, None).unwrap();
+
+        let mut module = dummy_module(&resolve, world, ManglingAndAbi::Standard32);
+        embed_component_metadata(&mut module, &resolve, world, StringEncoding::UTF8).unwrap();
+
+        let encoded = ComponentEncoder::default()
+            .rename_import(|name| match name {
+                "test:wit/i" => Some("vendor:wit/i".to_string()),
+                _ => None,
+            })
+            .module(&module)
+            .unwrap()
+            .validate(true)
+            .encode()
+            .unwrap();
+
+        let (resolve, world) = match crate::decode(&encoded).unwrap() {
+            crate::DecodedWasm::Component(resolve, world) => (resolve, world),
+            crate::DecodedWasm::WitPackage(..) => unreachable!(),
+        };
+        let imports = resolve.worlds[world]
+            .imports
+            .keys()
+            .map(|key| resolve.name_world_key(key))
+            .collect::<Vec<_>>();
+        assert_eq!(imports, ["vendor:wit/i", "test:wit/j"]);
+    }
+
+    #[test]
+    fn sort_world_items_encoding() {
+        let resolve = |world: &str| {
+            let mut resolve = Resolve::new();
+            let pkg = resolve
+                .push_str(
+                    "test.wit",
+                    &format!(
+                        r#"
+package test:wit;
+
+interface a {{
+    f: func();
+}}
+
+interface b {{
+    g: func();
+}}
+
+{world}
+"#
+                    ),
+                )
+                .unwrap();
+            let world = resolve.select_world(pkg, None).unwrap();
+            (resolve, world)
+        };
+        let (first, first_world) = resolve("world test { import a; import b; export h: func(); }");
+        let (second, second_world) =
+            resolve("world test { import b; import a; export h: func(); }");
+
+        // Use the same core module for both so only the embedded WIT differs.
+        let module = dummy_module(&first, first_world, ManglingAndAbi::Standard32);
+        let encode = |resolve: &Resolve, world, sort| {
+            let mut module = module.clone();
+            embed_component_metadata(&mut module, resolve, world, StringEncoding::UTF8).unwrap();
+            ComponentEncoder::default()
+                .sort_world_items(sort)
+                .module(&module)
+                .unwrap()
+                .validate(true)
+                .encode()
+                .unwrap()
+        };
+
+        assert_ne!(
+            encode(&first, first_world, false),
+            encode(&second, second_world, false)
+        );
+        assert_eq!(
+            encode(&first, first_world, true),
+            encode(&second, second_world, true)
+        );
+    }
 }
@@ -3,13 +3,15 @@
 #![deny(missing_docs)]
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 
+use std::collections::HashSet;
 use std::str::FromStr;
 use std::{borrow::Cow, fmt::Display};
 
 use anyhow::{Result, bail};
 use wasm_encoder::{CanonicalOption, Encode, Section};
-use wit_parser::{Resolve, WorldId};
+use wit_parser::{PackageId, Resolve, WorldId};
 
+mod canonical_options;
 mod encoding;
 mod gc;
 mod linking;
@@ -17,16 +19,21 @@ mod printing;
 mod targets;
 mod validation;
 
+pub use canonical_options::{CanonicalOptions, ExportOptions, decode_export_options};
 pub use encoding::{ComponentEncoder, LibraryInfo, encode};
 pub use linking::Linker;
 pub use printing::*;
 pub use targets::*;
-pub use wit_parser::decoding::{DecodedWasm, decode, decode_reader};
+pub use wasm_metadata::Producers;
+pub use wit_parser::decoding::{
+    DecodeProgress, DecodedWasm, decode, decode_reader, decode_with_progress,
+};
 
 pub mod metadata;
+pub use metadata::{BINARY_FORMAT_VERSION, binary_format_version};
 
 #[cfg(feature = "dummy-module")]
-pub use dummy::dummy_module;
+pub use dummy::{DummyModuleConfig, dummy_module, dummy_module_filtered, dummy_module_with_config};
 #[cfg(feature = "dummy-module")]
 mod dummy;
 
@@ -88,6 +95,17 @@ pub(crate) fn base_producers() -> wasm_metadata::Producers {
     producer
 }
 
+/// Reads the `producers` custom section of the component or module `bytes`,
+/// if it has one.
+///
+/// This describes the tools which produced `bytes`, such as the
+/// `processed-by` entry this crate adds to everything it encodes. Only the
+/// outermost section is read, so producers of nested modules and components
+/// are not included.
+pub fn producers(bytes: &[u8]) -> Result<Option<Producers>> {
+    Producers::from_wasm(bytes)
+}
+
 /// Embed component metadata in a buffer of bytes that contains a Wasm module
 pub fn embed_component_metadata(
     bytes: &mut Vec<u8>,
@@ -107,6 +125,56 @@ pub fn embed_component_metadata(
     Ok(())
 }
 
+/// The representations of a WIT package produced by [`export_package`].
+#[derive(Debug, Clone)]
+pub struct PackageArtifacts {
+    /// The package printed as WIT text.
+    ///
+    /// Packages the exported package depends on are printed as nested
+    /// packages after it, so this text can be parsed on its own.
+    pub wit_text: String,
+    /// The JSON serialization of a [`Resolve`] containing only the package
+    /// and the packages it depends on, as with [`Resolve::extract_package`].
+    pub json: serde_json::Value,
+    /// The package encoded as a WebAssembly component, as produced by
+    /// [`encode`].
+    pub binary: Vec<u8>,
+}
+
+/// Produces the WIT text, JSON, and binary representations of `pkg` together.
+///
+/// This is intended for publishing a package in several forms at once, and
+/// ensures they're all derived from the same [`Resolve`].
+pub fn export_package(resolve: &Resolve, pkg: PackageId) -> Result<PackageArtifacts> {
+    let deps = transitive_package_deps(resolve, pkg);
+    let nested = resolve
+        .topological_packages()
+        .into_iter()
+        .filter(|id| deps.contains(id))
+        .collect::<Vec<_>>();
+    let mut printer = WitPrinter::default();
+    printer.print(resolve, pkg, &nested)?;
+
+    Ok(PackageArtifacts {
+        wit_text: printer.output.to_string(),
+        json: serde_json::to_value(resolve.extract_package(pkg))?,
+        binary: encode(resolve, pkg)?,
+    })
+}
+
+fn transitive_package_deps(resolve: &Resolve, pkg: PackageId) -> HashSet<PackageId> {
+    let mut deps = HashSet::new();
+    let mut worklist = vec![pkg];
+    while let Some(id) = worklist.pop() {
+        for dep in resolve.package_direct_deps(id) {
+            if dep != pkg && deps.insert(dep) {
+                worklist.push(dep);
+            }
+        }
+    }
+    deps
+}
+
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
@@ -174,4 +242,28 @@ world test-world {}
 
         Ok(())
     }
+
+    #[test]
+    fn binary_format_version() -> Result<()> {
+        let mut resolver = Resolve::default();
+        let pkg = resolver.push_str("in-code.wit", COMPONENT_WIT)?;
+        let world = resolver.select_world(pkg, Some("test-world"))?;
+
+        let metadata = crate::metadata::encode(&resolver, world, StringEncoding::UTF8, None)?;
+        assert_eq!(
+            super::binary_format_version(&metadata)?,
+            super::BINARY_FORMAT_VERSION
+        );
+
+        let mut module = wat::parse_str(MODULE_WAT)?;
+        embed_component_metadata(&mut module, &resolver, world, StringEncoding::UTF8)?;
+        assert_eq!(
+            super::binary_format_version(&module)?,
+            super::BINARY_FORMAT_VERSION
+        );
+
+        let package = crate::encode(&resolver, pkg)?;
+        assert_eq!(super::binary_format_version(&package)?, 0);
+        Ok(())
+    }
 }
@@ -53,6 +53,10 @@ use wasmparser::{BinaryReader, Encoding, Parser, Payload};
 use wit_parser::{Package, PackageName, Resolve, World, WorldId, WorldItem, WorldKey};
 
 const CURRENT_VERSION: u8 = 0x04;
+
+/// The version of the bindings metadata format written by this crate, as
+/// returned by [`binary_format_version`].
+pub const BINARY_FORMAT_VERSION: u32 = CURRENT_VERSION as u32;
 const CUSTOM_SECTION_NAME: &str = "wit-component-encoding";
 
 /// The result of decoding binding information from a WebAssembly binary.
@@ -80,6 +84,7 @@ impl Default for Bindgen {
                 version: None,
             },
             docs: Default::default(),
+            leading_comments: None,
             interfaces: Default::default(),
             worlds: Default::default(),
         });
@@ -303,6 +308,43 @@ pub fn encode(
     Ok(builder.finish())
 }
 
+/// Returns the version of the format that the bindings metadata in `wasm` was
+/// encoded with, without decoding the rest of the metadata.
+///
+/// The `wasm` provided can either be the output of [`encode`] or a core wasm
+/// module with that output embedded in `component-type` custom sections. In
+/// the latter case the version of the first section found is returned.
+///
+/// If no version is stamped in `wasm`, for example because it's a WIT package
+/// encoded with [`crate::encode`] which doesn't contain bindings metadata,
+/// then 0 is returned. Otherwise the returned version can be compared with
+/// [`BINARY_FORMAT_VERSION`] to detect binaries that are too new or too old to
+/// be decoded.
+pub fn binary_format_version(wasm: &[u8]) -> Result<u32> {
+    let mut embedded = Vec::new();
+    for payload in Parser::new(0).parse_all(wasm) {
+        match payload? {
+            Payload::CustomSection(s) if s.name() == CUSTOM_SECTION_NAME => {
+                return match s.data() {
+                    [version, ..] => Ok(u32::from(*version)),
+                    [] => bail!("custom section `{CUSTOM_SECTION_NAME}` in unknown format"),
+                };
+            }
+            Payload::CustomSection(s) if s.name().starts_with("component-type") => {
+                embedded.push(s.data());
+            }
+            _ => {}
+        }
+    }
+    for data in embedded {
+        let version = binary_format_version(data)?;
+        if version != 0 {
+            return Ok(version);
+        }
+    }
+    Ok(0)
+}
+
 fn decode_custom_section(wasm: &[u8]) -> Result<(Resolve, WorldId, StringEncoding)> {
     let (resolve, world) = wit_parser::decoding::decode_world(wasm)?;
     let mut custom_section = None;
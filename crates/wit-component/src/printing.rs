@@ -1,9 +1,10 @@
 use anyhow::{Result, anyhow, bail};
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Display;
 use std::mem;
 use std::ops::Deref;
+use std::path::PathBuf;
 use wit_parser::*;
 
 // NB: keep in sync with `crates/wit-parser/src/ast/lex.rs`
@@ -22,6 +23,13 @@ pub struct WitPrinter<O: Output = OutputToString> {
     emit_docs: bool,
 
     print_f32_f64: bool,
+
+    // Whether to omit versions from paths to packages which are only present
+    // at a single version.
+    elide_unambiguous_versions: bool,
+
+    // Text printed as line comments before the main package.
+    header: Option<String>,
 }
 
 impl Default for WitPrinter {
@@ -41,6 +49,8 @@ impl<O: Output> WitPrinter<O> {
                 Ok(s) => s == "1",
                 Err(_) => PRINT_F32_F64_DEFAULT,
             },
+            elide_unambiguous_versions: false,
+            header: None,
         }
     }
 
@@ -60,6 +70,44 @@ impl<O: Output> WitPrinter<O> {
         Ok(())
     }
 
+    /// Prints every package in `resolve` to its own file, returning a map of
+    /// relative paths to file contents.
+    ///
+    /// Files are named after their package, for example
+    /// `namespace-name@1.0.0.wit`. The last package in topological order is
+    /// placed at the root and all other packages are placed in a `deps`
+    /// directory, so writing the files out to a directory produces a layout
+    /// which can be loaded with [`Resolve::push_dir`].
+    ///
+    /// Settings such as [`WitPrinter::emit_docs`] are used for each file
+    /// printed, but `self.output` is not written to.
+    pub fn print_bundle(&self, resolve: &Resolve) -> Result<BTreeMap<PathBuf, String>> {
+        let mut files = BTreeMap::new();
+        let packages = resolve.topological_packages();
+        for (i, pkg) in packages.iter().enumerate() {
+            let mut printer = WitPrinter::default();
+            printer.emit_docs = self.emit_docs;
+            printer.print_f32_f64 = self.print_f32_f64;
+            printer.elide_unambiguous_versions = self.elide_unambiguous_versions;
+            printer.header = self.header.clone();
+            printer.print(resolve, *pkg, &[])?;
+
+            let name = &resolve.packages[*pkg].name;
+            let mut file = format!("{}-{}", name.namespace, name.name);
+            if let Some(version) = &name.version {
+                file.push_str(&format!("@{version}"));
+            }
+            file.push_str(".wit");
+            let path = if i + 1 == packages.len() {
+                PathBuf::from(file)
+            } else {
+                PathBuf::from("deps").join(file)
+            };
+            files.insert(path, printer.output.to_string());
+        }
+        Ok(files)
+    }
+
     /// Configure whether doc comments will be printed.
     ///
     /// Defaults to true.
@@ -68,6 +116,40 @@ impl<O: Output> WitPrinter<O> {
         self
     }
 
+    /// Configure whether versions are omitted when referring to interfaces
+    /// in other packages, such as in `use` or `import`, when `resolve` only
+    /// contains a single version of that package.
+    ///
+    /// Versions are still printed for packages present at multiple versions
+    /// since the reference would otherwise be ambiguous. To parse the output
+    /// again [`Resolve::resolve_unversioned_deps`] must be enabled so that
+    /// unversioned references resolve to the only version of the package
+    /// available.
+    ///
+    /// Defaults to false.
+    pub fn elide_unambiguous_versions(&mut self, enabled: bool) -> &mut Self {
+        self.elide_unambiguous_versions = enabled;
+        self
+    }
+
+    /// Configure a banner, such as "DO NOT EDIT", printed at the top of the
+    /// output before the `package` statement.
+    ///
+    /// Each line of `comment` is printed as a `//` line comment, and the
+    /// banner is printed regardless of [`WitPrinter::emit_docs`].
+    ///
+    /// Note that WIT has no comments other than doc comments, so when the
+    /// output is parsed again the banner becomes part of the package's docs.
+    /// To keep the banner from repeating when such a package is printed again
+    /// with the same banner, package docs which start with the banner are
+    /// printed without it.
+    ///
+    /// Defaults to no banner.
+    pub fn set_header(&mut self, comment: &str) -> &mut Self {
+        self.header = Some(comment.to_string());
+        self
+    }
+
     /// Prints the specified `pkg`.
     ///
     /// If `is_main` is not set, nested package notation is used.
@@ -78,6 +160,9 @@ impl<O: Output> WitPrinter<O> {
         is_main: bool,
     ) -> Result<()> {
         let pkg = &resolve.packages[pkg];
+        if is_main {
+            self.print_header();
+        }
         self.print_package_outer(pkg)?;
 
         if is_main {
@@ -116,7 +201,27 @@ impl<O: Output> WitPrinter<O> {
     /// Print the specified package without its content.
     /// Does not print the semicolon nor starts the indentation.
     pub fn print_package_outer(&mut self, pkg: &Package) -> Result<()> {
-        self.print_docs(&pkg.docs);
+        match &pkg.leading_comments {
+            Some(comments) if self.emit_docs => {
+                let comments = self.strip_header(comments, |line| match line {
+                    "" => "//".to_string(),
+                    line => format!("// {line}"),
+                });
+                for line in comments.lines() {
+                    self.output.comment(line.trim_end());
+                }
+            }
+            _ => {
+                if let Some(contents) = &pkg.docs.contents {
+                    let contents = self.strip_header(contents, |line| line.to_string());
+                    if self.emit_docs {
+                        for line in contents.lines() {
+                            self.output.doc(line);
+                        }
+                    }
+                }
+            }
+        }
         self.output.keyword("package");
         self.output.str(" ");
         self.print_name_type(&pkg.name.namespace, TypeKind::NamespaceDeclaration);
@@ -152,6 +257,13 @@ impl<O: Output> WitPrinter<O> {
     }
 
     /// Print the inner content of a given WebAssembly interface.
+    ///
+    /// Only the body of the interface is printed, without the enclosing
+    /// `interface name { ... }` or `package` statement, so this can be used to
+    /// render a single interface in isolation. Interfaces in the same package
+    /// are referred to by name and interfaces in other packages by their
+    /// fully qualified path, so the output can be parsed again when wrapped in
+    /// an interface within the same package.
     pub fn print_interface(&mut self, resolve: &Resolve, id: InterfaceId) -> Result<()> {
         let prev_items = mem::replace(&mut self.any_items, false);
         let interface = &resolve.interfaces[id];
@@ -383,7 +495,10 @@ impl<O: Output> WitPrinter<O> {
         Ok(())
     }
 
-    /// Prints the world `id` within `resolve`.
+    /// Prints the inner content of the world `id` within `resolve`.
+    ///
+    /// As with [`WitPrinter::print_interface`] only the body of the world is
+    /// printed, without the enclosing `world name { ... }`.
     ///
     /// This is a little tricky to preserve round-tripping that WIT wants. This
     /// function inherently can't preserve ordering of imports because resource
@@ -402,10 +517,12 @@ impl<O: Output> WitPrinter<O> {
     ///
     /// This keeps things printed in a roughly topological fashion and makes
     /// round-tripping a bit more reliable.
-    fn print_world(&mut self, resolve: &Resolve, id: WorldId) -> Result<()> {
-        let prev_items = mem::replace(&mut self.any_items, false);
+    pub fn print_world(&mut self, resolve: &Resolve, id: WorldId) -> Result<()> {
         let world = &resolve.worlds[id];
-        let pkgid = world.package.unwrap();
+        let pkgid = world
+            .package
+            .ok_or_else(|| anyhow!("world `{}` is not part of a package", world.name))?;
+        let prev_items = mem::replace(&mut self.any_items, false);
         let mut types = Vec::new();
         let mut resource_funcs = HashMap::new();
         let mut function_imports_to_print = Vec::new();
@@ -529,7 +646,16 @@ impl<O: Output> WitPrinter<O> {
             self.output.str("/");
             self.print_name_type(iface.name.as_ref().unwrap(), TypeKind::InterfacePath);
             if let Some(version) = &pkg.version {
-                self.print_name_type(&format!("@{version}"), TypeKind::VersionPath);
+                let elide = self.elide_unambiguous_versions
+                    && resolve
+                        .package_names
+                        .keys()
+                        .filter(|n| n.namespace == pkg.namespace && n.name == pkg.name)
+                        .count()
+                        == 1;
+                if !elide {
+                    self.print_name_type(&format!("@{version}"), TypeKind::VersionPath);
+                }
             }
         }
         Ok(())
@@ -1093,6 +1219,38 @@ impl<O: Output> WitPrinter<O> {
         self.output.case(Self::escape_name(name).deref());
     }
 
+    fn print_header(&mut self) {
+        if let Some(header) = &self.header {
+            for line in header.lines() {
+                let line = line.trim_end();
+                if line.is_empty() {
+                    self.output.comment("//");
+                } else {
+                    self.output.comment(&format!("// {line}"));
+                }
+            }
+            self.output.newline();
+        }
+    }
+
+    /// Returns `text` without the banner configured with
+    /// [`WitPrinter::set_header`] at its start, where each line of the banner
+    /// is rendered with `line`.
+    fn strip_header<'a>(&self, text: &'a str, line: impl Fn(&str) -> String) -> &'a str {
+        let Some(header) = &self.header else {
+            return text;
+        };
+        let header = header
+            .lines()
+            .map(|l| line(l.trim_end()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        match text.strip_prefix(&header) {
+            Some(rest) if rest.is_empty() || rest.starts_with('\n') => rest.trim_start(),
+            _ => text,
+        }
+    }
+
     fn print_docs(&mut self, docs: &Docs) {
         if self.emit_docs {
             if let Some(contents) = &docs.contents {
@@ -1294,6 +1452,17 @@ pub trait Output {
         self.newline();
     }
 
+    /// Called when a single line of a non-documentation comment, such as a
+    /// license header, is added.
+    /// The `comment` parameter includes its comment markers, if any, and can be
+    /// an empty string for blank lines within a block comment.
+    fn comment(&mut self, comment: &str) {
+        assert!(!comment.contains('\n'));
+        self.indent_if_needed();
+        self.push_str(comment);
+        self.newline();
+    }
+
     /// A semicolon is added.
     fn semicolon(&mut self) {
         assert!(
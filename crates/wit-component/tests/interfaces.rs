@@ -61,9 +61,11 @@ fn run_test(path: &Path, is_dir: bool) -> Result<()> {
     let wasm = wit_component::encode(&resolve, package)?;
     let wat = wasmprinter::print_bytes(&wasm)?;
     assert_output(&path.with_extension("wat"), &wat)?;
-    wasmparser::Validator::new()
-        .validate_all(&wasm)
-        .context("failed to validate wasm output")?;
+    wasmparser::Validator::new_with_features(
+        wasmparser::WasmFeatures::default() | wasmparser::WasmFeatures::CM_FIXED_SIZE_LIST,
+    )
+    .validate_all(&wasm)
+    .context("failed to validate wasm output")?;
 
     // Next decode a fresh WIT package from the WebAssembly generated. Print
     // this package's documents and assert they all match the expectations.
@@ -0,0 +1,81 @@
+(component
+  (type (;0;)
+    (component
+      (type (;0;)
+        (instance
+          (type (;0;) (list u8 16))
+          (export (;1;) "digest" (type (eq 0)))
+          (type (;2;) (list u8 4))
+          (type (;3;) (list 2 2))
+          (export (;4;) "matrix" (type (eq 3)))
+          (type (;5;) (list u32 2))
+          (type (;6;) (list 1 2))
+          (type (;7;) (list 6 3))
+          (type (;8;) (record (field "header" 5) (field "rows" 7)))
+          (export (;9;) "block" (type (eq 8)))
+          (type (;10;) (list u8))
+          (type (;11;) (func (param "data" 10) (result 1)))
+          (export (;0;) "hash" (func (type 11)))
+          (type (;12;) (list u8 2))
+          (type (;13;) (list 12 4))
+          (type (;14;) (func (param "m" 4) (result 13)))
+          (export (;1;) "transpose" (func (type 14)))
+          (type (;15;) (list 9))
+          (type (;16;) (list 9 8))
+          (type (;17;) (option 16))
+          (type (;18;) (func (param "b" 15) (result 17)))
+          (export (;2;) "blocks" (func (type 18)))
+        )
+      )
+      (export (;0;) "foo:fixed-size-lists/fixed-size-lists" (instance (type 0)))
+    )
+  )
+  (export (;1;) "fixed-size-lists" (type 0))
+  (type (;2;)
+    (component
+      (type (;0;)
+        (component
+          (type (;0;)
+            (instance
+              (type (;0;) (list u8 16))
+              (export (;1;) "digest" (type (eq 0)))
+              (type (;2;) (list u8 4))
+              (type (;3;) (list 2 2))
+              (export (;4;) "matrix" (type (eq 3)))
+              (type (;5;) (list u32 2))
+              (type (;6;) (list 1 2))
+              (type (;7;) (list 6 3))
+              (type (;8;) (record (field "header" 5) (field "rows" 7)))
+              (export (;9;) "block" (type (eq 8)))
+              (type (;10;) (list u8))
+              (type (;11;) (func (param "data" 10) (result 1)))
+              (export (;0;) "hash" (func (type 11)))
+              (type (;12;) (list u8 2))
+              (type (;13;) (list 12 4))
+              (type (;14;) (func (param "m" 4) (result 13)))
+              (export (;1;) "transpose" (func (type 14)))
+              (type (;15;) (list 9))
+              (type (;16;) (list 9 8))
+              (type (;17;) (option 16))
+              (type (;18;) (func (param "b" 15) (result 17)))
+              (export (;2;) "blocks" (func (type 18)))
+            )
+          )
+          (import "foo:fixed-size-lists/fixed-size-lists" (instance (;0;) (type 0)))
+          (type (;1;) (list u8 16))
+          (type (;2;) (list s8 1))
+          (type (;3;) (list 2 1))
+          (type (;4;) (tuple 1 3))
+          (type (;5;) (func (param "a" 1) (result 4)))
+          (export (;0;) "digest" (func (type 5)))
+        )
+      )
+      (export (;0;) "foo:fixed-size-lists/fixed-size-lists-world" (component (type 0)))
+    )
+  )
+  (export (;3;) "fixed-size-lists-world" (type 2))
+  (@custom "package-docs" "\01{}")
+  (@producers
+    (processed-by "wit-component" "$CARGO_PKG_VERSION")
+  )
+)
@@ -1,7 +1,8 @@
 #![cfg(feature = "wat")]
 
 use anyhow::Result;
-use wit_parser::Resolve;
+use wit_component::{DecodedWasm, WitPrinter};
+use wit_parser::{Resolve, SourceMap, UnresolvedPackageGroup};
 
 /// Ensure that parse_wit_from_path works with directories
 #[test]
@@ -47,3 +48,804 @@ fn parse_wit_missing_path() -> Result<()> {
 
     Ok(())
 }
+
+/// Ensure that a package extracted from a larger `Resolve` can be encoded on
+/// its own.
+#[test]
+fn extract_package_encodes_standalone() -> Result<()> {
+    drop(env_logger::try_init());
+
+    let mut resolve = Resolve::default();
+    resolve.push_str(
+        "dep.wit",
+        r#"
+            package foo:dep;
+
+            interface types {
+                resource r;
+                record point { x: u32, y: u32 }
+            }
+        "#,
+    )?;
+    resolve.push_str(
+        "unrelated.wit",
+        r#"
+            package foo:unrelated;
+
+            interface i {
+                f: func() -> list<u8>;
+            }
+        "#,
+    )?;
+    let main = resolve.push_str(
+        "main.wit",
+        r#"
+            package foo:main;
+
+            interface api {
+                use foo:dep/types.{r, point};
+                f: func(p: point, r: borrow<r>) -> option<point>;
+            }
+
+            world w {
+                export api;
+            }
+        "#,
+    )?;
+
+    let extracted = resolve.extract_package(main);
+    let names = extracted
+        .package_names
+        .keys()
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["foo:dep", "foo:main"]);
+
+    let main = extracted.package_names.values().copied().last().unwrap();
+    let bytes = wit_component::encode(&extracted, main)?;
+    let decoded = wit_component::decode(&bytes)?;
+    assert_eq!(
+        decoded.resolve().packages[decoded.package()]
+            .name
+            .to_string(),
+        "foo:main"
+    );
+    Ok(())
+}
+
+/// Ensure that the comments before `package`, such as a license header, are
+/// printed as they were written.
+#[test]
+fn leading_comments_roundtrip() -> Result<()> {
+    drop(env_logger::try_init());
+
+    let header = "\
+// Copyright The Authors.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+/* Docs for the package. */";
+    let wit = format!(
+        "{header}
+package foo:bar;
+
+interface i {{
+  f: func();
+}}
+"
+    );
+
+    let group = UnresolvedPackageGroup::parse("test.wit", &wit)?;
+    assert_eq!(group.main.leading_comments.as_deref(), Some(header));
+
+    let mut resolve = Resolve::default();
+    let pkg = resolve.push_group(group)?;
+    let docs = resolve.packages[pkg].docs.clone();
+    let print = |resolve: &Resolve, pkg| -> Result<String> {
+        let mut printer = WitPrinter::default();
+        printer.print(resolve, pkg, &[])?;
+        Ok(printer.output.to_string())
+    };
+    let printed = print(&resolve, pkg)?;
+    assert!(
+        printed.starts_with(&format!("{header}\npackage")),
+        "{printed}"
+    );
+
+    let reparsed = UnresolvedPackageGroup::parse("test.wit", &printed)?;
+    assert_eq!(reparsed.main.leading_comments.as_deref(), Some(header));
+    assert_eq!(reparsed.main.docs, docs);
+
+    // The binary format only stores the docs themselves, which are printed
+    // as doc comments.
+    let decoded = wit_component::decode(&wit_component::encode(&resolve, pkg)?)?;
+    let decoded_pkg = &decoded.resolve().packages[decoded.package()];
+    assert!(decoded_pkg.leading_comments.is_none());
+    assert_eq!(decoded_pkg.docs, docs);
+
+    // Comments documenting items other than the package aren't captured.
+    let group =
+        UnresolvedPackageGroup::parse("test.wit", "package foo:bar;\n// docs\ninterface i {}")?;
+    assert!(group.main.leading_comments.is_none());
+
+    // The comments come from whichever file documents the package.
+    let mut map = SourceMap::new();
+    map.push("a.wit".as_ref(), "package foo:bar;\ninterface a {}");
+    map.push("b.wit".as_ref(), "// b\npackage foo:bar;\ninterface b {}");
+    let group = map.parse()?;
+    assert_eq!(group.main.leading_comments.as_deref(), Some("// b"));
+    Ok(())
+}
+
+/// Ensure that a bundle of printed packages can be loaded back as a directory.
+#[test]
+fn print_bundle_roundtrip() -> Result<()> {
+    drop(env_logger::try_init());
+
+    let mut resolve = Resolve::default();
+    resolve.push_str(
+        "dep.wit",
+        r#"
+            package foo:dep@1.0.0;
+
+            interface types {
+                record point { x: u32, y: u32 }
+            }
+        "#,
+    )?;
+    resolve.push_str(
+        "main.wit",
+        r#"
+            package foo:main;
+
+            interface api {
+                use foo:dep/types@1.0.0.{point};
+                f: func(p: point);
+            }
+
+            world w {
+                export api;
+            }
+        "#,
+    )?;
+
+    let files = WitPrinter::default().print_bundle(&resolve)?;
+    let paths = files
+        .keys()
+        .map(|p| p.to_str().unwrap().replace('\\', "/"))
+        .collect::<Vec<_>>();
+    assert_eq!(paths, ["deps/foo-dep@1.0.0.wit", "foo-main.wit"]);
+
+    let dir = tempfile::tempdir()?;
+    for (path, contents) in files.iter() {
+        let path = dir.path().join(path);
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        std::fs::write(&path, contents)?;
+    }
+
+    let mut reparsed = Resolve::default();
+    let (main, _) = reparsed.push_dir(dir.path())?;
+    assert_eq!(reparsed.packages[main].name.to_string(), "foo:main");
+    assert_eq!(reparsed.packages.len(), 2);
+    assert_eq!(WitPrinter::default().print_bundle(&reparsed)?, files);
+    Ok(())
+}
+
+/// Ensure that worlds which only differ in the order of their items print the
+/// same once sorted.
+#[test]
+fn sort_world_prints_identically() -> Result<()> {
+    drop(env_logger::try_init());
+
+    let print_sorted = |world: &str| -> Result<String> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            &format!(
+                r#"
+                    package foo:bar;
+
+                    interface types {{
+                        record point {{ x: u32, y: u32 }}
+                    }}
+                    interface a {{
+                        use types.{{point}};
+                        f: func(p: point);
+                    }}
+                    interface b {{}}
+
+                    {world}
+                "#
+            ),
+        )?;
+        let id = resolve.packages[pkg].worlds["w"];
+        resolve.sort_world(id);
+        let mut printer = WitPrinter::default();
+        printer.print(&resolve, pkg, &[])?;
+        Ok(printer.output.to_string())
+    };
+
+    let first = print_sorted(
+        r#"
+            world w {
+                import b;
+                import a;
+                import y: func();
+                import x: func();
+                export b;
+                export a;
+            }
+        "#,
+    )?;
+    let second = print_sorted(
+        r#"
+            world w {
+                import x: func();
+                import a;
+                import y: func();
+                import b;
+                export a;
+                export b;
+            }
+        "#,
+    )?;
+    assert_eq!(first, second);
+    Ok(())
+}
+
+/// Ensure that a world created with `Resolve::create_world_importing` prints
+/// as valid WIT which parses back to the same world.
+#[test]
+fn create_world_importing_roundtrip() -> Result<()> {
+    drop(env_logger::try_init());
+
+    let dep = r#"
+        package foo:dep;
+
+        interface types {
+            type t = u32;
+        }
+    "#;
+    let mut resolve = Resolve::default();
+    resolve.push_str("dep.wit", dep)?;
+    let pkg = resolve.push_str(
+        "test.wit",
+        r#"
+            package foo:bar;
+
+            interface local {
+                use foo:dep/types.{t};
+                type l = t;
+            }
+            interface uses {
+                use local.{l};
+                f: func() -> l;
+            }
+        "#,
+    )?;
+    let uses = resolve.packages[pkg].interfaces["uses"];
+    resolve.create_world_importing(uses, "just-uses")?;
+
+    let mut printer = WitPrinter::default();
+    printer.print(&resolve, pkg, &[])?;
+    let printed = printer.output.to_string();
+    assert!(printed.contains("world just-uses {"), "{printed}");
+
+    let mut reparsed = Resolve::default();
+    reparsed.push_str("dep.wit", dep)?;
+    let pkg = reparsed.push_str("test.wit", &printed)?;
+    let world = &reparsed.worlds[reparsed.packages[pkg].worlds["just-uses"]];
+    let imports = world
+        .imports
+        .keys()
+        .map(|key| reparsed.name_world_key(key))
+        .collect::<Vec<_>>();
+    assert_eq!(imports, ["foo:dep/types", "foo:bar/local", "foo:bar/uses"]);
+    assert!(world.exports.is_empty());
+
+    let mut printer = WitPrinter::default();
+    printer.print(&reparsed, pkg, &[])?;
+    assert_eq!(printer.output.to_string(), printed);
+    Ok(())
+}
+
+/// Ensure that build metadata in a package's version is preserved through
+/// printing and the binary encoding, while still being ignored for
+/// compatibility.
+#[test]
+fn version_build_metadata_roundtrip() -> Result<()> {
+    drop(env_logger::try_init());
+
+    const WIT: &str = "package foo:bar@1.2.3+build.7;
+
+interface i {
+  f: func();
+}
+
+world w {
+  import i;
+}
+";
+
+    let mut resolve = Resolve::default();
+    let pkg = resolve.push_str("test.wit", WIT)?;
+    let name = &resolve.packages[pkg].name;
+    let version = name.version.as_ref().unwrap();
+    assert_eq!(version.build.as_str(), "build.7");
+    assert_eq!(name.to_string(), "foo:bar@1.2.3+build.7");
+    assert_eq!(
+        wit_parser::PackageName::version_compat_track_string(version),
+        "1"
+    );
+
+    let mut printer = WitPrinter::default();
+    printer.print(&resolve, pkg, &[])?;
+    assert_eq!(printer.output.to_string(), WIT);
+
+    let wasm = wit_component::encode(&resolve, pkg)?;
+    let decoded = wit_component::decode(&wasm)?;
+    let mut printer = WitPrinter::default();
+    printer.print(decoded.resolve(), decoded.package(), &[])?;
+    assert_eq!(printer.output.to_string(), WIT);
+    Ok(())
+}
+
+/// Ensure that the artifacts of `export_package` agree with each other
+#[test]
+fn export_package_artifacts() -> Result<()> {
+    drop(env_logger::try_init());
+
+    let mut resolve = Resolve::default();
+    resolve.push_str(
+        "unrelated.wit",
+        r#"
+            package foo:unrelated;
+
+            interface i {}
+        "#,
+    )?;
+    let pkg = resolve.push_str(
+        "test.wit",
+        r#"
+            package foo:bar;
+
+            /// Greetings.
+            interface greet {
+                use foo:types/names.{name};
+
+                hello: func(who: name) -> string;
+            }
+
+            package foo:types {
+                interface names {
+                    type name = string;
+                }
+            }
+        "#,
+    )?;
+    let artifacts = wit_component::export_package(&resolve, pkg)?;
+
+    let mut reparsed = Resolve::default();
+    let reparsed_pkg = reparsed.push_str("reparsed.wit", &artifacts.wit_text)?;
+    assert_eq!(reparsed.packages[reparsed_pkg].name.to_string(), "foo:bar");
+    assert_eq!(reparsed.packages.len(), 2);
+
+    let (decoded, decoded_pkg) = match wit_component::decode(&artifacts.binary)? {
+        DecodedWasm::WitPackage(resolve, pkg) => (resolve, pkg),
+        DecodedWasm::Component(..) => unreachable!(),
+    };
+    let redone = wit_component::export_package(&decoded, decoded_pkg)?;
+    assert_eq!(redone.wit_text, artifacts.wit_text);
+    assert_eq!(redone.binary, artifacts.binary);
+
+    let packages = artifacts.json["packages"].as_array().unwrap();
+    let mut names = packages
+        .iter()
+        .map(|p| p["name"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, ["foo:bar", "foo:types"]);
+    Ok(())
+}
+
+/// Ensure that versions are only elided when they're unambiguous
+#[test]
+fn elide_unambiguous_versions() -> Result<()> {
+    drop(env_logger::try_init());
+
+    let deps = r#"
+        package foo:dep@0.2.0;
+
+        interface types {
+            type t = u32;
+        }
+    "#;
+    let main = r#"
+        package foo:bar;
+
+        interface i {
+            use foo:dep/types@0.2.0.{t};
+            f: func() -> t;
+        }
+
+        world w {
+            import foo:dep/types@0.2.0;
+        }
+    "#;
+
+    let print = |resolve: &Resolve, pkg| -> Result<String> {
+        let mut printer = WitPrinter::default();
+        printer.elide_unambiguous_versions(true);
+        printer.print(resolve, pkg, &[])?;
+        Ok(printer.output.to_string())
+    };
+
+    let mut resolve = Resolve::default();
+    resolve.push_str("deps.wit", deps)?;
+    let pkg = resolve.push_str("main.wit", main)?;
+    let printed = print(&resolve, pkg)?;
+    assert!(printed.contains("use foo:dep/types.{t};"), "{printed}");
+    assert!(printed.contains("import foo:dep/types;"), "{printed}");
+
+    let mut reparsed = Resolve::default();
+    reparsed.resolve_unversioned_deps = true;
+    reparsed.push_str("deps.wit", deps)?;
+    let reparsed_pkg = reparsed.push_str("main.wit", &printed)?;
+    assert_eq!(print(&reparsed, reparsed_pkg)?, printed);
+
+    resolve.push_str(
+        "other.wit",
+        r#"
+            package foo:dep@0.3.0;
+
+            interface types {
+                type t = u32;
+            }
+        "#,
+    )?;
+    let printed = print(&resolve, pkg)?;
+    assert!(
+        printed.contains("use foo:dep/types@0.2.0.{t};"),
+        "{printed}"
+    );
+    assert!(printed.contains("import foo:dep/types@0.2.0;"), "{printed}");
+    Ok(())
+}
+
+/// Ensure docs lost when encoding bindings metadata can be restored from the
+/// original WIT.
+#[test]
+fn import_docs_after_decode() -> Result<()> {
+    drop(env_logger::try_init());
+
+    let mut resolve = Resolve::default();
+    let pkg = resolve.push_str(
+        "test.wit",
+        r#"
+            /// The package.
+            package foo:bar;
+
+            /// An interface.
+            interface i {
+                /// A record.
+                record r {
+                    /// A field.
+                    x: u32,
+                    y: u32,
+                }
+                /// A function.
+                f: func(r: r);
+                g: func();
+            }
+
+            /// A world.
+            world w {
+                import i;
+                /// Runs things.
+                export run: func();
+            }
+        "#,
+    )?;
+    let world = resolve.select_world(pkg, None)?;
+    let mut module = wat::parse_str("(module)")?;
+    wit_component::embed_component_metadata(
+        &mut module,
+        &resolve,
+        world,
+        wit_component::StringEncoding::UTF8,
+    )?;
+
+    let mut decoded = wit_component::metadata::decode(&module)?.1.resolve;
+    let i = decoded
+        .interfaces
+        .iter()
+        .find(|(_, iface)| iface.name.as_deref() == Some("i"))
+        .unwrap()
+        .0;
+    assert!(decoded.interfaces[i].docs.contents.is_none());
+
+    assert_eq!(decoded.import_docs_from(&resolve), 7);
+    let iface = &decoded.interfaces[i];
+    assert_eq!(iface.docs.contents.as_deref(), Some("An interface."));
+    assert_eq!(
+        iface.functions["f"].docs.contents.as_deref(),
+        Some("A function.")
+    );
+    assert!(iface.functions["g"].docs.contents.is_none());
+    let wit_parser::TypeDefKind::Record(r) = &decoded.types[iface.types["r"]].kind else {
+        panic!("expected a record");
+    };
+    assert_eq!(r.fields[0].docs.contents.as_deref(), Some("A field."));
+    Ok(())
+}
+
+/// Ensure a header banner is printed before the package and is ignored when
+/// parsed again.
+#[test]
+fn print_header_banner() -> Result<()> {
+    drop(env_logger::try_init());
+
+    let mut resolve = Resolve::default();
+    let pkg = resolve.push_str(
+        "test.wit",
+        r#"
+            /// Package docs.
+            package foo:bar;
+
+            /// An interface.
+            interface i {
+                f: func();
+            }
+        "#,
+    )?;
+
+    let print = |resolve: &Resolve, pkg| -> Result<String> {
+        let mut printer = WitPrinter::default();
+        printer.set_header("DO NOT EDIT\n\nGenerated by a tool. /* not a block */");
+        printer.print(resolve, pkg, &[])?;
+        Ok(printer.output.to_string())
+    };
+    let printed = print(&resolve, pkg)?;
+    assert!(
+        printed.starts_with(
+            "\
+// DO NOT EDIT
+//
+// Generated by a tool. /* not a block */
+
+/// Package docs.
+package foo:bar;
+"
+        ),
+        "{printed}"
+    );
+
+    let mut reparsed = Resolve::default();
+    let reparsed_pkg = reparsed.push_str("test.wit", &printed)?;
+    let i = reparsed.packages[reparsed_pkg].interfaces["i"];
+    assert_eq!(
+        reparsed.interfaces[i].docs.contents.as_deref(),
+        Some("An interface.")
+    );
+
+    // The banner is read back as package docs, but printing the package again
+    // doesn't repeat it, so the package docs don't change on later round
+    // trips.
+    assert_eq!(print(&reparsed, reparsed_pkg)?, printed);
+    let mut again = Resolve::default();
+    let again_pkg = again.push_str("test.wit", &printed)?;
+    assert_eq!(
+        again.packages[again_pkg].docs,
+        reparsed.packages[reparsed_pkg].docs
+    );
+
+    // The same goes for docs without their source text, such as those
+    // decoded from a binary.
+    reparsed.packages[reparsed_pkg].leading_comments = None;
+    let printed = print(&reparsed, reparsed_pkg)?;
+    assert_eq!(printed.matches("DO NOT EDIT").count(), 1, "{printed}");
+    assert!(
+        printed.contains("\n/// Package docs.\npackage"),
+        "{printed}"
+    );
+    Ok(())
+}
+
+/// Ensure a single interface or world can be printed on its own and parsed
+/// again once wrapped by the caller.
+#[test]
+fn print_single_interface_and_world() -> Result<()> {
+    drop(env_logger::try_init());
+
+    let dep = r#"
+        package foo:dep;
+
+        interface types {
+            record r {
+                x: u32,
+            }
+        }
+    "#;
+    let mut resolve = Resolve::default();
+    resolve.push_str("dep.wit", dep)?;
+    let pkg = resolve.push_str(
+        "test.wit",
+        r#"
+            package foo:bar;
+
+            interface local {
+                type l = u32;
+            }
+
+            interface i {
+                use local.{l};
+                use foo:dep/types.{r};
+
+                f: func(x: r) -> l;
+            }
+
+            world w {
+                import i;
+                export run: func();
+            }
+        "#,
+    )?;
+    let i = resolve.packages[pkg].interfaces["i"];
+    let w = resolve.packages[pkg].worlds["w"];
+
+    let mut printer = WitPrinter::default();
+    printer.print_interface(&resolve, i)?;
+    let interface = printer.output.to_string();
+    assert_eq!(
+        interface,
+        "use local.{l};\nuse foo:dep/types.{r};\n\nf: func(x: r) -> l;\n"
+    );
+
+    let mut printer = WitPrinter::default();
+    printer.print_world(&resolve, w)?;
+    let world = printer.output.to_string();
+    assert!(!world.contains("package"), "{world}");
+
+    let mut reparsed = Resolve::default();
+    reparsed.push_str("dep.wit", dep)?;
+    let pkg = reparsed.push_str(
+        "test.wit",
+        &format!(
+            "package foo:bar;\n\
+             interface local {{ type l = u32; }}\n\
+             interface i {{\n{interface}}}\n\
+             world w {{\n{world}}}\n"
+        ),
+    )?;
+    let i = reparsed.packages[pkg].interfaces["i"];
+    assert!(reparsed.interfaces[i].functions.contains_key("f"));
+    let w = reparsed.packages[pkg].worlds["w"];
+    assert_eq!(reparsed.worlds[w].exports.len(), 1);
+
+    let mut orphan = reparsed.worlds[w].clone();
+    orphan.package = None;
+    let orphan = reparsed.worlds.alloc(orphan);
+    let err = WitPrinter::default()
+        .print_world(&reparsed, orphan)
+        .unwrap_err();
+    assert_eq!(err.to_string(), "world `w` is not part of a package");
+    Ok(())
+}
+
+/// Ensure that the producers section of an encoded package can be read back
+#[test]
+fn read_producers() -> Result<()> {
+    drop(env_logger::try_init());
+
+    let mut resolve = Resolve::default();
+    let pkg = resolve.push_str("test.wit", "package foo:bar; interface i {}")?;
+    let bytes = wit_component::encode(&resolve, pkg)?;
+
+    let producers = wit_component::producers(&bytes)?.unwrap();
+    let processed_by = producers.get("processed-by").unwrap();
+    assert!(processed_by.get("wit-component").is_some());
+
+    let mut extra = wasm_metadata::Producers::empty();
+    extra.add("processed-by", "wit-bindgen", "0.1.0");
+    let bytes = extra.add_to_wasm(&bytes)?;
+    let producers = wit_component::producers(&bytes)?.unwrap();
+    let processed_by = producers.get("processed-by").unwrap();
+    assert_eq!(processed_by.get("wit-bindgen").unwrap(), "0.1.0");
+    assert!(processed_by.get("wit-component").is_some());
+
+    assert!(wit_component::producers(&wat::parse_str("(module)")?)?.is_none());
+    Ok(())
+}
+
+/// Ensure that `decode_with_progress` reports progress and matches `decode`
+#[test]
+fn decode_with_progress() -> Result<()> {
+    drop(env_logger::try_init());
+
+    let mut resolve = Resolve::default();
+    let pkg = resolve.push_str(
+        "test.wit",
+        r#"
+            package foo:bar;
+
+            interface i {
+                record r { a: u32, b: string }
+                f: func(x: r) -> list<r>;
+            }
+
+            world w {
+                import i;
+                export run: func();
+            }
+        "#,
+    )?;
+    let bytes = wit_component::encode(&resolve, pkg)?;
+
+    let mut reports = Vec::new();
+    let decoded = wit_component::decode_with_progress(&bytes, |p| reports.push(p))?;
+    assert!(!reports.is_empty());
+    assert!(
+        reports
+            .windows(2)
+            .all(|w| w[0].bytes_read <= w[1].bytes_read)
+    );
+    assert!(reports.iter().any(|p| p.stage == "export section"));
+    let last = reports.last().unwrap();
+    assert_eq!(last.stage, "building resolve");
+    assert_eq!(
+        (last.bytes_read, last.total_bytes),
+        (bytes.len(), bytes.len())
+    );
+    assert_eq!(last.percent(), 100.0);
+
+    let expected = wit_component::decode(&bytes)?;
+    let print = |decoded: &DecodedWasm| -> Result<String> {
+        let mut printer = WitPrinter::default();
+        printer.print(decoded.resolve(), decoded.package(), &[])?;
+        Ok(printer.output.to_string())
+    };
+    assert_eq!(print(&decoded)?, print(&expected)?);
+    Ok(())
+}
+
+/// Fixed-size lists, including nested ones, print as `list<T, N>` and survive
+/// encoding, printing and re-parsing.
+#[test]
+fn fixed_size_lists_roundtrip_through_printing() -> Result<()> {
+    drop(env_logger::try_init());
+
+    let mut resolve = Resolve::default();
+    let pkg = resolve.push_str(
+        "test.wit",
+        r#"
+            package foo:bar;
+
+            interface i {
+                type digest = list<u8, 16>;
+                type grid = list<list<u8, 4>, 2>;
+                f: func(a: list<list<digest, 2>, 3>) -> list<list<u8, 2>, 4>;
+            }
+        "#,
+    )?;
+    let wasm = wit_component::encode(&resolve, pkg)?;
+
+    let decoded = wit_component::decode(&wasm)?;
+    let mut printer = WitPrinter::default();
+    printer.print(decoded.resolve(), decoded.package(), &[])?;
+    let output = printer.output.to_string();
+    assert!(output.contains("type digest = list<u8, 16>;"), "{output}");
+    assert!(
+        output.contains("type grid = list<list<u8, 4>, 2>;"),
+        "{output}"
+    );
+    assert!(
+        output.contains("f: func(a: list<list<digest, 2>, 3>) -> list<list<u8, 2>, 4>;"),
+        "{output}"
+    );
+
+    let mut new_resolve = Resolve::default();
+    let new_pkg = new_resolve.push_str("printed.wit", &output)?;
+    let wasm2 = wit_component::encode(&new_resolve, new_pkg)?;
+    assert_eq!(wasm, wasm2, "failed to roundtrip through text printing");
+    Ok(())
+}
@@ -1,9 +1,10 @@
 use crate::{
-    Enum, Flags, Ident, Interface, InterfaceItem, Package, PackageName, Params, Record, Resource,
-    ResourceFunc, Result_, StandaloneFunc, Tuple, Type, TypeDef, TypeDefKind, Variant, World,
-    WorldItem,
+    Docs, Enum, Flags, Ident, Interface, InterfaceItem, Package, PackageName, Params, Record,
+    Resource, ResourceFunc, Result_, StandaloneFunc, Tuple, Type, TypeDef, TypeDefKind, Variant,
+    World, WorldItem,
 };
 use id_arena::Id;
+use std::fmt;
 use wit_parser::PackageId;
 
 pub fn packages_from_parsed(resolve: &wit_parser::Resolve) -> Vec<Package> {
@@ -11,6 +12,53 @@ pub fn packages_from_parsed(resolve: &wit_parser::Resolve) -> Vec<Package> {
     converter.convert()
 }
 
+/// Collects the functions which a component targeting `world` must implement,
+/// grouped by the interface that they're exported through.
+///
+/// This is intended for scaffolding tools which generate stub implementations
+/// of a world's exports. Signatures and docs are preserved, and each exported
+/// interface gets one entry in the returned list. Functions exported directly
+/// from the world are grouped into a final entry with no interface.
+///
+/// Returns an error if `world` isn't part of a package.
+pub fn export_stubs_from_parsed(
+    resolve: &wit_parser::Resolve,
+    world: wit_parser::WorldId,
+) -> Result<Vec<ExportStubs>, WorldNotInPackage> {
+    let converter = Converter::new(resolve);
+    converter.export_stubs(world)
+}
+
+/// The error returned by [`export_stubs_from_parsed`] for a world which isn't
+/// part of a package.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorldNotInPackage {
+    /// The name of the world.
+    pub world: String,
+}
+
+impl fmt::Display for WorldNotInPackage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "world `{}` is not part of a package", self.world)
+    }
+}
+
+impl std::error::Error for WorldNotInPackage {}
+
+/// The functions to implement for one export of a world, as returned by
+/// [`export_stubs_from_parsed`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportStubs {
+    /// The name of the exported interface, or `None` for functions exported
+    /// directly from the world.
+    pub interface: Option<Ident>,
+    /// Freestanding functions, in the order they're defined.
+    pub functions: Vec<StandaloneFunc>,
+    /// Resources defined in the interface along with their constructors,
+    /// methods, and static functions.
+    pub resources: Vec<(Ident, Resource)>,
+}
+
 struct Converter<'a> {
     resolve: &'a wit_parser::Resolve,
 }
@@ -46,6 +94,77 @@ impl<'a> Converter<'a> {
         output
     }
 
+    fn export_stubs(
+        &self,
+        world_id: wit_parser::WorldId,
+    ) -> Result<Vec<ExportStubs>, WorldNotInPackage> {
+        let world = &self.resolve.worlds[world_id];
+        let package_id = world.package.ok_or_else(|| WorldNotInPackage {
+            world: world.name.clone(),
+        })?;
+        let mut output = Vec::new();
+        let mut world_functions = Vec::new();
+
+        for (key, item) in &world.exports {
+            match item {
+                wit_parser::WorldItem::Interface { id, .. } => {
+                    let interface = &self.resolve.interfaces[*id];
+                    let owner = wit_parser::TypeOwner::Interface(*id);
+                    let mut stubs = ExportStubs {
+                        interface: Some(self.interface_ident(package_id, Some(key), interface)),
+                        functions: Vec::new(),
+                        resources: Vec::new(),
+                    };
+                    for func in interface.functions.values() {
+                        if let Some(mut output) = self.standalone_func_convert(func) {
+                            output.set_docs(self.convert_docs(&func.docs));
+                            stubs.functions.push(output);
+                        }
+                    }
+                    for (name, type_id) in &interface.types {
+                        let type_def = &self.resolve.types[*type_id];
+                        if type_def.owner != owner
+                            || !matches!(type_def.kind, wit_parser::TypeDefKind::Resource)
+                        {
+                            continue;
+                        }
+                        let mut resource = Resource::empty();
+                        for func in interface.functions.values() {
+                            if let Some(mut method) =
+                                self.convert_resource_func(*type_id, name, func)
+                            {
+                                method.set_docs(self.convert_docs(&func.docs));
+                                resource.func(method);
+                            }
+                        }
+                        stubs.resources.push((Ident::new(name.clone()), resource));
+                    }
+                    output.push(stubs);
+                }
+                wit_parser::WorldItem::Function(func) => {
+                    if let Some(mut output) = self.standalone_func_convert(func) {
+                        output.set_docs(self.convert_docs(&func.docs));
+                        world_functions.push(output);
+                    }
+                }
+                wit_parser::WorldItem::Type(_) => {}
+            }
+        }
+
+        if !world_functions.is_empty() {
+            output.push(ExportStubs {
+                interface: None,
+                functions: world_functions,
+                resources: Vec::new(),
+            });
+        }
+        Ok(output)
+    }
+
+    fn convert_docs(&self, docs: &wit_parser::Docs) -> Option<Docs> {
+        docs.contents.as_ref().map(Docs::new)
+    }
+
     fn convert_package_name(&self, package: &wit_parser::PackageName) -> PackageName {
         PackageName::new(
             package.namespace.clone(),
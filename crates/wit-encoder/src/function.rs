@@ -57,6 +57,20 @@ impl Params {
         self.items.push((name.into(), item.into()));
     }
 
+    /// Returns an iterator over the parameters, in the order they're
+    /// declared.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = (&Ident, &Type)> + '_ {
+        self.items.iter().map(|(name, ty)| (name, ty))
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
     pub fn items(&self) -> &Vec<(Ident, Type)> {
         &self.items
     }
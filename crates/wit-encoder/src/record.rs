@@ -8,12 +8,23 @@ pub struct Record {
 }
 
 impl Record {
+    pub fn empty() -> Self {
+        Self { fields: vec![] }
+    }
+
     pub fn new(fields: impl IntoIterator<Item = impl Into<Field>>) -> Self {
         Self {
             fields: fields.into_iter().map(|f| f.into()).collect(),
         }
     }
 
+    /// Appends a field to this record, returning it so that its docs can be
+    /// set.
+    pub fn field(&mut self, name: impl Into<Ident>, ty: Type) -> &mut Field {
+        self.fields.push(Field::new(name, ty));
+        self.fields.last_mut().unwrap()
+    }
+
     pub fn fields(&self) -> &[Field] {
         &self.fields
     }
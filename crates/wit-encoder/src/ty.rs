@@ -215,6 +215,17 @@ where
     }
 }
 
+impl<N> Into<VariantCase> for (N, Option<Type>)
+where
+    N: Into<Ident>,
+{
+    fn into(self) -> VariantCase {
+        let mut case = VariantCase::empty(self.0);
+        case.type_ = self.1;
+        case
+    }
+}
+
 impl<N, D> Into<VariantCase> for (N, Type, D)
 where
     N: Into<Ident>,
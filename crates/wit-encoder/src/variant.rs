@@ -16,6 +16,13 @@ impl Variant {
         self.cases.push(case.into());
     }
 
+    /// Appends a case to this variant like [`Variant::case`], returning it so
+    /// that its docs can be set.
+    pub fn case_mut(&mut self, case: impl Into<VariantCase>) -> &mut VariantCase {
+        self.cases.push(case.into());
+        self.cases.last_mut().unwrap()
+    }
+
     pub fn cases(&self) -> &[VariantCase] {
         &self.cases
     }
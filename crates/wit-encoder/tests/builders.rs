@@ -0,0 +1,54 @@
+use pretty_assertions::assert_eq;
+use wit_encoder::{Interface, Package, PackageName, Record, Type, TypeDef, TypeDefKind, Variant};
+
+const PACKAGE: &str = indoc::indoc! {"
+    package foo:builders;
+
+    interface builders {
+      record point {
+        /// horizontal position
+        x: u32,
+        /// vertical position
+        y: u32,
+        label: option<string>,
+      }
+      variant shape {
+        /// nothing at all
+        empty,
+        circle(u32),
+        polygon(list<point>),
+      }
+    }
+"};
+
+#[test]
+fn record_and_variant_builders() {
+    let name = PackageName::new("foo", "builders", None);
+    let mut package = Package::new(name);
+
+    package.interface({
+        let mut interface = Interface::new("builders");
+
+        let mut point = Record::empty();
+        point
+            .field("x", Type::U32)
+            .set_docs(Some("horizontal position"));
+        point
+            .field("y", Type::U32)
+            .set_docs(Some("vertical position"));
+        point.field("label", Type::option(Type::String));
+        interface.type_def(TypeDef::new("point", TypeDefKind::Record(point)));
+
+        let mut shape = Variant::empty();
+        shape
+            .case_mut(("empty", None))
+            .set_docs(Some("nothing at all"));
+        shape.case(("circle", Some(Type::U32)));
+        shape.case(("polygon", Some(Type::list(Type::named("point")))));
+        interface.type_def(TypeDef::new("shape", TypeDefKind::Variant(shape)));
+
+        interface
+    });
+
+    assert_eq!(package.to_string(), PACKAGE);
+}
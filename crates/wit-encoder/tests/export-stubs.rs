@@ -0,0 +1,93 @@
+use pretty_assertions::assert_eq;
+use wit_encoder::{
+    ExportStubs, Ident, Params, Resource, ResourceFunc, StandaloneFunc, Type, WorldNotInPackage,
+    export_stubs_from_parsed,
+};
+
+const WIT: &str = indoc::indoc! {"
+    package foo:stubs;
+
+    interface imported {
+      ignored: func();
+    }
+
+    interface handler {
+      resource request {
+        /// Creates a request.
+        constructor(body: string);
+        body: func() -> string;
+      }
+
+      /// Handles a request.
+      handle: func(r: request) -> u32;
+    }
+
+    world service {
+      import imported;
+      export handler;
+      export run: func();
+    }
+"};
+
+#[test]
+fn export_stubs() {
+    let mut resolve = wit_parser::Resolve::new();
+    let pkg = resolve.push_str("test.wit", WIT).unwrap();
+    let world = resolve.select_world(pkg, None).unwrap();
+
+    let stubs = export_stubs_from_parsed(&resolve, world).unwrap();
+
+    let mut handle = StandaloneFunc::new("handle", false);
+    handle.set_params(("r", Type::named("request")));
+    handle.set_result(Some(Type::U32));
+    handle.set_docs(Some("Handles a request."));
+
+    let mut request = Resource::empty();
+    request.func({
+        let mut func = ResourceFunc::constructor();
+        func.set_params(("body", Type::String));
+        func.set_docs(Some("Creates a request."));
+        func
+    });
+    request.func({
+        let mut func = ResourceFunc::method("body", false);
+        func.set_params(Params::empty());
+        func.set_result(Some(Type::String));
+        func
+    });
+
+    assert_eq!(
+        stubs,
+        [
+            ExportStubs {
+                interface: Some(Ident::new("handler")),
+                functions: vec![handle],
+                resources: vec![(Ident::new("request"), request)],
+            },
+            ExportStubs {
+                interface: None,
+                functions: vec![StandaloneFunc::new("run", false)],
+                resources: Vec::new(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn export_stubs_world_not_in_package() {
+    let mut resolve = wit_parser::Resolve::new();
+    let pkg = resolve.push_str("test.wit", WIT).unwrap();
+    let world = resolve.select_world(pkg, None).unwrap();
+    let mut orphan = resolve.worlds[world].clone();
+    orphan.package = None;
+    let orphan = resolve.worlds.alloc(orphan);
+
+    let err = export_stubs_from_parsed(&resolve, orphan).unwrap_err();
+    assert_eq!(
+        err,
+        WorldNotInPackage {
+            world: "service".to_string()
+        }
+    );
+    assert_eq!(err.to_string(), "world `service` is not part of a package");
+}
@@ -72,3 +72,27 @@ fn concrete_types() {
 
     assert_eq!(package.to_string(), PACKAGE);
 }
+
+#[test]
+fn params_helpers() {
+    let mut params = Params::empty();
+    assert!(params.is_empty());
+    params.push("b", Type::U32);
+    params.push("a", Type::String);
+    params.item("c", Type::option(Type::U8));
+    assert_eq!(params.len(), 3);
+    assert!(!params.is_empty());
+
+    let names = params
+        .iter()
+        .map(|(name, ty)| format!("{name}: {ty}"))
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["b: u32", "a: string", "c: option<u8>"]);
+
+    let collected = params
+        .iter()
+        .map(|(name, ty)| (name.clone(), ty.clone()))
+        .collect::<Params>();
+    assert_eq!(collected, params);
+    assert_eq!(collected.to_string(), "b: u32, a: string, c: option<u8>");
+}
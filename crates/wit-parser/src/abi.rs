@@ -110,6 +110,18 @@ impl From<Int> for WasmType {
     }
 }
 
+#[cfg(feature = "wasm-encoder")]
+impl From<WasmType> for wasm_encoder::ValType {
+    fn from(ty: WasmType) -> wasm_encoder::ValType {
+        match ty {
+            WasmType::I32 | WasmType::Pointer | WasmType::Length => wasm_encoder::ValType::I32,
+            WasmType::I64 | WasmType::PointerOrI64 => wasm_encoder::ValType::I64,
+            WasmType::F32 => wasm_encoder::ValType::F32,
+            WasmType::F64 => wasm_encoder::ValType::F64,
+        }
+    }
+}
+
 /// We use a different ABI for wasm importing functions exported by the host
 /// than for wasm exporting functions imported by the host.
 ///
@@ -166,10 +178,34 @@ impl<'a> FlatTypes<'a> {
     }
 }
 
+/// The maximum number of flat core wasm parameters a function may have before
+/// its parameters are passed indirectly through memory.
+pub const MAX_FLAT_PARAMS: usize = 16;
+
+/// The maximum number of flat core wasm results a function may have before
+/// its results are returned indirectly through memory.
+pub const MAX_FLAT_RESULTS: usize = 1;
+
 impl Resolve {
-    const MAX_FLAT_PARAMS: usize = 16;
     const MAX_FLAT_ASYNC_PARAMS: usize = 4;
-    const MAX_FLAT_RESULTS: usize = 1;
+
+    /// Same as [`Resolve::wasm_signature`] but returns the parameters and
+    /// results as [`wasm_encoder::ValType`]s, ready to be used in a core wasm
+    /// function type.
+    ///
+    /// Pointers and lengths are lowered as 32-bit values.
+    #[cfg(feature = "wasm-encoder")]
+    pub fn flatten_function_valtypes(
+        &self,
+        func: &Function,
+        variant: AbiVariant,
+    ) -> (Vec<wasm_encoder::ValType>, Vec<wasm_encoder::ValType>) {
+        let sig = self.wasm_signature(variant, func);
+        (
+            sig.params.into_iter().map(Into::into).collect(),
+            sig.results.into_iter().map(Into::into).collect(),
+        )
+    }
 
     /// Get the WebAssembly type signature for this interface function
     ///
@@ -178,8 +214,69 @@ impl Resolve {
     pub fn wasm_signature(&self, variant: AbiVariant, func: &Function) -> WasmSignature {
         // Note that one extra parameter is allocated in case a return pointer
         // is needed down below for imports.
-        let mut storage = [WasmType::I32; Self::MAX_FLAT_PARAMS + 1];
-        let mut params = FlatTypes::new(&mut storage);
+        let mut params = [WasmType::I32; MAX_FLAT_PARAMS + 1];
+        let mut results = [WasmType::I32; MAX_FLAT_RESULTS];
+        self.wasm_signature_in(
+            variant,
+            func,
+            (MAX_FLAT_PARAMS, MAX_FLAT_RESULTS),
+            &mut params,
+            &mut results,
+        )
+    }
+
+    /// Returns the number of core wasm parameters `func` is lowered to for
+    /// `variant`.
+    ///
+    /// This counts the `self` handle of methods, which is an explicit
+    /// parameter after flattening, as well as the return pointer that
+    /// imports receive when their results don't fit in
+    /// [`MAX_FLAT_RESULTS`]. Functions whose parameters are passed
+    /// indirectly count as a single pointer parameter.
+    pub fn flattened_param_count(&self, func: &Function, variant: AbiVariant) -> usize {
+        self.wasm_signature(variant, func).params.len()
+    }
+
+    /// Same as [`Resolve::wasm_signature`] but with the limits on the number
+    /// of flat parameters and results overridden.
+    ///
+    /// The canonical ABI always uses [`MAX_FLAT_PARAMS`] and
+    /// [`MAX_FLAT_RESULTS`], so any other limits produce signatures which are
+    /// not standard and won't validate against components. This is only
+    /// intended for modeling alternative ABIs. The limit on parameters of
+    /// async imports is not affected.
+    pub fn flatten_function_with_limits(
+        &self,
+        variant: AbiVariant,
+        func: &Function,
+        max_flat_params: usize,
+        max_flat_results: usize,
+    ) -> WasmSignature {
+        let mut params = vec![WasmType::I32; max_flat_params.max(Self::MAX_FLAT_ASYNC_PARAMS) + 1];
+        // Note that at least one result is allocated for the return pointer
+        // or status code, even if no results may be returned flat.
+        let mut results = vec![WasmType::I32; max_flat_results.max(1)];
+        self.wasm_signature_in(
+            variant,
+            func,
+            (max_flat_params, max_flat_results),
+            &mut params,
+            &mut results,
+        )
+    }
+
+    /// Flattens the signature of `func` into `param_storage` and
+    /// `result_storage`, which must be large enough to hold the flat
+    /// parameters plus a return pointer and at least one result.
+    fn wasm_signature_in(
+        &self,
+        variant: AbiVariant,
+        func: &Function,
+        (max_flat_params, max_flat_results): (usize, usize),
+        param_storage: &mut [WasmType],
+        result_storage: &mut [WasmType],
+    ) -> WasmSignature {
+        let mut params = FlatTypes::new(param_storage);
         let ok = self.push_flat_list(func.params.iter().map(|(_, param)| param), &mut params);
         assert_eq!(ok, !params.overflow);
 
@@ -187,7 +284,7 @@ impl Resolve {
             AbiVariant::GuestImport
             | AbiVariant::GuestExport
             | AbiVariant::GuestExportAsync
-            | AbiVariant::GuestExportAsyncStackful => Self::MAX_FLAT_PARAMS,
+            | AbiVariant::GuestExportAsyncStackful => max_flat_params,
             AbiVariant::GuestImportAsync => Self::MAX_FLAT_ASYNC_PARAMS,
         };
 
@@ -216,15 +313,14 @@ impl Resolve {
             }
         }
 
-        let mut storage = [WasmType::I32; Self::MAX_FLAT_RESULTS];
-        let mut results = FlatTypes::new(&mut storage);
+        let mut results = FlatTypes::new(result_storage);
         let mut retptr = false;
         match variant {
             AbiVariant::GuestImport | AbiVariant::GuestExport => {
                 if let Some(ty) = &func.result {
                     self.push_flat(ty, &mut results);
                 }
-                retptr = results.overflow;
+                retptr = results.overflow || results.cur > max_flat_results;
 
                 // Rust/C don't support multi-value well right now, so if a
                 // function would have multiple results then instead truncate
@@ -393,3 +489,123 @@ impl Resolve {
         true
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{AbiVariant, MAX_FLAT_PARAMS, MAX_FLAT_RESULTS, WasmType};
+    use crate::Resolve;
+
+    #[test]
+    #[cfg(feature = "wasm-encoder")]
+    fn flatten_function_valtypes() {
+        use wasm_encoder::ValType;
+
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                    package foo:bar;
+
+                    interface foo {
+                        f: func(a: u64, b: f32) -> tuple<u32, string>;
+                    }
+                "#,
+            )
+            .unwrap();
+        let (_, interface) = resolve.interfaces.iter().next().unwrap();
+        let func = &interface.functions["f"];
+
+        let (params, results) = resolve.flatten_function_valtypes(func, AbiVariant::GuestImport);
+        assert_eq!(params, [ValType::I64, ValType::F32, ValType::I32]);
+        assert!(results.is_empty());
+
+        let (params, results) = resolve.flatten_function_valtypes(func, AbiVariant::GuestExport);
+        assert_eq!(params, [ValType::I64, ValType::F32]);
+        assert_eq!(results, [ValType::I32]);
+    }
+
+    #[test]
+    fn flatten_function_with_limits() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                    package foo:bar;
+
+                    interface foo {
+                        f: func(
+                            a: u32, b: u32, c: u32, d: u32, e: u32, f: u32, g: u32, h: u32,
+                            i: u32, j: u32, k: u32, l: u32, m: u32, n: u32, o: u32, p: u32,
+                            q: u32,
+                        ) -> tuple<u32, u32>;
+                    }
+                "#,
+            )
+            .unwrap();
+        let (_, interface) = resolve.interfaces.iter().next().unwrap();
+        let func = &interface.functions["f"];
+
+        let sig = resolve.flatten_function_with_limits(
+            AbiVariant::GuestExport,
+            func,
+            MAX_FLAT_PARAMS,
+            MAX_FLAT_RESULTS,
+        );
+        assert_eq!(sig, resolve.wasm_signature(AbiVariant::GuestExport, func));
+        assert!(sig.indirect_params);
+        assert_eq!(sig.params, [WasmType::Pointer]);
+        assert!(sig.retptr);
+        assert_eq!(sig.results, [WasmType::Pointer]);
+
+        let sig = resolve.flatten_function_with_limits(AbiVariant::GuestExport, func, 17, 2);
+        assert!(!sig.indirect_params);
+        assert_eq!(sig.params, [WasmType::I32; 17]);
+        assert!(!sig.retptr);
+        assert_eq!(sig.results, [WasmType::I32; 2]);
+
+        // With no flat results at all there's still room for the return
+        // pointer of exports and the status code of async functions.
+        let sig = resolve.flatten_function_with_limits(AbiVariant::GuestExport, func, 17, 0);
+        assert!(sig.retptr);
+        assert_eq!(sig.results, [WasmType::Pointer]);
+        let sig = resolve.flatten_function_with_limits(AbiVariant::GuestImport, func, 17, 0);
+        assert!(sig.retptr);
+        assert_eq!(sig.params.last(), Some(&WasmType::Pointer));
+        assert!(sig.results.is_empty());
+        let sig = resolve.flatten_function_with_limits(AbiVariant::GuestExportAsync, func, 17, 0);
+        assert_eq!(sig.results, [WasmType::I32]);
+    }
+
+    #[test]
+    fn flattened_param_count() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                    package foo:bar;
+
+                    interface foo {
+                        resource r {
+                            m: func(a: u32, b: u64, c: f32, d: bool);
+                            n: func(a: u32) -> tuple<u32, u32>;
+                        }
+                    }
+                "#,
+            )
+            .unwrap();
+        let (_, interface) = resolve.interfaces.iter().next().unwrap();
+        let m = &interface.functions["[method]r.m"];
+        let n = &interface.functions["[method]r.n"];
+
+        // The receiver is counted in addition to the four scalars.
+        assert_eq!(resolve.flattened_param_count(m, AbiVariant::GuestImport), 5);
+        assert_eq!(resolve.flattened_param_count(m, AbiVariant::GuestExport), 5);
+
+        // Imports take a return pointer, exports return one instead.
+        assert_eq!(resolve.flattened_param_count(n, AbiVariant::GuestImport), 3);
+        assert_eq!(resolve.flattened_param_count(n, AbiVariant::GuestExport), 2);
+    }
+}
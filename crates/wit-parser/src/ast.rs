@@ -17,6 +17,9 @@ pub use lex::validate_id;
 
 /// Representation of a single WIT `*.wit` file and nested packages.
 struct PackageFile<'a> {
+    /// Source text of the comments documenting `package_id`, such as a
+    /// license header.
+    leading_comments: Option<&'a str>,
     /// Optional `package foo:bar;` header
     package_id: Option<PackageName<'a>>,
     /// Other AST items.
@@ -31,22 +34,27 @@ impl<'a> PackageFile<'a> {
     fn parse(tokens: &mut Tokenizer<'a>) -> Result<Self> {
         let mut package_name_tokens_peek = tokens.clone();
         let docs = parse_docs(&mut package_name_tokens_peek)?;
+        let comments = match docs.docs.is_empty() {
+            true => None,
+            false => Some(tokens.get_span(docs.span)),
+        };
 
         // Parse `package foo:bar;` but throw it out if it's actually
         // `package foo:bar { ... }` since that's an ast item instead.
-        let package_id = if package_name_tokens_peek.eat(Token::Package)? {
+        let (package_id, leading_comments) = if package_name_tokens_peek.eat(Token::Package)? {
             let name = PackageName::parse(&mut package_name_tokens_peek, docs)?;
             if package_name_tokens_peek.eat(Token::Semicolon)? {
                 *tokens = package_name_tokens_peek;
-                Some(name)
+                (Some(name), comments)
             } else {
-                None
+                (None, None)
             }
         } else {
-            None
+            (None, None)
         };
         let decl_list = DeclList::parse_until(tokens, None)?;
         Ok(PackageFile {
+            leading_comments,
             package_id,
             decl_list,
         })
@@ -69,6 +77,7 @@ impl<'a> PackageFile<'a> {
         tokens.expect(Token::LeftBrace)?;
         let decl_list = DeclList::parse_until(tokens, Some(Token::RightBrace))?;
         Ok(PackageFile {
+            leading_comments: None,
             package_id: Some(package_id),
             decl_list,
         })
@@ -1864,7 +1873,9 @@ impl SourceMap {
         return msg;
     }
 
-    pub(crate) fn render_location(&self, span: Span) -> String {
+    /// Renders the start of `span` as `path:line:col`, where `span` is
+    /// relative to the sources in this map.
+    pub fn render_location(&self, span: Span) -> String {
         let src = self.source_for_offset(span.start);
         let start = src.to_relative_offset(span.start);
         let (line, col) = src.linecol(start);
@@ -1890,6 +1901,14 @@ impl SourceMap {
     }
 }
 
+impl fmt::Debug for SourceMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SourceMap")
+            .field("files", &self.source_files().collect::<Vec<_>>())
+            .finish_non_exhaustive()
+    }
+}
+
 impl Source {
     fn to_relative_offset(&self, offset: u32) -> usize {
         usize::try_from(offset - self.offset).unwrap()
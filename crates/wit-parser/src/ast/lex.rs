@@ -461,6 +461,20 @@ fn detect_invalid_input(input: &str) -> Result<()> {
     Ok(())
 }
 
+/// Returns whether `id` lexes as a keyword, in which case it must be written
+/// as `%id` to be used as an identifier.
+pub fn is_keyword(id: &str) -> bool {
+    let mut tokenizer = match Tokenizer::new(id, 0, None) {
+        Ok(tokenizer) => tokenizer,
+        Err(_) => return false,
+    };
+    match tokenizer.next() {
+        Ok(Some((_, Token::Id | Token::ExplicitId))) => false,
+        Ok(Some(_)) => matches!(tokenizer.next(), Ok(None)),
+        _ => false,
+    }
+}
+
 fn is_keylike_start(ch: char) -> bool {
     // Lex any XID start, `_`, or '-'. These aren't all valid identifier chars,
     // but we'll diagnose that after we've lexed the full string.
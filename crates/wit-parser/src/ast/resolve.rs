@@ -13,6 +13,10 @@ pub struct Resolver<'a> {
     /// Package docs.
     package_docs: Docs,
 
+    /// Source text of the package docs, see
+    /// `UnresolvedPackage::leading_comments`.
+    leading_comments: Option<String>,
+
     /// All non-`package` WIT decls are going to be resolved together.
     decl_lists: Vec<ast::DeclList<'a>>,
 
@@ -139,6 +143,7 @@ impl<'a> Resolver<'a> {
                     ))
                 }
                 self.package_docs = docs;
+                self.leading_comments = file.leading_comments.map(|s| s.to_string());
             }
         }
 
@@ -223,6 +228,7 @@ impl<'a> Resolver<'a> {
             package_name_span,
             name,
             docs: mem::take(&mut self.package_docs),
+            leading_comments: self.leading_comments.take(),
             worlds: mem::take(&mut self.worlds),
             types: mem::take(&mut self.types),
             interfaces: mem::take(&mut self.interfaces),
@@ -1327,9 +1333,13 @@ impl<'a> Resolver<'a> {
                     self.required_resource_types.push((cur, name.span));
                     break Ok(id);
                 }
-                _ => bail!(Error::new(
+                ref kind => bail!(Error::new(
                     name.span,
-                    format!("type `{}` used in a handle must be a resource", name.name),
+                    format!(
+                        "type `{}` used in a handle must be a resource, found `{}`",
+                        name.name,
+                        crate::type_def_kind_to_string(&self.types, kind),
+                    ),
                 )),
             }
         }
@@ -46,8 +46,13 @@ enum WitEncodingVersion {
 
 impl ComponentInfo {
     /// Creates a new component info by parsing the given WebAssembly component bytes.
-
-    fn from_reader(mut reader: impl Read) -> Result<Self> {
+    ///
+    /// The `progress` callback is invoked with each payload and the number of
+    /// bytes consumed from `reader` once it's been parsed.
+    fn from_reader(
+        mut reader: impl Read,
+        mut progress: impl FnMut(&Payload<'_>, usize),
+    ) -> Result<Self> {
         let mut validator = Validator::new_with_features(WasmFeatures::all());
         let mut externs = Vec::new();
         let mut depth = 1;
@@ -57,6 +62,7 @@ impl ComponentInfo {
         let mut eof = false;
         let mut stack = Vec::new();
         let mut buffer = Vec::new();
+        let mut offset = 0;
 
         loop {
             let chunk = cur.parse(&buffer, eof)?;
@@ -79,6 +85,8 @@ impl ComponentInfo {
 
                 Chunk::Parsed { consumed, payload } => (payload, consumed),
             };
+            offset += consumed;
+            progress(&payload, offset);
             match validator.payload(&payload)? {
                 ValidPayload::Ok => {}
                 ValidPayload::Parser(_) => depth += 1,
@@ -280,6 +288,7 @@ impl ComponentInfo {
             Package {
                 name,
                 docs: Docs::default(),
+                leading_comments: None,
                 interfaces,
                 worlds,
             }
@@ -321,6 +330,7 @@ impl ComponentInfo {
                 name: "component".to_string(),
             },
             docs: Default::default(),
+            leading_comments: None,
             worlds: [(world_name.to_string(), world)].into_iter().collect(),
             interfaces: Default::default(),
         };
@@ -344,6 +354,27 @@ impl ComponentInfo {
         let (resolve, _) = decoder.finish(package);
         Ok((resolve, world))
     }
+
+    fn decode(&self) -> Result<DecodedWasm> {
+        if let Some(version) = self.is_wit_package() {
+            match version {
+                WitEncodingVersion::V1 => {
+                    log::debug!("decoding a v1 WIT package encoded as wasm");
+                    let (resolve, pkg) = self.decode_wit_v1_package()?;
+                    Ok(DecodedWasm::WitPackage(resolve, pkg))
+                }
+                WitEncodingVersion::V2 => {
+                    log::debug!("decoding a v2 WIT package encoded as wasm");
+                    let (resolve, pkg) = self.decode_wit_v2_package()?;
+                    Ok(DecodedWasm::WitPackage(resolve, pkg))
+                }
+            }
+        } else {
+            log::debug!("inferring the WIT of a concrete component");
+            let (resolve, world) = self.decode_component()?;
+            Ok(DecodedWasm::Component(resolve, world))
+        }
+    }
 }
 
 /// Result of the [`decode`] function.
@@ -378,30 +409,85 @@ impl DecodedWasm {
     }
 }
 
-/// Decode for incremental reading
-pub fn decode_reader(reader: impl Read) -> Result<DecodedWasm> {
-    let info = ComponentInfo::from_reader(reader)?;
-
-    if let Some(version) = info.is_wit_package() {
-        match version {
-            WitEncodingVersion::V1 => {
-                log::debug!("decoding a v1 WIT package encoded as wasm");
-                let (resolve, pkg) = info.decode_wit_v1_package()?;
-                Ok(DecodedWasm::WitPackage(resolve, pkg))
-            }
-            WitEncodingVersion::V2 => {
-                log::debug!("decoding a v2 WIT package encoded as wasm");
-                let (resolve, pkg) = info.decode_wit_v2_package()?;
-                Ok(DecodedWasm::WitPackage(resolve, pkg))
-            }
+/// Progress of decoding a component or WIT package, as reported by
+/// [`decode_with_progress`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeProgress {
+    /// A description of what's being processed, such as `"type section"`.
+    pub stage: &'static str,
+    /// The number of bytes of the input which have been parsed.
+    pub bytes_read: usize,
+    /// The total size of the input in bytes.
+    pub total_bytes: usize,
+}
+
+impl DecodeProgress {
+    /// Returns how much of the input has been parsed, from 0 to 100.
+    pub fn percent(&self) -> f64 {
+        if self.total_bytes == 0 {
+            return 100.0;
         }
-    } else {
-        log::debug!("inferring the WIT of a concrete component");
-        let (resolve, world) = info.decode_component()?;
-        Ok(DecodedWasm::Component(resolve, world))
+        self.bytes_read as f64 * 100.0 / self.total_bytes as f64
     }
 }
 
+/// Returns a description of `payload` for [`DecodeProgress::stage`].
+fn payload_stage(payload: &Payload<'_>) -> &'static str {
+    match payload {
+        Payload::Version { .. } => "header",
+        Payload::TypeSection(_) => "core type section",
+        Payload::ImportSection(_) => "core import section",
+        Payload::FunctionSection(_) => "core function section",
+        Payload::ExportSection(_) => "core export section",
+        Payload::CodeSectionStart { .. } | Payload::CodeSectionEntry(_) => "core code section",
+        Payload::DataSection(_) => "core data section",
+        Payload::ModuleSection { .. } => "core module",
+        Payload::ComponentSection { .. } => "nested component",
+        Payload::ComponentTypeSection(_) => "type section",
+        Payload::ComponentImportSection(_) => "import section",
+        Payload::ComponentExportSection(_) => "export section",
+        Payload::ComponentAliasSection(_) => "alias section",
+        Payload::ComponentCanonicalSection(_) => "canonical function section",
+        Payload::ComponentInstanceSection(_) | Payload::InstanceSection(_) => "instance section",
+        Payload::CustomSection(_) => "custom section",
+        Payload::End(_) => "end",
+        _ => "section",
+    }
+}
+
+/// Decode for incremental reading
+pub fn decode_reader(reader: impl Read) -> Result<DecodedWasm> {
+    let info = ComponentInfo::from_reader(reader, |_, _| {})?;
+    info.decode()
+}
+
+/// Same as [`decode`], but `progress` is invoked as the input is processed.
+///
+/// `progress` is called once for each section of `bytes`, including sections
+/// of nested modules and components, after it's been parsed, and then once
+/// more with the stage `"building resolve"` before the WIT is reconstructed
+/// from the parsed types. This is intended for displaying progress when
+/// decoding large components and doesn't affect the result.
+pub fn decode_with_progress(
+    bytes: &[u8],
+    mut progress: impl FnMut(DecodeProgress),
+) -> Result<DecodedWasm> {
+    let total_bytes = bytes.len();
+    let info = ComponentInfo::from_reader(bytes, |payload, bytes_read| {
+        progress(DecodeProgress {
+            stage: payload_stage(payload),
+            bytes_read,
+            total_bytes,
+        })
+    })?;
+    progress(DecodeProgress {
+        stage: "building resolve",
+        bytes_read: total_bytes,
+        total_bytes,
+    });
+    info.decode()
+}
+
 /// Decodes an in-memory WebAssembly binary into a WIT [`Resolve`] and
 /// associated metadata.
 ///
@@ -489,6 +575,7 @@ pub fn decode_world(wasm: &[u8]) -> Result<(Resolve, WorldId)> {
         interfaces,
         worlds,
         docs: Default::default(),
+        leading_comments: None,
     });
     // The package decoded here should only have a single world so extract that
     // here to return.
@@ -557,6 +644,7 @@ impl WitPackageDecoder<'_> {
                 _ => bail!("package name is not a valid id: {name}"),
             },
             docs: Default::default(),
+            leading_comments: None,
             interfaces: Default::default(),
             worlds: Default::default(),
         };
@@ -885,6 +973,7 @@ impl WitPackageDecoder<'_> {
             .or_insert_with(|| Package {
                 name: package_name.clone(),
                 docs: Default::default(),
+                leading_comments: None,
                 interfaces: Default::default(),
                 worlds: Default::default(),
             });
@@ -1484,6 +1573,7 @@ impl WitPackageDecoder<'_> {
             interfaces,
             worlds,
             docs,
+            leading_comments,
         } = package;
 
         // Most of the time the `package` being inserted is not already present
@@ -1502,6 +1592,7 @@ impl WitPackageDecoder<'_> {
                     interfaces: Default::default(),
                     worlds: Default::default(),
                     docs,
+                    leading_comments,
                 });
                 let prev = self.resolve.package_names.insert(name, id);
                 assert!(prev.is_none());
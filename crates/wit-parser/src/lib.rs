@@ -6,7 +6,7 @@ use semver::Version;
 use std::borrow::Cow;
 use std::fmt;
 use std::hash::{Hash, Hasher};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 #[cfg(feature = "decoding")]
 pub mod decoding;
@@ -18,7 +18,7 @@ pub use metadata::PackageMetadata;
 pub mod abi;
 mod ast;
 pub use ast::SourceMap;
-use ast::lex::Span;
+pub use ast::lex::Span;
 pub use ast::{ParsedUsePath, parse_use_path};
 mod sizealign;
 pub use sizealign::*;
@@ -26,6 +26,8 @@ mod resolve;
 pub use resolve::*;
 mod live;
 pub use live::{LiveTypes, TypeIdVisitor};
+#[cfg(feature = "docs-gen")]
+mod markdown;
 
 #[cfg(feature = "serde")]
 use serde_derive::Serialize;
@@ -108,6 +110,14 @@ pub struct UnresolvedPackage {
     /// Doc comments for this package.
     pub docs: Docs,
 
+    /// The comments before the `package` statement, such as a license
+    /// header, as they were written in the source.
+    ///
+    /// These are the same comments that `docs` is parsed from, but kept
+    /// verbatim including comment markers and blank lines so that a
+    /// formatter can emit them unchanged.
+    pub leading_comments: Option<String>,
+
     package_name_span: Span,
     unknown_type_spans: Vec<Span>,
     interface_spans: Vec<InterfaceSpan>,
@@ -117,6 +127,46 @@ pub struct UnresolvedPackage {
     required_resource_types: Vec<(TypeId, Span)>,
 }
 
+impl UnresolvedPackage {
+    /// Returns where the type `id` is defined if it's a placeholder for a type
+    /// in a foreign package.
+    ///
+    /// Types used from foreign packages are represented with
+    /// [`TypeDefKind::Unknown`] until this package is resolved, and this
+    /// method can be used to describe them, for example for displaying an
+    /// unresolved package.
+    pub fn foreign_type_origin(&self, id: TypeId) -> Option<ForeignTypeOrigin<'_>> {
+        let ty = &self.types[id];
+        let (TypeDefKind::Unknown, TypeOwner::Interface(owner), Some(name)) =
+            (&ty.kind, ty.owner, &ty.name)
+        else {
+            return None;
+        };
+        self.foreign_deps.iter().find_map(|(package, items)| {
+            items.iter().find_map(|(interface, item)| match item {
+                AstItem::Interface(id) if *id == owner => Some(ForeignTypeOrigin {
+                    package,
+                    interface,
+                    name,
+                }),
+                _ => None,
+            })
+        })
+    }
+}
+
+/// The origin of a foreign type within an [`UnresolvedPackage`], as returned
+/// by [`UnresolvedPackage::foreign_type_origin`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ForeignTypeOrigin<'a> {
+    /// The foreign package the type is defined in.
+    pub package: &'a PackageName,
+    /// The name of the interface within `package` the type is defined in.
+    pub interface: &'a str,
+    /// The name of the type.
+    pub name: &'a str,
+}
+
 /// Tracks a set of packages, all pulled from the same group of WIT source files.
 #[derive(Clone)]
 pub struct UnresolvedPackageGroup {
@@ -250,6 +300,39 @@ impl fmt::Display for PackageName {
     }
 }
 
+impl std::str::FromStr for PackageName {
+    type Err = anyhow::Error;
+
+    /// Parses a package name such as `wasi:clocks` or `wasi:clocks@0.2.0`.
+    ///
+    /// The namespace and name must be valid identifiers. Interface paths such
+    /// as `wasi:clocks/wall-clock` are rejected.
+    fn from_str(s: &str) -> Result<PackageName> {
+        let (base, version) = match s.split_once('@') {
+            Some((base, version)) => {
+                let version = Version::parse(version)
+                    .map_err(|e| anyhow::anyhow!("invalid version in package name `{s}`: {e}"))?;
+                (base, Some(version))
+            }
+            None => (s, None),
+        };
+        if base.contains('/') {
+            bail!("package name `{s}` cannot contain an interface path");
+        }
+        let Some((namespace, name)) = base.split_once(':') else {
+            bail!("package name `{s}` must be of the form `namespace:name`");
+        };
+        validate_id(namespace)
+            .with_context(|| format!("invalid namespace in package name `{s}`"))?;
+        validate_id(name).with_context(|| format!("invalid name in package name `{s}`"))?;
+        Ok(PackageName {
+            namespace: namespace.to_string(),
+            name: name.to_string(),
+            version,
+        })
+    }
+}
+
 #[derive(Debug)]
 struct Error {
     span: Span,
@@ -321,6 +404,95 @@ impl fmt::Display for PackageNotFoundError {
 
 impl std::error::Error for PackageNotFoundError {}
 
+/// Error returned by [`Resolve::select_world`] when the requested world
+/// doesn't exist in its package.
+///
+/// The rendered message lists the worlds that are present in the package
+/// along with the closest match to the requested name, if any, to help
+/// diagnose typos.
+#[derive(Debug, Clone)]
+pub struct WorldNotFoundError {
+    requested: String,
+    package: PackageName,
+    known: Vec<String>,
+}
+
+impl WorldNotFoundError {
+    fn new(requested: String, package: PackageName, known: Vec<String>) -> Self {
+        Self {
+            requested,
+            package,
+            known,
+        }
+    }
+
+    /// Returns the name of the world that was requested.
+    pub fn requested(&self) -> &str {
+        &self.requested
+    }
+
+    /// Returns the name of the package that was searched.
+    pub fn package(&self) -> &PackageName {
+        &self.package
+    }
+
+    /// Returns the names of the worlds defined in the searched package.
+    pub fn known(&self) -> &[String] {
+        &self.known
+    }
+
+    /// Returns the known world whose name is closest to the requested name,
+    /// if one is close enough to plausibly be what was meant.
+    pub fn suggestion(&self) -> Option<&str> {
+        let max = (self.requested.len() / 3).max(1);
+        self.known
+            .iter()
+            .map(|known| (edit_distance(&self.requested, known), known))
+            .filter(|(distance, _)| *distance <= max)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, known)| known.as_str())
+    }
+}
+
+impl fmt::Display for WorldNotFoundError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "no world named `{}` in package `{}`.",
+            self.requested, self.package
+        )?;
+        if let Some(suggestion) = self.suggestion() {
+            write!(f, " did you mean `{suggestion}`?")?;
+        }
+        if self.known.is_empty() {
+            write!(f, " no known worlds.")?;
+        } else {
+            write!(f, " known worlds:\n")?;
+            for known in self.known.iter() {
+                write!(f, "    {known}\n")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for WorldNotFoundError {}
+
+/// Returns the Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = prev[j] + usize::from(ca != *cb);
+            cur[j + 1] = substitute.min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
 impl UnresolvedPackageGroup {
     /// Parses the given string as a wit document.
     ///
@@ -391,6 +563,27 @@ impl UnresolvedPackageGroup {
         }
         map.parse()
     }
+
+    /// Parses a WIT package from in-memory `files`, each given as a path and
+    /// its contents.
+    ///
+    /// This is the same as [`UnresolvedPackageGroup::parse_dir`] except that
+    /// nothing is read from the filesystem, so the files may instead come
+    /// from a virtual filesystem or the network. As with `parse_dir` only
+    /// paths ending in `.wit` are parsed and they're all assumed to be part of
+    /// the same package grouping. The paths are used for error reporting.
+    pub fn parse_files(
+        files: impl IntoIterator<Item = (PathBuf, String)>,
+    ) -> Result<UnresolvedPackageGroup> {
+        let mut map = SourceMap::default();
+        for (path, contents) in files {
+            if path.extension().and_then(|s| s.to_str()) != Some("wit") {
+                continue;
+            }
+            map.push(&path, contents);
+        }
+        map.parse()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -456,7 +649,7 @@ pub enum WorldKey {
 /// type is what helps encapsulate that rule. This is similar ish to `KebabStr`
 /// inside of `wasmparser` except that it's only used here for hashing/equality
 /// of `WorldKey` to ensure that world items all hash/dedupe as expected.
-#[derive(PartialEq, Hash)]
+#[derive(PartialEq, Eq, Hash)]
 enum KebabDedupe<'a> {
     Normal(&'a str),
 }
@@ -661,6 +854,192 @@ impl TypeDefKind {
             TypeDefKind::Unknown => "unknown",
         }
     }
+
+    /// Returns the types that this type directly refers to, in the order they
+    /// appear in its definition.
+    ///
+    /// This isn't transitive, so for example a `list<list<u8>>` only yields
+    /// the inner `list<u8>`. Handles yield the resource they refer to, an
+    /// alias yields the type it aliases, and `resource`, `flags`, and `enum`
+    /// yield nothing.
+    pub fn referenced_types(&self) -> impl Iterator<Item = Type> + '_ {
+        let types: Vec<Type> = match self {
+            TypeDefKind::Resource
+            | TypeDefKind::Flags(_)
+            | TypeDefKind::Enum(_)
+            | TypeDefKind::Unknown => Vec::new(),
+            TypeDefKind::Handle(Handle::Own(id) | Handle::Borrow(id)) => vec![Type::Id(*id)],
+            TypeDefKind::Record(r) => r.fields.iter().map(|f| f.ty).collect(),
+            TypeDefKind::Tuple(t) => t.types.clone(),
+            TypeDefKind::Variant(v) => v.cases.iter().filter_map(|c| c.ty).collect(),
+            TypeDefKind::Option(ty)
+            | TypeDefKind::List(ty)
+            | TypeDefKind::FixedSizeList(ty, _)
+            | TypeDefKind::Type(ty) => vec![*ty],
+            TypeDefKind::Result(r) => r.ok.into_iter().chain(r.err).collect(),
+            TypeDefKind::Future(ty) | TypeDefKind::Stream(ty) => ty.iter().copied().collect(),
+        };
+        types.into_iter()
+    }
+
+    /// Returns whether this is one of the anonymous structural types, such as
+    /// `list<T>` or `tuple<A, B>`, which are identified by their contents
+    /// rather than by a name.
+    ///
+    /// Returns `false` for the nominal definitions `record`, `resource`,
+    /// `flags`, `variant`, and `enum`. Aliases, `TypeDefKind::Type`, are also
+    /// `false` since `type foo = bar` gives `bar` a new name. Note that
+    /// anonymous types are still sometimes given a name through an alias, for
+    /// example `type l = list<u8>`, which is instead represented as a named
+    /// [`TypeDef`] whose kind is `TypeDefKind::List`.
+    pub fn is_anonymous(&self) -> bool {
+        match self {
+            TypeDefKind::List(_)
+            | TypeDefKind::Option(_)
+            | TypeDefKind::Result(_)
+            | TypeDefKind::Tuple(_)
+            | TypeDefKind::Handle(_)
+            | TypeDefKind::Future(_)
+            | TypeDefKind::Stream(_)
+            | TypeDefKind::FixedSizeList(..) => true,
+            TypeDefKind::Record(_)
+            | TypeDefKind::Resource
+            | TypeDefKind::Flags(_)
+            | TypeDefKind::Variant(_)
+            | TypeDefKind::Enum(_)
+            | TypeDefKind::Type(_)
+            | TypeDefKind::Unknown => false,
+        }
+    }
+}
+
+/// Renders `ty` in WIT syntax, see [`Resolve::type_to_string`].
+pub(crate) fn type_to_string(types: &Arena<TypeDef>, ty: &Type) -> String {
+    match ty {
+        Type::Bool => "bool".to_string(),
+        Type::U8 => "u8".to_string(),
+        Type::U16 => "u16".to_string(),
+        Type::U32 => "u32".to_string(),
+        Type::U64 => "u64".to_string(),
+        Type::S8 => "s8".to_string(),
+        Type::S16 => "s16".to_string(),
+        Type::S32 => "s32".to_string(),
+        Type::S64 => "s64".to_string(),
+        Type::F32 => "f32".to_string(),
+        Type::F64 => "f64".to_string(),
+        Type::Char => "char".to_string(),
+        Type::String => "string".to_string(),
+        Type::ErrorContext => "error-context".to_string(),
+        Type::Id(id) => match &types[*id].name {
+            Some(name) => escape_name(name).into_owned(),
+            None => type_def_kind_to_string(types, &types[*id].kind),
+        },
+    }
+}
+
+/// Returns `name` as it's written in WIT, with a leading `%` if it would
+/// otherwise be parsed as a keyword.
+fn escape_name(name: &str) -> Cow<'_, str> {
+    if ast::lex::is_keyword(name) {
+        Cow::Owned(format!("%{name}"))
+    } else {
+        Cow::Borrowed(name)
+    }
+}
+
+/// Renders the declaration of the named type `def` in WIT syntax, see
+/// [`Resolve::type_declaration`].
+pub(crate) fn type_declaration(types: &Arena<TypeDef>, def: &TypeDef) -> Option<String> {
+    let name = escape_name(def.name.as_ref()?);
+    let render = |ty: &Type| type_to_string(types, ty);
+    let body = |items: Vec<String>| {
+        if items.is_empty() {
+            "{}".to_string()
+        } else {
+            format!("{{ {} }}", items.join(", "))
+        }
+    };
+    Some(match &def.kind {
+        TypeDefKind::Record(r) => format!(
+            "record {name} {}",
+            body(
+                r.fields
+                    .iter()
+                    .map(|f| format!("{}: {}", escape_name(&f.name), render(&f.ty)))
+                    .collect()
+            )
+        ),
+        TypeDefKind::Variant(v) => format!(
+            "variant {name} {}",
+            body(
+                v.cases
+                    .iter()
+                    .map(|c| match &c.ty {
+                        Some(ty) => format!("{}({})", escape_name(&c.name), render(ty)),
+                        None => escape_name(&c.name).into_owned(),
+                    })
+                    .collect()
+            )
+        ),
+        TypeDefKind::Enum(e) => format!(
+            "enum {name} {}",
+            body(
+                e.cases
+                    .iter()
+                    .map(|c| escape_name(&c.name).into_owned())
+                    .collect()
+            )
+        ),
+        TypeDefKind::Flags(f) => format!(
+            "flags {name} {}",
+            body(
+                f.flags
+                    .iter()
+                    .map(|f| escape_name(&f.name).into_owned())
+                    .collect()
+            )
+        ),
+        TypeDefKind::Resource => format!("resource {name}"),
+        kind => format!("type {name} = {}", type_def_kind_to_string(types, kind)),
+    })
+}
+
+/// Renders the definition of a type in WIT syntax.
+///
+/// Types which can only be defined with a name, such as records, are
+/// rendered as just their kind.
+pub(crate) fn type_def_kind_to_string(types: &Arena<TypeDef>, kind: &TypeDefKind) -> String {
+    let render = |ty: &Type| type_to_string(types, ty);
+    let render_opt = |ty: &Option<Type>| ty.as_ref().map(render).unwrap_or("_".to_string());
+    match kind {
+        TypeDefKind::Type(ty) => render(ty),
+        TypeDefKind::List(ty) => format!("list<{}>", render(ty)),
+        TypeDefKind::FixedSizeList(ty, size) => format!("list<{}, {size}>", render(ty)),
+        TypeDefKind::Option(ty) => format!("option<{}>", render(ty)),
+        TypeDefKind::Result(r) => match (&r.ok, &r.err) {
+            (None, None) => "result".to_string(),
+            (Some(ok), None) => format!("result<{}>", render(ok)),
+            (ok, Some(err)) => format!("result<{}, {}>", render_opt(ok), render(err)),
+        },
+        TypeDefKind::Tuple(t) => format!(
+            "tuple<{}>",
+            t.types.iter().map(render).collect::<Vec<_>>().join(", ")
+        ),
+        TypeDefKind::Handle(Handle::Own(id)) => format!("own<{}>", render(&Type::Id(*id))),
+        TypeDefKind::Handle(Handle::Borrow(id)) => {
+            format!("borrow<{}>", render(&Type::Id(*id)))
+        }
+        TypeDefKind::Future(None) => "future".to_string(),
+        TypeDefKind::Future(Some(ty)) => format!("future<{}>", render(ty)),
+        TypeDefKind::Stream(None) => "stream".to_string(),
+        TypeDefKind::Stream(Some(ty)) => format!("stream<{}>", render(ty)),
+        TypeDefKind::Record(_)
+        | TypeDefKind::Resource
+        | TypeDefKind::Flags(_)
+        | TypeDefKind::Variant(_)
+        | TypeDefKind::Enum(_)
+        | TypeDefKind::Unknown => kind.as_str().to_string(),
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -716,6 +1095,20 @@ pub enum Int {
     U64,
 }
 
+impl Int {
+    /// Returns the integer type used for the discriminant of a variant or
+    /// enum with `num_cases` cases, as laid out by the Canonical ABI.
+    ///
+    /// This is the same as [`Variant::tag`] and [`Enum::tag`] use.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_cases` is larger than `u32::MAX + 1`.
+    pub fn discriminant_for(num_cases: usize) -> Int {
+        discriminant_type(num_cases)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Hash, Eq)]
 #[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct Record {
@@ -850,6 +1243,33 @@ impl Docs {
     pub fn is_empty(&self) -> bool {
         self.contents.is_none()
     }
+
+    /// Returns the first paragraph of these docs, with surrounding
+    /// whitespace removed, for use as a short summary.
+    ///
+    /// Paragraphs are separated by blank lines, and both `\n` and `\r\n` line
+    /// endings are supported. Docs consisting of a single paragraph are
+    /// returned in full. Returns `None` if there are no docs or they're
+    /// entirely blank.
+    pub fn summary(&self) -> Option<&str> {
+        let contents = self.contents.as_deref()?;
+        let mut start = None;
+        let mut end = contents.len();
+        let mut pos = 0;
+        for line in contents.split_inclusive('\n') {
+            let blank = line.trim().is_empty();
+            match start {
+                None if !blank => start = Some(pos),
+                Some(_) if blank => {
+                    end = pos;
+                    break;
+                }
+                _ => {}
+            }
+            pos += line.len();
+        }
+        Some(contents[start?..end].trim())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -980,6 +1400,16 @@ impl FunctionKind {
             | FunctionKind::AsyncStatic(id) => Some(id),
         }
     }
+
+    /// Returns whether this is the kind of an `async` function.
+    pub fn is_async(&self) -> bool {
+        matches!(
+            self,
+            FunctionKind::AsyncFreestanding
+                | FunctionKind::AsyncMethod(_)
+                | FunctionKind::AsyncStatic(_)
+        )
+    }
 }
 
 /// Possible forms of name mangling that are supported by this crate.
@@ -989,6 +1419,14 @@ pub enum Mangling {
     /// memories. This is specified in WebAssembly/component-model#378
     Standard32,
 
+    /// The 64-bit counterpart of [`Mangling::Standard32`], using the
+    /// `cm64p2` prefix instead of `cm32p2`.
+    ///
+    /// This only affects the names generated by this crate. Note that the
+    /// rest of the toolchain, such as `wit-component`, doesn't support this
+    /// mangling yet.
+    Standard64,
+
     /// The "legacy" name mangling supported in versions 218-and-prior for this
     /// crate. This is the original support for how components were created from
     /// core wasm modules and this does not correspond to any standard. This is
@@ -1003,10 +1441,11 @@ impl std::str::FromStr for Mangling {
         match s {
             "legacy" => Ok(Mangling::Legacy),
             "standard32" => Ok(Mangling::Standard32),
+            "standard64" => Ok(Mangling::Standard64),
             _ => {
                 bail!(
                     "unknown name mangling `{s}`, \
-                     supported values are `legacy` or `standard32`"
+                     supported values are `legacy`, `standard32`, or `standard64`"
                 )
             }
         }
@@ -1071,6 +1510,12 @@ pub enum ManglingAndAbi {
     /// synchronous ABI.
     Standard32,
 
+    /// See [`Mangling::Standard64`].
+    ///
+    /// Like [`ManglingAndAbi::Standard32`] this only supports the synchronous
+    /// ABI.
+    Standard64,
+
     /// See [`Mangling::Legacy`] and [`LiftLowerAbi`].
     Legacy(LiftLowerAbi),
 }
@@ -1079,7 +1524,7 @@ impl ManglingAndAbi {
     /// Get the import [`AbiVariant`] corresponding to this [`ManglingAndAbi`]
     pub fn import_variant(self) -> AbiVariant {
         match self {
-            Self::Standard32 => AbiVariant::GuestImport,
+            Self::Standard32 | Self::Standard64 => AbiVariant::GuestImport,
             Self::Legacy(abi) => abi.import_variant(),
         }
     }
@@ -1087,7 +1532,7 @@ impl ManglingAndAbi {
     /// Get the export [`AbiVariant`] corresponding to this [`ManglingAndAbi`]
     pub fn export_variant(self) -> AbiVariant {
         match self {
-            Self::Standard32 => AbiVariant::GuestExport,
+            Self::Standard32 | Self::Standard64 => AbiVariant::GuestExport,
             Self::Legacy(abi) => abi.export_variant(),
         }
     }
@@ -1095,7 +1540,7 @@ impl ManglingAndAbi {
     /// Switch the ABI to be sync if it's async.
     pub fn sync(self) -> Self {
         match self {
-            Self::Standard32 | Self::Legacy(LiftLowerAbi::Sync) => self,
+            Self::Standard32 | Self::Standard64 | Self::Legacy(LiftLowerAbi::Sync) => self,
             Self::Legacy(LiftLowerAbi::AsyncCallback)
             | Self::Legacy(LiftLowerAbi::AsyncStackful) => Self::Legacy(LiftLowerAbi::Sync),
         }
@@ -1104,7 +1549,7 @@ impl ManglingAndAbi {
     /// Returns whether this is an async ABI
     pub fn is_async(&self) -> bool {
         match self {
-            Self::Standard32 | Self::Legacy(LiftLowerAbi::Sync) => false,
+            Self::Standard32 | Self::Standard64 | Self::Legacy(LiftLowerAbi::Sync) => false,
             Self::Legacy(LiftLowerAbi::AsyncCallback)
             | Self::Legacy(LiftLowerAbi::AsyncStackful) => true,
         }
@@ -1124,6 +1569,21 @@ impl Function {
         }
     }
 
+    /// Returns whether this function is `async`, see
+    /// [`FunctionKind::is_async`].
+    pub fn is_async(&self) -> bool {
+        self.kind.is_async()
+    }
+
+    /// Returns whether this function is a method of a resource, either sync
+    /// or `async`, and so takes `self` as its first parameter.
+    pub fn is_method(&self) -> bool {
+        matches!(
+            self.kind,
+            FunctionKind::Method(_) | FunctionKind::AsyncMethod(_)
+        )
+    }
+
     /// Returns an iterator over the types used in parameters and results.
     ///
     /// Note that this iterator is not transitive, it only iterates over the
@@ -1149,10 +1609,12 @@ impl Function {
         match interface {
             Some(interface) => match mangling {
                 Mangling::Standard32 => Cow::Owned(format!("cm32p2|{interface}|{}", self.name)),
+                Mangling::Standard64 => Cow::Owned(format!("cm64p2|{interface}|{}", self.name)),
                 Mangling::Legacy => Cow::Owned(format!("{interface}#{}", self.name)),
             },
             None => match mangling {
                 Mangling::Standard32 => Cow::Owned(format!("cm32p2||{}", self.name)),
+                Mangling::Standard64 => Cow::Owned(format!("cm64p2||{}", self.name)),
                 Mangling::Legacy => Cow::Borrowed(&self.name),
             },
         }
@@ -1174,73 +1636,21 @@ impl Function {
     /// indicate a call to `stream.new` for `stream<u8>`.
     pub fn find_futures_and_streams(&self, resolve: &Resolve) -> Vec<TypeId> {
         let mut results = Vec::new();
+        let mut push = |id| {
+            if let TypeDefKind::Future(_) | TypeDefKind::Stream(_) = resolve.types[id].kind {
+                results.push(id);
+            }
+        };
         for (_, ty) in self.params.iter() {
-            find_futures_and_streams(resolve, *ty, &mut results);
+            resolve.visit_type_deps(*ty, &mut push);
         }
         if let Some(ty) = self.result {
-            find_futures_and_streams(resolve, ty, &mut results);
+            resolve.visit_type_deps(ty, &mut push);
         }
         results
     }
 }
 
-fn find_futures_and_streams(resolve: &Resolve, ty: Type, results: &mut Vec<TypeId>) {
-    let Type::Id(id) = ty else {
-        return;
-    };
-
-    match &resolve.types[id].kind {
-        TypeDefKind::Resource
-        | TypeDefKind::Handle(_)
-        | TypeDefKind::Flags(_)
-        | TypeDefKind::Enum(_) => {}
-        TypeDefKind::Record(r) => {
-            for Field { ty, .. } in &r.fields {
-                find_futures_and_streams(resolve, *ty, results);
-            }
-        }
-        TypeDefKind::Tuple(t) => {
-            for ty in &t.types {
-                find_futures_and_streams(resolve, *ty, results);
-            }
-        }
-        TypeDefKind::Variant(v) => {
-            for Case { ty, .. } in &v.cases {
-                if let Some(ty) = ty {
-                    find_futures_and_streams(resolve, *ty, results);
-                }
-            }
-        }
-        TypeDefKind::Option(ty)
-        | TypeDefKind::List(ty)
-        | TypeDefKind::FixedSizeList(ty, ..)
-        | TypeDefKind::Type(ty) => {
-            find_futures_and_streams(resolve, *ty, results);
-        }
-        TypeDefKind::Result(r) => {
-            if let Some(ty) = r.ok {
-                find_futures_and_streams(resolve, ty, results);
-            }
-            if let Some(ty) = r.err {
-                find_futures_and_streams(resolve, ty, results);
-            }
-        }
-        TypeDefKind::Future(ty) => {
-            if let Some(ty) = ty {
-                find_futures_and_streams(resolve, *ty, results);
-            }
-            results.push(id);
-        }
-        TypeDefKind::Stream(ty) => {
-            if let Some(ty) = ty {
-                find_futures_and_streams(resolve, *ty, results);
-            }
-            results.push(id);
-        }
-        TypeDefKind::Unknown => unreachable!(),
-    }
-}
-
 /// Representation of the stability attributes associated with a world,
 /// interface, function, or type.
 ///
@@ -1303,6 +1713,52 @@ impl Stability {
     pub fn is_stable(&self) -> bool {
         matches!(self, Stability::Stable { .. })
     }
+
+    /// Returns the name of the feature which must be enabled for an item with
+    /// this stability to be included.
+    ///
+    /// This is only `Some` for `Stability::Unstable`, as stable items and items
+    /// without an annotation are always included.
+    pub fn as_feature_requirement(&self) -> Option<String> {
+        match self {
+            Stability::Unstable { feature, .. } => Some(feature.clone()),
+            Stability::Stable { .. } | Stability::Unknown => None,
+        }
+    }
+
+    /// Returns the version this item was deprecated in, if it's annotated with
+    /// `@deprecated`.
+    pub fn deprecated(&self) -> Option<&Version> {
+        match self {
+            Stability::Stable { deprecated, .. } | Stability::Unstable { deprecated, .. } => {
+                deprecated.as_ref()
+            }
+            Stability::Unknown => None,
+        }
+    }
+
+    /// Returns whether this item is annotated with `@deprecated`.
+    pub fn is_deprecated(&self) -> bool {
+        self.deprecated().is_some()
+    }
+
+    /// Returns a short description of this stability suitable for display,
+    /// such as `stable@1.0.0` or `unstable(http)`.
+    ///
+    /// A deprecation is appended as `deprecated@1.5.0`, for example
+    /// `stable@1.0.0 deprecated@1.5.0`. `Stability::Unknown` produces an empty
+    /// string.
+    pub fn badge(&self) -> String {
+        let badge = match self {
+            Stability::Unknown => return String::new(),
+            Stability::Stable { since, .. } => format!("stable@{since}"),
+            Stability::Unstable { feature, .. } => format!("unstable({feature})"),
+        };
+        match self.deprecated() {
+            Some(version) => format!("{badge} deprecated@{version}"),
+            None => badge,
+        }
+    }
 }
 
 impl Default for Stability {
@@ -1315,6 +1771,311 @@ impl Default for Stability {
 mod test {
     use super::*;
 
+    #[test]
+    fn test_docs_summary() {
+        let docs = |s: &str| Docs {
+            contents: Some(s.to_string()),
+        };
+        assert_eq!(
+            docs("Opens a file.\n\nThe file is created if it's missing.").summary(),
+            Some("Opens a file.")
+        );
+        assert_eq!(
+            docs("\r\n  Reads bytes.  \r\n  \r\nMore details.").summary(),
+            Some("Reads bytes.")
+        );
+        assert_eq!(
+            docs("Reads bytes from\nthe stream.\n\nBlocks.\n\nMore.").summary(),
+            Some("Reads bytes from\nthe stream.")
+        );
+        assert_eq!(
+            docs("Reads bytes from\nthe stream.\n").summary(),
+            Some("Reads bytes from\nthe stream.")
+        );
+        assert_eq!(docs(" \n\t\n").summary(), None);
+        assert_eq!(Docs::default().summary(), None);
+
+        let mut resolve = Resolve::default();
+        let pkg = resolve
+            .push_str(
+                "test.wit",
+                "package a:b;\n\
+                 interface i {\n\
+                     /// Does a thing.\n\
+                     ///\n\
+                     /// In great detail.\n\
+                     f: func();\n\
+                 }\n",
+            )
+            .unwrap();
+        let i = resolve.packages[pkg].interfaces["i"];
+        let f = &resolve.interfaces[i].functions["f"];
+        assert_eq!(f.docs.summary(), Some("Does a thing."));
+    }
+
+    #[test]
+    fn test_package_name_from_str() {
+        for s in [
+            "wasi:clocks",
+            "wasi:clocks@0.2.0",
+            "a-b:c-d@1.2.3-rc.1+build",
+        ] {
+            let name = s.parse::<PackageName>().unwrap();
+            assert_eq!(name.to_string(), s);
+            assert_eq!(name.to_string().parse::<PackageName>().unwrap(), name);
+        }
+        let name = "wasi:clocks@0.2.0".parse::<PackageName>().unwrap();
+        assert_eq!(name.namespace, "wasi");
+        assert_eq!(name.name, "clocks");
+        assert_eq!(name.version, Some(Version::new(0, 2, 0)));
+
+        let err = |s: &str| s.parse::<PackageName>().unwrap_err().to_string();
+        assert!(err("wasi:clocks/wall-clock@0.2.0").contains("interface path"));
+        assert!(err("wasi").contains("namespace:name"));
+        assert!(err("Wasi:clocks").contains("invalid namespace"));
+        assert!(err("wasi:clocks:x").contains("invalid name"));
+        assert!(err("wasi:clocks@1").contains("invalid version"));
+    }
+
+    #[test]
+    fn test_function_predicates() {
+        let mut resolve = Resolve::default();
+        let pkg = resolve
+            .push_str(
+                "test.wit",
+                "package a:b;\n\
+                 interface i {\n\
+                     resource r {\n\
+                         constructor();\n\
+                         m: func();\n\
+                         am: async func();\n\
+                         s: static func();\n\
+                         sa: static async func();\n\
+                     }\n\
+                     f: func();\n\
+                     af: async func();\n\
+                 }\n",
+            )
+            .unwrap();
+        let i = resolve.packages[pkg].interfaces["i"];
+        let preds = resolve.interfaces[i]
+            .functions
+            .values()
+            .map(|f| {
+                assert_eq!(f.is_async(), f.kind.is_async());
+                (f.item_name(), f.is_async(), f.is_method())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            preds,
+            [
+                ("constructor", false, false),
+                ("m", false, true),
+                ("am", true, true),
+                ("s", false, false),
+                ("sa", true, false),
+                ("f", false, false),
+                ("af", true, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nested_packages_same_namespace() {
+        let group = UnresolvedPackageGroup::parse(
+            "test.wit",
+            "package mycorp:root;\n\
+             package mycorp:a { interface types { type t = u32; } }\n\
+             package mycorp:b { interface uses { use mycorp:a/types.{t}; } }\n",
+        )
+        .unwrap();
+        let names = group
+            .nested
+            .iter()
+            .map(|pkg| pkg.name.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["mycorp:a", "mycorp:b"]);
+
+        let mut resolve = Resolve::default();
+        resolve.push_group(group).unwrap();
+        let b = resolve.package_names[&"mycorp:b".parse::<PackageName>().unwrap()];
+        let uses = resolve.packages[b].interfaces["uses"];
+        let TypeDefKind::Type(Type::Id(t)) =
+            resolve.types[resolve.interfaces[uses].types["t"]].kind
+        else {
+            panic!("expected an alias");
+        };
+        let TypeOwner::Interface(owner) = resolve.types[t].owner else {
+            panic!("expected an interface owner");
+        };
+        let a = resolve.package_names[&"mycorp:a".parse::<PackageName>().unwrap()];
+        assert_ne!(a, b);
+        assert_eq!(resolve.interfaces[owner].package, Some(a));
+    }
+
+    #[test]
+    fn test_parse_files() {
+        let files = [
+            (
+                PathBuf::from("wit/types.wit"),
+                "package a:b;\ninterface types { type t = u32; }\n".to_string(),
+            ),
+            (
+                PathBuf::from("wit/world.wit"),
+                "world w { import types; import f: func(x: u32); }\n".to_string(),
+            ),
+            (PathBuf::from("wit/README.md"), "not wit".to_string()),
+        ];
+        let group = UnresolvedPackageGroup::parse_files(files).unwrap();
+        assert_eq!(group.main.name.to_string(), "a:b");
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_group(group).unwrap();
+        assert!(resolve.packages[pkg].interfaces.contains_key("types"));
+        assert!(resolve.packages[pkg].worlds.contains_key("w"));
+
+        let Err(err) = UnresolvedPackageGroup::parse_files([
+            (PathBuf::from("wit/a.wit"), "package a:b;\n".to_string()),
+            (PathBuf::from("wit/b.wit"), "interface {}\n".to_string()),
+        ]) else {
+            panic!("expected an error");
+        };
+        assert!(format!("{err:?}").contains("wit/b.wit:1:"), "{err:?}");
+    }
+
+    #[test]
+    fn test_type_def_kind_is_anonymous() {
+        let mut resolve = Resolve::default();
+        let pkg = resolve
+            .push_str(
+                "test.wit",
+                "package a:b;\n\
+                 interface i {\n\
+                     record r { x: u32 }\n\
+                     resource res;\n\
+                     enum e { a }\n\
+                     type alias = r;\n\
+                     type l = list<u8>;\n\
+                     f: func(a: tuple<u8, u8>, b: option<r>, c: borrow<res>);\n\
+                 }\n",
+            )
+            .unwrap();
+        let i = resolve.packages[pkg].interfaces["i"];
+        let types = &resolve.interfaces[i].types;
+        for (name, anonymous) in [
+            ("r", false),
+            ("res", false),
+            ("e", false),
+            ("alias", false),
+            ("l", true),
+        ] {
+            assert_eq!(resolve.types[types[name]].kind.is_anonymous(), anonymous);
+        }
+        for (_, ty) in resolve.interfaces[i].functions["f"].params.iter() {
+            let Type::Id(id) = ty else { unreachable!() };
+            assert!(resolve.types[*id].kind.is_anonymous());
+            assert!(resolve.types[*id].name.is_none());
+        }
+    }
+
+    #[test]
+    fn test_type_def_kind_referenced_types() {
+        let mut resolve = Resolve::default();
+        let pkg = resolve
+            .push_str(
+                "test.wit",
+                "package a:b;\n\
+                 interface i {\n\
+                     resource res;\n\
+                     enum e { a, b }\n\
+                     record r { x: u32, y: list<string> }\n\
+                     variant v { a(r), b, c(e) }\n\
+                     type res2 = res;\n\
+                     f: func(a: result<_, r>, b: own<res>, c: tuple<u8, list<list<u8>>>);\n\
+                 }\n",
+            )
+            .unwrap();
+        let i = &resolve.interfaces[resolve.packages[pkg].interfaces["i"]];
+        let refs = |id: TypeId| {
+            resolve.types[id]
+                .kind
+                .referenced_types()
+                .collect::<Vec<_>>()
+        };
+        let ty = |name: &str| i.types[name];
+        assert!(refs(ty("res")).is_empty());
+        assert!(refs(ty("e")).is_empty());
+        let r = refs(ty("r"));
+        assert_eq!(r[0], Type::U32);
+        assert!(
+            matches!(r[1], Type::Id(id) if resolve.types[id].kind == TypeDefKind::List(Type::String))
+        );
+        assert_eq!(refs(ty("v")), [Type::Id(ty("r")), Type::Id(ty("e"))]);
+        assert_eq!(refs(ty("res2")), [Type::Id(ty("res"))]);
+
+        let params = i.functions["f"]
+            .params
+            .iter()
+            .map(|(_, ty)| match ty {
+                Type::Id(id) => refs(*id),
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(params[0], [Type::Id(ty("r"))]);
+        assert_eq!(params[1], [Type::Id(ty("res"))]);
+        assert_eq!(params[2].len(), 2);
+        assert_eq!(params[2][0], Type::U8);
+    }
+
+    #[test]
+    fn test_standard64_mangling() {
+        assert_eq!(
+            "standard64".parse::<Mangling>().unwrap(),
+            Mangling::Standard64
+        );
+        assert!("standard128".parse::<Mangling>().is_err());
+        assert_eq!(
+            ManglingAndAbi::Standard64.import_variant(),
+            ManglingAndAbi::Standard32.import_variant()
+        );
+        assert_eq!(
+            ManglingAndAbi::Standard64.export_variant(),
+            ManglingAndAbi::Standard32.export_variant()
+        );
+        assert!(!ManglingAndAbi::Standard64.is_async());
+
+        let mut resolve = Resolve::default();
+        let pkg = resolve
+            .push_str(
+                "test.wit",
+                "package foo:bar; interface i { f: func(); } world w { export i; }",
+            )
+            .unwrap();
+        let i = resolve.packages[pkg].interfaces["i"];
+        let func = &resolve.interfaces[i].functions["f"];
+        assert_eq!(
+            func.core_export_name(Some("foo:bar/i"), Mangling::Standard64),
+            "cm64p2|foo:bar/i|f"
+        );
+        assert_eq!(
+            func.core_export_name(None, Mangling::Standard64),
+            "cm64p2||f"
+        );
+
+        let key = WorldKey::Interface(i);
+        let (module, name) = resolve.wasm_import_name(
+            ManglingAndAbi::Standard64,
+            WasmImport::Func {
+                interface: Some(&key),
+                func,
+            },
+        );
+        assert_eq!((module.as_str(), name.as_str()), ("cm64p2|foo:bar/i", "f"));
+        assert_eq!(
+            resolve.wasm_export_name(ManglingAndAbi::Standard64, WasmExport::Memory),
+            "cm64p2_memory"
+        );
+    }
+
     #[test]
     fn test_discriminant_type() {
         assert_eq!(discriminant_type(1), Int::U8);
@@ -1327,6 +2088,164 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_int_discriminant_for() {
+        assert_eq!(Int::discriminant_for(0), Int::U8);
+        assert_eq!(Int::discriminant_for(256), Int::U8);
+        assert_eq!(Int::discriminant_for(257), Int::U16);
+        assert_eq!(Int::discriminant_for(0x10001), Int::U32);
+        let cases = (0..300)
+            .map(|i| EnumCase {
+                name: format!("c{i}"),
+                docs: Docs::default(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(Int::discriminant_for(300), Enum { cases }.tag());
+    }
+
+    #[test]
+    fn test_as_feature_requirement() {
+        assert_eq!(Stability::Unknown.as_feature_requirement(), None);
+        let stable = Stability::Stable {
+            since: Version::new(0, 2, 0),
+            deprecated: None,
+        };
+        assert_eq!(stable.as_feature_requirement(), None);
+        let unstable = Stability::Unstable {
+            feature: "fancy-thing".to_string(),
+            deprecated: Some(Version::new(0, 3, 0)),
+        };
+        assert_eq!(
+            unstable.as_feature_requirement().as_deref(),
+            Some("fancy-thing")
+        );
+    }
+
+    #[test]
+    fn test_foreign_type_origin() -> Result<()> {
+        let group = UnresolvedPackageGroup::parse(
+            "test.wit",
+            r#"
+                package foo:bar;
+
+                interface i {
+                    use wasi:io/streams@0.2.0.{input-stream};
+                    type t = u32;
+                    read: func(s: borrow<input-stream>);
+                }
+            "#,
+        )?;
+        let pkg = &group.main;
+        let mut origins = pkg
+            .types
+            .iter()
+            .filter_map(|(id, _)| pkg.foreign_type_origin(id));
+        let origin = origins.next().unwrap();
+        assert!(origins.next().is_none());
+        assert_eq!(origin.package.to_string(), "wasi:io@0.2.0");
+        assert_eq!(origin.interface, "streams");
+        assert_eq!(origin.name, "input-stream");
+        Ok(())
+    }
+
+    #[test]
+    fn test_badge() {
+        assert_eq!(Stability::Unknown.badge(), "");
+        let stable = |deprecated| Stability::Stable {
+            since: Version::new(1, 0, 0),
+            deprecated,
+        };
+        assert_eq!(stable(None).badge(), "stable@1.0.0");
+        assert_eq!(
+            stable(Some(Version::new(1, 5, 0))).badge(),
+            "stable@1.0.0 deprecated@1.5.0"
+        );
+        let unstable = |deprecated| Stability::Unstable {
+            feature: "http".to_string(),
+            deprecated,
+        };
+        assert_eq!(unstable(None).badge(), "unstable(http)");
+        assert_eq!(
+            unstable(Some(Version::new(1, 5, 0))).badge(),
+            "unstable(http) deprecated@1.5.0"
+        );
+    }
+
+    #[test]
+    fn test_deprecated() {
+        let version = Version::new(1, 5, 0);
+        assert_eq!(Stability::Unknown.deprecated(), None);
+        assert!(!Stability::Unknown.is_deprecated());
+        let stable = Stability::Stable {
+            since: Version::new(1, 0, 0),
+            deprecated: Some(version.clone()),
+        };
+        assert_eq!(stable.deprecated(), Some(&version));
+        assert!(stable.is_deprecated());
+        let unstable = Stability::Unstable {
+            feature: "http".to_string(),
+            deprecated: None,
+        };
+        assert_eq!(unstable.deprecated(), None);
+        assert!(!unstable.is_deprecated());
+    }
+
+    #[test]
+    fn test_multiline_attributes() {
+        let stability = |wit: &str| {
+            let mut resolve = Resolve::default();
+            resolve.features.insert("fancy".to_string());
+            let pkg = resolve.push_str("test.wit", wit).unwrap();
+            let iface = &resolve.interfaces[resolve.packages[pkg].interfaces["i"]];
+            (
+                iface.stability.clone(),
+                iface.functions["f"].stability.clone(),
+            )
+        };
+
+        let compact = stability(
+            r#"
+                package a:b@1.0.0;
+
+                @since(version = 1.0.0-rc.1+build.2)
+                @deprecated(version = 1.0.0)
+                interface i {
+                    @unstable(feature = fancy)
+                    f: func();
+                }
+            "#,
+        );
+        let multiline = stability(
+            r#"
+                package a:b@1.0.0;
+
+                @since(
+                    // the first release candidate
+                    version = 1.0.0-rc.1+build.2
+                )
+                @deprecated( /* replaced by `j` */
+                    version
+                    =
+                    1.0.0 // in the stable release
+                )
+                interface i {
+                    @unstable(
+                        feature = fancy
+                    )
+                    f: func();
+                }
+            "#,
+        );
+        assert_eq!(compact, multiline);
+        assert_eq!(
+            compact.0,
+            Stability::Stable {
+                since: "1.0.0-rc.1+build.2".parse().unwrap(),
+                deprecated: Some(Version::new(1, 0, 0)),
+            }
+        );
+    }
+
     #[test]
     fn test_find_futures_and_streams() {
         let mut resolve = Resolve::default();
@@ -0,0 +1,227 @@
+//! Rendering of a WIT package as a Markdown API reference.
+
+use crate::{
+    Docs, Function, FunctionKind, InterfaceId, PackageId, Resolve, Stability, Type, WorldId,
+    WorldItem,
+};
+use std::fmt::Write;
+
+impl Resolve {
+    /// Renders the package `pkg` as a Markdown API reference.
+    ///
+    /// The output has a section for each interface and world in `pkg`, in the
+    /// order they're defined, listing each function's signature along with its
+    /// docs and stability. Worlds additionally list the interfaces they import
+    /// and export. The output only depends on the contents of `pkg`, so it's
+    /// suitable for checking in or diffing between releases.
+    pub fn to_markdown(&self, pkg: PackageId) -> String {
+        let package = &self.packages[pkg];
+        let mut out = String::new();
+        writeln!(out, "# Package `{}`", package.name).unwrap();
+        push_docs(&mut out, &package.docs);
+
+        for id in package.interfaces.values() {
+            self.interface_markdown(&mut out, *id);
+        }
+        for id in package.worlds.values() {
+            self.world_markdown(&mut out, *id);
+        }
+        out
+    }
+
+    fn interface_markdown(&self, out: &mut String, id: InterfaceId) {
+        let iface = &self.interfaces[id];
+        let name = iface.name.as_deref().unwrap_or("<anonymous>");
+        writeln!(out, "\n## Interface `{name}`").unwrap();
+        push_stability(out, &iface.stability);
+        push_docs(out, &iface.docs);
+        for func in iface.functions.values() {
+            self.function_markdown(out, func, "###");
+        }
+    }
+
+    fn world_markdown(&self, out: &mut String, id: WorldId) {
+        let world = &self.worlds[id];
+        writeln!(out, "\n## World `{}`", world.name).unwrap();
+        push_stability(out, &world.stability);
+        push_docs(out, &world.docs);
+
+        for (title, items) in [("Imports", &world.imports), ("Exports", &world.exports)] {
+            let interfaces = items
+                .iter()
+                .filter(|(_, item)| matches!(item, WorldItem::Interface { .. }))
+                .collect::<Vec<_>>();
+            let funcs = items
+                .values()
+                .filter_map(|item| match item {
+                    WorldItem::Function(func) => Some(func),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            if interfaces.is_empty() && funcs.is_empty() {
+                continue;
+            }
+            writeln!(out, "\n### {title}").unwrap();
+            if !interfaces.is_empty() {
+                writeln!(out).unwrap();
+                for (key, _) in interfaces {
+                    writeln!(out, "- `{}`", self.name_world_key(key)).unwrap();
+                }
+            }
+            for func in funcs {
+                self.function_markdown(out, func, "####");
+            }
+        }
+    }
+
+    fn function_markdown(&self, out: &mut String, func: &Function, heading: &str) {
+        writeln!(out, "\n{heading} `{}`", self.function_signature(func)).unwrap();
+        push_stability(out, &func.stability);
+        push_docs(out, &func.docs);
+    }
+
+    fn function_signature(&self, func: &Function) -> String {
+        // Methods have an implicit `self` parameter and constructors an
+        // implicit result, neither of which are written in WIT.
+        let (prefix, skip_self, skip_result) = match &func.kind {
+            FunctionKind::Freestanding => ("func", false, false),
+            FunctionKind::AsyncFreestanding => ("async func", false, false),
+            FunctionKind::Method(_) => ("func", true, false),
+            FunctionKind::AsyncMethod(_) => ("async func", true, false),
+            FunctionKind::Static(_) => ("static func", false, false),
+            FunctionKind::AsyncStatic(_) => ("static async func", false, false),
+            FunctionKind::Constructor(_) => ("constructor", false, true),
+        };
+        let mut sig = match func.kind.resource() {
+            Some(id) => {
+                let resource = self.type_to_string(&Type::Id(id));
+                match func.kind {
+                    FunctionKind::Constructor(_) => format!("{resource}: "),
+                    _ => format!("{resource}.{}: ", func.item_name()),
+                }
+            }
+            None => format!("{}: ", func.item_name()),
+        };
+        sig.push_str(prefix);
+        sig.push('(');
+        let params = func
+            .params
+            .iter()
+            .skip(usize::from(skip_self))
+            .map(|(name, ty)| format!("{name}: {}", self.type_to_string(ty)))
+            .collect::<Vec<_>>();
+        sig.push_str(&params.join(", "));
+        sig.push(')');
+        if let (Some(ty), false) = (&func.result, skip_result) {
+            sig.push_str(" -> ");
+            sig.push_str(&self.type_to_string(ty));
+        }
+        sig
+    }
+}
+
+fn push_stability(out: &mut String, stability: &Stability) {
+    if !stability.is_unknown() {
+        writeln!(out, "\n_{}_", stability.badge()).unwrap();
+    }
+}
+
+fn push_docs(out: &mut String, docs: &Docs) {
+    if let Some(contents) = &docs.contents {
+        writeln!(out, "\n{}", contents.trim_end()).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Resolve;
+
+    #[test]
+    fn to_markdown() {
+        let mut resolve = Resolve::default();
+        resolve.features.insert("fancy".to_string());
+        let pkg = resolve
+            .push_str(
+                "test.wit",
+                r#"
+                    /// An example package.
+                    package foo:bar@0.1.0;
+
+                    /// Files and such.
+                    @since(version = 0.1.0)
+                    interface files {
+                        resource file {
+                            /// Opens a file.
+                            constructor(path: string);
+                            /// Reads some bytes.
+                            read: func(len: u32) -> list<u8>;
+                        }
+
+                        @unstable(feature = fancy)
+                        stat: func(f: borrow<file>) -> option<u64>;
+                    }
+
+                    world app {
+                        import files;
+                        /// Runs the app.
+                        export run: func(args: list<string>) -> result;
+                    }
+
+                    world tool {
+                        import log: func(msg: string);
+                        export main: func();
+                    }
+                "#,
+            )
+            .unwrap();
+
+        assert_eq!(
+            resolve.to_markdown(pkg),
+            "\
+# Package `foo:bar@0.1.0`
+
+An example package.
+
+## Interface `files`
+
+_stable@0.1.0_
+
+Files and such.
+
+### `file: constructor(path: string)`
+
+Opens a file.
+
+### `file.read: func(len: u32) -> list<u8>`
+
+Reads some bytes.
+
+### `stat: func(f: borrow<file>) -> option<u64>`
+
+_unstable(fancy)_
+
+## World `app`
+
+### Imports
+
+- `foo:bar/files@0.1.0`
+
+### Exports
+
+#### `run: func(args: list<string>) -> result`
+
+Runs the app.
+
+## World `tool`
+
+### Imports
+
+#### `log: func(msg: string)`
+
+### Exports
+
+#### `main: func()`
+"
+        );
+    }
+}
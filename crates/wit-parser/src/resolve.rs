@@ -1,9 +1,12 @@
+use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::collections::hash_map;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt;
+use std::iter;
 use std::mem;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use anyhow::{Context, Result, anyhow, bail};
 use id_arena::{Arena, Id};
@@ -15,12 +18,13 @@ use serde_derive::Serialize;
 use crate::ast::lex::Span;
 use crate::ast::{ParsedUsePath, parse_use_path};
 #[cfg(feature = "serde")]
-use crate::serde_::{serialize_arena, serialize_id_map};
+use crate::serde_::{serialize_arena, serialize_id_map, serialize_optional_version};
 use crate::{
-    AstItem, Docs, Error, Function, FunctionKind, Handle, IncludeName, Interface, InterfaceId,
-    InterfaceSpan, LiftLowerAbi, ManglingAndAbi, PackageName, PackageNotFoundError, SourceMap,
-    Stability, Type, TypeDef, TypeDefKind, TypeId, TypeIdVisitor, TypeOwner, UnresolvedPackage,
-    UnresolvedPackageGroup, World, WorldId, WorldItem, WorldKey, WorldSpan,
+    AstItem, Case, Docs, Enum, EnumCase, Error, Field, Function, FunctionKind, Handle, IncludeName,
+    Interface, InterfaceId, InterfaceSpan, LiftLowerAbi, LiveTypes, Mangling, ManglingAndAbi,
+    PackageName, PackageNotFoundError, Record, SizeAlign, SourceMap, Stability, Type, TypeDef,
+    TypeDefKind, TypeId, TypeIdVisitor, TypeOwner, UnresolvedPackage, UnresolvedPackageGroup,
+    Variant, World, WorldId, WorldItem, WorldKey, WorldNotFoundError, WorldSpan, validate_id,
 };
 
 mod clone;
@@ -86,6 +90,38 @@ pub struct Resolve {
     /// Activate all features for this [`Resolve`].
     #[cfg_attr(feature = "serde", serde(skip))]
     pub all_features: bool,
+
+    /// Whether an unversioned reference to another package in loaded WIT
+    /// documents, such as `use foo:dep/types.{t}`, may refer to a versioned
+    /// package when it's the only version of that package present.
+    ///
+    /// This is disabled by default, in which case references must name the
+    /// exact version. It's intended for parsing the output of a printer which
+    /// elides unambiguous versions. A reference matching several versions of
+    /// a package is an error either way.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub resolve_unversioned_deps: bool,
+
+    /// Offsets within the original source of functions in interfaces, keyed
+    /// by function name, for interfaces that were parsed from WIT source.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    interface_function_offsets: HashMap<InterfaceId, HashMap<String, u32>>,
+
+    /// Spans of the names of items that were parsed from WIT source, as
+    /// returned by [`Resolve::item_span`], along with the source map of the
+    /// push the span is relative to.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    item_spans: HashMap<ItemRef, (Span, Arc<SourceMap>)>,
+
+    /// Items of `include`d worlds which were overridden by the including
+    /// world, as returned by [`Resolve::resolve_include_overrides`].
+    #[cfg_attr(feature = "serde", serde(skip))]
+    include_overrides: HashMap<WorldId, Vec<Override>>,
+
+    /// Sizes and alignments of all types, as filled in by
+    /// [`Resolve::precompute_layout`].
+    #[cfg_attr(feature = "serde", serde(skip))]
+    layout: Option<SizeAlign>,
 }
 
 /// A WIT package within a `Resolve`.
@@ -103,6 +139,14 @@ pub struct Package {
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Docs::is_empty"))]
     pub docs: Docs,
 
+    /// The source text of `docs`, as captured in
+    /// [`UnresolvedPackage::leading_comments`].
+    ///
+    /// Printers emit this in place of `docs` when it's set, so it should be
+    /// cleared when `docs` is changed.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub leading_comments: Option<String>,
+
     /// All interfaces contained in this packaged, keyed by the interface's
     /// name.
     #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_id_map"))]
@@ -131,7 +175,7 @@ impl PackageSourceMap {
     }
 
     fn from_source_maps(
-        source_maps: Vec<SourceMap>,
+        source_maps: Vec<Arc<SourceMap>>,
         package_id_to_source_map_idx: BTreeMap<PackageId, usize>,
     ) -> PackageSourceMap {
         for (package_id, idx) in &package_id_to_source_map_idx {
@@ -186,13 +230,100 @@ enum ParsedFile {
     Unresolved(UnresolvedPackageGroup),
 }
 
+/// Finds the first `future`, `stream`, or `error-context` type that's
+/// visited, which require the async features of the component model.
+struct AsyncTypes<'a> {
+    resolve: &'a Resolve,
+    seen: HashSet<TypeId>,
+    found: Option<Type>,
+}
+
+impl<'a> AsyncTypes<'a> {
+    fn new(resolve: &'a Resolve) -> Self {
+        AsyncTypes {
+            resolve,
+            seen: HashSet::new(),
+            found: None,
+        }
+    }
+}
+
+impl TypeIdVisitor for AsyncTypes<'_> {
+    fn before_visit_type_id(&mut self, id: TypeId) -> bool {
+        if self.found.is_none()
+            && matches!(
+                self.resolve.types[id].kind,
+                TypeDefKind::Future(_) | TypeDefKind::Stream(_)
+            )
+        {
+            self.found = Some(Type::Id(id));
+        }
+        self.found.is_none() && self.seen.insert(id)
+    }
+
+    fn visit_type(&mut self, resolve: &Resolve, ty: &Type) {
+        match ty {
+            Type::Id(id) => self.visit_type_id(resolve, *id),
+            Type::ErrorContext if self.found.is_none() => self.found = Some(Type::ErrorContext),
+            _ => {}
+        }
+    }
+}
+
+/// Replaces `dst` with `src` if `src` has any contents, as part of
+/// [`Resolve::import_docs_from`].
+fn copy_docs(dst: &mut Docs, src: &Docs, count: &mut usize) {
+    if src.contents.is_some() {
+        *dst = src.clone();
+        *count += 1;
+    }
+}
+
+/// Looks up the package `name` in `packages`.
+///
+/// If `unversioned` is set then, like [`Resolve::select_world`], an
+/// unversioned `name` is also allowed to refer to a versioned package so long
+/// as only one version of that package is present. An error describing the
+/// candidates is returned if there are several.
+fn lookup_package_name<'a, T>(
+    packages: impl IntoIterator<Item = (&'a PackageName, T)>,
+    name: &PackageName,
+    unversioned: bool,
+) -> Result<Option<T>, String> {
+    let mut candidates = Vec::new();
+    for (pkg, value) in packages {
+        if pkg == name {
+            return Ok(Some(value));
+        }
+        if unversioned
+            && name.version.is_none()
+            && pkg.namespace == name.namespace
+            && pkg.name == name.name
+        {
+            candidates.push((pkg, value));
+        }
+    }
+    if candidates.len() > 1 {
+        let versions = candidates
+            .iter()
+            .map(|(pkg, _)| pkg.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Err(format!(
+            "package `{name}` is ambiguous as multiple versions are present: {versions}"
+        ));
+    }
+    Ok(candidates.pop().map(|(_, value)| value))
+}
+
 /// Visitor helper for performing topological sort on a group of packages.
 fn visit<'a>(
     pkg: &'a UnresolvedPackage,
     pkg_details_map: &'a BTreeMap<PackageName, (UnresolvedPackage, usize)>,
     order: &mut IndexSet<PackageName>,
     visiting: &mut HashSet<&'a PackageName>,
-    source_maps: &[SourceMap],
+    source_maps: &[Arc<SourceMap>],
+    unversioned: bool,
 ) -> Result<()> {
     if order.contains(&pkg.name) {
         return Ok(());
@@ -207,9 +338,18 @@ fn visit<'a>(
                     if !visiting.insert(dep) {
                         bail!(Error::new(span, "package depends on itself"));
                     }
-                    if let Some(dep) = pkg_details_map.get(dep) {
+                    // Ambiguous references are reported once the package is
+                    // resolved.
+                    if let Ok(Some(dep)) = lookup_package_name(pkg_details_map, dep, unversioned) {
                         let (dep_pkg, _) = dep;
-                        visit(dep_pkg, pkg_details_map, order, visiting, source_maps)?;
+                        visit(
+                            dep_pkg,
+                            pkg_details_map,
+                            order,
+                            visiting,
+                            source_maps,
+                            unversioned,
+                        )?;
                     }
                     assert!(visiting.remove(dep));
                 }
@@ -227,6 +367,24 @@ impl Resolve {
         Resolve::default()
     }
 
+    /// Returns whether items gated behind `@unstable(feature = ...)` with this
+    /// `feature` are included when packages are pushed into this [`Resolve`].
+    ///
+    /// This is `true` for every feature if [`Resolve::all_features`] is set,
+    /// and otherwise only for those listed in [`Resolve::features`].
+    pub fn feature_enabled(&self, feature: &str) -> bool {
+        self.all_features || self.features.contains(feature)
+    }
+
+    /// Returns the features which were explicitly enabled through
+    /// [`Resolve::features`], in the order they were added.
+    ///
+    /// Note that when [`Resolve::all_features`] is set every feature is
+    /// enabled, even those which aren't returned here.
+    pub fn enabled_features(&self) -> impl Iterator<Item = &str> + '_ {
+        self.features.iter().map(|f| f.as_str())
+    }
+
     /// Parse WIT packages from the input `path`.
     ///
     /// The input `path` can be one of:
@@ -287,7 +445,8 @@ impl Resolve {
                 source_map,
             } = group;
             let i = source_maps.len();
-            source_maps.push(source_map);
+            // Shared by all packages in the group for their item spans.
+            source_maps.push(Arc::new(source_map));
 
             for pkg in nested.into_iter().chain([main]) {
                 let name = pkg.name.clone();
@@ -328,6 +487,7 @@ package {name} is defined in two different locations:\n\
                 &mut order,
                 &mut visiting,
                 &source_maps,
+                self.resolve_unversioned_deps,
             )?;
         }
 
@@ -340,7 +500,7 @@ package {name} is defined in two different locations:\n\
             let (pkg, source_map_index) = pkg_details_map.remove(&name).unwrap();
             let source_map = &source_maps[source_map_index];
             let is_main = pkg.name == main_name;
-            let id = self.push(pkg, source_map)?;
+            let id = self.push_shared(pkg, source_map)?;
             if is_main {
                 assert!(main_pkg_id.is_none());
                 main_pkg_id = Some(id);
@@ -524,7 +684,18 @@ package {name} is defined in two different locations:\n\
         unresolved: UnresolvedPackage,
         source_map: &SourceMap,
     ) -> Result<PackageId> {
-        let ret = source_map.rewrite_error(|| Remap::default().append(self, unresolved));
+        self.push_shared(unresolved, &Arc::new(source_map.clone()))
+    }
+
+    /// Same as [`Resolve::push`] except that `source_map` is shared with the
+    /// item spans of the new package rather than copied.
+    fn push_shared(
+        &mut self,
+        unresolved: UnresolvedPackage,
+        source_map: &Arc<SourceMap>,
+    ) -> Result<PackageId> {
+        let ret =
+            source_map.rewrite_error(|| Remap::default().append(self, unresolved, source_map));
         if ret.is_ok() {
             #[cfg(debug_assertions)]
             self.assert_valid();
@@ -555,6 +726,559 @@ package {name} is defined in two different locations:\n\
         self.push_group(UnresolvedPackageGroup::parse(path.as_ref(), contents)?)
     }
 
+    /// Same as [`Resolve::push`] except that `policy` dictates what happens
+    /// when a package of the same name, including version, is already present
+    /// in this `Resolve`.
+    ///
+    /// With [`DuplicatePolicy::MergeIfIdentical`] the identifier of the
+    /// existing package is returned if the two packages are structurally the
+    /// same, and an error is returned otherwise. With
+    /// [`DuplicatePolicy::Replace`] the new package is inserted and lookups by
+    /// name afterwards resolve to it. Packages which already depended on the
+    /// previous package continue to refer to the previous copy.
+    ///
+    /// When no package of the same name is present this behaves the same as
+    /// [`Resolve::push`] regardless of `policy`.
+    pub fn push_with_policy(
+        &mut self,
+        unresolved: UnresolvedPackage,
+        source_map: &SourceMap,
+        policy: DuplicatePolicy,
+    ) -> Result<PackageId> {
+        let existing = match self.package_names.get(&unresolved.name) {
+            Some(id) => *id,
+            None => return self.push(unresolved, source_map),
+        };
+        let name = unresolved.name.clone();
+        match policy {
+            DuplicatePolicy::Error => self.push(unresolved, source_map),
+            DuplicatePolicy::MergeIfIdentical => {
+                // Resolve the new package in a scratch copy of `self` so the
+                // two copies can be compared side-by-side without modifying
+                // `self`.
+                let mut scratch = self.clone();
+                scratch.package_names.shift_remove(&name);
+                let new = scratch.push(unresolved, source_map)?;
+                scratch
+                    .assert_packages_identical(new, existing)
+                    .with_context(|| {
+                        format!("package `{name}` conflicts with the package already present")
+                    })?;
+                Ok(existing)
+            }
+            DuplicatePolicy::Replace => {
+                let mut scratch = self.clone();
+                scratch.package_names.shift_remove(&name);
+                let new = scratch.push(unresolved, source_map)?;
+                *self = scratch;
+                Ok(new)
+            }
+        }
+    }
+
+    /// Returns an error if packages `a` and `b` do not contain exactly the
+    /// same set of interfaces and worlds with matching contents.
+    fn assert_packages_identical(&self, a: PackageId, b: PackageId) -> Result<()> {
+        let a_pkg = &self.packages[a];
+        let b_pkg = &self.packages[b];
+        for (kind, a_names, b_names) in [
+            (
+                "interface",
+                a_pkg.interfaces.keys().collect::<Vec<_>>(),
+                b_pkg.interfaces.keys().collect::<Vec<_>>(),
+            ),
+            (
+                "world",
+                a_pkg.worlds.keys().collect(),
+                b_pkg.worlds.keys().collect(),
+            ),
+        ] {
+            for name in a_names.iter() {
+                if !b_names.contains(name) {
+                    bail!("{kind} `{name}` is not present in both packages");
+                }
+            }
+            for name in b_names.iter() {
+                if !a_names.contains(name) {
+                    bail!("{kind} `{name}` is not present in both packages");
+                }
+            }
+        }
+        MergeMap::new(self, self).build_package(a, b)?;
+        MergeMap::new(self, self).build_package(b, a)?;
+        Ok(())
+    }
+
+    /// Renders `ty` as it would be written in WIT.
+    ///
+    /// Named types are rendered as their name, escaped with `%` if it's a
+    /// keyword, and anonymous types such as `list<T>` are rendered
+    /// structurally. This is intended for diagnostics
+    /// and isn't guaranteed to be parseable, for example when a type refers
+    /// to something defined in another interface.
+    pub fn type_to_string(&self, ty: &Type) -> String {
+        crate::type_to_string(&self.types, ty)
+    }
+
+    /// Shorthand for [`Resolve::type_to_string`] on `Type::Id(id)`.
+    ///
+    /// Prefer [`Resolve::type_to_string`], which is the canonical way to
+    /// render types and also handles primitives. This returns the declared
+    /// name of `id` if it has one, and otherwise its structure such as
+    /// `list<u8>` or `own<file>`.
+    pub fn type_name(&self, id: TypeId) -> String {
+        self.type_to_string(&Type::Id(id))
+    }
+
+    /// Renders the declaration of the named type `id` as it would be written
+    /// in WIT, for example `record point { x: f32, y: f32 }`.
+    ///
+    /// The declaration is rendered on a single line without docs, which is
+    /// suitable for hover text and snippets. Types referenced by the
+    /// declaration are rendered as with [`Resolve::type_to_string`], and the
+    /// functions of a resource aren't included. Names which are keywords are
+    /// escaped with `%`, as in `record r { %type: u8 }`. Returns `None` for
+    /// anonymous types since they have no declaration.
+    pub fn type_declaration(&self, id: TypeId) -> Option<String> {
+        crate::type_declaration(&self.types, &self.types[id])
+    }
+
+    /// Compares the definition of `old_id` in `old` against `new_id` in
+    /// `new`, for example two versions of the same type from two versions of
+    /// a package.
+    ///
+    /// Records are compared field by field and variants, enums, and flags
+    /// case by case, matching fields and cases up by name. Types referred to
+    /// by fields, cases, and other definitions are compared by how they're
+    /// written in WIT, as with [`Resolve::type_to_string`], so changes to the
+    /// definition of a named type used by `old_id` are not included and
+    /// should be diffed separately. See [`TypeChange::is_breaking`] for which
+    /// changes are considered breaking.
+    pub fn diff_type(old: &Resolve, old_id: TypeId, new: &Resolve, new_id: TypeId) -> TypeDiff {
+        let (a, b) = (&old.types[old_id], &new.types[new_id]);
+        let mut changes = Vec::new();
+        if a.name != b.name {
+            changes.push(TypeChange::Renamed {
+                old: a.name.clone(),
+                new: b.name.clone(),
+            });
+        }
+
+        // Compares two lists of named items, where `ty` renders the payload
+        // of each item for comparison, if it has one.
+        fn diff_items<T>(
+            changes: &mut Vec<TypeChange>,
+            (old, new): (&[T], &[T]),
+            name: impl Fn(&T) -> &str,
+            ty: impl Fn(bool, &T) -> Option<String>,
+            kind: fn(ItemChange) -> TypeChange,
+        ) {
+            let find = |items: &[T], n: &str| items.iter().position(|i| name(i) == n);
+            for item in old.iter() {
+                match find(new, name(item)) {
+                    Some(i) => {
+                        let (a, b) = (ty(true, item), ty(false, &new[i]));
+                        if a != b {
+                            changes.push(kind(ItemChange::TypeChanged {
+                                name: name(item).to_string(),
+                                old: a,
+                                new: b,
+                            }));
+                        }
+                    }
+                    None => changes.push(kind(ItemChange::Removed(name(item).to_string()))),
+                }
+            }
+            for item in new.iter() {
+                if find(old, name(item)).is_none() {
+                    changes.push(kind(ItemChange::Added(name(item).to_string())));
+                }
+            }
+            let common = |a: &[T], b: &[T]| {
+                a.iter()
+                    .filter(|i| find(b, name(i)).is_some())
+                    .map(|i| name(i).to_string())
+                    .collect::<Vec<_>>()
+            };
+            if common(old, new) != common(new, old) {
+                changes.push(kind(ItemChange::Reordered));
+            }
+        }
+
+        let render = |is_old: bool, ty: &Type| match is_old {
+            true => old.type_to_string(ty),
+            false => new.type_to_string(ty),
+        };
+        match (&a.kind, &b.kind) {
+            (TypeDefKind::Record(a), TypeDefKind::Record(b)) => diff_items(
+                &mut changes,
+                (&a.fields, &b.fields),
+                |f| &f.name,
+                |is_old, f| Some(render(is_old, &f.ty)),
+                TypeChange::Field,
+            ),
+            (TypeDefKind::Variant(a), TypeDefKind::Variant(b)) => diff_items(
+                &mut changes,
+                (&a.cases, &b.cases),
+                |c| &c.name,
+                |is_old, c| c.ty.as_ref().map(|ty| render(is_old, ty)),
+                TypeChange::Case,
+            ),
+            (TypeDefKind::Enum(a), TypeDefKind::Enum(b)) => diff_items(
+                &mut changes,
+                (&a.cases, &b.cases),
+                |c| &c.name,
+                |_, _| None,
+                TypeChange::Case,
+            ),
+            (TypeDefKind::Flags(a), TypeDefKind::Flags(b)) => diff_items(
+                &mut changes,
+                (&a.flags, &b.flags),
+                |f| &f.name,
+                |_, _| None,
+                TypeChange::Flag,
+            ),
+            (a, b) if mem::discriminant(a) != mem::discriminant(b) => {
+                changes.push(TypeChange::KindChanged {
+                    old: a.as_str(),
+                    new: b.as_str(),
+                });
+            }
+            (a, b) => {
+                let a = crate::type_def_kind_to_string(&old.types, a);
+                let b = crate::type_def_kind_to_string(&new.types, b);
+                if a != b {
+                    changes.push(TypeChange::DefinitionChanged { old: a, new: b });
+                }
+            }
+        }
+        TypeDiff { changes }
+    }
+
+    /// Returns whether `a` and `b` are the same type once `type` aliases are
+    /// looked through.
+    ///
+    /// Records, variants, enums, flags, and resources are only equivalent to
+    /// themselves. All other types, such as `list<T>` or `own<T>`, are
+    /// compared structurally whether or not they're given a name, since in
+    /// WIT `type bytes = list<u8>` is just another way to write `list<u8>`.
+    pub fn types_equivalent(&self, a: Type, b: Type) -> bool {
+        match (self.dealias(a), self.dealias(b)) {
+            (Type::Id(a), Type::Id(b)) => {
+                if a == b {
+                    return true;
+                }
+                let (a, b) = (&self.types[a], &self.types[b]);
+                let opt = |a: &Option<Type>, b: &Option<Type>| match (a, b) {
+                    (Some(a), Some(b)) => self.types_equivalent(*a, *b),
+                    (None, None) => true,
+                    _ => false,
+                };
+                match (&a.kind, &b.kind) {
+                    (TypeDefKind::List(a), TypeDefKind::List(b))
+                    | (TypeDefKind::Option(a), TypeDefKind::Option(b)) => {
+                        self.types_equivalent(*a, *b)
+                    }
+                    (TypeDefKind::FixedSizeList(a, n), TypeDefKind::FixedSizeList(b, m)) => {
+                        n == m && self.types_equivalent(*a, *b)
+                    }
+                    (TypeDefKind::Result(a), TypeDefKind::Result(b)) => {
+                        opt(&a.ok, &b.ok) && opt(&a.err, &b.err)
+                    }
+                    (TypeDefKind::Tuple(a), TypeDefKind::Tuple(b)) => {
+                        a.types.len() == b.types.len()
+                            && a.types
+                                .iter()
+                                .zip(&b.types)
+                                .all(|(a, b)| self.types_equivalent(*a, *b))
+                    }
+                    (TypeDefKind::Handle(Handle::Own(a)), TypeDefKind::Handle(Handle::Own(b)))
+                    | (
+                        TypeDefKind::Handle(Handle::Borrow(a)),
+                        TypeDefKind::Handle(Handle::Borrow(b)),
+                    ) => self.types_equivalent(Type::Id(*a), Type::Id(*b)),
+                    (TypeDefKind::Future(a), TypeDefKind::Future(b))
+                    | (TypeDefKind::Stream(a), TypeDefKind::Stream(b)) => opt(a, b),
+                    _ => false,
+                }
+            }
+            (a, b) => a == b,
+        }
+    }
+
+    /// Follows `type` aliases from `ty` to the type they refer to.
+    fn dealias(&self, mut ty: Type) -> Type {
+        while let Type::Id(id) = ty {
+            match self.types[id].kind {
+                TypeDefKind::Type(next) => ty = next,
+                _ => break,
+            }
+        }
+        ty
+    }
+
+    /// Shortens chains of `type` aliases so each alias refers directly to the
+    /// end of its chain.
+    ///
+    /// For example with `type b = u32; type a = b;` the alias `a` is
+    /// rewritten to `type a = u32;`. Only aliases defined alongside the alias
+    /// being rewritten are skipped over, so chains never collapse across a
+    /// `use` of another interface or world and the printed WIT keeps the same
+    /// `use` statements. Skipped aliases themselves are left in place.
+    ///
+    /// Documentation on the skipped aliases would otherwise no longer be
+    /// reachable from the rewritten alias, so it's appended to the rewritten
+    /// alias's own docs in chain order, separated by blank lines. Paragraphs
+    /// which are already present aren't repeated.
+    pub fn collapse_type_aliases(&mut self) {
+        self.layout = None;
+        let ids = self.types.iter().map(|(id, _)| id).collect::<Vec<_>>();
+        for id in ids {
+            let owner = self.types[id].owner;
+            let mut target = match self.types[id].kind {
+                TypeDefKind::Type(Type::Id(next)) => next,
+                _ => continue,
+            };
+            let mut docs = Vec::new();
+            let mut ty = None;
+            while let TypeDefKind::Type(next) = self.types[target].kind {
+                if self.types[target].owner != owner {
+                    break;
+                }
+                if let Type::Id(next) = next {
+                    if self.types[next].owner != owner {
+                        break;
+                    }
+                }
+                if let Some(contents) = &self.types[target].docs.contents {
+                    docs.push(contents.clone());
+                }
+                ty = Some(next);
+                match next {
+                    Type::Id(next) if next != id => target = next,
+                    _ => break,
+                }
+            }
+            let Some(ty) = ty else { continue };
+
+            let def = &mut self.types[id];
+            def.kind = TypeDefKind::Type(ty);
+            for contents in docs {
+                for paragraph in contents.split("\n\n") {
+                    match &mut def.docs.contents {
+                        Some(existing) if existing.split("\n\n").any(|p| p == paragraph) => {}
+                        Some(existing) => {
+                            existing.push_str("\n\n");
+                            existing.push_str(paragraph);
+                        }
+                        None => def.docs.contents = Some(paragraph.to_string()),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Computes the size and alignment of every type, caching the result for
+    /// [`Resolve::layout`].
+    ///
+    /// This is worthwhile for code generators which query the layout of types
+    /// repeatedly. Adding types and the methods of `Resolve` which rewrite
+    /// types, such as [`Resolve::enums_as_variants`], discard the cache, but
+    /// it must be recomputed by calling this again after directly modifying
+    /// the definition of an existing type in [`Resolve::types`].
+    pub fn precompute_layout(&mut self) {
+        let mut layout = SizeAlign::default();
+        layout.fill(self);
+        self.layout = Some(layout);
+    }
+
+    /// Returns the size and alignment of all types in this `Resolve`.
+    ///
+    /// The result of [`Resolve::precompute_layout`] is returned if it's still
+    /// current, and otherwise the layout is computed from scratch.
+    pub fn layout(&self) -> Cow<'_, SizeAlign> {
+        match &self.layout {
+            Some(layout) if layout.covers(self) => Cow::Borrowed(layout),
+            _ => {
+                let mut layout = SizeAlign::default();
+                layout.fill(self);
+                Cow::Owned(layout)
+            }
+        }
+    }
+
+    /// Returns the number of padding bytes in the canonical ABI layout of
+    /// `record` on 32-bit platforms.
+    ///
+    /// This is the padding inserted before fields to align them plus the
+    /// padding at the end which rounds the size up to the record's alignment.
+    /// Ordering fields from largest to smallest alignment minimizes it.
+    pub fn record_padding(&self, record: &Record) -> usize {
+        let layout = self.layout();
+        let fields = record.fields.iter().map(|f| &f.ty);
+        let mut end = 0;
+        let mut padding = 0;
+        for (offset, ty) in layout.field_offsets(fields.clone()) {
+            padding += offset.size_wasm32() - end;
+            end = offset.size_wasm32() + layout.size(ty).size_wasm32();
+        }
+        padding + layout.record(fields).size.size_wasm32() - end
+    }
+
+    /// Converts each `enum` used by `world` into the equivalent `variant`
+    /// whose cases have no payloads.
+    ///
+    /// Cases keep their order, and so their discriminants, along with their
+    /// docs, so the canonical ABI of the types is unchanged. This is useful
+    /// for backends which only implement variants. Types are found as with
+    /// [`LiveTypes::add_world`].
+    pub fn enums_as_variants(&mut self, world: WorldId) {
+        self.layout = None;
+        let mut live = LiveTypes::default();
+        live.add_world(self, world);
+        for id in live.iter() {
+            let kind = &mut self.types[id].kind;
+            if let TypeDefKind::Enum(e) = kind {
+                let cases = mem::take(&mut e.cases)
+                    .into_iter()
+                    .map(|case| Case {
+                        name: case.name,
+                        ty: None,
+                        docs: case.docs,
+                    })
+                    .collect();
+                *kind = TypeDefKind::Variant(Variant { cases });
+            }
+        }
+    }
+
+    /// The inverse of [`Resolve::enums_as_variants`], converting each
+    /// `variant` used by `world` whose cases all lack payloads into an
+    /// `enum`.
+    ///
+    /// Variants with at least one payload are left untouched, as are
+    /// variants without any cases since an `enum` must have a case.
+    pub fn variants_as_enums(&mut self, world: WorldId) {
+        self.layout = None;
+        let mut live = LiveTypes::default();
+        live.add_world(self, world);
+        for id in live.iter() {
+            let kind = &mut self.types[id].kind;
+            if let TypeDefKind::Variant(v) = kind {
+                if v.cases.is_empty() || v.cases.iter().any(|c| c.ty.is_some()) {
+                    continue;
+                }
+                let cases = mem::take(&mut v.cases)
+                    .into_iter()
+                    .map(|case| EnumCase {
+                        name: case.name,
+                        docs: case.docs,
+                    })
+                    .collect();
+                *kind = TypeDefKind::Enum(Enum { cases });
+            }
+        }
+    }
+
+    /// Checks the names used throughout this [`Resolve`] for conflicts which
+    /// would prevent it from being published.
+    ///
+    /// WIT parsing already rejects most of these, but a [`Resolve`] which was
+    /// decoded from a binary or edited in memory can still contain them. All
+    /// violations are collected and returned together rather than stopping at
+    /// the first one found. The checks performed are:
+    ///
+    /// * types within an interface or world with the same name,
+    /// * functions within an interface or world with the same name, where
+    ///   `x` and `[async]x` are considered the same,
+    /// * world imports or exports whose names collide, using the same
+    ///   deduplication as [`WorldKey`].
+    ///
+    /// Identifiers which are WIT keywords, such as a field named `type`, are
+    /// legal and aren't reported: they're stored unescaped and only need to
+    /// be written as `%type` when printed as WIT.
+    pub fn validate_names(&self) -> Result<(), Vec<NameError>> {
+        let mut errors = Vec::new();
+
+        for (id, iface) in self.interfaces.iter() {
+            let owner = self
+                .id_of(id)
+                .unwrap_or_else(|| "<anonymous interface>".to_string());
+            self.validate_type_names(&owner, iface.types.values(), &mut errors);
+            self.validate_function_names(&owner, iface.functions.values(), &mut errors);
+        }
+
+        for (id, world) in self.worlds.iter() {
+            let owner = match world.package {
+                Some(pkg) => self.id_of_name(pkg, &world.name),
+                None => world.name.clone(),
+            };
+
+            for items in [&world.imports, &world.exports] {
+                let names = items
+                    .keys()
+                    .map(|key| self.name_world_key(key))
+                    .collect::<Vec<_>>();
+                let mut seen = HashSet::new();
+                let mut types = Vec::new();
+                for ((_, item), name) in items.iter().zip(&names) {
+                    if !seen.insert(crate::KebabDedupe::from(name.as_str())) {
+                        errors.push(NameError::DuplicateWorldKey {
+                            world: id,
+                            name: name.clone(),
+                        });
+                    }
+                    if let WorldItem::Type(t) = item {
+                        types.push(*t);
+                    }
+                }
+                self.validate_type_names(&owner, types.iter(), &mut errors);
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn validate_type_names<'a>(
+        &self,
+        owner: &str,
+        types: impl Iterator<Item = &'a TypeId>,
+        errors: &mut Vec<NameError>,
+    ) {
+        let mut seen = HashSet::new();
+        for id in types {
+            let ty = &self.types[*id];
+            let Some(name) = &ty.name else { continue };
+            if !seen.insert(name.as_str()) {
+                errors.push(NameError::DuplicateType {
+                    owner: owner.to_string(),
+                    name: name.clone(),
+                });
+            }
+        }
+    }
+
+    fn validate_function_names<'a>(
+        &self,
+        owner: &str,
+        funcs: impl IntoIterator<Item = &'a Function>,
+        errors: &mut Vec<NameError>,
+    ) {
+        let mut seen = HashSet::new();
+        for func in funcs {
+            if !seen.insert(crate::KebabDedupe::from(func.name.as_str())) {
+                errors.push(NameError::DuplicateFunction {
+                    owner: owner.to_string(),
+                    name: func.name.clone(),
+                });
+            }
+        }
+    }
+
     pub fn all_bits_valid(&self, ty: &Type) -> bool {
         match ty {
             Type::U8
@@ -599,6 +1323,119 @@ package {name} is defined in two different locations:\n\
         }
     }
 
+    /// Returns whether `ty` is either `string` or `list<char>`, possibly
+    /// through type aliases.
+    ///
+    /// Both of these represent a sequence of unicode scalar values, but note
+    /// that they have different canonical ABI representations. A `string` is
+    /// lifted and lowered as encoded bytes (e.g. utf-8) while `list<char>` is a
+    /// list of 32-bit values.
+    pub fn is_stringlike(&self, ty: &Type) -> bool {
+        match ty {
+            Type::String => true,
+            Type::Id(id) => match &self.types[*id].kind {
+                TypeDefKind::Type(t) => self.is_stringlike(t),
+                TypeDefKind::List(Type::Char) => true,
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Rewrites all uses of `list<char>` in this `Resolve` to `string`.
+    ///
+    /// This is intended for tools which treat the two as the same type and
+    /// only wish to implement one of them. Note that this changes the
+    /// canonical ABI of any function using `list<char>`, see
+    /// [`Resolve::is_stringlike`], so components built against the normalized
+    /// WIT are not compatible with the original.
+    ///
+    /// Named types such as `type s = list<char>` become aliases of `string`
+    /// and anonymous `list<char>` types are replaced with `string` wherever
+    /// they're referenced.
+    pub fn normalize_char_lists(&mut self) {
+        self.layout = None;
+        let mut anonymous = HashSet::new();
+        for (id, ty) in self.types.iter_mut() {
+            if let TypeDefKind::List(Type::Char) = ty.kind {
+                if ty.name.is_some() {
+                    ty.kind = TypeDefKind::Type(Type::String);
+                } else {
+                    anonymous.insert(id);
+                }
+            }
+        }
+        if anonymous.is_empty() {
+            return;
+        }
+        self.visit_types_mut(&mut |ty| {
+            if let Type::Id(id) = ty {
+                if anonymous.contains(id) {
+                    *ty = Type::String;
+                }
+            }
+        });
+    }
+
+    /// Invokes `f` with every [`Type`] referenced from a type definition or a
+    /// function signature within this `Resolve`.
+    fn visit_types_mut(&mut self, f: &mut dyn FnMut(&mut Type)) {
+        self.layout = None;
+        fn visit_func(func: &mut Function, f: &mut dyn FnMut(&mut Type)) {
+            for (_, ty) in func.params.iter_mut() {
+                f(ty);
+            }
+            if let Some(ty) = &mut func.result {
+                f(ty);
+            }
+        }
+
+        for (_, ty) in self.types.iter_mut() {
+            match &mut ty.kind {
+                TypeDefKind::Type(t)
+                | TypeDefKind::List(t)
+                | TypeDefKind::FixedSizeList(t, ..)
+                | TypeDefKind::Option(t)
+                | TypeDefKind::Future(Some(t))
+                | TypeDefKind::Stream(Some(t)) => f(t),
+                TypeDefKind::Record(r) => r.fields.iter_mut().for_each(|field| f(&mut field.ty)),
+                TypeDefKind::Tuple(t) => t.types.iter_mut().for_each(|t| f(t)),
+                TypeDefKind::Variant(v) => v
+                    .cases
+                    .iter_mut()
+                    .filter_map(|c| c.ty.as_mut())
+                    .for_each(|t| f(t)),
+                TypeDefKind::Result(r) => {
+                    if let Some(t) = &mut r.ok {
+                        f(t);
+                    }
+                    if let Some(t) = &mut r.err {
+                        f(t);
+                    }
+                }
+                TypeDefKind::Handle(_)
+                | TypeDefKind::Resource
+                | TypeDefKind::Flags(_)
+                | TypeDefKind::Enum(_)
+                | TypeDefKind::Future(None)
+                | TypeDefKind::Stream(None)
+                | TypeDefKind::Unknown => {}
+            }
+        }
+        for (_, iface) in self.interfaces.iter_mut() {
+            for func in iface.functions.values_mut() {
+                visit_func(func, f);
+            }
+        }
+        for (_, world) in self.worlds.iter_mut() {
+            for item in world.imports.values_mut().chain(world.exports.values_mut()) {
+                if let WorldItem::Function(func) = item {
+                    visit_func(func, f);
+                }
+            }
+        }
+    }
+
     /// Merges all the contents of a different `Resolve` into this one. The
     /// `Remap` structure returned provides a mapping from all old indices to
     /// new indices
@@ -654,6 +1491,9 @@ package {name} is defined in two different locations:\n\
             interfaces,
             packages,
             package_names,
+            mut interface_function_offsets,
+            item_spans,
+            include_overrides,
             features: _,
             ..
         } = resolve;
@@ -687,7 +1527,11 @@ package {name} is defined in two different locations:\n\
                     log::debug!("moving interface {:?}", iface.name);
                     moved_interfaces.push(id);
                     remap.update_interface(self, &mut iface, None)?;
-                    self.interfaces.alloc(iface)
+                    let new_id = self.interfaces.alloc(iface);
+                    if let Some(offsets) = interface_function_offsets.remove(&id) {
+                        self.interface_function_offsets.insert(new_id, offsets);
+                    }
+                    new_id
                 }
             };
             assert_eq!(remap.interfaces.len(), id.index());
@@ -764,6 +1608,16 @@ package {name} is defined in two different locations:\n\
                 assert_eq!(prev, id);
             }
         }
+        for (item, span) in item_spans {
+            if let Some(item) = remap.map_item_ref(&item) {
+                self.item_spans.entry(item).or_insert(span);
+            }
+        }
+        for (world, overrides) in include_overrides {
+            if let Some((world, overrides)) = remap.map_overrides(world, overrides) {
+                self.include_overrides.entry(world).or_insert(overrides);
+            }
+        }
 
         // Fixup all "parent" links now.
         //
@@ -817,7 +1671,46 @@ package {name} is defined in two different locations:\n\
         Ok(remap)
     }
 
-    fn update_world_imports_stability(
+    /// Same as [`Resolve::merge`], but additionally returns which package each
+    /// type and interface of `resolve` came from.
+    ///
+    /// The returned [`MergeProvenance`] is keyed by ids within `self` after
+    /// the merge. Items of `resolve` which were unioned with an existing item
+    /// in `self` are recorded under the id of that existing item.
+    pub fn merge_detailed(&mut self, resolve: Resolve) -> Result<(Remap, MergeProvenance)> {
+        let package_name = |pkg: Option<PackageId>| pkg.map(|p| resolve.packages[p].name.clone());
+        let interfaces = resolve
+            .interfaces
+            .iter()
+            .map(|(_, iface)| package_name(iface.package))
+            .collect::<Vec<_>>();
+        let types = resolve
+            .types
+            .iter()
+            .map(|(_, ty)| match ty.owner {
+                TypeOwner::Interface(i) => package_name(resolve.interfaces[i].package),
+                TypeOwner::World(w) => package_name(resolve.worlds[w].package),
+                TypeOwner::None => None,
+            })
+            .collect::<Vec<_>>();
+
+        let remap = self.merge(resolve)?;
+
+        let mut provenance = MergeProvenance::default();
+        for (id, name) in remap.interfaces.iter().zip(interfaces) {
+            if let (Some(id), Some(name)) = (id, name) {
+                provenance.interfaces.insert(*id, name);
+            }
+        }
+        for (id, name) in remap.types.iter().zip(types) {
+            if let (Some(id), Some(name)) = (id, name) {
+                provenance.types.insert(*id, name);
+            }
+        }
+        Ok((remap, provenance))
+    }
+
+    fn update_world_imports_stability(
         from_item: (&WorldKey, &WorldItem),
         into_items: &mut IndexMap<WorldKey, WorldItem>,
         interface_map: &HashMap<Id<Interface>, Id<Interface>>,
@@ -857,6 +1750,149 @@ package {name} is defined in two different locations:\n\
         }
     }
 
+    /// Copies documentation from items in `other` onto the items of the same
+    /// name in `self`, returning how many items had docs applied.
+    ///
+    /// Packages are matched by name, and items within them by their name
+    /// within their package, interface, or world. This includes the fields
+    /// and cases of types. Items in `other` without docs are skipped, as are
+    /// items that don't exist in `self`, so this can be used to restore docs
+    /// after a round-trip through a binary that didn't preserve them.
+    pub fn import_docs_from(&mut self, other: &Resolve) -> usize {
+        let mut count = 0;
+        let packages = self
+            .packages
+            .iter()
+            .filter_map(|(id, pkg)| Some((id, *other.package_names.get(&pkg.name)?)))
+            .collect::<Vec<_>>();
+        for (pkg, other_pkg) in packages {
+            copy_docs(
+                &mut self.packages[pkg].docs,
+                &other.packages[other_pkg].docs,
+                &mut count,
+            );
+            for (name, other_iface) in other.packages[other_pkg].interfaces.iter() {
+                if let Some(iface) = self.packages[pkg].interfaces.get(name).copied() {
+                    count += self.import_interface_docs(iface, other, *other_iface);
+                }
+            }
+            for (name, other_world) in other.packages[other_pkg].worlds.iter() {
+                if let Some(world) = self.packages[pkg].worlds.get(name).copied() {
+                    count += self.import_world_docs(world, other, *other_world);
+                }
+            }
+        }
+        count
+    }
+
+    fn import_interface_docs(
+        &mut self,
+        id: InterfaceId,
+        other: &Resolve,
+        from: InterfaceId,
+    ) -> usize {
+        let mut count = 0;
+        let from = &other.interfaces[from];
+        for (name, other_ty) in from.types.iter() {
+            if let Some(ty) = self.interfaces[id].types.get(name).copied() {
+                count += self.import_type_docs(ty, other, *other_ty);
+            }
+        }
+        let iface = &mut self.interfaces[id];
+        copy_docs(&mut iface.docs, &from.docs, &mut count);
+        for (name, other_func) in from.functions.iter() {
+            if let Some(func) = iface.functions.get_mut(name) {
+                copy_docs(&mut func.docs, &other_func.docs, &mut count);
+            }
+        }
+        count
+    }
+
+    fn import_world_docs(&mut self, id: WorldId, other: &Resolve, from: WorldId) -> usize {
+        let mut count = 0;
+        let from = &other.worlds[from];
+        copy_docs(&mut self.worlds[id].docs, &from.docs, &mut count);
+        for (key, other_item) in from.imports.iter().chain(from.exports.iter()) {
+            if !matches!(key, WorldKey::Name(_)) {
+                continue;
+            }
+            let world = &mut self.worlds[id];
+            let item = match world.imports.get_mut(key) {
+                Some(item) => Some(item),
+                None => world.exports.get_mut(key),
+            };
+            match (item, other_item) {
+                (Some(WorldItem::Function(func)), WorldItem::Function(other_func)) => {
+                    copy_docs(&mut func.docs, &other_func.docs, &mut count);
+                }
+                (Some(WorldItem::Type(ty)), WorldItem::Type(other_ty)) => {
+                    let ty = *ty;
+                    count += self.import_type_docs(ty, other, *other_ty);
+                }
+                (
+                    Some(WorldItem::Interface { id: iface, .. }),
+                    WorldItem::Interface {
+                        id: other_iface, ..
+                    },
+                ) => {
+                    let iface = *iface;
+                    count += self.import_interface_docs(iface, other, *other_iface);
+                }
+                _ => {}
+            }
+        }
+        count
+    }
+
+    fn import_type_docs(&mut self, id: TypeId, other: &Resolve, from: TypeId) -> usize {
+        let mut count = 0;
+        let from = &other.types[from];
+        let ty = &mut self.types[id];
+        copy_docs(&mut ty.docs, &from.docs, &mut count);
+        let mut copy_named = |dst: Vec<(&str, &mut Docs)>, src: Vec<(&str, &Docs)>| {
+            for (name, docs) in dst {
+                if let Some((_, src)) = src.iter().find(|(n, _)| *n == name) {
+                    copy_docs(docs, src, &mut count);
+                }
+            }
+        };
+        match (&mut ty.kind, &from.kind) {
+            (TypeDefKind::Record(a), TypeDefKind::Record(b)) => copy_named(
+                a.fields
+                    .iter_mut()
+                    .map(|f| (f.name.as_str(), &mut f.docs))
+                    .collect(),
+                b.fields
+                    .iter()
+                    .map(|f| (f.name.as_str(), &f.docs))
+                    .collect(),
+            ),
+            (TypeDefKind::Variant(a), TypeDefKind::Variant(b)) => copy_named(
+                a.cases
+                    .iter_mut()
+                    .map(|c| (c.name.as_str(), &mut c.docs))
+                    .collect(),
+                b.cases.iter().map(|c| (c.name.as_str(), &c.docs)).collect(),
+            ),
+            (TypeDefKind::Enum(a), TypeDefKind::Enum(b)) => copy_named(
+                a.cases
+                    .iter_mut()
+                    .map(|c| (c.name.as_str(), &mut c.docs))
+                    .collect(),
+                b.cases.iter().map(|c| (c.name.as_str(), &c.docs)).collect(),
+            ),
+            (TypeDefKind::Flags(a), TypeDefKind::Flags(b)) => copy_named(
+                a.flags
+                    .iter_mut()
+                    .map(|f| (f.name.as_str(), &mut f.docs))
+                    .collect(),
+                b.flags.iter().map(|f| (f.name.as_str(), &f.docs)).collect(),
+            ),
+            _ => {}
+        }
+        count
+    }
+
     /// Merges the world `from` into the world `into`.
     ///
     /// This will attempt to merge one world into another, unioning all of its
@@ -866,10 +1902,17 @@ package {name} is defined in two different locations:\n\
     /// producing a singular world that will be the final component's
     /// interface.
     ///
+    /// Items present in both worlds keep the [`Stability`] of `into`, except
+    /// that an `@unstable` item of `into` becomes `@since` if it's stable in
+    /// `from`. An item without an annotation, for example one decoded from a
+    /// binary, provides no information and never changes that of `into`. The
+    /// world `from` itself is left unmodified.
+    ///
     /// This operation can fail if the imports/exports overlap.
     pub fn merge_worlds(&mut self, from: WorldId, into: WorldId) -> Result<()> {
         let mut new_imports = Vec::new();
         let mut new_exports = Vec::new();
+        let mut stabilities = Vec::new();
 
         let from_world = &self.worlds[from];
         let into_world = &self.worlds[into];
@@ -890,6 +1933,9 @@ package {name} is defined in two different locations:\n\
                     log::trace!("info/from shared import on `{name_str}`");
                     self.merge_world_item(from_import, into_import)
                         .with_context(|| format!("failed to merge world import {name_str}"))?;
+                    if let Some(stability) = self.more_stable_world_item(from_import, into_import) {
+                        stabilities.push((true, name.clone(), stability));
+                    }
                 }
                 None => {
                     log::trace!("new import: `{name_str}`");
@@ -925,6 +1971,9 @@ package {name} is defined in two different locations:\n\
                     log::trace!("info/from shared export on `{name_str}`");
                     self.merge_world_item(from_export, into_export)
                         .with_context(|| format!("failed to merge world export {name_str}"))?;
+                    if let Some(stability) = self.more_stable_world_item(from_export, into_export) {
+                        stabilities.push((false, name.clone(), stability));
+                    }
                 }
                 None => {
                     log::trace!("new export `{name_str}`");
@@ -974,3192 +2023,9596 @@ package {name} is defined in two different locations:\n\
             assert!(prev.is_none());
         }
 
+        // Finally upgrade the stability of shared items.
+        for (import, name, stability) in stabilities {
+            let into_world = &mut self.worlds[into];
+            let items = if import {
+                &mut into_world.imports
+            } else {
+                &mut into_world.exports
+            };
+            match &mut items[&name] {
+                WorldItem::Interface { stability: s, .. } => *s = stability,
+                WorldItem::Function(f) => f.stability = stability,
+                WorldItem::Type(id) => self.types[*id].stability = stability,
+            }
+        }
+
         #[cfg(debug_assertions)]
         self.assert_valid();
         Ok(())
     }
 
-    fn merge_world_item(&self, from: &WorldItem, into: &WorldItem) -> Result<()> {
-        let mut map = MergeMap::new(self, self);
-        match (from, into) {
-            (WorldItem::Interface { id: from, .. }, WorldItem::Interface { id: into, .. }) => {
-                // If these imports are the same that can happen, for
-                // example, when both worlds to `import foo:bar/baz;`. That
-                // foreign interface will point to the same interface within
-                // `Resolve`.
-                if from == into {
-                    return Ok(());
-                }
+    /// Creates a new world named `new_name` whose imports and exports are the
+    /// union of those of the worlds `a` and `b`.
+    ///
+    /// The new world is placed in the same package as `a`, and neither `a`
+    /// nor `b` is modified. Items present in both worlds under the same
+    /// [`WorldKey`] must be the same, and otherwise an error is returned. See
+    /// [`Resolve::merge_worlds`] for more details about how worlds are
+    /// combined.
+    pub fn union_worlds(&mut self, a: WorldId, b: WorldId, new_name: &str) -> Result<WorldId> {
+        let package = self.worlds[a]
+            .package
+            .ok_or_else(|| anyhow!("world `{}` is not in a package", self.worlds[a].name))?;
+        if self.packages[package].worlds.contains_key(new_name) {
+            bail!(
+                "world `{new_name}` already exists in package `{}`",
+                self.packages[package].name
+            );
+        }
 
-                // .. otherwise this MUST be a case of
-                // `import foo: interface { ... }`. If `from != into` but
-                // both `from` and `into` have the same name then the
-                // `WorldKey::Interface` case is ruled out as otherwise
-                // they'd have different names.
-                //
-                // In the case of an anonymous interface all we can do is
-                // ensure that the interfaces both match, so use `MergeMap`
-                // for that.
-                map.build_interface(*from, *into)
-                    .context("failed to merge interfaces")?;
-            }
+        let id = self.worlds.alloc(World {
+            name: new_name.to_string(),
+            imports: Default::default(),
+            exports: Default::default(),
+            package: Some(package),
+            docs: Default::default(),
+            stability: Default::default(),
+            includes: Default::default(),
+            include_names: Default::default(),
+        });
+        self.packages[package]
+            .worlds
+            .insert(new_name.to_string(), id);
 
-            // Like `WorldKey::Name` interfaces for functions and types the
-            // structure is asserted to be the same.
-            (WorldItem::Function(from), WorldItem::Function(into)) => {
-                map.build_function(from, into)
-                    .context("failed to merge functions")?;
-            }
-            (WorldItem::Type(from), WorldItem::Type(into)) => {
-                map.build_type_id(*from, *into)
-                    .context("failed to merge types")?;
-            }
+        let result = self.merge_worlds(a, id).and_then(|()| {
+            self.merge_worlds(b, id).with_context(|| {
+                format!(
+                    "failed to union world `{}` with world `{}`",
+                    self.worlds[a].name, self.worlds[b].name
+                )
+            })
+        });
+        if let Err(e) = result {
+            // Detach the partially-built world from its package so it's not
+            // visible to any further operations.
+            self.packages[package].worlds.shift_remove(new_name);
+            self.worlds[id].package = None;
+            return Err(e);
+        }
+        Ok(id)
+    }
 
-            // Kind-level mismatches are caught here.
-            (WorldItem::Interface { .. }, _)
-            | (WorldItem::Function { .. }, _)
-            | (WorldItem::Type { .. }, _) => {
-                bail!("different kinds of items");
-            }
+    /// Creates a new world which imports interface `from` and exports
+    /// interface `to`, suitable for a component bridging between two versions
+    /// of the same interface.
+    ///
+    /// Each function in `to` which is also present in `from` must have the
+    /// same signature in both, and otherwise an error is returned. Functions
+    /// which are only present in `to` are left for the adapter to implement
+    /// itself. Any interfaces needed by `from` or `to` are imported as well.
+    ///
+    /// The new world is named `{to}-adapter`, where `{to}` is the name of the
+    /// interface `to`, and is placed in the same package as `to`.
+    pub fn generate_version_adapter_world(
+        &mut self,
+        from: InterfaceId,
+        to: InterfaceId,
+    ) -> Result<WorldId> {
+        let from_iface = &self.interfaces[from];
+        let to_iface = &self.interfaces[to];
+        let (Some(_), Some(to_name)) = (&from_iface.name, &to_iface.name) else {
+            bail!("cannot adapt between anonymous interfaces");
+        };
+        let (Some(_), Some(package)) = (from_iface.package, to_iface.package) else {
+            bail!("cannot adapt between interfaces which aren't in a package");
+        };
+
+        for (name, to_func) in to_iface.functions.iter() {
+            let Some(from_func) = from_iface.functions.get(name) else {
+                continue;
+            };
+            MergeMap::new(self, self)
+                .build_function(from_func, to_func)
+                .with_context(|| {
+                    format!(
+                        "function `{name}` differs between interface `{}` and `{}`",
+                        self.id_of(from).unwrap(),
+                        self.id_of(to).unwrap(),
+                    )
+                })?;
         }
-        assert!(map.interfaces_to_add.is_empty());
-        assert!(map.worlds_to_add.is_empty());
-        Ok(())
+
+        let name = format!("{to_name}-adapter");
+        if self.packages[package].worlds.contains_key(&name) {
+            bail!(
+                "world `{name}` already exists in package `{}`",
+                self.packages[package].name
+            );
+        }
+        let import = WorldItem::Interface {
+            id: from,
+            stability: Default::default(),
+        };
+        let export = WorldItem::Interface {
+            id: to,
+            stability: Default::default(),
+        };
+        let id = self.worlds.alloc(World {
+            name: name.clone(),
+            imports: [(WorldKey::Interface(from), import)].into_iter().collect(),
+            exports: [(WorldKey::Interface(to), export)].into_iter().collect(),
+            package: Some(package),
+            docs: Default::default(),
+            stability: Default::default(),
+            includes: Default::default(),
+            include_names: Default::default(),
+        });
+        self.packages[package].worlds.insert(name.clone(), id);
+
+        if let Err(e) = self.elaborate_world(id) {
+            self.packages[package].worlds.shift_remove(&name);
+            self.worlds[id].package = None;
+            return Err(e);
+        }
+
+        #[cfg(debug_assertions)]
+        self.assert_valid();
+        Ok(id)
     }
 
-    /// This method ensures that the world export of `name` and `item` can be
-    /// added to the world `into` without changing the meaning of `into`.
-    ///
-    /// All dependencies of world exports must either be:
+    /// Creates a new world named `name` which imports only `interface`,
+    /// along with any interfaces it transitively depends on.
     ///
-    /// * An export themselves
-    /// * An import with all transitive dependencies of the import also imported
+    /// This is useful for working with a single interface in isolation, for
+    /// example generating bindings or a stub component for it. The new world
+    /// is placed in the same package as `interface`.
     ///
-    /// It's not possible to depend on an import which then also depends on an
-    /// export at some point, for example. This method ensures that if `name`
-    /// and `item` are added that this property is upheld.
-    fn ensure_can_add_world_export(
-        &self,
-        into: &World,
-        name: &WorldKey,
-        item: &WorldItem,
-        must_be_imported: &HashMap<InterfaceId, WorldKey>,
-    ) -> Result<()> {
-        assert!(!into.exports.contains_key(name));
-        let name = self.name_world_key(name);
+    /// If the new world fails to elaborate, an error is returned. The package
+    /// is left unchanged in that case, but the new world has already been
+    /// allocated. It stays in [`Resolve::worlds`] with its `package` set to
+    /// `None`, because worlds can't be removed from the arena.
+    pub fn create_world_importing(
+        &mut self,
+        interface: InterfaceId,
+        name: &str,
+    ) -> Result<WorldId> {
+        let iface = &self.interfaces[interface];
+        if iface.name.is_none() {
+            bail!("cannot create a world importing an anonymous interface");
+        }
+        let Some(package) = iface.package else {
+            bail!("cannot create a world importing an interface which isn't in a package");
+        };
+        if self.packages[package].worlds.contains_key(name) {
+            bail!(
+                "world `{name}` already exists in package `{}`",
+                self.packages[package].name
+            );
+        }
+        let import = WorldItem::Interface {
+            id: interface,
+            stability: Default::default(),
+        };
+        let id = self.worlds.alloc(World {
+            name: name.to_string(),
+            imports: [(WorldKey::Interface(interface), import)]
+                .into_iter()
+                .collect(),
+            exports: Default::default(),
+            package: Some(package),
+            docs: Default::default(),
+            stability: Default::default(),
+            includes: Default::default(),
+            include_names: Default::default(),
+        });
+        self.packages[package].worlds.insert(name.to_string(), id);
 
-        // First make sure that all of this item's dependencies are either
-        // exported or the entire chain of imports rooted at that dependency are
-        // all imported.
-        for dep in self.world_item_direct_deps(item) {
-            if into.exports.contains_key(&WorldKey::Interface(dep)) {
-                continue;
-            }
-            self.ensure_not_exported(into, dep)
-                .with_context(|| format!("failed validating export of `{name}`"))?;
+        if let Err(e) = self.elaborate_world(id) {
+            self.packages[package].worlds.shift_remove(name);
+            self.worlds[id].package = None;
+            return Err(e);
         }
 
-        // Second make sure that this item, if it's an interface, will not alter
-        // the meaning of the preexisting world by ensuring that it's not in the
-        // set of "must be imported" items.
-        if let WorldItem::Interface { id, .. } = item {
-            if let Some(export) = must_be_imported.get(&id) {
-                let export_name = self.name_world_key(export);
+        #[cfg(debug_assertions)]
+        self.assert_valid();
+        Ok(id)
+    }
+
+    /// Renames the world `id` to `new_name`, updating both [`World::name`]
+    /// and the world's entry in its package.
+    ///
+    /// The world keeps its position within the package. Returns an error if
+    /// `new_name` isn't a valid identifier or if the package already has a
+    /// world with that name.
+    pub fn rename_world(&mut self, id: WorldId, new_name: &str) -> Result<()> {
+        validate_id(new_name).with_context(|| format!("invalid world name `{new_name}`"))?;
+        let world = &self.worlds[id];
+        if world.name == new_name {
+            return Ok(());
+        }
+        let old = world.name.clone();
+        if let Some(pkg) = world.package {
+            let pkg = &mut self.packages[pkg];
+            if pkg.worlds.contains_key(new_name) {
                 bail!(
-                    "export `{export_name}` depends on `{name}` \
-                     previously as an import which will change meaning \
-                     if `{name}` is added as an export"
+                    "world `{new_name}` already exists in package `{}`",
+                    pkg.name
                 );
             }
+            pkg.worlds = rename_key(mem::take(&mut pkg.worlds), &old, new_name);
         }
+        self.worlds[id].name = new_name.to_string();
 
+        #[cfg(debug_assertions)]
+        self.assert_valid();
         Ok(())
     }
 
-    fn ensure_not_exported(&self, world: &World, id: InterfaceId) -> Result<()> {
-        let key = WorldKey::Interface(id);
-        let name = self.name_world_key(&key);
-        if world.exports.contains_key(&key) {
-            bail!(
-                "world exports `{name}` but it's also transitively used by an \
-                     import \
-                   which means that this is not valid"
-            )
+    /// Renames the interface `id` to `new_name`, updating both
+    /// [`Interface::name`] and the interface's entry in its package.
+    ///
+    /// The interface keeps its position within the package, and worlds
+    /// referring to it by id don't need to be updated. Returns an error if
+    /// `new_name` isn't a valid identifier, if the package already has an
+    /// interface with that name, or if `id` is an anonymous interface.
+    pub fn rename_interface(&mut self, id: InterfaceId, new_name: &str) -> Result<()> {
+        validate_id(new_name).with_context(|| format!("invalid interface name `{new_name}`"))?;
+        let iface = &self.interfaces[id];
+        let Some(old) = iface.name.clone() else {
+            bail!("cannot rename an anonymous interface");
+        };
+        if old == new_name {
+            return Ok(());
         }
-        for dep in self.interface_direct_deps(id) {
-            self.ensure_not_exported(world, dep)
-                .with_context(|| format!("failed validating transitive import dep `{name}`"))?;
+        if let Some(pkg) = iface.package {
+            let pkg = &mut self.packages[pkg];
+            if pkg.interfaces.contains_key(new_name) {
+                bail!(
+                    "interface `{new_name}` already exists in package `{}`",
+                    pkg.name
+                );
+            }
+            pkg.interfaces = rename_key(mem::take(&mut pkg.interfaces), &old, new_name);
         }
+        self.interfaces[id].name = Some(new_name.to_string());
+
+        #[cfg(debug_assertions)]
+        self.assert_valid();
         Ok(())
     }
 
-    /// Returns an iterator of all the direct interface dependencies of this
-    /// `item`.
+    /// Renames the function `old` in `interface` to `new`.
     ///
-    /// Note that this doesn't include transitive dependencies, that must be
-    /// followed manually.
-    fn world_item_direct_deps(&self, item: &WorldItem) -> impl Iterator<Item = InterfaceId> + '_ {
-        let mut interface = None;
-        let mut ty = None;
-        match item {
-            WorldItem::Function(_) => {}
-            WorldItem::Type(id) => ty = Some(*id),
-            WorldItem::Interface { id, .. } => interface = Some(*id),
+    /// The name `old` is the function's key in [`Interface::functions`], for
+    /// example `[method]r.get`, while `new` is the plain name of the function
+    /// such as `fetch`. The prefix implied by the function's [`FunctionKind`]
+    /// is preserved so a method stays a method of the same resource. The
+    /// function keeps its position within the interface.
+    ///
+    /// Returns an error if `old` isn't found, if `new` isn't a valid
+    /// identifier, or if `old` is a constructor since constructors don't have
+    /// a name of their own. The name `new` must also not be taken: by another
+    /// method or static function of the same resource for resource functions,
+    /// and otherwise by a freestanding function or a type of the interface.
+    pub fn rename_function(&mut self, interface: InterfaceId, old: &str, new: &str) -> Result<()> {
+        validate_id(new).with_context(|| format!("invalid function name `{new}`"))?;
+        let iface = &self.interfaces[interface];
+        let Some(func) = iface.functions.get(old) else {
+            bail!("function `{old}` not found in interface");
+        };
+        let full_name = match &func.kind {
+            FunctionKind::Freestanding => new.to_string(),
+            FunctionKind::AsyncFreestanding => format!("[async]{new}"),
+            FunctionKind::Method(_)
+            | FunctionKind::Static(_)
+            | FunctionKind::AsyncMethod(_)
+            | FunctionKind::AsyncStatic(_) => {
+                let Some(dot) = func.name.find('.') else {
+                    bail!("function `{old}` is missing the name of its resource");
+                };
+                format!("{}{new}", &func.name[..dot + 1])
+            }
+            FunctionKind::Constructor(_) => bail!("cannot rename constructor `{old}`"),
+        };
+        if full_name == old {
+            return Ok(());
+        }
+        // Methods and static functions of a resource share one namespace, as
+        // do freestanding functions and the types of the interface.
+        let resource = func.kind.resource();
+        if let Some(other) = iface.functions.values().find(|f| {
+            f.name != old
+                && !matches!(f.kind, FunctionKind::Constructor(_))
+                && f.kind.resource() == resource
+                && f.item_name() == new
+        }) {
+            bail!(
+                "cannot rename `{old}` as function `{}` already exists",
+                other.name
+            );
+        }
+        if resource.is_none() && iface.types.contains_key(new) {
+            bail!("cannot rename `{old}` as type `{new}` already exists");
         }
 
-        interface
+        let iface = &mut self.interfaces[interface];
+        iface.functions = mem::take(&mut iface.functions)
             .into_iter()
-            .flat_map(move |id| self.interface_direct_deps(id))
-            .chain(ty.and_then(|t| self.type_interface_dep(t)))
+            .map(|(name, mut func)| {
+                if name == old {
+                    func.name = full_name.clone();
+                    (full_name.clone(), func)
+                } else {
+                    (name, func)
+                }
+            })
+            .collect();
+        if let Some(offsets) = self.interface_function_offsets.get_mut(&interface) {
+            if let Some(offset) = offsets.remove(old) {
+                offsets.insert(full_name.clone(), offset);
+            }
+        }
+        let item = ItemRef::InterfaceFunction(interface, old.to_string());
+        if let Some(span) = self.item_spans.remove(&item) {
+            self.item_spans
+                .insert(ItemRef::InterfaceFunction(interface, full_name), span);
+        }
+        Ok(())
     }
 
-    /// Invokes `f` with `id` and all transitive interface dependencies of `id`.
+    /// Creates a new interface containing only the functions `keep` of
+    /// interface `id`, along with the types of `id` those functions require.
     ///
-    /// Note that `f` may be called with the same id multiple times.
-    fn foreach_interface_dep(&self, id: InterfaceId, f: &mut dyn FnMut(InterfaceId)) {
-        self._foreach_interface_dep(id, f, &mut HashSet::new())
-    }
+    /// Functions are named by their key in [`Interface::functions`], for
+    /// example `[method]r.get`. A type is kept if it's reachable from the
+    /// signature of a kept function, and kept types are copied into the new
+    /// interface while types `use`d from other interfaces continue to refer
+    /// to those interfaces. The new interface is anonymous and belongs to the
+    /// same package as `id`, so it can be exported from a world under a
+    /// [`WorldKey::Name`] to build a restricted facade of `id`. The original
+    /// interface is left unchanged.
+    ///
+    /// Returns an error if any name in `keep` isn't a function of `id`.
+    pub fn subset_interface(&mut self, id: InterfaceId, keep: &[&str]) -> Result<InterfaceId> {
+        let iface = &self.interfaces[id];
+        if let Some(name) = keep
+            .iter()
+            .find(|name| !iface.functions.contains_key(**name))
+        {
+            bail!(
+                "function `{name}` not found in interface `{}`",
+                self.id_of(id)
+                    .unwrap_or_else(|| "<anonymous interface>".to_string())
+            );
+        }
 
-    // Internal detail of `foreach_interface_dep` which uses a hash map to prune
-    // the visit tree to ensure that this doesn't visit an exponential number of
-    // interfaces.
-    fn _foreach_interface_dep(
+        let mut live = LiveTypes::default();
+        let functions = iface
+            .functions
+            .iter()
+            .filter(|(name, _)| keep.contains(&name.as_str()))
+            .map(|(name, func)| {
+                live.add_func(self, func);
+                (name.clone(), func.clone())
+            })
+            .collect::<IndexMap<_, _>>();
+        let live = live.iter().collect::<HashSet<_>>();
+        let new = Interface {
+            name: None,
+            types: iface
+                .types
+                .iter()
+                .filter(|(_, ty)| live.contains(*ty))
+                .map(|(name, ty)| (name.clone(), *ty))
+                .collect(),
+            functions,
+            docs: iface.docs.clone(),
+            stability: iface.stability.clone(),
+            package: iface.package,
+        };
+        let new_id = clone::Cloner::alloc_interface(self, id, new);
+
+        #[cfg(debug_assertions)]
+        self.assert_valid();
+        Ok(new_id)
+    }
+
+    /// Copies the interface `src` into the package `dest_pkg`, returning the
+    /// id of the copy.
+    ///
+    /// The copy is named `new_name`, or the name of `src` if that's `None`.
+    /// All types and functions defined in `src` are copied, so the copy can
+    /// be modified independently of `src`. Types which `src` uses from other
+    /// interfaces are copied as well: each `use`d type is replaced with a
+    /// copy of its definition under the name it's used as, and any other
+    /// types those definitions refer to are added to the copy under their own
+    /// names. The copy, and so `dest_pkg`, therefore doesn't depend on any
+    /// other interface. Note that only types are copied, so the methods of a
+    /// resource used from another interface aren't part of the copy.
+    ///
+    /// Returns an error if `dest_pkg` already has an interface with the same
+    /// name, or if a type which needs to be added to the copy has the same
+    /// name as another type of the copy.
+    pub fn copy_interface(
+        &mut self,
+        src: InterfaceId,
+        dest_pkg: PackageId,
+        new_name: Option<&str>,
+    ) -> Result<InterfaceId> {
+        let name = match new_name.or(self.interfaces[src].name.as_deref()) {
+            Some(name) => name.to_string(),
+            None => bail!("a name is required to copy an anonymous interface"),
+        };
+        validate_id(&name).with_context(|| format!("invalid interface name `{name}`"))?;
+        let dest = &self.packages[dest_pkg];
+        if dest.interfaces.contains_key(&name) {
+            bail!(
+                "interface `{name}` already exists in package `{}`",
+                dest.name
+            );
+        }
+
+        let used = self.types_used_from_other_interfaces(src)?;
+
+        let mut new = self.interfaces[src].clone();
+        new.name = Some(name.clone());
+        new.package = Some(dest_pkg);
+        let id = clone::Cloner::alloc_interface(self, src, new);
+        self.packages[dest_pkg].interfaces.insert(name, id);
+        self.copy_used_types(id, &used);
+
+        #[cfg(debug_assertions)]
+        self.assert_valid();
+        Ok(id)
+    }
+
+    /// Returns the definitions of types in other interfaces which the types
+    /// of `id` refer to, transitively, along with the name each one is given
+    /// when copied into `id`.
+    ///
+    /// Types `use`d by `id` keep the name they're used as, and all other
+    /// types keep their own name, which must not already be taken in `id`.
+    fn types_used_from_other_interfaces(
         &self,
         id: InterfaceId,
-        f: &mut dyn FnMut(InterfaceId),
-        visited: &mut HashSet<InterfaceId>,
-    ) {
-        if !visited.insert(id) {
-            return;
+    ) -> Result<IndexMap<TypeId, String>> {
+        let owner = TypeOwner::Interface(id);
+        let iface = &self.interfaces[id];
+        let mut used = IndexMap::new();
+        for (name, ty) in iface.types.iter() {
+            if let TypeDefKind::Type(Type::Id(other)) = self.types[*ty].kind {
+                if self.types[other].owner != owner {
+                    used.entry(self.use_root(other))
+                        .or_insert_with(|| name.clone());
+                }
+            }
         }
-        f(id);
-        for dep in self.interface_direct_deps(id) {
-            self._foreach_interface_dep(dep, f, visited);
+
+        let mut names = iface.types.keys().collect::<HashSet<_>>();
+        let mut stack = used.keys().copied().collect::<Vec<_>>();
+        while let Some(ty) = stack.pop() {
+            for referenced in self.types[ty].kind.referenced_types() {
+                let Type::Id(referenced) = referenced else {
+                    continue;
+                };
+                let referenced = self.use_root(referenced);
+                let def = &self.types[referenced];
+                match def.owner {
+                    TypeOwner::None => stack.push(referenced),
+                    o if o == owner || used.contains_key(&referenced) => {}
+                    _ => {
+                        let name = def.name.as_ref().unwrap();
+                        if !names.insert(name) {
+                            bail!(
+                                "cannot copy interface `{}` as it would contain \
+                                 two types named `{name}`",
+                                iface.name.as_deref().unwrap_or("<anonymous>"),
+                            );
+                        }
+                        used.insert(referenced, name.clone());
+                        stack.push(referenced);
+                    }
+                }
+            }
         }
+        Ok(used)
     }
 
-    /// Returns the ID of the specified `interface`.
-    ///
-    /// Returns `None` for unnamed interfaces.
-    pub fn id_of(&self, interface: InterfaceId) -> Option<String> {
-        let interface = &self.interfaces[interface];
-        Some(self.id_of_name(interface.package.unwrap(), interface.name.as_ref()?))
+    /// Follows `id` through any `use` of it from other interfaces to its
+    /// definition.
+    fn use_root(&self, mut id: TypeId) -> TypeId {
+        loop {
+            let def = &self.types[id];
+            match def.kind {
+                TypeDefKind::Type(Type::Id(other))
+                    if def.owner != TypeOwner::None && self.types[other].owner != def.owner =>
+                {
+                    id = other;
+                }
+                _ => break id,
+            }
+        }
     }
 
-    /// Returns the "canonicalized interface name" of `interface`.
-    ///
-    /// Returns `None` for unnamed interfaces. See `BuildTargets.md` in the
-    /// upstream component model repository for more information about this.
-    pub fn canonicalized_id_of(&self, interface: InterfaceId) -> Option<String> {
-        let interface = &self.interfaces[interface];
-        Some(self.canonicalized_id_of_name(interface.package.unwrap(), interface.name.as_ref()?))
+    /// Copies the definitions of `used`, as returned by
+    /// [`Resolve::types_used_from_other_interfaces`], into the interface `id`.
+    fn copy_used_types(&mut self, id: InterfaceId, used: &IndexMap<TypeId, String>) {
+        let owner = TypeOwner::Interface(id);
+        let mut map = HashMap::new();
+        for (ty, name) in used {
+            let def = &self.types[*ty];
+            let copy = match self.interfaces[id].types.get(name) {
+                Some(copy) => *copy,
+                None => {
+                    let copy = self.types.alloc(TypeDef {
+                        name: Some(name.clone()),
+                        kind: TypeDefKind::Unknown,
+                        owner,
+                        docs: def.docs.clone(),
+                        stability: def.stability.clone(),
+                    });
+                    self.interfaces[id].types.insert(name.clone(), copy);
+                    copy
+                }
+            };
+            map.insert(*ty, copy);
+        }
+        for ty in used.keys() {
+            let mut kind = self.types[*ty].kind.clone();
+            self.copy_type_refs(&mut kind, &mut map);
+            self.types[map[ty]].kind = kind;
+        }
+
+        // Remaining `use`s refer to a type that's already been copied under
+        // another name.
+        let aliases = self.interfaces[id]
+            .types
+            .values()
+            .copied()
+            .collect::<Vec<_>>();
+        for ty in aliases {
+            if let TypeDefKind::Type(Type::Id(other)) = self.types[ty].kind {
+                if self.types[other].owner != owner {
+                    let copy = map[&self.use_root(other)];
+                    self.types[ty].kind = TypeDefKind::Type(Type::Id(copy));
+                }
+            }
+        }
     }
 
-    /// Convert a world to an "importized" version where the world is updated
-    /// in-place to reflect what it would look like to be imported.
+    /// Updates the types referred to by `kind` to their copies in `map`,
+    /// copying anonymous types along the way.
+    fn copy_type_refs(&mut self, kind: &mut TypeDefKind, map: &mut HashMap<TypeId, TypeId>) {
+        let mut copy_id = |resolve: &mut Resolve, id: &mut TypeId| {
+            let root = resolve.use_root(*id);
+            if let Some(copy) = map.get(&root) {
+                *id = *copy;
+            } else if resolve.types[root].owner == TypeOwner::None {
+                let mut def = resolve.types[root].clone();
+                resolve.copy_type_refs(&mut def.kind, map);
+                let copy = resolve.types.alloc(def);
+                map.insert(root, copy);
+                *id = copy;
+            }
+        };
+        let mut copy_ty = |resolve: &mut Resolve, ty: &mut Type| {
+            if let Type::Id(id) = ty {
+                copy_id(resolve, id);
+            }
+        };
+        match kind {
+            TypeDefKind::Type(t)
+            | TypeDefKind::List(t)
+            | TypeDefKind::FixedSizeList(t, ..)
+            | TypeDefKind::Option(t)
+            | TypeDefKind::Future(Some(t))
+            | TypeDefKind::Stream(Some(t)) => copy_ty(self, t),
+            TypeDefKind::Handle(Handle::Own(id) | Handle::Borrow(id)) => copy_id(self, id),
+            TypeDefKind::Record(r) => {
+                for field in r.fields.iter_mut() {
+                    copy_ty(self, &mut field.ty);
+                }
+            }
+            TypeDefKind::Tuple(t) => {
+                for ty in t.types.iter_mut() {
+                    copy_ty(self, ty);
+                }
+            }
+            TypeDefKind::Variant(v) => {
+                for ty in v.cases.iter_mut().filter_map(|c| c.ty.as_mut()) {
+                    copy_ty(self, ty);
+                }
+            }
+            TypeDefKind::Result(r) => {
+                if let Some(t) = &mut r.ok {
+                    copy_ty(self, t);
+                }
+                if let Some(t) = &mut r.err {
+                    copy_ty(self, t);
+                }
+            }
+            TypeDefKind::Resource
+            | TypeDefKind::Flags(_)
+            | TypeDefKind::Enum(_)
+            | TypeDefKind::Future(None)
+            | TypeDefKind::Stream(None)
+            | TypeDefKind::Unknown => {}
+        }
+    }
+
+    /// Splits the interface `id` by moving the functions of each of `groups`
+    /// into a new interface, returning the ids of the new interfaces in the
+    /// same order as `groups`.
     ///
-    /// This is a transformation which is used as part of the process of
-    /// importing a component today. For example when a component depends on
-    /// another component this is useful for generating WIT which can be use to
-    /// represent the component being imported. The general idea is that this
-    /// function will update the `world_id` specified such it imports the
-    /// functionality that it previously exported. The world will be left with
-    /// no exports.
+    /// Each group is the name of the new interface and the functions to move
+    /// into it, named by their key in [`Interface::functions`] such as
+    /// `[method]r.get`. Functions which aren't listed remain in `id`. The new
+    /// interfaces are added to the package of `id`, after its existing
+    /// interfaces.
     ///
-    /// This world is then suitable for merging into other worlds or generating
-    /// bindings in a context that is importing the original world. This
-    /// is intended to be used as part of language tooling when depending on
-    /// other components.
-    pub fn importize(&mut self, world_id: WorldId, out_world_name: Option<String>) -> Result<()> {
-        // Rename the world to avoid having it get confused with the original
-        // name of the world. Add `-importized` to it for now. Precisely how
-        // this new world is created may want to be updated over time if this
-        // becomes problematic.
-        let world = &mut self.worlds[world_id];
-        let pkg = &mut self.packages[world.package.unwrap()];
-        pkg.worlds.shift_remove(&world.name);
-        if let Some(name) = out_world_name {
-            world.name = name.clone();
-            pkg.worlds.insert(name, world_id);
-        } else {
-            world.name.push_str("-importized");
-            pkg.worlds.insert(world.name.clone(), world_id);
+    /// A type of `id` is moved along with the functions of a group if only
+    /// that group uses it. Types which are used by several groups, by the
+    /// functions remaining in `id`, or from outside of `id` stay in `id` and
+    /// are `use`d by the new interfaces which need them, so no type is ever
+    /// duplicated. As methods must be defined next to their resource, the
+    /// functions of a resource can only be moved if the resource itself is.
+    ///
+    /// Worlds which import or export `id` also import or export the new
+    /// interfaces, right after `id`, so they still contain every function of
+    /// the original interface.
+    ///
+    /// Returns an error if a function isn't found in `id` or is listed in more
+    /// than one group, if a group's name is invalid, already taken, or listed
+    /// twice, if a resource's functions can't be moved, or if a world contains
+    /// `id` under a [`WorldKey::Name`].
+    pub fn split_interface(
+        &mut self,
+        id: InterfaceId,
+        groups: &[(&str, &[&str])],
+    ) -> Result<Vec<InterfaceId>> {
+        self.layout = None;
+        let iface = &self.interfaces[id];
+        let Some(package) = iface.package else {
+            bail!("cannot split an interface which isn't in a package");
+        };
+        let iface_name = self
+            .id_of(id)
+            .unwrap_or_else(|| "<anonymous interface>".to_string());
+        let mut listed = HashMap::new();
+        for (group, funcs) in groups {
+            validate_id(group).with_context(|| format!("invalid interface name `{group}`"))?;
+            if self.packages[package].interfaces.contains_key(*group) {
+                bail!(
+                    "interface `{group}` already exists in package `{}`",
+                    self.packages[package].name
+                );
+            }
+            if groups.iter().filter(|(g, _)| g == group).count() > 1 {
+                bail!("interface `{group}` is listed more than once");
+            }
+            for func in funcs.iter() {
+                if !iface.functions.contains_key(*func) {
+                    bail!("function `{func}` not found in interface `{iface_name}`");
+                }
+                if let Some(prev) = listed.insert(*func, *group) {
+                    bail!("function `{func}` is listed in both `{prev}` and `{group}`");
+                }
+            }
         }
 
-        // Trim all non-type definitions from imports. Types can be used by
-        // exported functions, for example, so they're preserved.
-        world.imports.retain(|_, item| match item {
-            WorldItem::Type(_) => true,
-            _ => false,
-        });
+        for (_, world) in self.worlds.iter() {
+            for (key, item) in world.imports.iter().chain(world.exports.iter()) {
+                if let (WorldKey::Name(name), WorldItem::Interface { id: other, .. }) = (key, item)
+                {
+                    if *other == id {
+                        bail!(
+                            "cannot split interface `{iface_name}` as world `{}` refers to it \
+                             as `{name}`",
+                            world.name
+                        );
+                    }
+                }
+            }
+        }
 
-        for (name, export) in mem::take(&mut world.exports) {
-            match (name.clone(), world.imports.insert(name, export)) {
-                // no previous item? this insertion was ok
-                (_, None) => {}
+        // Determine which types of `id` are used by each group, and which
+        // need to stay in `id`.
+        let own_types = iface.types.values().copied().collect::<HashSet<_>>();
+        let mut remaining = LiveTypes::default();
+        let mut group_live = groups
+            .iter()
+            .map(|_| LiveTypes::default())
+            .collect::<Vec<_>>();
+        for func in iface.functions.values() {
+            match groups
+                .iter()
+                .position(|(group, _)| listed.get(func.name.as_str()) == Some(group))
+            {
+                Some(i) => group_live[i].add_func(self, func),
+                None => remaining.add_func(self, func),
+            }
+        }
+        let group_live = group_live
+            .iter()
+            .map(|live| {
+                live.iter()
+                    .filter(|ty| own_types.contains(ty))
+                    .collect::<HashSet<_>>()
+            })
+            .collect::<Vec<_>>();
+        let mut staying = LiveTypes::default();
+        for ty in remaining.iter() {
+            staying.add_type_id(self, ty);
+        }
+        for (other_id, _) in self.interfaces.iter() {
+            if other_id != id {
+                staying.add_interface(self, other_id);
+            }
+        }
+        for (_, world) in self.worlds.iter() {
+            for item in world.imports.values().chain(world.exports.values()) {
+                match item {
+                    WorldItem::Interface { id: other, .. } if *other == id => {}
+                    item => staying.add_world_item(self, item),
+                }
+            }
+        }
+        for ty in iface.types.values() {
+            let users = group_live.iter().filter(|live| live.contains(ty)).count();
+            if users != 1 {
+                staying.add_type_id(self, *ty);
+            }
+        }
+        let staying = staying
+            .iter()
+            .filter(|ty| own_types.contains(ty))
+            .collect::<HashSet<_>>();
+
+        for (func, group) in listed.iter() {
+            let i = groups.iter().position(|(g, _)| g == group).unwrap();
+            if let Some(resource) = iface.functions[*func].kind.resource() {
+                if staying.contains(&resource) || !group_live[i].contains(&resource) {
+                    bail!(
+                        "cannot move function `{func}` to interface `{group}` as its \
+                         resource `{}` is also used elsewhere",
+                        self.types[resource]
+                            .name
+                            .as_deref()
+                            .unwrap_or("<anonymous>")
+                    );
+                }
+            }
+        }
 
-                // cannot overwrite an import with an export
-                (WorldKey::Name(name), Some(_)) => {
-                    bail!("world export `{name}` conflicts with import of same name");
+        let mut ret = Vec::new();
+        for ((group, _), live) in groups.iter().zip(&group_live) {
+            let moved = live
+                .iter()
+                .filter(|ty| !staying.contains(ty))
+                .copied()
+                .collect::<HashSet<_>>();
+            let iface = &mut self.interfaces[id];
+            let mut functions = IndexMap::new();
+            iface.functions.retain(|name, func| {
+                if listed.get(name.as_str()) != Some(group) {
+                    return true;
+                }
+                functions.insert(name.clone(), func.clone());
+                false
+            });
+            let old_types = iface.types.clone();
+            iface.types.retain(|_, ty| !moved.contains(ty));
+            let stability = iface.stability.clone();
+
+            // Types of `id` which moved functions or types refer to directly
+            // are replaced with `use`s of the original.
+            let mut used = HashSet::new();
+            for func in functions.values() {
+                for ty in func.parameter_and_result_types() {
+                    self.direct_named_types(ty, &mut used);
+                }
+            }
+            for ty in moved.iter() {
+                for ty in self.types[*ty].kind.referenced_types() {
+                    self.direct_named_types(ty, &mut used);
                 }
+            }
 
-                // Exports already don't overlap each other and the only imports
-                // preserved above were types so this shouldn't be reachable.
-                (WorldKey::Interface(_), _) => unreachable!(),
+            let new_id = self.interfaces.next_id();
+            let mut types = IndexMap::new();
+            let mut aliases = Vec::new();
+            for (name, ty) in old_types.iter() {
+                if moved.contains(ty) {
+                    types.insert(name.clone(), *ty);
+                } else if used.contains(ty) {
+                    let alias = self.types.alloc(TypeDef {
+                        name: Some(name.clone()),
+                        kind: TypeDefKind::Type(Type::Id(*ty)),
+                        owner: TypeOwner::Interface(new_id),
+                        docs: Default::default(),
+                        stability: self.types[*ty].stability.clone(),
+                    });
+                    types.insert(name.clone(), alias);
+                    aliases.push((*ty, alias));
+                }
+            }
+
+            let mut clone =
+                clone::Cloner::new(self, TypeOwner::Interface(id), TypeOwner::Interface(new_id));
+            for ty in moved.iter() {
+                clone.register_type(*ty, *ty);
+            }
+            for (ty, alias) in aliases {
+                clone.register_type(ty, alias);
             }
+            for ty in moved.iter() {
+                let mut def = clone.resolve.types[*ty].clone();
+                clone.type_def(&mut def);
+                clone.resolve.types[*ty] = def;
+            }
+            for func in functions.values_mut() {
+                clone.function(func);
+            }
+
+            if let Some(offsets) = self.interface_function_offsets.get_mut(&id) {
+                let moved = functions
+                    .keys()
+                    .filter_map(|name| Some((name.clone(), offsets.remove(name)?)))
+                    .collect::<HashMap<_, _>>();
+                self.interface_function_offsets.insert(new_id, moved);
+            }
+            for name in functions.keys() {
+                let item = ItemRef::InterfaceFunction(id, name.clone());
+                if let Some(span) = self.item_spans.remove(&item) {
+                    self.item_spans
+                        .insert(ItemRef::InterfaceFunction(new_id, name.clone()), span);
+                }
+            }
+
+            let new = self.interfaces.alloc(Interface {
+                name: Some(group.to_string()),
+                types,
+                functions,
+                docs: Default::default(),
+                stability,
+                package: Some(package),
+            });
+            assert_eq!(new, new_id);
+            self.packages[package]
+                .interfaces
+                .insert(group.to_string(), new);
+            ret.push(new);
         }
 
-        // Fill out any missing transitive interface imports by elaborating this
-        // world which does that for us.
-        self.elaborate_world(world_id)?;
+        let mut worlds = Vec::new();
+        for (world_id, world) in self.worlds.iter_mut() {
+            for items in [&mut world.imports, &mut world.exports] {
+                let Some(stability) = items.get(&WorldKey::Interface(id)).map(|item| match item {
+                    WorldItem::Interface { stability, .. } => stability.clone(),
+                    _ => unreachable!(),
+                }) else {
+                    continue;
+                };
+                *items = mem::take(items)
+                    .into_iter()
+                    .flat_map(|(key, item)| {
+                        let split = if key == WorldKey::Interface(id) {
+                            &ret[..]
+                        } else {
+                            &[]
+                        };
+                        iter::once((key, item)).chain(split.iter().map(|new| {
+                            let item = WorldItem::Interface {
+                                id: *new,
+                                stability: stability.clone(),
+                            };
+                            (WorldKey::Interface(*new), item)
+                        }))
+                    })
+                    .collect();
+                worlds.push(world_id);
+            }
+        }
+        worlds.dedup();
+        for world in worlds {
+            self.elaborate_world(world)?;
+        }
 
         #[cfg(debug_assertions)]
         self.assert_valid();
-        Ok(())
+        Ok(ret)
     }
 
-    /// Returns the ID of the specified `name` within the `pkg`.
-    pub fn id_of_name(&self, pkg: PackageId, name: &str) -> String {
-        let package = &self.packages[pkg];
-        let mut base = String::new();
-        base.push_str(&package.name.namespace);
-        base.push_str(":");
-        base.push_str(&package.name.name);
-        base.push_str("/");
-        base.push_str(name);
-        if let Some(version) = &package.name.version {
-            base.push_str(&format!("@{version}"));
+    /// Adds the named types that `ty` refers to to `out`, looking through
+    /// anonymous types like `list<T>` but stopping at the first named type.
+    fn direct_named_types(&self, ty: Type, out: &mut HashSet<TypeId>) {
+        let Type::Id(id) = ty else { return };
+        if self.types[id].name.is_some() {
+            out.insert(id);
+            return;
         }
-        base
-    }
-
-    /// Returns the "canonicalized interface name" of the specified `name`
-    /// within the `pkg`.
-    ///
-    /// See `BuildTargets.md` in the upstream component model repository for
-    /// more information about this.
-    pub fn canonicalized_id_of_name(&self, pkg: PackageId, name: &str) -> String {
-        let package = &self.packages[pkg];
-        let mut base = String::new();
-        base.push_str(&package.name.namespace);
-        base.push_str(":");
-        base.push_str(&package.name.name);
-        base.push_str("/");
-        base.push_str(name);
-        if let Some(version) = &package.name.version {
-            base.push_str("@");
-            let string = PackageName::version_compat_track_string(version);
-            base.push_str(&string);
+        for ty in self.types[id].kind.referenced_types() {
+            self.direct_named_types(ty, out);
         }
-        base
     }
 
-    /// Attempts to locate a world given the "default" set of `packages` and the
-    /// optional string specifier `world`.
+    /// Replaces the parameters of the function `func` with a single parameter
+    /// whose type is a new record named `record_name`.
     ///
-    /// This method is intended to be used by bindings generation tools to
-    /// select a world from either `packages` or a package in this `Resolve`.
-    /// The `packages` list is a return value from methods such as
-    /// [`push_path`](Resolve::push_path), [`push_dir`](Resolve::push_dir),
-    /// [`push_file`](Resolve::push_file), [`push_group`](Resolve::push_group),
-    /// or [`push_str`](Resolve::push_str). The return values of those methods
-    /// are the "main package list" which is specified by the user and is used
-    /// as a heuristic for world selection.
-    ///
-    /// If `world` is `None` then `packages` must have one entry and that
-    /// package must have exactly one world. If this is the case then that world
-    /// will be returned, otherwise an error will be returned.
-    ///
-    /// If `world` is `Some` then it can either be:
-    ///
-    /// * A kebab-name of a world such as `"the-world"`. In this situation
-    ///   the `packages` list must have only a single entry. If `packages` has
-    ///   no entries or more than one, or if the kebab-name does not exist in
-    ///   the one package specified, then an error will be returned.
-    ///
-    /// * An ID-based form of a world which is selected within this `Resolve`,
-    ///   for example `"wasi:http/proxy"`. In this situation the `packages`
-    ///   array is ignored and the ID specified is use to lookup a package. Note
-    ///   that a version does not need to be specified in this string if there's
-    ///   only one package of the same name and it has a version. In this
-    ///   situation the version can be omitted.
-    ///
-    /// If successful the corresponding `WorldId` is returned, otherwise an
-    /// error is returned.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use anyhow::Result;
-    /// use wit_parser::Resolve;
-    ///
-    /// fn main() -> Result<()> {
-    ///     let mut resolve = Resolve::default();
-    ///
-    ///     // For inputs which have a single package and only one world `None`
-    ///     // can be specified.
-    ///     let id = resolve.push_str(
-    ///         "./my-test.wit",
-    ///         r#"
-    ///             package example:wit1;
-    ///
-    ///             world foo {
-    ///                 // ...
-    ///             }
-    ///         "#,
-    ///     )?;
-    ///     assert!(resolve.select_world(id, None).is_ok());
-    ///
-    ///     // For inputs which have a single package and multiple worlds then
-    ///     // a world must be specified.
-    ///     let id = resolve.push_str(
-    ///         "./my-test.wit",
-    ///         r#"
-    ///             package example:wit2;
-    ///
-    ///             world foo { /* ... */ }
-    ///
-    ///             world bar { /* ... */ }
-    ///         "#,
-    ///     )?;
-    ///     assert!(resolve.select_world(id, None).is_err());
-    ///     assert!(resolve.select_world(id, Some("foo")).is_ok());
-    ///     assert!(resolve.select_world(id, Some("bar")).is_ok());
-    ///
-    ///     // For inputs which have more than one package then a fully
-    ///     // qualified name must be specified.
+    /// The fields of the record mirror the original parameters, in order, and
+    /// the new parameter is also named `record_name`. The implicit `self`
+    /// parameter of methods is left in place. The record is defined next to
+    /// the function, in its interface or world, with the same stability as the
+    /// function.
     ///
-    ///     // Note that the `ids` or `packages` argument is ignored if a fully
-    ///     // qualified world specified is provided meaning previous worlds
-    ///     // can be selected.
-    ///     assert!(resolve.select_world(id, Some("example:wit1/foo")).is_ok());
-    ///     assert!(resolve.select_world(id, Some("example:wit2/foo")).is_ok());
-    ///
-    ///     // When selecting with a version it's ok to drop the version when
-    ///     // there's only a single copy of that package in `Resolve`.
-    ///     resolve.push_str(
-    ///         "./my-test.wit",
-    ///         r#"
-    ///             package example:wit5@1.0.0;
-    ///
-    ///             world foo { /* ... */ }
-    ///         "#,
-    ///     )?;
-    ///     assert!(resolve.select_world(id, Some("example:wit5/foo")).is_ok());
-    ///
-    ///     // However when a single package has multiple versions in a resolve
-    ///     // it's required to specify the version to select which one.
-    ///     resolve.push_str(
-    ///         "./my-test.wit",
-    ///         r#"
-    ///             package example:wit5@2.0.0;
+    /// Note that this changes the ABI of the function, so it's only suitable
+    /// for generating higher-level wrappers, not for describing an existing
+    /// component.
     ///
-    ///             world foo { /* ... */ }
-    ///         "#,
-    ///     )?;
-    ///     assert!(resolve.select_world(id, Some("example:wit5/foo")).is_err());
-    ///     assert!(resolve.select_world(id, Some("example:wit5/foo@1.0.0")).is_ok());
-    ///     assert!(resolve.select_world(id, Some("example:wit5/foo@2.0.0")).is_ok());
-    ///
-    ///     Ok(())
-    /// }
-    /// ```
-    pub fn select_world(&self, package: PackageId, world: Option<&str>) -> Result<WorldId> {
-        let world_path = match world {
-            Some(world) => Some(
-                parse_use_path(world)
-                    .with_context(|| format!("failed to parse world specifier `{world}`"))?,
-            ),
-            None => None,
+    /// Returns an error if `func` doesn't refer to a function, if it has no
+    /// parameters other than `self`, if an item named `record_name` already
+    /// exists next to it, or if any parameter name isn't a valid field name.
+    pub fn coalesce_params_to_record(&mut self, func: ItemRef, record_name: &str) -> Result<()> {
+        self.layout = None;
+        validate_id(record_name).with_context(|| format!("invalid record name `{record_name}`"))?;
+        let (owner, function) = match &func {
+            ItemRef::InterfaceFunction(id, name) => {
+                match self.interfaces[*id].functions.get(name) {
+                    Some(f) => (TypeOwner::Interface(*id), f),
+                    None => bail!("function `{name}` not found in interface"),
+                }
+            }
+            ItemRef::WorldItem(id, key) => match self.worlds[*id]
+                .imports
+                .get(key)
+                .or_else(|| self.worlds[*id].exports.get(key))
+            {
+                Some(WorldItem::Function(f)) => (TypeOwner::World(*id), f),
+                _ => bail!("`{}` is not a function in world", self.name_world_key(key)),
+            },
+            _ => bail!("item is not a function"),
         };
-
-        let (pkg, world_name) = match world_path {
-            Some(ParsedUsePath::Name(name)) => (package, name),
-            Some(ParsedUsePath::Package(pkg, interface)) => {
-                let pkg = match self.package_names.get(&pkg) {
-                    Some(pkg) => *pkg,
-                    None => {
-                        let mut candidates = self.package_names.iter().filter(|(name, _)| {
-                            pkg.version.is_none()
-                                && pkg.name == name.name
-                                && pkg.namespace == name.namespace
-                                && name.version.is_some()
-                        });
-                        let candidate = candidates.next();
-                        if let Some((c2, _)) = candidates.next() {
-                            let (c1, _) = candidate.unwrap();
-                            bail!(
-                                "package name `{pkg}` is available at both \
-                                 versions {} and {} but which is not specified",
-                                c1.version.as_ref().unwrap(),
-                                c2.version.as_ref().unwrap(),
-                            );
-                        }
-                        match candidate {
-                            Some((_, id)) => *id,
-                            None => bail!("unknown package `{pkg}`"),
-                        }
-                    }
-                };
-                (pkg, interface.to_string())
+        let exists = match owner {
+            TypeOwner::Interface(id) => {
+                let iface = &self.interfaces[id];
+                iface.types.contains_key(record_name) || iface.functions.contains_key(record_name)
             }
-            None => {
-                let pkg = &self.packages[package];
-                let worlds = pkg
-                    .worlds
-                    .values()
-                    .map(|world| (package, *world))
-                    .collect::<Vec<_>>();
-
-                match &worlds[..] {
-                    [] => bail!("The main package `{}` contains no worlds", pkg.name),
-                    [(_, world)] => return Ok(*world),
-                    _ => bail!(
-                        "multiple worlds found; one must be explicitly chosen:{}",
-                        worlds
-                            .iter()
-                            .map(|(pkg, world)| format!(
-                                "\n  {}/{}",
-                                self.packages[*pkg].name, self.worlds[*world].name
-                            ))
-                            .collect::<String>()
-                    ),
-                }
+            TypeOwner::World(id) => {
+                let world = &self.worlds[id];
+                let key = WorldKey::Name(record_name.to_string());
+                world.imports.contains_key(&key) || world.exports.contains_key(&key)
             }
+            TypeOwner::None => unreachable!(),
         };
-        let pkg = &self.packages[pkg];
-        pkg.worlds
-            .get(&world_name)
-            .copied()
-            .ok_or_else(|| anyhow!("no world named `{world_name}` in package"))
-    }
-
-    /// Assigns a human readable name to the `WorldKey` specified.
-    pub fn name_world_key(&self, key: &WorldKey) -> String {
-        match key {
-            WorldKey::Name(s) => s.to_string(),
-            WorldKey::Interface(i) => self.id_of(*i).expect("unexpected anonymous interface"),
+        if exists {
+            bail!(
+                "cannot define record `{record_name}` for function `{}` as the name is already in use",
+                function.name
+            );
         }
-    }
 
-    /// Same as [`Resolve::name_world_key`] except that `WorldKey::Interfaces`
-    /// uses [`Resolve::canonicalized_id_of`].
-    pub fn name_canonicalized_world_key(&self, key: &WorldKey) -> String {
-        match key {
-            WorldKey::Name(s) => s.to_string(),
-            WorldKey::Interface(i) => self
-                .canonicalized_id_of(*i)
-                .expect("unexpected anonymous interface"),
+        let skip = usize::from(matches!(
+            function.kind,
+            FunctionKind::Method(_) | FunctionKind::AsyncMethod(_)
+        ));
+        if function.params.len() == skip {
+            bail!("function `{}` has no parameters to coalesce", function.name);
         }
-    }
+        let fields = function.params[skip..]
+            .iter()
+            .map(|(name, ty)| {
+                validate_id(name).with_context(|| {
+                    format!(
+                        "parameter `{name}` of function `{}` is not a valid field name",
+                        function.name
+                    )
+                })?;
+                Ok(Field {
+                    name: name.clone(),
+                    ty: *ty,
+                    docs: Docs::default(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let stability = function.stability.clone();
+        let record = self.types.alloc(TypeDef {
+            name: Some(record_name.to_string()),
+            kind: TypeDefKind::Record(Record { fields }),
+            owner,
+            docs: Docs::default(),
+            stability,
+        });
 
-    /// Returns the interface that `id` uses a type from, if it uses a type from
-    /// a different interface than `id` is defined within.
-    ///
-    /// If `id` is not a use-of-a-type or it's using a type in the same
-    /// interface then `None` is returned.
-    pub fn type_interface_dep(&self, id: TypeId) -> Option<InterfaceId> {
-        let ty = &self.types[id];
-        let dep = match ty.kind {
-            TypeDefKind::Type(Type::Id(id)) => id,
-            _ => return None,
-        };
-        let other = &self.types[dep];
-        if ty.owner == other.owner {
-            None
-        } else {
-            match other.owner {
-                TypeOwner::Interface(id) => Some(id),
-                _ => unreachable!(),
+        let function = match (&func, owner) {
+            (ItemRef::InterfaceFunction(_, name), TypeOwner::Interface(id)) => {
+                let iface = &mut self.interfaces[id];
+                iface.types.insert(record_name.to_string(), record);
+                iface.functions.get_mut(name).unwrap()
             }
-        }
-    }
+            (ItemRef::WorldItem(_, key), TypeOwner::World(id)) => {
+                let world = &mut self.worlds[id];
+                world.imports.insert(
+                    WorldKey::Name(record_name.to_string()),
+                    WorldItem::Type(record),
+                );
+                match world.imports.get_mut(key).or(world.exports.get_mut(key)) {
+                    Some(WorldItem::Function(f)) => f,
+                    _ => unreachable!(),
+                }
+            }
+            _ => unreachable!(),
+        };
+        function.params.truncate(skip);
+        function
+            .params
+            .push((record_name.to_string(), Type::Id(record)));
 
-    /// Returns an iterator of all interfaces that the interface `id` depends
-    /// on.
-    ///
-    /// Interfaces may depend on others for type information to resolve type
-    /// imports.
-    ///
-    /// Note that the returned iterator may yield the same interface as a
-    /// dependency multiple times. Additionally only direct dependencies of `id`
-    /// are yielded, not transitive dependencies.
-    pub fn interface_direct_deps(&self, id: InterfaceId) -> impl Iterator<Item = InterfaceId> + '_ {
-        self.interfaces[id]
-            .types
-            .iter()
-            .filter_map(move |(_name, ty)| self.type_interface_dep(*ty))
+        #[cfg(debug_assertions)]
+        self.assert_valid();
+        Ok(())
     }
 
-    /// Returns an iterator of all packages that the package `id` depends
-    /// on.
+    /// Redirects all uses of the interface `old` to the interface `new`.
     ///
-    /// Packages may depend on others for type information to resolve type
-    /// imports or interfaces to resolve worlds.
+    /// World imports and exports of `old` are replaced with `new`, and types
+    /// which are `use`d from `old` elsewhere are changed to refer to the type of
+    /// the same name in `new`. The interface `old` itself is left in its
+    /// package. This is useful, for example, to swap a mock interface for the
+    /// real one.
     ///
-    /// Note that the returned iterator may yield the same package as a
-    /// dependency multiple times. Additionally only direct dependencies of `id`
-    /// are yielded, not transitive dependencies.
-    pub fn package_direct_deps(&self, id: PackageId) -> impl Iterator<Item = PackageId> + '_ {
-        let pkg = &self.packages[id];
+    /// The interface `new` must define every type and function that `old`
+    /// does, and otherwise an error is returned listing what's missing. These
+    /// must also match structurally, as when merging two copies of an
+    /// interface with [`Resolve::merge`]. Worlds which previously contained
+    /// `old` are elaborated again so that any dependencies of `new` are
+    /// imported as well.
+    pub fn replace_interface(&mut self, old: InterfaceId, new: InterfaceId) -> Result<()> {
+        self.layout = None;
+        if old == new {
+            return Ok(());
+        }
+        let old_iface = &self.interfaces[old];
+        let new_iface = &self.interfaces[new];
+        let describe = |id| {
+            self.id_of(id)
+                .unwrap_or_else(|| "<anonymous interface>".to_string())
+        };
 
-        pkg.interfaces
+        let missing = old_iface
+            .types
+            .keys()
+            .filter(|name| !new_iface.types.contains_key(*name))
+            .map(|name| format!("type `{name}`"))
+            .chain(
+                old_iface
+                    .functions
+                    .keys()
+                    .filter(|name| !new_iface.functions.contains_key(*name))
+                    .map(|name| format!("function `{name}`")),
+            )
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            bail!(
+                "interface `{}` cannot replace `{}` as it's missing: {}",
+                describe(new),
+                describe(old),
+                missing.join(", ")
+            );
+        }
+        // Like `merge_world_item` the structure of `old` is checked against
+        // `new` with `MergeMap`, which also covers the types of `old`.
+        MergeMap::new(self, self)
+            .build_interface(old, new)
+            .with_context(|| {
+                format!(
+                    "interface `{}` cannot replace `{}`",
+                    describe(new),
+                    describe(old),
+                )
+            })?;
+
+        let type_map = old_iface
+            .types
             .iter()
-            .flat_map(move |(_name, id)| self.interface_direct_deps(*id))
-            .chain(pkg.worlds.iter().flat_map(move |(_name, id)| {
-                let world = &self.worlds[*id];
-                world
-                    .imports
-                    .iter()
-                    .chain(world.exports.iter())
-                    .filter_map(move |(_name, item)| match item {
-                        WorldItem::Interface { id, .. } => Some(*id),
-                        WorldItem::Function(_) => None,
-                        WorldItem::Type(t) => self.type_interface_dep(*t),
-                    })
-            }))
-            .filter_map(move |iface_id| {
-                let pkg = self.interfaces[iface_id].package?;
-                if pkg == id { None } else { Some(pkg) }
-            })
-    }
-
-    /// Returns a topological ordering of packages contained in this `Resolve`.
-    ///
-    /// This returns a list of `PackageId` such that when visited in order it's
-    /// guaranteed that all dependencies will have been defined by prior items
-    /// in the list.
-    pub fn topological_packages(&self) -> Vec<PackageId> {
-        let mut pushed = vec![false; self.packages.len()];
-        let mut order = Vec::new();
-        for (id, _) in self.packages.iter() {
-            self.build_topological_package_ordering(id, &mut pushed, &mut order);
+            .map(|(name, id)| (*id, new_iface.types[name]))
+            .collect::<HashMap<_, _>>();
+        for (_, ty) in self.types.iter_mut() {
+            if ty.owner == TypeOwner::Interface(old) {
+                continue;
+            }
+            if let TypeDefKind::Type(Type::Id(id)) = &mut ty.kind {
+                if let Some(new_id) = type_map.get(id) {
+                    *id = *new_id;
+                }
+            }
         }
-        order
-    }
 
-    fn build_topological_package_ordering(
-        &self,
-        id: PackageId,
-        pushed: &mut Vec<bool>,
-        order: &mut Vec<PackageId>,
-    ) {
-        if pushed[id.index()] {
-            return;
+        let mut worlds = Vec::new();
+        for (world_id, world) in self.worlds.iter_mut() {
+            for items in [&mut world.imports, &mut world.exports] {
+                if !items
+                    .values()
+                    .any(|item| matches!(item, WorldItem::Interface { id, .. } if *id == old))
+                {
+                    continue;
+                }
+                *items = mem::take(items)
+                    .into_iter()
+                    .map(|(key, item)| match item {
+                        WorldItem::Interface { id, stability } if id == old => {
+                            let key = match key {
+                                WorldKey::Interface(_) => WorldKey::Interface(new),
+                                key => key,
+                            };
+                            (key, WorldItem::Interface { id: new, stability })
+                        }
+                        item => (key, item),
+                    })
+                    .collect();
+                worlds.push(world_id);
+            }
         }
-        for dep in self.package_direct_deps(id) {
-            self.build_topological_package_ordering(dep, pushed, order);
+        worlds.dedup();
+        for world in worlds {
+            self.elaborate_world(world)?;
         }
-        order.push(id);
-        pushed[id.index()] = true;
+
+        #[cfg(debug_assertions)]
+        self.assert_valid();
+        Ok(())
     }
 
-    #[doc(hidden)]
-    pub fn assert_valid(&self) {
-        let mut package_interfaces = Vec::new();
-        let mut package_worlds = Vec::new();
-        for (id, pkg) in self.packages.iter() {
-            let mut interfaces = HashSet::new();
-            for (name, iface) in pkg.interfaces.iter() {
-                assert!(interfaces.insert(*iface));
-                let iface = &self.interfaces[*iface];
-                assert_eq!(name, iface.name.as_ref().unwrap());
-                assert_eq!(iface.package.unwrap(), id);
-            }
-            package_interfaces.push(pkg.interfaces.values().copied().collect::<HashSet<_>>());
-            let mut worlds = HashSet::new();
-            for (name, world) in pkg.worlds.iter() {
-                assert!(worlds.insert(*world));
-                assert_eq!(
-                    pkg.worlds.get_key_value(name),
-                    Some((name, world)),
-                    "`MutableKeys` impl may have been used to change a key's hash or equality"
-                );
-                let world = &self.worlds[*world];
-                assert_eq!(*name, world.name);
-                assert_eq!(world.package.unwrap(), id);
-            }
-            package_worlds.push(pkg.worlds.values().copied().collect::<HashSet<_>>());
-        }
+    /// Returns the stability of `from` if it's more stable than that of
+    /// `into`, where both are the same item in two worlds being merged.
+    fn more_stable_world_item(&self, from: &WorldItem, into: &WorldItem) -> Option<Stability> {
+        let stability = |item: &WorldItem| match item {
+            WorldItem::Interface { stability, .. } => stability.clone(),
+            WorldItem::Function(f) => f.stability.clone(),
+            WorldItem::Type(id) => self.types[*id].stability.clone(),
+        };
+        // Like `update_stability` an unknown stability carries no
+        // information, so only an unstable item can be upgraded.
+        let (from, into) = (stability(from), stability(into));
+        (from.is_stable() && matches!(into, Stability::Unstable { .. })).then_some(from)
+    }
 
-        let mut interface_types = Vec::new();
-        for (id, iface) in self.interfaces.iter() {
-            assert!(self.packages.get(iface.package.unwrap()).is_some());
-            if iface.name.is_some() {
-                assert!(package_interfaces[iface.package.unwrap().index()].contains(&id));
+    fn merge_world_item(&self, from: &WorldItem, into: &WorldItem) -> Result<()> {
+        let mut map = MergeMap::new(self, self);
+        match (from, into) {
+            (WorldItem::Interface { id: from, .. }, WorldItem::Interface { id: into, .. }) => {
+                // If these imports are the same that can happen, for
+                // example, when both worlds to `import foo:bar/baz;`. That
+                // foreign interface will point to the same interface within
+                // `Resolve`.
+                if from == into {
+                    return Ok(());
+                }
+
+                // .. otherwise this MUST be a case of
+                // `import foo: interface { ... }`. If `from != into` but
+                // both `from` and `into` have the same name then the
+                // `WorldKey::Interface` case is ruled out as otherwise
+                // they'd have different names.
+                //
+                // In the case of an anonymous interface all we can do is
+                // ensure that the interfaces both match, so use `MergeMap`
+                // for that.
+                map.build_interface(*from, *into)
+                    .context("failed to merge interfaces")?;
             }
 
-            for (name, ty) in iface.types.iter() {
-                let ty = &self.types[*ty];
-                assert_eq!(ty.name.as_ref(), Some(name));
-                assert_eq!(ty.owner, TypeOwner::Interface(id));
+            // Like `WorldKey::Name` interfaces for functions and types the
+            // structure is asserted to be the same.
+            (WorldItem::Function(from), WorldItem::Function(into)) => {
+                map.build_function(from, into)
+                    .context("failed to merge functions")?;
             }
-            interface_types.push(iface.types.values().copied().collect::<HashSet<_>>());
-            for (name, f) in iface.functions.iter() {
-                assert_eq!(*name, f.name);
+            (WorldItem::Type(from), WorldItem::Type(into)) => {
+                map.build_type_id(*from, *into)
+                    .context("failed to merge types")?;
             }
-        }
 
-        let mut world_types = Vec::new();
-        for (id, world) in self.worlds.iter() {
-            log::debug!("validating world {}", &world.name);
-            if let Some(package) = world.package {
-                assert!(self.packages.get(package).is_some());
-                assert!(package_worlds[package.index()].contains(&id));
+            // Kind-level mismatches are caught here.
+            (WorldItem::Interface { .. }, _)
+            | (WorldItem::Function { .. }, _)
+            | (WorldItem::Type { .. }, _) => {
+                bail!("different kinds of items");
             }
-            assert!(world.includes.is_empty());
+        }
+        assert!(map.interfaces_to_add.is_empty());
+        assert!(map.worlds_to_add.is_empty());
+        Ok(())
+    }
 
-            let mut types = HashSet::new();
-            for (name, item) in world.imports.iter().chain(world.exports.iter()) {
-                log::debug!("validating world item: {}", self.name_world_key(name));
-                match item {
-                    WorldItem::Interface { id, .. } => {
-                        // anonymous interfaces must belong to the same package
-                        // as the world's package.
-                        if matches!(name, WorldKey::Name(_)) {
-                            assert_eq!(self.interfaces[*id].package, world.package);
-                        }
-                    }
-                    WorldItem::Function(f) => {
-                        assert!(!matches!(name, WorldKey::Interface(_)));
-                        assert_eq!(f.name, name.clone().unwrap_name());
-                    }
-                    WorldItem::Type(ty) => {
-                        assert!(!matches!(name, WorldKey::Interface(_)));
-                        assert!(types.insert(*ty));
-                        let ty = &self.types[*ty];
-                        assert_eq!(ty.name, Some(name.clone().unwrap_name()));
-                        assert_eq!(ty.owner, TypeOwner::World(id));
-                    }
-                }
+    /// This method ensures that the world export of `name` and `item` can be
+    /// added to the world `into` without changing the meaning of `into`.
+    ///
+    /// All dependencies of world exports must either be:
+    ///
+    /// * An export themselves
+    /// * An import with all transitive dependencies of the import also imported
+    ///
+    /// It's not possible to depend on an import which then also depends on an
+    /// export at some point, for example. This method ensures that if `name`
+    /// and `item` are added that this property is upheld.
+    fn ensure_can_add_world_export(
+        &self,
+        into: &World,
+        name: &WorldKey,
+        item: &WorldItem,
+        must_be_imported: &HashMap<InterfaceId, WorldKey>,
+    ) -> Result<()> {
+        assert!(!into.exports.contains_key(name));
+        let name = self.name_world_key(name);
+
+        // First make sure that all of this item's dependencies are either
+        // exported or the entire chain of imports rooted at that dependency are
+        // all imported.
+        for dep in self.world_item_direct_deps(item) {
+            if into.exports.contains_key(&WorldKey::Interface(dep)) {
+                continue;
             }
-            self.assert_world_elaborated(world);
-            world_types.push(types);
+            self.ensure_not_exported(into, dep)
+                .with_context(|| format!("failed validating export of `{name}`"))?;
         }
 
-        for (ty_id, ty) in self.types.iter() {
-            match ty.owner {
-                TypeOwner::Interface(id) => {
-                    assert!(self.interfaces.get(id).is_some());
-                    assert!(interface_types[id.index()].contains(&ty_id));
-                }
-                TypeOwner::World(id) => {
-                    assert!(self.worlds.get(id).is_some());
-                    assert!(world_types[id.index()].contains(&ty_id));
-                }
-                TypeOwner::None => {}
+        // Second make sure that this item, if it's an interface, will not alter
+        // the meaning of the preexisting world by ensuring that it's not in the
+        // set of "must be imported" items.
+        if let WorldItem::Interface { id, .. } = item {
+            if let Some(export) = must_be_imported.get(&id) {
+                let export_name = self.name_world_key(export);
+                bail!(
+                    "export `{export_name}` depends on `{name}` \
+                     previously as an import which will change meaning \
+                     if `{name}` is added as an export"
+                );
             }
         }
 
-        self.assert_topologically_sorted();
+        Ok(())
     }
 
-    fn assert_topologically_sorted(&self) {
-        let mut positions = IndexMap::new();
-        for id in self.topological_packages() {
-            let pkg = &self.packages[id];
-            log::debug!("pkg {}", pkg.name);
-            let prev = positions.insert(Some(id), IndexSet::new());
-            assert!(prev.is_none());
+    fn ensure_not_exported(&self, world: &World, id: InterfaceId) -> Result<()> {
+        let key = WorldKey::Interface(id);
+        let name = self.name_world_key(&key);
+        if world.exports.contains_key(&key) {
+            bail!(
+                "world exports `{name}` but it's also transitively used by an \
+                     import \
+                   which means that this is not valid"
+            )
         }
-        positions.insert(None, IndexSet::new());
-
-        for (id, iface) in self.interfaces.iter() {
-            log::debug!("iface {:?}", iface.name);
-            let ok = positions.get_mut(&iface.package).unwrap().insert(id);
-            assert!(ok);
+        for dep in self.interface_direct_deps(id) {
+            self.ensure_not_exported(world, dep)
+                .with_context(|| format!("failed validating transitive import dep `{name}`"))?;
         }
+        Ok(())
+    }
 
-        for (_, world) in self.worlds.iter() {
-            log::debug!("world {:?}", world.name);
-
-            let my_package = world.package;
-            let my_package_pos = positions.get_index_of(&my_package).unwrap();
-
-            for (_, item) in world.imports.iter().chain(&world.exports) {
-                let id = match item {
-                    WorldItem::Interface { id, .. } => *id,
-                    _ => continue,
-                };
-                let other_package = self.interfaces[id].package;
-                let other_package_pos = positions.get_index_of(&other_package).unwrap();
-
-                assert!(other_package_pos <= my_package_pos);
-            }
+    /// Returns an iterator of all the direct interface dependencies of this
+    /// `item`.
+    ///
+    /// Note that this doesn't include transitive dependencies, that must be
+    /// followed manually.
+    fn world_item_direct_deps(&self, item: &WorldItem) -> impl Iterator<Item = InterfaceId> + '_ {
+        let mut interface = None;
+        let mut ty = None;
+        match item {
+            WorldItem::Function(_) => {}
+            WorldItem::Type(id) => ty = Some(*id),
+            WorldItem::Interface { id, .. } => interface = Some(*id),
         }
 
-        for (_id, ty) in self.types.iter() {
-            log::debug!("type {:?} {:?}", ty.name, ty.owner);
-            let other_id = match ty.kind {
-                TypeDefKind::Type(Type::Id(ty)) => ty,
-                _ => continue,
-            };
-            let other = &self.types[other_id];
-            if ty.kind == other.kind {
-                continue;
-            }
-            let my_interface = match ty.owner {
-                TypeOwner::Interface(id) => id,
-                _ => continue,
-            };
-            let other_interface = match other.owner {
-                TypeOwner::Interface(id) => id,
-                _ => continue,
-            };
+        interface
+            .into_iter()
+            .flat_map(move |id| self.interface_direct_deps(id))
+            .chain(ty.and_then(|t| self.type_interface_dep(t)))
+    }
 
-            let my_package = self.interfaces[my_interface].package;
-            let other_package = self.interfaces[other_interface].package;
-            let my_package_pos = positions.get_index_of(&my_package).unwrap();
-            let other_package_pos = positions.get_index_of(&other_package).unwrap();
+    /// Invokes `f` with `id` and all transitive interface dependencies of `id`.
+    ///
+    /// Note that `f` may be called with the same id multiple times.
+    fn foreach_interface_dep(&self, id: InterfaceId, f: &mut dyn FnMut(InterfaceId)) {
+        self._foreach_interface_dep(id, f, &mut HashSet::new())
+    }
 
-            if my_package_pos == other_package_pos {
-                let interfaces = &positions[&my_package];
-                let my_interface_pos = interfaces.get_index_of(&my_interface).unwrap();
-                let other_interface_pos = interfaces.get_index_of(&other_interface).unwrap();
-                assert!(other_interface_pos <= my_interface_pos);
-            } else {
-                assert!(other_package_pos < my_package_pos);
-            }
+    // Internal detail of `foreach_interface_dep` which uses a hash map to prune
+    // the visit tree to ensure that this doesn't visit an exponential number of
+    // interfaces.
+    fn _foreach_interface_dep(
+        &self,
+        id: InterfaceId,
+        f: &mut dyn FnMut(InterfaceId),
+        visited: &mut HashSet<InterfaceId>,
+    ) {
+        if !visited.insert(id) {
+            return;
+        }
+        f(id);
+        for dep in self.interface_direct_deps(id) {
+            self._foreach_interface_dep(dep, f, visited);
         }
     }
 
-    fn assert_world_elaborated(&self, world: &World) {
-        for (key, item) in world.imports.iter() {
-            log::debug!(
-                "asserting elaborated world import {}",
-                self.name_world_key(key)
-            );
-            match item {
-                WorldItem::Type(t) => self.assert_world_imports_type_deps(world, key, *t),
+    /// Returns the ID of the specified `interface`.
+    ///
+    /// Returns `None` for unnamed interfaces.
+    pub fn id_of(&self, interface: InterfaceId) -> Option<String> {
+        let interface = &self.interfaces[interface];
+        Some(self.id_of_name(interface.package.unwrap(), interface.name.as_ref()?))
+    }
 
-                // All types referred to must be imported.
-                WorldItem::Function(f) => self.assert_world_function_imports_types(world, key, f),
+    /// Returns the "canonicalized interface name" of `interface`.
+    ///
+    /// Returns `None` for unnamed interfaces. See `BuildTargets.md` in the
+    /// upstream component model repository for more information about this.
+    pub fn canonicalized_id_of(&self, interface: InterfaceId) -> Option<String> {
+        let interface = &self.interfaces[interface];
+        Some(self.canonicalized_id_of_name(interface.package.unwrap(), interface.name.as_ref()?))
+    }
 
-                // All direct dependencies of this interface must be imported.
-                WorldItem::Interface { id, .. } => {
-                    for dep in self.interface_direct_deps(*id) {
-                        assert!(
-                            world.imports.contains_key(&WorldKey::Interface(dep)),
-                            "world import of {} is missing transitive dep of {}",
-                            self.name_world_key(key),
-                            self.id_of(dep).unwrap(),
-                        );
-                    }
-                }
-            }
+    /// Convert a world to an "importized" version where the world is updated
+    /// in-place to reflect what it would look like to be imported.
+    ///
+    /// This is a transformation which is used as part of the process of
+    /// importing a component today. For example when a component depends on
+    /// another component this is useful for generating WIT which can be use to
+    /// represent the component being imported. The general idea is that this
+    /// function will update the `world_id` specified such it imports the
+    /// functionality that it previously exported. The world will be left with
+    /// no exports, and each former export keeps its [`Stability`] annotation
+    /// as an import.
+    ///
+    /// This world is then suitable for merging into other worlds or generating
+    /// bindings in a context that is importing the original world. This
+    /// is intended to be used as part of language tooling when depending on
+    /// other components.
+    pub fn importize(&mut self, world_id: WorldId, out_world_name: Option<String>) -> Result<()> {
+        // Rename the world to avoid having it get confused with the original
+        // name of the world. Add `-importized` to it for now. Precisely how
+        // this new world is created may want to be updated over time if this
+        // becomes problematic.
+        let world = &mut self.worlds[world_id];
+        let pkg = &mut self.packages[world.package.unwrap()];
+        pkg.worlds.shift_remove(&world.name);
+        if let Some(name) = out_world_name {
+            world.name = name.clone();
+            pkg.worlds.insert(name, world_id);
+        } else {
+            world.name.push_str("-importized");
+            pkg.worlds.insert(world.name.clone(), world_id);
         }
-        for (key, item) in world.exports.iter() {
-            log::debug!(
-                "asserting elaborated world export {}",
-                self.name_world_key(key)
-            );
-            match item {
-                // Types referred to by this function must be imported.
-                WorldItem::Function(f) => self.assert_world_function_imports_types(world, key, f),
 
-                // Dependencies of exported interfaces must also be exported, or
-                // if imported then that entire chain of imports must be
-                // imported and not exported.
-                WorldItem::Interface { id, .. } => {
-                    for dep in self.interface_direct_deps(*id) {
-                        let dep_key = WorldKey::Interface(dep);
-                        if world.exports.contains_key(&dep_key) {
-                            continue;
-                        }
-                        self.foreach_interface_dep(dep, &mut |dep| {
-                            let dep_key = WorldKey::Interface(dep);
-                            assert!(
-                                world.imports.contains_key(&dep_key),
-                                "world should import {} (required by {})",
-                                self.name_world_key(&dep_key),
-                                self.name_world_key(key),
-                            );
-                            assert!(
-                                !world.exports.contains_key(&dep_key),
-                                "world should not export {} (required by {})",
-                                self.name_world_key(&dep_key),
-                                self.name_world_key(key),
-                            );
-                        });
-                    }
-                }
+        // Trim all non-type definitions from imports. Types can be used by
+        // exported functions, for example, so they're preserved.
+        world.imports.retain(|_, item| match item {
+            WorldItem::Type(_) => true,
+            _ => false,
+        });
 
-                // exported types not allowed at this time
-                WorldItem::Type(_) => unreachable!(),
-            }
-        }
-    }
+        for (name, export) in mem::take(&mut world.exports) {
+            match (name.clone(), world.imports.insert(name, export)) {
+                // no previous item? this insertion was ok
+                (_, None) => {}
 
-    fn assert_world_imports_type_deps(&self, world: &World, key: &WorldKey, ty: TypeId) {
-        // If this is a `use` statement then the referred-to interface must be
-        // imported into this world.
-        let ty = &self.types[ty];
-        if let TypeDefKind::Type(Type::Id(other)) = ty.kind {
-            if let TypeOwner::Interface(id) = self.types[other].owner {
-                let key = WorldKey::Interface(id);
-                assert!(world.imports.contains_key(&key));
-                return;
+                // cannot overwrite an import with an export
+                (WorldKey::Name(name), Some(_)) => {
+                    bail!("world export `{name}` conflicts with import of same name");
+                }
+
+                // Exports already don't overlap each other and the only imports
+                // preserved above were types so this shouldn't be reachable.
+                (WorldKey::Interface(_), _) => unreachable!(),
             }
         }
 
-        // ... otherwise any named type that this type refers to, one level
-        // deep, must be imported into this world under that name.
-
-        let mut visitor = MyVisit(self, Vec::new());
-        visitor.visit_type_def(self, ty);
-        for ty in visitor.1 {
-            let ty = &self.types[ty];
-            let Some(name) = ty.name.clone() else {
-                continue;
-            };
-            let dep_key = WorldKey::Name(name);
-            assert!(
-                world.imports.contains_key(&dep_key),
-                "world import `{}` should also force an import of `{}`",
-                self.name_world_key(key),
-                self.name_world_key(&dep_key),
-            );
-        }
+        // Fill out any missing transitive interface imports by elaborating this
+        // world which does that for us.
+        self.elaborate_world(world_id)?;
 
-        struct MyVisit<'a>(&'a Resolve, Vec<TypeId>);
+        #[cfg(debug_assertions)]
+        self.assert_valid();
+        Ok(())
+    }
 
-        impl TypeIdVisitor for MyVisit<'_> {
-            fn before_visit_type_id(&mut self, id: TypeId) -> bool {
-                self.1.push(id);
-                // recurse into unnamed types to look at all named types
-                self.0.types[id].name.is_none()
-            }
+    /// Returns the ID of the specified `name` within the `pkg`.
+    pub fn id_of_name(&self, pkg: PackageId, name: &str) -> String {
+        let package = &self.packages[pkg];
+        let mut base = String::new();
+        base.push_str(&package.name.namespace);
+        base.push_str(":");
+        base.push_str(&package.name.name);
+        base.push_str("/");
+        base.push_str(name);
+        if let Some(version) = &package.name.version {
+            base.push_str(&format!("@{version}"));
         }
+        base
     }
 
-    /// This asserts that all types referred to by `func` are imported into
-    /// `world` under `WorldKey::Name`. Note that this is only applicable to
-    /// named type
-    fn assert_world_function_imports_types(&self, world: &World, key: &WorldKey, func: &Function) {
-        for ty in func
-            .parameter_and_result_types()
-            .chain(func.kind.resource().map(Type::Id))
-        {
-            let Type::Id(id) = ty else {
-                continue;
-            };
-            self.assert_world_imports_type_deps(world, key, id);
+    /// Returns the "canonicalized interface name" of the specified `name`
+    /// within the `pkg`.
+    ///
+    /// See `BuildTargets.md` in the upstream component model repository for
+    /// more information about this.
+    pub fn canonicalized_id_of_name(&self, pkg: PackageId, name: &str) -> String {
+        let package = &self.packages[pkg];
+        let mut base = String::new();
+        base.push_str(&package.name.namespace);
+        base.push_str(":");
+        base.push_str(&package.name.name);
+        base.push_str("/");
+        base.push_str(name);
+        if let Some(version) = &package.name.version {
+            base.push_str("@");
+            let string = PackageName::version_compat_track_string(version);
+            base.push_str(&string);
         }
+        base
     }
 
-    /// Returns whether the `stability` annotation contained within `pkg_id`
-    /// should be included or not.
+    /// Attempts to locate a world given the "default" set of `packages` and the
+    /// optional string specifier `world`.
     ///
-    /// The `span` provided here is an optional span pointing to the item that
-    /// is annotated with `stability`.
+    /// This method is intended to be used by bindings generation tools to
+    /// select a world from either `packages` or a package in this `Resolve`.
+    /// The `packages` list is a return value from methods such as
+    /// [`push_path`](Resolve::push_path), [`push_dir`](Resolve::push_dir),
+    /// [`push_file`](Resolve::push_file), [`push_group`](Resolve::push_group),
+    /// or [`push_str`](Resolve::push_str). The return values of those methods
+    /// are the "main package list" which is specified by the user and is used
+    /// as a heuristic for world selection.
     ///
-    /// Returns `Ok(true)` if the item is included, or `Ok(false)` if the item
-    /// is not.
+    /// If `world` is `None` then `packages` must have one entry and that
+    /// package must have exactly one world. If this is the case then that world
+    /// will be returned, otherwise an error will be returned.
     ///
-    /// # Errors
+    /// If `world` is `Some` then it can either be:
     ///
-    /// Returns an error if the `pkg_id` isn't annotated with sufficient version
-    /// information to have a `stability` annotation. For example if `pkg_id`
-    /// has no version listed then an error will be returned if `stability`
-    /// mentions a version.
-    fn include_stability(
-        &self,
-        stability: &Stability,
-        pkg_id: &PackageId,
-        span: Option<Span>,
-    ) -> Result<bool> {
-        let err = |msg: String| match span {
-            Some(span) => Error::new(span, msg).into(),
-            None => anyhow::Error::msg(msg),
+    /// * A kebab-name of a world such as `"the-world"`. In this situation
+    ///   the `packages` list must have only a single entry. If `packages` has
+    ///   no entries or more than one, or if the kebab-name does not exist in
+    ///   the one package specified, then an error will be returned.
+    ///
+    /// * An ID-based form of a world which is selected within this `Resolve`,
+    ///   for example `"wasi:http/proxy"`. In this situation the `packages`
+    ///   array is ignored and the ID specified is use to lookup a package. Note
+    ///   that a version does not need to be specified in this string if there's
+    ///   only one package of the same name and it has a version. In this
+    ///   situation the version can be omitted.
+    ///
+    /// If successful the corresponding `WorldId` is returned, otherwise an
+    /// error is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anyhow::Result;
+    /// use wit_parser::Resolve;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let mut resolve = Resolve::default();
+    ///
+    ///     // For inputs which have a single package and only one world `None`
+    ///     // can be specified.
+    ///     let id = resolve.push_str(
+    ///         "./my-test.wit",
+    ///         r#"
+    ///             package example:wit1;
+    ///
+    ///             world foo {
+    ///                 // ...
+    ///             }
+    ///         "#,
+    ///     )?;
+    ///     assert!(resolve.select_world(id, None).is_ok());
+    ///
+    ///     // For inputs which have a single package and multiple worlds then
+    ///     // a world must be specified.
+    ///     let id = resolve.push_str(
+    ///         "./my-test.wit",
+    ///         r#"
+    ///             package example:wit2;
+    ///
+    ///             world foo { /* ... */ }
+    ///
+    ///             world bar { /* ... */ }
+    ///         "#,
+    ///     )?;
+    ///     assert!(resolve.select_world(id, None).is_err());
+    ///     assert!(resolve.select_world(id, Some("foo")).is_ok());
+    ///     assert!(resolve.select_world(id, Some("bar")).is_ok());
+    ///
+    ///     // For inputs which have more than one package then a fully
+    ///     // qualified name must be specified.
+    ///
+    ///     // Note that the `ids` or `packages` argument is ignored if a fully
+    ///     // qualified world specified is provided meaning previous worlds
+    ///     // can be selected.
+    ///     assert!(resolve.select_world(id, Some("example:wit1/foo")).is_ok());
+    ///     assert!(resolve.select_world(id, Some("example:wit2/foo")).is_ok());
+    ///
+    ///     // When selecting with a version it's ok to drop the version when
+    ///     // there's only a single copy of that package in `Resolve`.
+    ///     resolve.push_str(
+    ///         "./my-test.wit",
+    ///         r#"
+    ///             package example:wit5@1.0.0;
+    ///
+    ///             world foo { /* ... */ }
+    ///         "#,
+    ///     )?;
+    ///     assert!(resolve.select_world(id, Some("example:wit5/foo")).is_ok());
+    ///
+    ///     // However when a single package has multiple versions in a resolve
+    ///     // it's required to specify the version to select which one.
+    ///     resolve.push_str(
+    ///         "./my-test.wit",
+    ///         r#"
+    ///             package example:wit5@2.0.0;
+    ///
+    ///             world foo { /* ... */ }
+    ///         "#,
+    ///     )?;
+    ///     assert!(resolve.select_world(id, Some("example:wit5/foo")).is_err());
+    ///     assert!(resolve.select_world(id, Some("example:wit5/foo@1.0.0")).is_ok());
+    ///     assert!(resolve.select_world(id, Some("example:wit5/foo@2.0.0")).is_ok());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn select_world(&self, package: PackageId, world: Option<&str>) -> Result<WorldId> {
+        let world_path = match world {
+            Some(world) => Some(
+                parse_use_path(world)
+                    .with_context(|| format!("failed to parse world specifier `{world}`"))?,
+            ),
+            None => None,
         };
-        Ok(match stability {
-            Stability::Unknown => true,
-            // NOTE: deprecations are intentionally omitted -- an existing
-            // `@since` takes precedence over `@deprecated`
-            Stability::Stable { since, .. } => {
-                let Some(p) = self.packages.get(*pkg_id) else {
-                    // We can't check much without a package (possibly dealing
-                    // with an item in an `UnresolvedPackage`), @since version &
-                    // deprecations can't be checked because there's no package
-                    // version to compare to.
-                    //
-                    // Feature requirements on stabilized features are ignored
-                    // in resolved packages, so we do the same here.
-                    return Ok(true);
-                };
 
-                // Use of feature gating with version specifiers inside a
-                // package that is not versioned is not allowed
-                let package_version = p.name.version.as_ref().ok_or_else(|| {
-                    err(format!(
-                        "package [{}] contains a feature gate with a version \
-                         specifier, so it must have a version",
-                        p.name
-                    ))
-                })?;
+        let (pkg, world_name) = match world_path {
+            Some(ParsedUsePath::Name(name)) => (package, name),
+            Some(ParsedUsePath::Package(pkg, interface)) => {
+                let pkg = match self.package_names.get(&pkg) {
+                    Some(pkg) => *pkg,
+                    None => {
+                        let mut candidates = self.package_names.iter().filter(|(name, _)| {
+                            pkg.version.is_none()
+                                && pkg.name == name.name
+                                && pkg.namespace == name.namespace
+                                && name.version.is_some()
+                        });
+                        let candidate = candidates.next();
+                        if let Some((c2, _)) = candidates.next() {
+                            let (c1, _) = candidate.unwrap();
+                            bail!(
+                                "package name `{pkg}` is available at both \
+                                 versions {} and {} but which is not specified",
+                                c1.version.as_ref().unwrap(),
+                                c2.version.as_ref().unwrap(),
+                            );
+                        }
+                        match candidate {
+                            Some((_, id)) => *id,
+                            None => bail!("unknown package `{pkg}`"),
+                        }
+                    }
+                };
+                (pkg, interface.to_string())
+            }
+            None => {
+                let pkg = &self.packages[package];
+                let worlds = pkg
+                    .worlds
+                    .values()
+                    .map(|world| (package, *world))
+                    .collect::<Vec<_>>();
 
-                // If the version on the feature gate is:
-                // - released, then we can include it
-                // - unreleased, then we must check the feature (if present)
-                if since > package_version {
-                    return Err(err(format!(
-                        "feature gate cannot reference unreleased version \
-                        {since} of package [{}] (current version {package_version})",
-                        p.name
-                    )));
+                match &worlds[..] {
+                    [] => bail!("The main package `{}` contains no worlds", pkg.name),
+                    [(_, world)] => return Ok(*world),
+                    _ => bail!(
+                        "multiple worlds found; one must be explicitly chosen:{}",
+                        worlds
+                            .iter()
+                            .map(|(pkg, world)| format!(
+                                "\n  {}/{}",
+                                self.packages[*pkg].name, self.worlds[*world].name
+                            ))
+                            .collect::<String>()
+                    ),
                 }
-
-                true
-            }
-            Stability::Unstable { feature, .. } => {
-                self.features.contains(feature) || self.all_features
             }
+        };
+        let pkg = &self.packages[pkg];
+        pkg.worlds.get(&world_name).copied().ok_or_else(|| {
+            WorldNotFoundError::new(
+                world_name,
+                pkg.name.clone(),
+                pkg.worlds.keys().cloned().collect(),
+            )
+            .into()
         })
     }
 
-    /// Convenience wrapper around `include_stability` specialized for types
-    /// with a more targeted error message.
-    fn include_type(&self, ty: &TypeDef, pkgid: PackageId, span: Span) -> Result<bool> {
-        self.include_stability(&ty.stability, &pkgid, Some(span))
-            .with_context(|| {
-                format!(
-                    "failed to process feature gate for type [{}] in package [{}]",
-                    ty.name.as_ref().map(String::as_str).unwrap_or("<unknown>"),
-                    self.packages[pkgid].name,
-                )
+    /// Looks up an interface by its fully qualified name, such as
+    /// `wasi:io/streams@0.2.0`.
+    ///
+    /// As with [`Resolve::select_world`] the version may be omitted if only
+    /// one version of the package is present. The version may also omit its
+    /// patch, or its minor and patch, in which case the latest version with
+    /// the given components is used. For example `wasi:io/streams@0.2` finds
+    /// `wasi:io/streams@0.2.3` if that's the newest `0.2.x` present, and
+    /// `wasi:io/streams@1` finds the newest `1.x.y`.
+    ///
+    /// Returns `None` if `id` can't be parsed or no such interface exists.
+    pub fn interface_by_name(&self, id: &str) -> Option<InterfaceId> {
+        let (path, version) = match id.rsplit_once('@') {
+            Some((path, version)) => (path, Some(version)),
+            None => (id, None),
+        };
+        let ParsedUsePath::Package(mut name, interface) = parse_use_path(path).ok()? else {
+            return None;
+        };
+        let pkg = match version {
+            None => *lookup_package_name(&self.package_names, &name, true).ok()??,
+            Some(version) => match Version::parse(version) {
+                Ok(version) => {
+                    name.version = Some(version);
+                    *self.package_names.get(&name)?
+                }
+                Err(_) => {
+                    let (major, minor) = match version.split_once('.') {
+                        Some((major, minor)) => (major, Some(minor.parse::<u64>().ok()?)),
+                        None => (version, None),
+                    };
+                    let major = major.parse::<u64>().ok()?;
+                    *self
+                        .package_names
+                        .iter()
+                        .filter(|(n, _)| {
+                            n.namespace == name.namespace
+                                && n.name == name.name
+                                && n.version.as_ref().is_some_and(|v| {
+                                    v.major == major && minor.map_or(true, |minor| v.minor == minor)
+                                })
+                        })
+                        .max_by_key(|(n, _)| n.version.as_ref())?
+                        .1
+                }
+            },
+        };
+        self.packages[pkg].interfaces.get(&interface).copied()
+    }
+
+    /// Returns a URL-safe identifier for `world` such as
+    /// `wasi-cli-command-0.2.0`.
+    ///
+    /// The slug is the package namespace, package name, world name, and
+    /// package version, if any, joined with `-`. Any character other than an
+    /// ASCII letter, digit, `-`, or `.` is replaced with `-`, which only
+    /// affects versions with build metadata such as `1.0.0+abc`. The slug
+    /// only depends on these names, not on the order items were added to
+    /// this [`Resolve`], so it's stable across runs. Note that different
+    /// worlds may produce the same slug, for example world `b-c` in `a:x`
+    /// and world `c` in `a:x-b`.
+    pub fn world_slug(&self, world: WorldId) -> String {
+        let world = &self.worlds[world];
+        let mut slug = String::new();
+        if let Some(pkg) = world.package {
+            let name = &self.packages[pkg].name;
+            slug.push_str(&format!("{}-{}-", name.namespace, name.name));
+        }
+        slug.push_str(&world.name);
+        if let Some(version) = world
+            .package
+            .and_then(|pkg| self.packages[pkg].name.version.as_ref())
+        {
+            slug.push_str(&format!("-{version}"));
+        }
+        slug.chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '.' => c,
+                _ => '-',
             })
+            .collect()
     }
 
-    /// Performs the "elaboration process" necessary for the `world_id`
-    /// specified to ensure that all of its transitive imports are listed.
+    /// Assigns a human readable name to the `WorldKey` specified.
+    pub fn name_world_key(&self, key: &WorldKey) -> String {
+        match key {
+            WorldKey::Name(s) => s.to_string(),
+            WorldKey::Interface(i) => self.id_of(*i).expect("unexpected anonymous interface"),
+        }
+    }
+
+    /// Same as [`Resolve::name_world_key`] except that `WorldKey::Interfaces`
+    /// uses [`Resolve::canonicalized_id_of`].
+    pub fn name_canonicalized_world_key(&self, key: &WorldKey) -> String {
+        match key {
+            WorldKey::Name(s) => s.to_string(),
+            WorldKey::Interface(i) => self
+                .canonicalized_id_of(*i)
+                .expect("unexpected anonymous interface"),
+        }
+    }
+
+    /// Serializes this [`Resolve`] to JSON, keying world imports and exports
+    /// of interfaces by the interface's qualified name.
     ///
-    /// This function will take the unordered lists of the specified world's
-    /// imports and exports and "elaborate" them to ensure that they're
-    /// topographically sorted where all transitively required interfaces by
-    /// imports, or exports, are listed. This will additionally validate that
-    /// the exports are all valid and present, specifically with the restriction
-    /// noted on `elaborate_world_exports`.
+    /// The default serialization keys these as `interface-{index}`, which
+    /// depends on the order interfaces were added to this [`Resolve`]. Here
+    /// keys such as `wasi:io/streams@0.2.0` are used instead, with the index
+    /// form only remaining for anonymous interfaces. All other ids in the
+    /// output are still arena indices.
+    #[cfg(feature = "serde")]
+    pub fn to_stable_json(&self) -> serde_json::Value {
+        let mut json = serde_json::to_value(self).expect("serializing a `Resolve` can't fail");
+        let worlds = json["worlds"]
+            .as_array_mut()
+            .expect("worlds should be an array");
+        for ((_, world), json) in self.worlds.iter().zip(worlds) {
+            for (items, field) in [(&world.imports, "imports"), (&world.exports, "exports")] {
+                let map = json[field]
+                    .as_object_mut()
+                    .expect("world items should be a map");
+                for key in items.keys() {
+                    let WorldKey::Interface(id) = key else {
+                        continue;
+                    };
+                    let Some(name) = self.id_of(*id) else {
+                        continue;
+                    };
+                    if let Some(value) = map.remove(&String::from(key.clone())) {
+                        map.insert(name, value);
+                    }
+                }
+            }
+        }
+        json
+    }
+
+    /// Sorts the imports and exports of the world `id` into a deterministic
+    /// order.
     ///
-    /// The world is mutated in-place in this `Resolve`.
-    fn elaborate_world(&mut self, world_id: WorldId) -> Result<()> {
-        // First process all imports. This is easier than exports since the only
-        // requirement here is that all interfaces need to be added with a
-        // topological order between them.
-        let mut new_imports = IndexMap::new();
-        let world = &self.worlds[world_id];
+    /// Interfaces imported or exported by name, such as `import foo:bar/baz;`,
+    /// come first sorted by their fully qualified name. These are followed by
+    /// all other items sorted by their kebab-case name. Items which depend on
+    /// others within the same list, for example an interface using types from
+    /// another interface, are still placed after their dependencies, so the
+    /// world remains valid and equivalent to before.
+    pub fn sort_world(&mut self, id: WorldId) {
+        let world = &self.worlds[id];
+        let imports = self.sorted_world_items(id, &world.imports);
+        let exports = self.sorted_world_items(id, &world.exports);
+        let world = &mut self.worlds[id];
+        world.imports = imports;
+        world.exports = exports;
+    }
 
-        // Sort the imports by "class" to ensure that this matches the order
-        // that items are printed and that items are in topological order.
-        //
-        // When printing worlds in WIT:
-        //
-        // * interfaces come first
-        // * types are next
-        //   * type imports are first
-        //   * type definitions are next
-        //   * resource definitions have methods printed inline
-        // * freestanding functions are last
-        //
-        // This reflects the topological order between items where types
-        // can refer to imports and functions can refer to these types. Ordering
-        // within a single class (e.g. imports depending on each other, types
-        // referring to each other) is already preserved by other passes in this
-        // file and general AST resolution. That means that a stable sort here
-        // can be used to ensure that each class is in the right location
-        // relative to the others.
-        //
-        // Overall this ensures that round-trips of WIT through wasm should
-        // always produce the same result.
-        let sort_key = |resolve: &Resolve, item: &WorldItem| match item {
-            WorldItem::Interface { .. } => 0,
-            WorldItem::Type(ty) => {
-                let ty = &resolve.types[*ty];
-                match ty.kind {
-                    TypeDefKind::Type(Type::Id(t)) if resolve.types[t].owner != ty.owner => 1,
-                    _ => 2,
-                }
+    fn sorted_world_items(
+        &self,
+        world: WorldId,
+        items: &IndexMap<WorldKey, WorldItem>,
+    ) -> IndexMap<WorldKey, WorldItem> {
+        let sort_key = |key: &WorldKey| match key {
+            WorldKey::Interface(_) => (0, self.name_world_key(key)),
+            WorldKey::Name(name) => (1, name.clone()),
+        };
+        let mut keys = items.keys().collect::<Vec<_>>();
+        keys.sort_by_cached_key(|key| sort_key(key));
+
+        // Dependencies of an item are the other items in `items` that the
+        // types it refers to are defined in.
+        let deps = |key: &WorldKey, item: &WorldItem| {
+            let mut live = LiveTypes::default();
+            live.add_world_item(self, item);
+            let mut deps = live
+                .iter()
+                .filter_map(|ty| {
+                    let ty = &self.types[ty];
+                    let dep = match ty.owner {
+                        TypeOwner::Interface(i) => WorldKey::Interface(i),
+                        TypeOwner::World(w) if w == world => WorldKey::Name(ty.name.clone()?),
+                        _ => return None,
+                    };
+                    match items.get_key_value(&dep) {
+                        Some((dep, _)) if dep != key => Some(dep),
+                        _ => None,
+                    }
+                })
+                .collect::<Vec<_>>();
+            deps.sort_by_cached_key(|key| sort_key(key));
+            deps.dedup();
+            deps
+        };
+
+        fn visit<'a>(
+            key: &'a WorldKey,
+            items: &'a IndexMap<WorldKey, WorldItem>,
+            deps: &dyn Fn(&WorldKey, &WorldItem) -> Vec<&'a WorldKey>,
+            visiting: &mut HashSet<&'a WorldKey>,
+            ret: &mut IndexMap<WorldKey, WorldItem>,
+        ) {
+            if ret.contains_key(key) || !visiting.insert(key) {
+                return;
             }
-            WorldItem::Function(f) => {
-                if f.kind.resource().is_none() {
-                    3
-                } else {
-                    4
-                }
+            let item = &items[key];
+            for dep in deps(key, item) {
+                visit(dep, items, deps, visiting, ret);
             }
-        };
+            ret.insert(key.clone(), item.clone());
+        }
 
-        // Sort world items when we start to elaborate the world to start with a
-        // topological view of items.
-        let mut world_imports = world.imports.iter().collect::<Vec<_>>();
-        world_imports.sort_by_key(|(_name, import)| sort_key(self, import));
-        for (name, item) in world_imports {
-            match item {
-                // Interfaces get their dependencies added first followed by the
-                // interface itself.
-                WorldItem::Interface { id, stability } => {
-                    self.elaborate_world_import(&mut new_imports, name.clone(), *id, &stability);
-                }
+        let mut ret = IndexMap::new();
+        let mut visiting = HashSet::new();
+        for key in keys {
+            visit(key, items, &deps, &mut visiting, &mut ret);
+        }
+        ret
+    }
 
-                // Functions are added as-is since their dependence on types in
-                // the world should already be satisfied.
-                WorldItem::Function(_) => {
-                    let prev = new_imports.insert(name.clone(), item.clone());
-                    assert!(prev.is_none());
-                }
+    /// Returns the named types defined in `pkg` which nothing refers to.
+    ///
+    /// A type is considered referenced if it's mentioned by any function or
+    /// any other type definition anywhere in this `Resolve`, including through
+    /// a `use` in another interface. Being listed in an interface or world is
+    /// not considered a reference on its own. Resources are never reported as
+    /// they're part of an interface's surface through their methods alone.
+    ///
+    /// Only direct references are considered, so a type which is only
+    /// referenced from another unused type isn't reported until that type is
+    /// removed.
+    pub fn unused_types(&self, pkg: PackageId) -> Vec<TypeId> {
+        struct Referenced(HashSet<TypeId>);
 
-                // Types may depend on an interface, in which case a (possibly)
-                // recursive addition of that interface happens here. Afterwards
-                // the type itself can be added safely.
-                WorldItem::Type(id) => {
-                    if let Some(dep) = self.type_interface_dep(*id) {
-                        self.elaborate_world_import(
-                            &mut new_imports,
-                            WorldKey::Interface(dep),
-                            dep,
-                            &self.types[*id].stability,
-                        );
-                    }
-                    let prev = new_imports.insert(name.clone(), item.clone());
-                    assert!(prev.is_none());
-                }
+        impl TypeIdVisitor for Referenced {
+            fn before_visit_type_id(&mut self, id: TypeId) -> bool {
+                self.0.insert(id);
+                false
             }
         }
 
-        // Exports are trickier than imports, notably to uphold the invariant
-        // required by `elaborate_world_exports`. To do this the exports are
-        // partitioned into interfaces/functions. All functions are added to
-        // the new exports list during this loop but interfaces are all deferred
-        // to be handled in the `elaborate_world_exports` function.
-        let mut new_exports = IndexMap::new();
-        let mut export_interfaces = IndexMap::new();
-        for (name, item) in world.exports.iter() {
-            match item {
-                WorldItem::Interface { id, stability } => {
-                    let prev = export_interfaces.insert(*id, (name.clone(), stability));
-                    assert!(prev.is_none());
-                }
-                WorldItem::Function(_) => {
-                    let prev = new_exports.insert(name.clone(), item.clone());
-                    assert!(prev.is_none());
+        let mut referenced = Referenced(HashSet::new());
+        for (_, ty) in self.types.iter() {
+            referenced.visit_type_def(self, ty);
+        }
+        for (_, iface) in self.interfaces.iter() {
+            for func in iface.functions.values() {
+                referenced.visit_func(self, func);
+            }
+        }
+        for (_, world) in self.worlds.iter() {
+            for item in world.imports.values().chain(world.exports.values()) {
+                if let WorldItem::Function(func) = item {
+                    referenced.visit_func(self, func);
                 }
-                WorldItem::Type(_) => unreachable!(),
             }
         }
 
-        self.elaborate_world_exports(&export_interfaces, &mut new_imports, &mut new_exports)?;
-
-        // In addition to sorting at the start of elaboration also sort here at
-        // the end of elaboration to handle types being interspersed with
-        // interfaces as they're found.
-        new_imports.sort_by_cached_key(|_name, import| sort_key(self, import));
+        let in_package = |owner: TypeOwner| match owner {
+            TypeOwner::Interface(id) => self.interfaces[id].package == Some(pkg),
+            TypeOwner::World(id) => self.worlds[id].package == Some(pkg),
+            TypeOwner::None => false,
+        };
+        self.types
+            .iter()
+            .filter(|(id, ty)| {
+                ty.name.is_some()
+                    && !matches!(ty.kind, TypeDefKind::Resource)
+                    && in_package(ty.owner)
+                    && !referenced.0.contains(id)
+            })
+            .map(|(id, _)| id)
+            .collect()
+    }
 
-        // And with all that done the world is updated in-place with
-        // imports/exports.
-        log::trace!("imports = {new_imports:?}");
-        log::trace!("exports = {new_exports:?}");
-        let world = &mut self.worlds[world_id];
-        world.imports = new_imports;
-        world.exports = new_exports;
+    /// Returns whether any function targets the resource `id` as a method,
+    /// static function, or constructor.
+    ///
+    /// Only functions in the interface or world that owns `id` are considered
+    /// since that's the only place its functions can be defined. Returns
+    /// `false` if `id` isn't a resource.
+    pub fn resource_has_methods(&self, id: TypeId) -> bool {
+        let ty = &self.types[id];
+        if !matches!(ty.kind, TypeDefKind::Resource) {
+            return false;
+        }
+        let targets = |func: &Function| func.kind.resource() == Some(id);
+        match ty.owner {
+            TypeOwner::Interface(iface) => self.interfaces[iface].functions.values().any(targets),
+            TypeOwner::World(world) => {
+                let world = &self.worlds[world];
+                world
+                    .imports
+                    .values()
+                    .chain(world.exports.values())
+                    .any(|item| matches!(item, WorldItem::Function(func) if targets(func)))
+            }
+            TypeOwner::None => false,
+        }
+    }
 
-        Ok(())
+    /// Returns the resources defined in `pkg` which have no functions at all.
+    ///
+    /// A resource without a constructor, methods, or static functions can
+    /// only be passed around as an opaque handle, which is legal but is often
+    /// an authoring mistake. See [`Resolve::resource_has_methods`].
+    pub fn resources_without_methods(&self, pkg: PackageId) -> Vec<TypeId> {
+        let in_package = |owner: TypeOwner| match owner {
+            TypeOwner::Interface(id) => self.interfaces[id].package == Some(pkg),
+            TypeOwner::World(id) => self.worlds[id].package == Some(pkg),
+            TypeOwner::None => false,
+        };
+        self.types
+            .iter()
+            .filter(|(id, ty)| {
+                matches!(ty.kind, TypeDefKind::Resource)
+                    && in_package(ty.owner)
+                    && !self.resource_has_methods(*id)
+            })
+            .map(|(id, _)| id)
+            .collect()
     }
 
-    fn elaborate_world_import(
-        &self,
-        imports: &mut IndexMap<WorldKey, WorldItem>,
-        key: WorldKey,
-        id: InterfaceId,
-        stability: &Stability,
-    ) {
-        if imports.contains_key(&key) {
-            return;
+    /// Returns the items in `pkg` annotated with `@since(version = ...)` for
+    /// exactly `version`.
+    ///
+    /// This covers the package's interfaces and worlds themselves, the types
+    /// and functions within interfaces, and the imports and exports of worlds.
+    /// Items are returned in declaration order, interfaces first. Items with
+    /// no annotation or an `@unstable` annotation are never returned, and the
+    /// version of an enclosing item isn't inherited by the items within it.
+    pub fn items_introduced_in(&self, pkg: PackageId, version: &Version) -> Vec<ItemRef> {
+        let introduced = |stability: &Stability| match stability {
+            Stability::Stable { since, .. } => since == version,
+            _ => false,
+        };
+        let package = &self.packages[pkg];
+        let mut ret = Vec::new();
+        for id in package.interfaces.values().copied() {
+            let iface = &self.interfaces[id];
+            if introduced(&iface.stability) {
+                ret.push(ItemRef::Interface(id));
+            }
+            for ty in iface.types.values().copied() {
+                if introduced(&self.types[ty].stability) {
+                    ret.push(ItemRef::Type(ty));
+                }
+            }
+            for (name, func) in iface.functions.iter() {
+                if introduced(&func.stability) {
+                    ret.push(ItemRef::InterfaceFunction(id, name.clone()));
+                }
+            }
         }
-        for dep in self.interface_direct_deps(id) {
-            self.elaborate_world_import(imports, WorldKey::Interface(dep), dep, stability);
+        for id in package.worlds.values().copied() {
+            let world = &self.worlds[id];
+            if introduced(&world.stability) {
+                ret.push(ItemRef::World(id));
+            }
+            for (key, item) in world.imports.iter().chain(world.exports.iter()) {
+                if !introduced(item.stability(self)) {
+                    continue;
+                }
+                ret.push(match item {
+                    WorldItem::Type(ty) => ItemRef::Type(*ty),
+                    _ => ItemRef::WorldItem(id, key.clone()),
+                });
+            }
         }
-        let prev = imports.insert(
-            key,
-            WorldItem::Interface {
-                id,
-                stability: stability.clone(),
-            },
-        );
-        assert!(prev.is_none());
+        ret
     }
 
-    /// This function adds all of the interfaces in `export_interfaces` to the
-    /// list of exports of the `world` specified.
+    /// Returns the span of the name of `item` in the WIT source it was
+    /// parsed from.
     ///
-    /// This method is more involved than adding imports because it is fallible.
-    /// Chiefly what can happen is that the dependencies of all exports must be
-    /// satisfied by other exports or imports, but not both. For example given a
-    /// situation such as:
+    /// Spans are recorded for interfaces, worlds, named types, interface
+    /// functions, and the imports and exports of worlds. The returned span is
+    /// relative to the returned [`SourceMap`], which the item's package was
+    /// parsed from, and can be rendered with [`SourceMap::render_location`].
+    /// Returns `None` for items that didn't come from WIT source, such as
+    /// those decoded from a binary.
+    pub fn item_span(&self, item: ItemRef) -> Option<(Span, &SourceMap)> {
+        let (span, source_map) = self.item_spans.get(&item)?;
+        Some((*span, source_map))
+    }
+
+    /// Returns the resource that `func` creates, if its result is `own<r>`.
     ///
-    /// ```wit
-    /// interface a {
-    ///     type t = u32
-    /// }
-    /// interface b {
-    ///     use a.{t}
-    /// }
-    /// interface c {
-    ///     use a.{t}
-    ///     use b.{t as t2}
-    /// }
-    /// ```
+    /// This can be used to present freestanding functions shaped like a
+    /// factory as constructors of the resource they return. Type aliases are
+    /// looked through both for the result and the resource.
+    pub fn function_returns_owned_resource(&self, func: &Function) -> Option<TypeId> {
+        self.owned_resource(func.result.as_ref()?)
+    }
+
+    /// Returns the resource consumed by `func`, if it takes an `own<r>`
+    /// parameter.
     ///
-    /// where `c` depends on `b` and `a` where `b` depends on `a`, then the
-    /// purpose of this method is to reject this world:
-    ///
-    /// ```wit
-    /// world foo {
-    ///     export a
-    ///     export c
-    /// }
-    /// ```
+    /// This is the counterpart of [`Resolve::function_returns_owned_resource`]
+    /// for detecting functions shaped like a destructor. If multiple owned
+    /// resources are taken the first is returned.
+    pub fn function_consumes_owned_resource(&self, func: &Function) -> Option<TypeId> {
+        func.params
+            .iter()
+            .find_map(|(_, ty)| self.owned_resource(ty))
+    }
+
+    /// Returns the resource `r` if `ty` is `own<r>`, looking through aliases.
+    fn owned_resource(&self, ty: &Type) -> Option<TypeId> {
+        let mut id = match ty {
+            Type::Id(id) => *id,
+            _ => return None,
+        };
+        let mut owned = false;
+        loop {
+            match self.types[id].kind {
+                TypeDefKind::Type(Type::Id(next)) => id = next,
+                TypeDefKind::Handle(Handle::Own(next)) if !owned => {
+                    owned = true;
+                    id = next;
+                }
+                TypeDefKind::Resource if owned => break Some(id),
+                _ => break None,
+            }
+        }
+    }
+
+    /// Checks that every import of the world `candidate` is also permitted by
+    /// the world `policy`.
     ///
-    /// The reasoning here is unfortunately subtle and is additionally the
-    /// subject of WebAssembly/component-model#208. Effectively the `c`
-    /// interface depends on `b`, but it's not listed explicitly as an import,
-    /// so it's then implicitly added as an import. This then transitively
-    /// depends on `a` so it's also added as an import. At this point though `c`
-    /// also depends on `a`, and it's also exported, so naively it should depend
-    /// on the export and not implicitly add an import. This means though that
-    /// `c` has access to two copies of `a`, one imported and one exported. This
-    /// is not valid, especially in the face of resource types.
+    /// Imported interfaces are compared by name, with package versions
+    /// compared by [`PackageName::version_compat_track`] so, for example,
+    /// `wasi:io/streams@0.2.1` is permitted by a policy importing
+    /// `wasi:io/streams@0.2.0`. Imports with plain names, such as functions,
+    /// must be present in `policy` under the same name with the same shape.
+    /// Imported types aren't capabilities and are always permitted.
     ///
-    /// Overall this method is tasked with rejecting the above world by walking
-    /// over all the exports and adding their dependencies. Each dependency is
-    /// recorded with whether it's required to be imported, and then if an
-    /// export is added for something that's required to be an error then the
-    /// operation fails.
-    fn elaborate_world_exports(
+    /// On failure the imports of `candidate` which aren't permitted are
+    /// returned.
+    pub fn world_imports_subset_of(
         &self,
-        export_interfaces: &IndexMap<InterfaceId, (WorldKey, &Stability)>,
-        imports: &mut IndexMap<WorldKey, WorldItem>,
-        exports: &mut IndexMap<WorldKey, WorldItem>,
-    ) -> Result<()> {
-        let mut required_imports = HashSet::new();
-        for (id, (key, stability)) in export_interfaces.iter() {
-            let name = self.name_world_key(&key);
-            let ok = add_world_export(
-                self,
-                imports,
-                exports,
-                export_interfaces,
-                &mut required_imports,
-                *id,
-                key,
-                true,
-                stability,
-            );
-            if !ok {
-                bail!(
-                    // FIXME: this is not a great error message and basically no
-                    // one will know what to do when it gets printed. Improving
-                    // this error message, however, is a chunk of work that may
-                    // not be best spent doing this at this time, so I'm writing
-                    // this comment instead.
-                    //
-                    // More-or-less what should happen here is that a "path"
-                    // from this interface to the conflicting interface should
-                    // be printed. It should be explained why an import is being
-                    // injected, why that's conflicting with an export, and
-                    // ideally with a suggestion of "add this interface to the
-                    // export list to fix this error".
-                    //
-                    // That's a lot of info that's not easy to get at without
-                    // more refactoring, so it's left to a future date in the
-                    // hopes that most folks won't actually run into this for
-                    // the time being.
-                    InvalidTransitiveDependency(name),
-                );
-            }
+        candidate: WorldId,
+        policy: WorldId,
+    ) -> Result<(), Vec<WorldKey>> {
+        let compat_key = |id: InterfaceId| {
+            let iface = &self.interfaces[id];
+            let package = &self.packages[iface.package?].name;
+            Some((
+                &package.namespace,
+                &package.name,
+                package
+                    .version
+                    .as_ref()
+                    .map(PackageName::version_compat_track),
+                iface.name.as_ref()?,
+            ))
+        };
+        let policy = &self.worlds[policy];
+        let allowed = policy
+            .imports
+            .keys()
+            .filter_map(|key| match key {
+                WorldKey::Interface(id) => compat_key(*id),
+                WorldKey::Name(_) => None,
+            })
+            .collect::<HashSet<_>>();
+
+        let denied = self.worlds[candidate]
+            .imports
+            .iter()
+            .filter(|(key, item)| match (key, item) {
+                (_, WorldItem::Type(_)) => false,
+                (WorldKey::Interface(id), _) => match compat_key(*id) {
+                    Some(key) => !allowed.contains(&key),
+                    None => true,
+                },
+                (WorldKey::Name(_), _) => match policy.imports.get(*key) {
+                    Some(allowed) => self.merge_world_item(item, allowed).is_err(),
+                    None => true,
+                },
+            })
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        if denied.is_empty() {
+            Ok(())
+        } else {
+            Err(denied)
         }
-        return Ok(());
+    }
 
-        fn add_world_export(
-            resolve: &Resolve,
-            imports: &mut IndexMap<WorldKey, WorldItem>,
-            exports: &mut IndexMap<WorldKey, WorldItem>,
-            export_interfaces: &IndexMap<InterfaceId, (WorldKey, &Stability)>,
-            required_imports: &mut HashSet<InterfaceId>,
-            id: InterfaceId,
-            key: &WorldKey,
-            add_export: bool,
-            stability: &Stability,
-        ) -> bool {
-            if exports.contains_key(key) {
-                if add_export {
-                    return true;
+    /// Finds exports of the same name in different worlds of `pkg` which
+    /// disagree with each other.
+    ///
+    /// Exports are matched up by their name, so `export foo:bar/baz;` in two
+    /// worlds is compared as is `export run: func();`. Each later export is
+    /// compared against the first world exporting that name, in the order
+    /// worlds are defined in `pkg`. Two exports conflict if their stability
+    /// differs or if their signatures, including the contents of inline
+    /// interfaces, don't match.
+    pub fn cross_world_conflicts(&self, pkg: PackageId) -> Vec<Conflict> {
+        let mut first: IndexMap<String, (WorldId, &WorldItem)> = IndexMap::new();
+        let mut conflicts = Vec::new();
+        for world_id in self.packages[pkg].worlds.values() {
+            for (key, item) in self.worlds[*world_id].exports.iter() {
+                let name = self.name_world_key(key);
+                let (first_world, first_item) = match first.get(&name) {
+                    Some(prev) => *prev,
+                    None => {
+                        first.insert(name, (*world_id, item));
+                        continue;
+                    }
+                };
+                let kind = if let Err(e) = self.merge_world_item(item, first_item) {
+                    ConflictKind::Signature(format!("{e:#}"))
+                } else if item.stability(self) != first_item.stability(self) {
+                    ConflictKind::Stability
                 } else {
-                    return false;
-                }
+                    continue;
+                };
+                conflicts.push(Conflict {
+                    name,
+                    worlds: (first_world, *world_id),
+                    kind,
+                });
             }
-            // If this is an import and it's already in the `required_imports`
-            // set then we can skip it as we've already visited this interface.
-            if !add_export && required_imports.contains(&id) {
-                return true;
+        }
+        conflicts
+    }
+
+    /// Returns the imports of `world` which are listed under a plain name,
+    /// such as `import f: func();`, rather than being an imported interface.
+    pub fn world_named_imports(
+        &self,
+        world: WorldId,
+    ) -> impl Iterator<Item = (&str, &WorldItem)> + '_ {
+        self.worlds[world]
+            .imports
+            .iter()
+            .filter_map(|(key, item)| match key {
+                WorldKey::Name(name) => Some((name.as_str(), item)),
+                WorldKey::Interface(_) => None,
+            })
+    }
+
+    /// Returns every function imported or exported by `world`, depending on
+    /// `direction`, along with the key of the world item it was reached
+    /// through.
+    ///
+    /// Functions imported or exported directly by the world are yielded with
+    /// their own key, and functions of imported or exported interfaces are
+    /// yielded with the key of the interface. Resource methods, static
+    /// functions, and constructors are stored alongside the other functions
+    /// of an interface and so are included as well. Types are skipped.
+    pub fn world_functions(
+        &self,
+        world: WorldId,
+        direction: WorldItemKind,
+    ) -> impl Iterator<Item = (&WorldKey, &Function)> + '_ {
+        let world = &self.worlds[world];
+        let items = match direction {
+            WorldItemKind::Import => &world.imports,
+            WorldItemKind::Export => &world.exports,
+        };
+        items.iter().flat_map(move |(key, item)| {
+            let funcs: Box<dyn Iterator<Item = &Function>> = match item {
+                WorldItem::Interface { id, .. } => {
+                    Box::new(self.interfaces[*id].functions.values())
+                }
+                WorldItem::Function(func) => Box::new(std::iter::once(func)),
+                WorldItem::Type(_) => Box::new(std::iter::empty()),
+            };
+            funcs.map(move |func| (key, func))
+        })
+    }
+
+    /// Looks up the function named `method` on `resource` within `interface`.
+    ///
+    /// Functions on resources are stored under their mangled names, such as
+    /// `[method]r.foo` or `[static]r.foo`, and this instead matches on the
+    /// short [`Function::item_name`] of methods, static functions, and
+    /// constructors of `resource`. The constructor is found by looking up
+    /// `constructor`.
+    pub fn resolve_method(
+        &self,
+        interface: InterfaceId,
+        resource: TypeId,
+        method: &str,
+    ) -> Option<&Function> {
+        self.interfaces[interface]
+            .functions
+            .values()
+            .find(|func| func.kind.resource() == Some(resource) && func.item_name() == method)
+    }
+
+    /// Returns the constructor of `resource` within `interface`, if it has
+    /// one.
+    ///
+    /// Resources without a constructor can only be created by the other side
+    /// of the interface, for example by being returned from a function
+    /// implemented by the host.
+    pub fn resource_constructor(
+        &self,
+        interface: InterfaceId,
+        resource: TypeId,
+    ) -> Option<&Function> {
+        self.interfaces[interface]
+            .functions
+            .values()
+            .find(|func| func.kind == FunctionKind::Constructor(resource))
+    }
+
+    /// Returns the imports of `world` which the export `key` refers to.
+    ///
+    /// WIT doesn't describe which imports an export's implementation calls, so
+    /// this is an approximation based only on types: an import is required if
+    /// a type used in the export's signature, transitively, is defined in it.
+    /// That includes interfaces the export `use`s types from, as well as types
+    /// imported into the world directly. Imports that are only called by the
+    /// implementation aren't found, so the result is a lower bound which
+    /// should be combined with other information before pruning imports.
+    ///
+    /// Imports are returned in the order the world imports them. Returns an
+    /// empty set if `key` isn't exported by `world`.
+    pub fn imports_required_for_export(
+        &self,
+        world: WorldId,
+        key: &WorldKey,
+    ) -> IndexSet<WorldKey> {
+        let world = &self.worlds[world];
+        let Some(export) = world.exports.get(key) else {
+            return IndexSet::new();
+        };
+        let mut live = LiveTypes::default();
+        live.add_world_item(self, export);
+        let live = live.iter().collect::<HashSet<_>>();
+        let owners = live
+            .iter()
+            .map(|id| self.types[*id].owner)
+            .collect::<HashSet<_>>();
+        world
+            .imports
+            .iter()
+            .filter(|(_, item)| match item {
+                WorldItem::Interface { id, .. } => owners.contains(&TypeOwner::Interface(*id)),
+                WorldItem::Type(id) => live.contains(id),
+                WorldItem::Function(_) => false,
+            })
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
+    /// Returns a summary of the interfaces imported by `world`, which is what
+    /// a component targeting `world` is able to do.
+    ///
+    /// Interfaces from well-known WASI packages such as `wasi:filesystem` are
+    /// tagged with a [`CapabilityCategory`], while those from other packages
+    /// have no category. Interfaces are returned in import order, and
+    /// functions and types imported directly into the world are skipped.
+    pub fn capability_manifest(&self, world: WorldId) -> Vec<Capability> {
+        self.worlds[world]
+            .imports
+            .iter()
+            .filter_map(|(key, item)| {
+                let WorldItem::Interface { id, .. } = item else {
+                    return None;
+                };
+                let iface = &self.interfaces[*id];
+                let package = iface.package.map(|pkg| &self.packages[pkg].name);
+                let interface = match (key, package, &iface.name) {
+                    (WorldKey::Interface(_), Some(pkg), Some(name)) => {
+                        format!("{}:{}/{name}", pkg.namespace, pkg.name)
+                    }
+                    _ => self.name_world_key(key),
+                };
+                Some(Capability {
+                    interface,
+                    version: package.and_then(|pkg| pkg.version.clone()),
+                    category: package.and_then(CapabilityCategory::of_package),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns whether the interface `id` defines no types and no functions.
+    pub fn interface_is_empty(&self, id: InterfaceId) -> bool {
+        let iface = &self.interfaces[id];
+        iface.types.is_empty() && iface.functions.is_empty()
+    }
+
+    /// Returns whether the world `id` has no imports and no exports.
+    pub fn world_is_empty(&self, id: WorldId) -> bool {
+        let world = &self.worlds[id];
+        world.imports.is_empty() && world.exports.is_empty()
+    }
+
+    /// Removes imports and exports of empty interfaces from all worlds,
+    /// returning the interfaces which were removed.
+    ///
+    /// This is useful to clean up after interfaces lose all of their contents,
+    /// for example when features are disabled. An interface is empty as
+    /// determined by [`Resolve::interface_is_empty`]. Note that the interfaces
+    /// themselves remain in their packages, since nothing in a `Resolve` is
+    /// deallocated, but worlds no longer refer to them.
+    pub fn prune_empty_interfaces(&mut self) -> Vec<InterfaceId> {
+        let empty = self
+            .interfaces
+            .iter()
+            .map(|(id, _)| id)
+            .filter(|id| self.interface_is_empty(*id))
+            .collect::<HashSet<_>>();
+        let mut removed = IndexSet::new();
+        for (_, world) in self.worlds.iter_mut() {
+            for items in [&mut world.imports, &mut world.exports] {
+                items.retain(|_, item| match item {
+                    WorldItem::Interface { id, .. } if empty.contains(id) => {
+                        removed.insert(*id);
+                        false
+                    }
+                    _ => true,
+                });
             }
-            let ok = resolve.interface_direct_deps(id).all(|dep| {
-                let key = WorldKey::Interface(dep);
-                let add_export = add_export && export_interfaces.contains_key(&dep);
-                add_world_export(
-                    resolve,
-                    imports,
-                    exports,
-                    export_interfaces,
-                    required_imports,
-                    dep,
-                    &key,
-                    add_export,
-                    stability,
-                )
+        }
+
+        #[cfg(debug_assertions)]
+        self.assert_valid();
+        removed.into_iter().collect()
+    }
+
+    /// Returns the interfaces exported by `world` along with the stability of
+    /// each export.
+    ///
+    /// Functions and types exported directly from the world are skipped.
+    pub fn world_exported_interfaces(
+        &self,
+        world: WorldId,
+    ) -> Vec<(WorldKey, InterfaceId, &Stability)> {
+        self.worlds[world]
+            .exports
+            .iter()
+            .filter_map(|(key, item)| match item {
+                WorldItem::Interface { id, stability } => Some((key.clone(), *id, stability)),
+                WorldItem::Function(_) | WorldItem::Type(_) => None,
+            })
+            .collect()
+    }
+
+    /// Returns whether anything imported or exported by `world` requires the
+    /// async features of the component model.
+    ///
+    /// This is the case when any function is declared `async`, or when any
+    /// type used by the world contains a `future`, `stream`, or
+    /// `error-context`. Worlds for which this returns `true` can't be used
+    /// with the synchronous [`ManglingAndAbi::Standard32`] ABI.
+    pub fn world_contains_async(&self, world: WorldId) -> bool {
+        let world = &self.worlds[world];
+        let any_async_func = world
+            .imports
+            .values()
+            .chain(world.exports.values())
+            .any(|item| match item {
+                WorldItem::Interface { id, .. } => self.interfaces[*id]
+                    .functions
+                    .values()
+                    .any(Function::is_async),
+                WorldItem::Function(func) => func.is_async(),
+                WorldItem::Type(_) => false,
             });
-            if !ok {
-                return false;
+        if any_async_func {
+            return true;
+        }
+
+        let mut types = AsyncTypes::new(self);
+        for item in world.imports.values().chain(world.exports.values()) {
+            types.visit_world_item(self, item);
+        }
+        types.found.is_some()
+    }
+
+    /// Validates that `world` can be used with the ABI `abi`.
+    ///
+    /// The `future`, `stream`, and `error-context` types can only be used
+    /// with an async ABI, so an error is returned naming the first function
+    /// using such a type, along with the type, if `abi` is synchronous. Any
+    /// world is valid with an async ABI.
+    pub fn validate_async_types(&self, world: WorldId, abi: ManglingAndAbi) -> Result<()> {
+        if abi.is_async() {
+            return Ok(());
+        }
+        let world = &self.worlds[world];
+        for (key, item) in world.imports.iter().chain(world.exports.iter()) {
+            let funcs = match item {
+                WorldItem::Interface { id, .. } => self.interfaces[*id]
+                    .functions
+                    .values()
+                    .map(|func| (Some(key), func))
+                    .collect::<Vec<_>>(),
+                WorldItem::Function(func) => vec![(None, func)],
+                WorldItem::Type(_) => continue,
+            };
+            for (iface, func) in funcs {
+                let mut types = AsyncTypes::new(self);
+                types.visit_func(self, func);
+                let Some(ty) = types.found else {
+                    continue;
+                };
+                let name = match iface {
+                    Some(key) => format!("`{}` in `{}`", func.name, self.name_world_key(key)),
+                    None => format!("`{}`", func.name),
+                };
+                bail!(
+                    "function {name} uses `{}` which requires an async ABI, \
+                     but `{abi:?}` is synchronous",
+                    self.type_to_string(&ty),
+                );
             }
-            let item = WorldItem::Interface {
-                id,
-                stability: stability.clone(),
+        }
+        Ok(())
+    }
+
+    /// Returns the instances a host must provide to instantiate a component
+    /// targeting `world`, keyed by their import name such as
+    /// `wasi:io/streams@0.2.0`.
+    ///
+    /// Each imported interface is described by the names of the functions and
+    /// resources the component expects within it, in the same order as the
+    /// interface. Functions and types imported directly into the world aren't
+    /// imported as instances and are not included.
+    pub fn component_import_shape(&self, world: WorldId) -> IndexMap<String, ItemShape> {
+        let mut ret = IndexMap::new();
+        for (key, item) in self.worlds[world].imports.iter() {
+            let WorldItem::Interface { id, .. } = item else {
+                continue;
             };
-            if add_export {
-                if required_imports.contains(&id) {
-                    return false;
-                }
-                exports.insert(key.clone(), item);
-            } else {
-                required_imports.insert(id);
-                imports.insert(key.clone(), item);
+            ret.insert(self.name_world_key(key), self.interface_shape(*id));
+        }
+        ret
+    }
+
+    /// Returns the instances a component targeting `world` exports, keyed by
+    /// their export name such as `wasi:http/incoming-handler@0.2.0`.
+    ///
+    /// This is the export counterpart of [`Resolve::component_import_shape`]
+    /// and describes what a host needs to look up in an instantiated
+    /// component to call into it. Functions exported directly from the world
+    /// aren't exported as instances and are not included.
+    pub fn component_export_shape(&self, world: WorldId) -> IndexMap<String, ItemShape> {
+        let mut ret = IndexMap::new();
+        for (key, item) in self.worlds[world].exports.iter() {
+            let WorldItem::Interface { id, .. } = item else {
+                continue;
+            };
+            ret.insert(self.name_world_key(key), self.interface_shape(*id));
+        }
+        ret
+    }
+
+    /// Returns the items of worlds `include`d by `world` which were
+    /// overridden by an item defined in `world` itself.
+    ///
+    /// When a world imports or exports an interface, such as
+    /// `import wasi:io/streams;`, which an included world also imports or
+    /// exports, then the including world's item takes precedence and the
+    /// included one is dropped. Named items such as functions can't be
+    /// overridden, and a world defining one with the same name as an
+    /// included item is an error.
+    ///
+    /// Overrides are only recorded for worlds parsed from WIT source.
+    pub fn resolve_include_overrides(&self, world: WorldId) -> Vec<Override> {
+        self.include_overrides
+            .get(&world)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Returns the names of the functions and resources defined in `id`.
+    fn interface_shape(&self, id: InterfaceId) -> ItemShape {
+        let iface = &self.interfaces[id];
+        let functions = iface.functions.keys().cloned().collect();
+        let resources = iface
+            .types
+            .iter()
+            .filter(|(_, ty)| matches!(self.types[**ty].kind, TypeDefKind::Resource))
+            .map(|(name, _)| name.clone())
+            .collect();
+        ItemShape {
+            functions,
+            resources,
+        }
+    }
+
+    /// Checks that every interface exported by `world` is a named interface,
+    /// returning the keys of the exports that aren't.
+    ///
+    /// Exporting an anonymous interface, such as `export foo: interface { ..
+    /// }`, is valid WIT but means the export can't be referred to from other
+    /// packages, so registries may want to reject it for published packages.
+    /// Exported functions and imports aren't checked.
+    pub fn validate_named_exports(&self, world: WorldId) -> Result<(), Vec<WorldKey>> {
+        let anonymous = self.worlds[world]
+            .exports
+            .iter()
+            .filter(|(_, item)| match item {
+                WorldItem::Interface { id, .. } => self.interfaces[*id].name.is_none(),
+                WorldItem::Function(_) | WorldItem::Type(_) => false,
+            })
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        if anonymous.is_empty() {
+            Ok(())
+        } else {
+            Err(anonymous)
+        }
+    }
+
+    /// Checks that the names of the imports and exports of `world` can be
+    /// mangled into core wasm names with `mangling`, returning the names which
+    /// can't.
+    ///
+    /// Names produced by the WIT parser are always valid, but names in a
+    /// [`Resolve`] built or edited programmatically may contain characters
+    /// that the mangling scheme uses as delimiters, which would produce core
+    /// wasm names that are parsed back differently. This checks the names of
+    /// interfaces, functions, and resources: [`Mangling::Legacy`] rejects `#`,
+    /// and the standard manglings reject `|` as well as `_`, which separates
+    /// names from suffixes such as `_post` and `_dtor`.
+    ///
+    /// Each name is reported once, in the order it's found in the world.
+    pub fn validate_mangleable(
+        &self,
+        world: WorldId,
+        mangling: Mangling,
+    ) -> Result<(), Vec<String>> {
+        let reserved: &[char] = match mangling {
+            Mangling::Legacy => &['#'],
+            Mangling::Standard32 | Mangling::Standard64 => &['|', '_'],
+        };
+        let mut invalid = Vec::new();
+        let mut check = |name: &str| {
+            if name.contains(reserved) && !invalid.iter().any(|n| n == name) {
+                invalid.push(name.to_string());
+            }
+        };
+        let world = &self.worlds[world];
+        for (key, item) in world.imports.iter().chain(world.exports.iter()) {
+            match item {
+                WorldItem::Function(func) => check(&func.name),
+                WorldItem::Interface { id, .. } => {
+                    check(&match mangling {
+                        Mangling::Legacy => self.name_world_key(key),
+                        Mangling::Standard32 | Mangling::Standard64 => {
+                            self.name_canonicalized_world_key(key)
+                        }
+                    });
+                    let iface = &self.interfaces[*id];
+                    for func in iface.functions.values() {
+                        check(&func.name);
+                    }
+                    for (name, ty) in iface.types.iter() {
+                        if let TypeDefKind::Resource = self.types[*ty].kind {
+                            check(name);
+                        }
+                    }
+                }
+                WorldItem::Type(id) => {
+                    if let (TypeDefKind::Resource, Some(name)) =
+                        (&self.types[*id].kind, &self.types[*id].name)
+                    {
+                        check(name);
+                    }
+                }
+            }
+        }
+        if invalid.is_empty() {
+            Ok(())
+        } else {
+            Err(invalid)
+        }
+    }
+
+    /// Calls `f` with every type that `ty` refers to, transitively, including
+    /// `ty` itself if it's a [`Type::Id`].
+    ///
+    /// Types are visited depth-first in post-order: the types a type refers
+    /// to, as given by [`TypeDefKind::referenced_types`], are visited in order
+    /// before the type itself. This is the order that
+    /// [`Function::find_futures_and_streams`] uses to number futures and
+    /// streams. Types reached more than once, for example a record used twice
+    /// in a tuple, are visited each time they're reached. Traversal stops at
+    /// resources, which don't refer to other types.
+    pub fn visit_type_deps(&self, ty: Type, f: &mut impl FnMut(TypeId)) {
+        let Type::Id(id) = ty else {
+            return;
+        };
+        for ty in self.types[id].kind.referenced_types() {
+            self.visit_type_deps(ty, f);
+        }
+        f(id);
+    }
+
+    /// Classifies whether the named type `id` can refer back to itself.
+    ///
+    /// Types in WIT can only be recursive through resources: a resource
+    /// refers to every type used by its methods, static functions, and
+    /// constructor, excluding the implicit `self` parameter of methods and
+    /// the return value of constructors. Other types refer to the types they
+    /// contain, looking through anonymous types such as `list<T>` or
+    /// `own<T>`.
+    ///
+    /// The returned classification is based on the strongly connected
+    /// component of `id` within this graph of named types. Anonymous types
+    /// are never part of a cycle themselves and are always
+    /// [`Recursion::Acyclic`].
+    pub fn type_recursion(&self, id: TypeId) -> Recursion {
+        if self.types[id].name.is_none() {
+            return Recursion::Acyclic;
+        }
+
+        // Collect the edges of everything reachable from `id`.
+        let mut edges = HashMap::new();
+        let mut stack = vec![id];
+        while let Some(ty) = stack.pop() {
+            if edges.contains_key(&ty) {
+                continue;
+            }
+            let refs = self.named_type_refs(ty);
+            stack.extend(refs.iter().copied());
+            edges.insert(ty, refs);
+        }
+
+        // Then find everything reachable from `id` which can get back to
+        // `id`, which is the component that `id` is in.
+        let mut component = HashSet::from([id]);
+        loop {
+            let prev = component.len();
+            for (ty, refs) in edges.iter() {
+                if refs.iter().any(|r| component.contains(r)) {
+                    component.insert(*ty);
+                }
+            }
+            if component.len() == prev {
+                break;
+            }
+        }
+
+        if component.len() > 1 {
+            let mut members = component.into_iter().collect::<Vec<_>>();
+            members.sort();
+            Recursion::MutuallyRecursive(members)
+        } else if edges[&id].contains(&id) {
+            Recursion::SelfRecursive
+        } else {
+            Recursion::Acyclic
+        }
+    }
+
+    /// Returns the named types that the named type `id` directly refers to,
+    /// as defined by [`Resolve::type_recursion`].
+    fn named_type_refs(&self, id: TypeId) -> Vec<TypeId> {
+        // Types are recorded here without being recursed into, and anonymous
+        // ones are expanded below.
+        struct Pending(Vec<TypeId>);
+
+        impl TypeIdVisitor for Pending {
+            fn before_visit_type_id(&mut self, id: TypeId) -> bool {
+                self.0.push(id);
+                false
+            }
+        }
+
+        let mut worklist = Pending(Vec::new());
+        let ty = &self.types[id];
+        worklist.visit_type_def(self, ty);
+        if let TypeDefKind::Resource = ty.kind {
+            let funcs: Vec<&Function> = match ty.owner {
+                TypeOwner::Interface(i) => self.interfaces[i].functions.values().collect(),
+                TypeOwner::World(w) => {
+                    let world = &self.worlds[w];
+                    world
+                        .imports
+                        .values()
+                        .chain(world.exports.values())
+                        .filter_map(|item| match item {
+                            WorldItem::Function(f) => Some(f),
+                            _ => None,
+                        })
+                        .collect()
+                }
+                TypeOwner::None => Vec::new(),
+            };
+            for func in funcs {
+                if func.kind.resource() != Some(id) {
+                    continue;
+                }
+                let skip_self = matches!(
+                    func.kind,
+                    FunctionKind::Method(_) | FunctionKind::AsyncMethod(_)
+                );
+                for (_, param) in func.params.iter().skip(usize::from(skip_self)) {
+                    worklist.visit_type(self, param);
+                }
+                if let (Some(result), false) = (
+                    &func.result,
+                    matches!(func.kind, FunctionKind::Constructor(_)),
+                ) {
+                    worklist.visit_type(self, result);
+                }
+            }
+        }
+
+        let mut refs = Vec::new();
+        let mut seen = HashSet::new();
+        while let Some(ty) = worklist.0.pop() {
+            if !seen.insert(ty) {
+                continue;
+            }
+            if self.types[ty].name.is_some() {
+                refs.push(ty);
+            } else {
+                worklist.visit_type_def(self, &self.types[ty]);
+            }
+        }
+        refs
+    }
+
+    /// Returns the interface that `id` uses a type from, if it uses a type from
+    /// a different interface than `id` is defined within.
+    ///
+    /// If `id` is not a use-of-a-type or it's using a type in the same
+    /// interface then `None` is returned.
+    pub fn type_interface_dep(&self, id: TypeId) -> Option<InterfaceId> {
+        let ty = &self.types[id];
+        let dep = match ty.kind {
+            TypeDefKind::Type(Type::Id(id)) => id,
+            _ => return None,
+        };
+        let other = &self.types[dep];
+        if ty.owner == other.owner {
+            None
+        } else {
+            match other.owner {
+                TypeOwner::Interface(id) => Some(id),
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    /// Returns an iterator of all interfaces that the interface `id` depends
+    /// on.
+    ///
+    /// Interfaces may depend on others for type information to resolve type
+    /// imports.
+    ///
+    /// Note that the returned iterator may yield the same interface as a
+    /// dependency multiple times. Additionally only direct dependencies of `id`
+    /// are yielded, not transitive dependencies.
+    pub fn interface_direct_deps(&self, id: InterfaceId) -> impl Iterator<Item = InterfaceId> + '_ {
+        self.interfaces[id]
+            .types
+            .iter()
+            .filter_map(move |(_name, ty)| self.type_interface_dep(*ty))
+    }
+
+    /// Returns an iterator of all packages that the package `id` depends
+    /// on.
+    ///
+    /// Packages may depend on others for type information to resolve type
+    /// imports or interfaces to resolve worlds.
+    ///
+    /// Note that the returned iterator may yield the same package as a
+    /// dependency multiple times. Additionally only direct dependencies of `id`
+    /// are yielded, not transitive dependencies.
+    pub fn package_direct_deps(&self, id: PackageId) -> impl Iterator<Item = PackageId> + '_ {
+        let pkg = &self.packages[id];
+
+        pkg.interfaces
+            .iter()
+            .flat_map(move |(_name, id)| self.interface_direct_deps(*id))
+            .chain(pkg.worlds.iter().flat_map(move |(_name, id)| {
+                let world = &self.worlds[*id];
+                world
+                    .imports
+                    .iter()
+                    .chain(world.exports.iter())
+                    .filter_map(move |(_name, item)| match item {
+                        WorldItem::Interface { id, .. } => Some(*id),
+                        WorldItem::Function(_) => None,
+                        WorldItem::Type(t) => self.type_interface_dep(*t),
+                    })
+            }))
+            .filter_map(move |iface_id| {
+                let pkg = self.interfaces[iface_id].package?;
+                if pkg == id { None } else { Some(pkg) }
+            })
+    }
+
+    /// Returns a topological ordering of packages contained in this `Resolve`.
+    ///
+    /// This returns a list of `PackageId` such that when visited in order it's
+    /// guaranteed that all dependencies will have been defined by prior items
+    /// in the list.
+    pub fn topological_packages(&self) -> Vec<PackageId> {
+        let mut pushed = vec![false; self.packages.len()];
+        let mut order = Vec::new();
+        for (id, _) in self.packages.iter() {
+            self.build_topological_package_ordering(id, &mut pushed, &mut order);
+        }
+        order
+    }
+
+    /// Returns the names of the functions in the interface `id` as they'd be
+    /// referred to by a user, in the order of [`Interface::functions`].
+    ///
+    /// Freestanding functions are listed by their [`Function::item_name`],
+    /// while functions of a resource are prefixed with the resource's name,
+    /// such as `file.read` for a method or static function and
+    /// `file.constructor` for a constructor.
+    pub fn interface_function_names(&self, id: InterfaceId) -> Vec<String> {
+        self.interfaces[id]
+            .functions
+            .values()
+            .map(|func| match func.kind.resource() {
+                Some(resource) => {
+                    let resource = self.types[resource]
+                        .name
+                        .as_deref()
+                        .unwrap_or("<anonymous>");
+                    format!("{resource}.{}", func.item_name())
+                }
+                None => func.item_name().to_string(),
+            })
+            .collect()
+    }
+
+    /// Returns the functions of the interface `id` in the order they were
+    /// written in the WIT source.
+    ///
+    /// The order of [`Interface::functions`] is not guaranteed to match the
+    /// source after the interface has been modified, for example. Source
+    /// positions are only available for interfaces parsed from WIT text within
+    /// this `Resolve`, and otherwise the order of [`Interface::functions`] is
+    /// used. Functions without a known source position are listed last.
+    pub fn interface_functions_in_source_order(&self, id: InterfaceId) -> Vec<&Function> {
+        let mut funcs = self.interfaces[id].functions.values().collect::<Vec<_>>();
+        if let Some(offsets) = self.interface_function_offsets.get(&id) {
+            funcs.sort_by_key(|func| offsets.get(&func.name).copied().unwrap_or(u32::MAX));
+        }
+        funcs
+    }
+
+    /// Creates a new [`Resolve`] which contains only the package `pkg` and the
+    /// packages it transitively depends on.
+    ///
+    /// All items in the returned `Resolve` are allocated afresh, so ids from
+    /// `self` are not valid in the returned `Resolve`. Items are looked up in
+    /// the result by name instead, for example through
+    /// [`Resolve::package_names`]. This is useful to carve out a single
+    /// package, for example to encode it, from a `Resolve` containing many
+    /// unrelated packages.
+    pub fn extract_package(&self, pkg: PackageId) -> Resolve {
+        let mut kept = vec![false; self.packages.len()];
+        let mut stack = vec![pkg];
+        while let Some(id) = stack.pop() {
+            if kept[id.index()] {
+                continue;
+            }
+            kept[id.index()] = true;
+            stack.extend(self.package_direct_deps(id));
+        }
+        let keep_package = |id: Option<PackageId>| id.is_some_and(|id| kept[id.index()]);
+
+        let mut live = LiveTypes::default();
+        for (id, iface) in self.interfaces.iter() {
+            if keep_package(iface.package) {
+                live.add_interface(self, id);
+            }
+        }
+        for (id, world) in self.worlds.iter() {
+            if keep_package(world.package) {
+                live.add_world(self, id);
+            }
+        }
+        let live = live.iter().collect::<HashSet<_>>();
+
+        let mut ret = Resolve {
+            features: self.features.clone(),
+            all_features: self.all_features,
+            resolve_unversioned_deps: self.resolve_unversioned_deps,
+            ..Resolve::default()
+        };
+        let mut remap = Remap::default();
+        let mut package_map = HashMap::new();
+        for (id, pkg) in self.packages.iter() {
+            if !kept[id.index()] {
+                continue;
+            }
+            let new_id = ret.packages.alloc(Package {
+                name: pkg.name.clone(),
+                docs: pkg.docs.clone(),
+                leading_comments: pkg.leading_comments.clone(),
+                interfaces: Default::default(),
+                worlds: Default::default(),
+            });
+            package_map.insert(id, new_id);
+        }
+        let map_package = |id: &mut Option<PackageId>| {
+            if let Some(id) = id {
+                *id = package_map[id];
+            }
+        };
+
+        // Note that all the `remap` operations below are infallible since the
+        // dependency closure calculated above contains everything that kept
+        // items refer to.
+        for (id, ty) in self.types.iter() {
+            if !live.contains(&id) {
+                remap.types.push(None);
+                continue;
+            }
+            let mut ty = ty.clone();
+            remap.update_typedef(&mut ret, &mut ty, None).unwrap();
+            remap.types.push(Some(ret.types.alloc(ty)));
+        }
+
+        for (id, iface) in self.interfaces.iter() {
+            if !keep_package(iface.package) {
+                remap.interfaces.push(None);
+                continue;
+            }
+            let mut iface = iface.clone();
+            map_package(&mut iface.package);
+            for ty in iface.types.values_mut() {
+                remap.update_type_id(ty, None).unwrap();
+            }
+            for func in iface.functions.values_mut() {
+                remap.update_function(&mut ret, func, None).unwrap();
+            }
+            let new_id = ret.interfaces.alloc(iface);
+            if let Some(offsets) = self.interface_function_offsets.get(&id) {
+                ret.interface_function_offsets
+                    .insert(new_id, offsets.clone());
+            }
+            remap.interfaces.push(Some(new_id));
+        }
+
+        for (_, world) in self.worlds.iter() {
+            if !keep_package(world.package) {
+                remap.worlds.push(None);
+                continue;
+            }
+            let mut world = world.clone();
+            map_package(&mut world.package);
+            for map in [&mut world.imports, &mut world.exports] {
+                for (mut name, mut item) in mem::take(map) {
+                    remap.update_world_key(&mut name, None).unwrap();
+                    match &mut item {
+                        WorldItem::Function(f) => remap.update_function(&mut ret, f, None).unwrap(),
+                        WorldItem::Interface { id, .. } => {
+                            *id = remap.map_interface(*id, None).unwrap()
+                        }
+                        WorldItem::Type(i) => *i = remap.map_type(*i, None).unwrap(),
+                    }
+                    map.insert(name, item);
+                }
+            }
+            remap.worlds.push(Some(ret.worlds.alloc(world)));
+        }
+
+        for (id, pkg) in self.packages.iter() {
+            let Some(new_id) = package_map.get(&id) else {
+                continue;
+            };
+            let new_pkg = &mut ret.packages[*new_id];
+            for (name, id) in pkg.interfaces.iter() {
+                let id = remap.map_interface(*id, None).unwrap();
+                new_pkg.interfaces.insert(name.clone(), id);
+            }
+            for (name, id) in pkg.worlds.iter() {
+                let id = remap.map_world(*id, None).unwrap();
+                new_pkg.worlds.insert(name.clone(), id);
+            }
+        }
+        for (name, id) in self.package_names.iter() {
+            if let Some(id) = package_map.get(id) {
+                ret.package_names.insert(name.clone(), *id);
+            }
+        }
+
+        // Fixup type owners now that all interfaces and worlds are allocated.
+        for (_, ty) in ret.types.iter_mut() {
+            match &mut ty.owner {
+                TypeOwner::Interface(id) => *id = remap.map_interface(*id, None).unwrap(),
+                TypeOwner::World(id) => *id = remap.map_world(*id, None).unwrap(),
+                TypeOwner::None => {}
+            }
+        }
+        for (item, span) in self.item_spans.iter() {
+            if let Some(item) = remap.map_item_ref(item) {
+                ret.item_spans.insert(item, span.clone());
+            }
+        }
+        for (world, overrides) in self.include_overrides.iter() {
+            if let Some((world, overrides)) = remap.map_overrides(*world, overrides.clone()) {
+                ret.include_overrides.insert(world, overrides);
+            }
+        }
+
+        #[cfg(debug_assertions)]
+        ret.assert_valid();
+        ret
+    }
+
+    fn build_topological_package_ordering(
+        &self,
+        id: PackageId,
+        pushed: &mut Vec<bool>,
+        order: &mut Vec<PackageId>,
+    ) {
+        if pushed[id.index()] {
+            return;
+        }
+        for dep in self.package_direct_deps(id) {
+            self.build_topological_package_ordering(dep, pushed, order);
+        }
+        order.push(id);
+        pushed[id.index()] = true;
+    }
+
+    #[doc(hidden)]
+    pub fn assert_valid(&self) {
+        let mut package_interfaces = Vec::new();
+        let mut package_worlds = Vec::new();
+        for (id, pkg) in self.packages.iter() {
+            let mut interfaces = HashSet::new();
+            for (name, iface) in pkg.interfaces.iter() {
+                assert!(interfaces.insert(*iface));
+                let iface = &self.interfaces[*iface];
+                assert_eq!(name, iface.name.as_ref().unwrap());
+                assert_eq!(iface.package.unwrap(), id);
+            }
+            package_interfaces.push(pkg.interfaces.values().copied().collect::<HashSet<_>>());
+            let mut worlds = HashSet::new();
+            for (name, world) in pkg.worlds.iter() {
+                assert!(worlds.insert(*world));
+                assert_eq!(
+                    pkg.worlds.get_key_value(name),
+                    Some((name, world)),
+                    "`MutableKeys` impl may have been used to change a key's hash or equality"
+                );
+                let world = &self.worlds[*world];
+                assert_eq!(*name, world.name);
+                assert_eq!(world.package.unwrap(), id);
+            }
+            package_worlds.push(pkg.worlds.values().copied().collect::<HashSet<_>>());
+        }
+
+        let mut interface_types = Vec::new();
+        for (id, iface) in self.interfaces.iter() {
+            assert!(self.packages.get(iface.package.unwrap()).is_some());
+            if iface.name.is_some() {
+                assert!(package_interfaces[iface.package.unwrap().index()].contains(&id));
+            }
+
+            for (name, ty) in iface.types.iter() {
+                let ty = &self.types[*ty];
+                assert_eq!(ty.name.as_ref(), Some(name));
+                assert_eq!(ty.owner, TypeOwner::Interface(id));
+            }
+            interface_types.push(iface.types.values().copied().collect::<HashSet<_>>());
+            for (name, f) in iface.functions.iter() {
+                assert_eq!(*name, f.name);
+            }
+        }
+
+        let mut world_types = Vec::new();
+        for (id, world) in self.worlds.iter() {
+            log::debug!("validating world {}", &world.name);
+            if let Some(package) = world.package {
+                assert!(self.packages.get(package).is_some());
+                assert!(package_worlds[package.index()].contains(&id));
+            }
+            assert!(world.includes.is_empty());
+
+            let mut types = HashSet::new();
+            for (name, item) in world.imports.iter().chain(world.exports.iter()) {
+                log::debug!("validating world item: {}", self.name_world_key(name));
+                match item {
+                    WorldItem::Interface { id, .. } => {
+                        // anonymous interfaces must belong to the same package
+                        // as the world's package.
+                        if matches!(name, WorldKey::Name(_)) {
+                            assert_eq!(self.interfaces[*id].package, world.package);
+                        }
+                    }
+                    WorldItem::Function(f) => {
+                        assert!(!matches!(name, WorldKey::Interface(_)));
+                        assert_eq!(f.name, name.clone().unwrap_name());
+                    }
+                    WorldItem::Type(ty) => {
+                        assert!(!matches!(name, WorldKey::Interface(_)));
+                        assert!(types.insert(*ty));
+                        let ty = &self.types[*ty];
+                        assert_eq!(ty.name, Some(name.clone().unwrap_name()));
+                        assert_eq!(ty.owner, TypeOwner::World(id));
+                    }
+                }
+            }
+            self.assert_world_elaborated(world);
+            world_types.push(types);
+        }
+
+        for (ty_id, ty) in self.types.iter() {
+            match ty.owner {
+                TypeOwner::Interface(id) => {
+                    assert!(self.interfaces.get(id).is_some());
+                    assert!(interface_types[id.index()].contains(&ty_id));
+                }
+                TypeOwner::World(id) => {
+                    assert!(self.worlds.get(id).is_some());
+                    assert!(world_types[id.index()].contains(&ty_id));
+                }
+                TypeOwner::None => {}
+            }
+        }
+
+        self.assert_topologically_sorted();
+    }
+
+    fn assert_topologically_sorted(&self) {
+        let mut positions = IndexMap::new();
+        for id in self.topological_packages() {
+            let pkg = &self.packages[id];
+            log::debug!("pkg {}", pkg.name);
+            let prev = positions.insert(Some(id), IndexSet::new());
+            assert!(prev.is_none());
+        }
+        positions.insert(None, IndexSet::new());
+
+        for (id, iface) in self.interfaces.iter() {
+            log::debug!("iface {:?}", iface.name);
+            let ok = positions.get_mut(&iface.package).unwrap().insert(id);
+            assert!(ok);
+        }
+
+        for (_, world) in self.worlds.iter() {
+            log::debug!("world {:?}", world.name);
+
+            let my_package = world.package;
+            let my_package_pos = positions.get_index_of(&my_package).unwrap();
+
+            for (_, item) in world.imports.iter().chain(&world.exports) {
+                let id = match item {
+                    WorldItem::Interface { id, .. } => *id,
+                    _ => continue,
+                };
+                let other_package = self.interfaces[id].package;
+                let other_package_pos = positions.get_index_of(&other_package).unwrap();
+
+                assert!(other_package_pos <= my_package_pos);
+            }
+        }
+
+        for (_id, ty) in self.types.iter() {
+            log::debug!("type {:?} {:?}", ty.name, ty.owner);
+            let other_id = match ty.kind {
+                TypeDefKind::Type(Type::Id(ty)) => ty,
+                _ => continue,
+            };
+            let other = &self.types[other_id];
+            if ty.kind == other.kind {
+                continue;
+            }
+            let my_interface = match ty.owner {
+                TypeOwner::Interface(id) => id,
+                _ => continue,
+            };
+            let other_interface = match other.owner {
+                TypeOwner::Interface(id) => id,
+                _ => continue,
+            };
+
+            let my_package = self.interfaces[my_interface].package;
+            let other_package = self.interfaces[other_interface].package;
+            let my_package_pos = positions.get_index_of(&my_package).unwrap();
+            let other_package_pos = positions.get_index_of(&other_package).unwrap();
+
+            if my_package_pos == other_package_pos {
+                let interfaces = &positions[&my_package];
+                let my_interface_pos = interfaces.get_index_of(&my_interface).unwrap();
+                let other_interface_pos = interfaces.get_index_of(&other_interface).unwrap();
+                assert!(other_interface_pos <= my_interface_pos);
+            } else {
+                assert!(other_package_pos < my_package_pos);
+            }
+        }
+    }
+
+    fn assert_world_elaborated(&self, world: &World) {
+        for (key, item) in world.imports.iter() {
+            log::debug!(
+                "asserting elaborated world import {}",
+                self.name_world_key(key)
+            );
+            match item {
+                WorldItem::Type(t) => self.assert_world_imports_type_deps(world, key, *t),
+
+                // All types referred to must be imported.
+                WorldItem::Function(f) => self.assert_world_function_imports_types(world, key, f),
+
+                // All direct dependencies of this interface must be imported.
+                WorldItem::Interface { id, .. } => {
+                    for dep in self.interface_direct_deps(*id) {
+                        assert!(
+                            world.imports.contains_key(&WorldKey::Interface(dep)),
+                            "world import of {} is missing transitive dep of {}",
+                            self.name_world_key(key),
+                            self.id_of(dep).unwrap(),
+                        );
+                    }
+                }
+            }
+        }
+        for (key, item) in world.exports.iter() {
+            log::debug!(
+                "asserting elaborated world export {}",
+                self.name_world_key(key)
+            );
+            match item {
+                // Types referred to by this function must be imported.
+                WorldItem::Function(f) => self.assert_world_function_imports_types(world, key, f),
+
+                // Dependencies of exported interfaces must also be exported, or
+                // if imported then that entire chain of imports must be
+                // imported and not exported.
+                WorldItem::Interface { id, .. } => {
+                    for dep in self.interface_direct_deps(*id) {
+                        let dep_key = WorldKey::Interface(dep);
+                        if world.exports.contains_key(&dep_key) {
+                            continue;
+                        }
+                        self.foreach_interface_dep(dep, &mut |dep| {
+                            let dep_key = WorldKey::Interface(dep);
+                            assert!(
+                                world.imports.contains_key(&dep_key),
+                                "world should import {} (required by {})",
+                                self.name_world_key(&dep_key),
+                                self.name_world_key(key),
+                            );
+                            assert!(
+                                !world.exports.contains_key(&dep_key),
+                                "world should not export {} (required by {})",
+                                self.name_world_key(&dep_key),
+                                self.name_world_key(key),
+                            );
+                        });
+                    }
+                }
+
+                // exported types not allowed at this time
+                WorldItem::Type(_) => unreachable!(),
+            }
+        }
+    }
+
+    fn assert_world_imports_type_deps(&self, world: &World, key: &WorldKey, ty: TypeId) {
+        // If this is a `use` statement then the referred-to interface must be
+        // imported into this world.
+        let ty = &self.types[ty];
+        if let TypeDefKind::Type(Type::Id(other)) = ty.kind {
+            if let TypeOwner::Interface(id) = self.types[other].owner {
+                let key = WorldKey::Interface(id);
+                assert!(world.imports.contains_key(&key));
+                return;
+            }
+        }
+
+        // ... otherwise any named type that this type refers to, one level
+        // deep, must be imported into this world under that name.
+
+        let mut visitor = MyVisit(self, Vec::new());
+        visitor.visit_type_def(self, ty);
+        for ty in visitor.1 {
+            let ty = &self.types[ty];
+            let Some(name) = ty.name.clone() else {
+                continue;
+            };
+            let dep_key = WorldKey::Name(name);
+            assert!(
+                world.imports.contains_key(&dep_key),
+                "world import `{}` should also force an import of `{}`",
+                self.name_world_key(key),
+                self.name_world_key(&dep_key),
+            );
+        }
+
+        struct MyVisit<'a>(&'a Resolve, Vec<TypeId>);
+
+        impl TypeIdVisitor for MyVisit<'_> {
+            fn before_visit_type_id(&mut self, id: TypeId) -> bool {
+                self.1.push(id);
+                // recurse into unnamed types to look at all named types
+                self.0.types[id].name.is_none()
+            }
+        }
+    }
+
+    /// This asserts that all types referred to by `func` are imported into
+    /// `world` under `WorldKey::Name`. Note that this is only applicable to
+    /// named type
+    fn assert_world_function_imports_types(&self, world: &World, key: &WorldKey, func: &Function) {
+        for ty in func
+            .parameter_and_result_types()
+            .chain(func.kind.resource().map(Type::Id))
+        {
+            let Type::Id(id) = ty else {
+                continue;
+            };
+            self.assert_world_imports_type_deps(world, key, id);
+        }
+    }
+
+    /// Returns whether the `stability` annotation contained within `pkg_id`
+    /// should be included or not.
+    ///
+    /// The `span` provided here is an optional span pointing to the item that
+    /// is annotated with `stability`.
+    ///
+    /// Returns `Ok(true)` if the item is included, or `Ok(false)` if the item
+    /// is not.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `pkg_id` isn't annotated with sufficient version
+    /// information to have a `stability` annotation. For example if `pkg_id`
+    /// has no version listed then an error will be returned if `stability`
+    /// mentions a version.
+    fn include_stability(
+        &self,
+        stability: &Stability,
+        pkg_id: &PackageId,
+        span: Option<Span>,
+    ) -> Result<bool> {
+        let err = |msg: String| match span {
+            Some(span) => Error::new(span, msg).into(),
+            None => anyhow::Error::msg(msg),
+        };
+        Ok(match stability {
+            Stability::Unknown => true,
+            // NOTE: deprecations are intentionally omitted -- an existing
+            // `@since` takes precedence over `@deprecated`
+            Stability::Stable { since, .. } => {
+                let Some(p) = self.packages.get(*pkg_id) else {
+                    // We can't check much without a package (possibly dealing
+                    // with an item in an `UnresolvedPackage`), @since version &
+                    // deprecations can't be checked because there's no package
+                    // version to compare to.
+                    //
+                    // Feature requirements on stabilized features are ignored
+                    // in resolved packages, so we do the same here.
+                    return Ok(true);
+                };
+
+                // Use of feature gating with version specifiers inside a
+                // package that is not versioned is not allowed
+                let package_version = p.name.version.as_ref().ok_or_else(|| {
+                    err(format!(
+                        "package [{}] contains a feature gate with a version \
+                         specifier, so it must have a version",
+                        p.name
+                    ))
+                })?;
+
+                // If the version on the feature gate is:
+                // - released, then we can include it
+                // - unreleased, then we must check the feature (if present)
+                if since > package_version {
+                    return Err(err(format!(
+                        "feature gate cannot reference unreleased version \
+                        {since} of package [{}] (current version {package_version})",
+                        p.name
+                    )));
+                }
+
+                true
+            }
+            Stability::Unstable { feature, .. } => self.feature_enabled(feature),
+        })
+    }
+
+    /// Convenience wrapper around `include_stability` specialized for types
+    /// with a more targeted error message.
+    fn include_type(&self, ty: &TypeDef, pkgid: PackageId, span: Span) -> Result<bool> {
+        self.include_stability(&ty.stability, &pkgid, Some(span))
+            .with_context(|| {
+                format!(
+                    "failed to process feature gate for type [{}] in package [{}]",
+                    ty.name.as_ref().map(String::as_str).unwrap_or("<unknown>"),
+                    self.packages[pkgid].name,
+                )
+            })
+    }
+
+    /// Performs the "elaboration process" necessary for the `world_id`
+    /// specified to ensure that all of its transitive imports are listed.
+    ///
+    /// This function will take the unordered lists of the specified world's
+    /// imports and exports and "elaborate" them to ensure that they're
+    /// topographically sorted where all transitively required interfaces by
+    /// imports, or exports, are listed. This will additionally validate that
+    /// the exports are all valid and present, specifically with the restriction
+    /// noted on `elaborate_world_exports`.
+    ///
+    /// The world is mutated in-place in this `Resolve`.
+    fn elaborate_world(&mut self, world_id: WorldId) -> Result<()> {
+        // First process all imports. This is easier than exports since the only
+        // requirement here is that all interfaces need to be added with a
+        // topological order between them.
+        let mut new_imports = IndexMap::new();
+        let world = &self.worlds[world_id];
+
+        // Sort the imports by "class" to ensure that this matches the order
+        // that items are printed and that items are in topological order.
+        //
+        // When printing worlds in WIT:
+        //
+        // * interfaces come first
+        // * types are next
+        //   * type imports are first
+        //   * type definitions are next
+        //   * resource definitions have methods printed inline
+        // * freestanding functions are last
+        //
+        // This reflects the topological order between items where types
+        // can refer to imports and functions can refer to these types. Ordering
+        // within a single class (e.g. imports depending on each other, types
+        // referring to each other) is already preserved by other passes in this
+        // file and general AST resolution. That means that a stable sort here
+        // can be used to ensure that each class is in the right location
+        // relative to the others.
+        //
+        // Overall this ensures that round-trips of WIT through wasm should
+        // always produce the same result.
+        let sort_key = |resolve: &Resolve, item: &WorldItem| match item {
+            WorldItem::Interface { .. } => 0,
+            WorldItem::Type(ty) => {
+                let ty = &resolve.types[*ty];
+                match ty.kind {
+                    TypeDefKind::Type(Type::Id(t)) if resolve.types[t].owner != ty.owner => 1,
+                    _ => 2,
+                }
+            }
+            WorldItem::Function(f) => {
+                if f.kind.resource().is_none() {
+                    3
+                } else {
+                    4
+                }
+            }
+        };
+
+        // Sort world items when we start to elaborate the world to start with a
+        // topological view of items.
+        let mut world_imports = world.imports.iter().collect::<Vec<_>>();
+        world_imports.sort_by_key(|(_name, import)| sort_key(self, import));
+        for (name, item) in world_imports {
+            match item {
+                // Interfaces get their dependencies added first followed by the
+                // interface itself.
+                WorldItem::Interface { id, stability } => {
+                    self.elaborate_world_import(&mut new_imports, name.clone(), *id, &stability);
+                }
+
+                // Functions are added as-is since their dependence on types in
+                // the world should already be satisfied.
+                WorldItem::Function(_) => {
+                    let prev = new_imports.insert(name.clone(), item.clone());
+                    assert!(prev.is_none());
+                }
+
+                // Types may depend on an interface, in which case a (possibly)
+                // recursive addition of that interface happens here. Afterwards
+                // the type itself can be added safely.
+                WorldItem::Type(id) => {
+                    if let Some(dep) = self.type_interface_dep(*id) {
+                        self.elaborate_world_import(
+                            &mut new_imports,
+                            WorldKey::Interface(dep),
+                            dep,
+                            &self.types[*id].stability,
+                        );
+                    }
+                    let prev = new_imports.insert(name.clone(), item.clone());
+                    assert!(prev.is_none());
+                }
+            }
+        }
+
+        // Exports are trickier than imports, notably to uphold the invariant
+        // required by `elaborate_world_exports`. To do this the exports are
+        // partitioned into interfaces/functions. All functions are added to
+        // the new exports list during this loop but interfaces are all deferred
+        // to be handled in the `elaborate_world_exports` function.
+        let mut new_exports = IndexMap::new();
+        let mut export_interfaces = IndexMap::new();
+        for (name, item) in world.exports.iter() {
+            match item {
+                WorldItem::Interface { id, stability } => {
+                    let prev = export_interfaces.insert(*id, (name.clone(), stability));
+                    assert!(prev.is_none());
+                }
+                WorldItem::Function(_) => {
+                    let prev = new_exports.insert(name.clone(), item.clone());
+                    assert!(prev.is_none());
+                }
+                WorldItem::Type(_) => unreachable!(),
+            }
+        }
+
+        self.elaborate_world_exports(&export_interfaces, &mut new_imports, &mut new_exports)?;
+
+        // In addition to sorting at the start of elaboration also sort here at
+        // the end of elaboration to handle types being interspersed with
+        // interfaces as they're found.
+        new_imports.sort_by_cached_key(|_name, import| sort_key(self, import));
+
+        // And with all that done the world is updated in-place with
+        // imports/exports.
+        log::trace!("imports = {new_imports:?}");
+        log::trace!("exports = {new_exports:?}");
+        let world = &mut self.worlds[world_id];
+        world.imports = new_imports;
+        world.exports = new_exports;
+
+        Ok(())
+    }
+
+    fn elaborate_world_import(
+        &self,
+        imports: &mut IndexMap<WorldKey, WorldItem>,
+        key: WorldKey,
+        id: InterfaceId,
+        stability: &Stability,
+    ) {
+        if imports.contains_key(&key) {
+            return;
+        }
+        for dep in self.interface_direct_deps(id) {
+            self.elaborate_world_import(imports, WorldKey::Interface(dep), dep, stability);
+        }
+        let prev = imports.insert(
+            key,
+            WorldItem::Interface {
+                id,
+                stability: stability.clone(),
+            },
+        );
+        assert!(prev.is_none());
+    }
+
+    /// This function adds all of the interfaces in `export_interfaces` to the
+    /// list of exports of the `world` specified.
+    ///
+    /// This method is more involved than adding imports because it is fallible.
+    /// Chiefly what can happen is that the dependencies of all exports must be
+    /// satisfied by other exports or imports, but not both. For example given a
+    /// situation such as:
+    ///
+    /// ```wit
+    /// interface a {
+    ///     type t = u32
+    /// }
+    /// interface b {
+    ///     use a.{t}
+    /// }
+    /// interface c {
+    ///     use a.{t}
+    ///     use b.{t as t2}
+    /// }
+    /// ```
+    ///
+    /// where `c` depends on `b` and `a` where `b` depends on `a`, then the
+    /// purpose of this method is to reject this world:
+    ///
+    /// ```wit
+    /// world foo {
+    ///     export a
+    ///     export c
+    /// }
+    /// ```
+    ///
+    /// The reasoning here is unfortunately subtle and is additionally the
+    /// subject of WebAssembly/component-model#208. Effectively the `c`
+    /// interface depends on `b`, but it's not listed explicitly as an import,
+    /// so it's then implicitly added as an import. This then transitively
+    /// depends on `a` so it's also added as an import. At this point though `c`
+    /// also depends on `a`, and it's also exported, so naively it should depend
+    /// on the export and not implicitly add an import. This means though that
+    /// `c` has access to two copies of `a`, one imported and one exported. This
+    /// is not valid, especially in the face of resource types.
+    ///
+    /// Overall this method is tasked with rejecting the above world by walking
+    /// over all the exports and adding their dependencies. Each dependency is
+    /// recorded with whether it's required to be imported, and then if an
+    /// export is added for something that's required to be an error then the
+    /// operation fails.
+    fn elaborate_world_exports(
+        &self,
+        export_interfaces: &IndexMap<InterfaceId, (WorldKey, &Stability)>,
+        imports: &mut IndexMap<WorldKey, WorldItem>,
+        exports: &mut IndexMap<WorldKey, WorldItem>,
+    ) -> Result<()> {
+        let mut required_imports = HashSet::new();
+        for (id, (key, stability)) in export_interfaces.iter() {
+            let name = self.name_world_key(&key);
+            let ok = add_world_export(
+                self,
+                imports,
+                exports,
+                export_interfaces,
+                &mut required_imports,
+                *id,
+                key,
+                true,
+                stability,
+            );
+            if !ok {
+                bail!(
+                    // FIXME: this is not a great error message and basically no
+                    // one will know what to do when it gets printed. Improving
+                    // this error message, however, is a chunk of work that may
+                    // not be best spent doing this at this time, so I'm writing
+                    // this comment instead.
+                    //
+                    // More-or-less what should happen here is that a "path"
+                    // from this interface to the conflicting interface should
+                    // be printed. It should be explained why an import is being
+                    // injected, why that's conflicting with an export, and
+                    // ideally with a suggestion of "add this interface to the
+                    // export list to fix this error".
+                    //
+                    // That's a lot of info that's not easy to get at without
+                    // more refactoring, so it's left to a future date in the
+                    // hopes that most folks won't actually run into this for
+                    // the time being.
+                    InvalidTransitiveDependency(name),
+                );
+            }
+        }
+        return Ok(());
+
+        fn add_world_export(
+            resolve: &Resolve,
+            imports: &mut IndexMap<WorldKey, WorldItem>,
+            exports: &mut IndexMap<WorldKey, WorldItem>,
+            export_interfaces: &IndexMap<InterfaceId, (WorldKey, &Stability)>,
+            required_imports: &mut HashSet<InterfaceId>,
+            id: InterfaceId,
+            key: &WorldKey,
+            add_export: bool,
+            stability: &Stability,
+        ) -> bool {
+            if exports.contains_key(key) {
+                if add_export {
+                    return true;
+                } else {
+                    return false;
+                }
+            }
+            // If this is an import and it's already in the `required_imports`
+            // set then we can skip it as we've already visited this interface.
+            if !add_export && required_imports.contains(&id) {
+                return true;
+            }
+            let ok = resolve.interface_direct_deps(id).all(|dep| {
+                let key = WorldKey::Interface(dep);
+                let add_export = add_export && export_interfaces.contains_key(&dep);
+                add_world_export(
+                    resolve,
+                    imports,
+                    exports,
+                    export_interfaces,
+                    required_imports,
+                    dep,
+                    &key,
+                    add_export,
+                    stability,
+                )
+            });
+            if !ok {
+                return false;
+            }
+            let item = WorldItem::Interface {
+                id,
+                stability: stability.clone(),
+            };
+            if add_export {
+                if required_imports.contains(&id) {
+                    return false;
+                }
+                exports.insert(key.clone(), item);
+            } else {
+                required_imports.insert(id);
+                imports.insert(key.clone(), item);
+            }
+            true
+        }
+    }
+
+    /// Remove duplicate imports from a world if they import from the same
+    /// interface with semver-compatible versions.
+    ///
+    /// This will merge duplicate interfaces present at multiple versions in
+    /// both a world by selecting the larger version of the two interfaces. This
+    /// requires that the interfaces are indeed semver-compatible and it means
+    /// that some imports might be removed and replaced. Note that this is only
+    /// done within a single semver track, for example the world imports 0.2.0
+    /// and 0.2.1 then the result afterwards will be that it imports
+    /// 0.2.1. If, however, 0.3.0 where imported then the final result would
+    /// import both 0.2.0 and 0.3.0.
+    pub fn merge_world_imports_based_on_semver(&mut self, world_id: WorldId) -> Result<()> {
+        self.merge_world_imports_based_on_semver_reported(world_id)?;
+        Ok(())
+    }
+
+    /// Same as [`Resolve::merge_world_imports_based_on_semver`] but
+    /// additionally returns which imports were merged.
+    ///
+    /// Each entry in the returned list is an import which was removed followed
+    /// by the import which replaced it, for example `wasi:clocks/wall-clock@0.2.0`
+    /// followed by `wasi:clocks/wall-clock@0.2.1`. Entries are listed in the
+    /// order the removed imports were originally listed in the world.
+    pub fn merge_world_imports_based_on_semver_reported(
+        &mut self,
+        world_id: WorldId,
+    ) -> Result<Vec<(WorldKey, WorldKey)>> {
+        let world = &self.worlds[world_id];
+
+        // The first pass here is to build a map of "semver tracks" where they
+        // key is per-interface and the value is the maximal version found in
+        // that semver-compatible-track plus the interface which is the maximal
+        // version.
+        //
+        // At the same time a `to_remove` set is maintained to remember what
+        // interfaces are being removed from `from` and `into`. All of
+        // `to_remove` are placed with a known other version.
+        let mut semver_tracks = HashMap::new();
+        let mut to_remove = HashSet::new();
+        for (key, _) in world.imports.iter() {
+            let iface_id = match key {
+                WorldKey::Interface(id) => *id,
+                WorldKey::Name(_) => continue,
+            };
+            let (track, version) = match self.semver_track(iface_id) {
+                Some(track) => track,
+                None => continue,
+            };
+            log::debug!(
+                "{} is on track {}/{}",
+                self.id_of(iface_id).unwrap(),
+                track.0,
+                track.1,
+            );
+            match semver_tracks.entry(track.clone()) {
+                hash_map::Entry::Vacant(e) => {
+                    e.insert((version, iface_id));
+                }
+                hash_map::Entry::Occupied(mut e) => match version.cmp(&e.get().0) {
+                    Ordering::Greater => {
+                        to_remove.insert(e.get().1);
+                        e.insert((version, iface_id));
+                    }
+                    Ordering::Equal => {}
+                    Ordering::Less => {
+                        to_remove.insert(iface_id);
+                    }
+                },
+            }
+        }
+
+        // Build a map of "this interface is replaced with this interface" using
+        // the results of the loop above.
+        let mut replacements = HashMap::new();
+        for id in to_remove {
+            let (track, _) = self.semver_track(id).unwrap();
+            let (_, latest) = semver_tracks[&track];
+            let prev = replacements.insert(id, latest);
+            assert!(prev.is_none());
+        }
+
+        self.replace_world_imports(world_id, &replacements)
+    }
+
+    /// Upgrades each interface imported by `world` to the latest version of
+    /// that interface present anywhere in this [`Resolve`], within the same
+    /// semver track.
+    ///
+    /// Unlike [`Resolve::merge_world_imports_based_on_semver`], which only
+    /// considers versions the world already imports, this upgrades to any
+    /// loaded version. For example if the world imports
+    /// `wasi:clocks/wall-clock@0.2.0` and `wasi:clocks@0.2.3` has also been
+    /// loaded then the world imports `wasi:clocks/wall-clock@0.2.3`
+    /// afterwards. Versions are never upgraded across a breaking boundary as
+    /// determined by [`PackageName::version_compat_track`], so `0.3.0` would
+    /// not be used in this example.
+    ///
+    /// Each entry in the returned list is an import which was replaced
+    /// followed by its replacement, in the order the replaced imports were
+    /// originally listed in the world. Returns an error if a newer version
+    /// is missing items of the version it would replace.
+    pub fn upgrade_world_imports_to_latest(
+        &mut self,
+        world_id: WorldId,
+    ) -> Result<Vec<(WorldKey, WorldKey)>> {
+        let mut latest = HashMap::new();
+        for (id, _) in self.interfaces.iter() {
+            let Some((track, version)) = self.semver_track(id) else {
+                continue;
+            };
+            match latest.entry(track) {
+                hash_map::Entry::Vacant(e) => {
+                    e.insert((version, id));
+                }
+                hash_map::Entry::Occupied(mut e) => {
+                    if version > e.get().0 {
+                        e.insert((version, id));
+                    }
+                }
+            }
+        }
+
+        let mut replacements = HashMap::new();
+        for key in self.worlds[world_id].imports.keys() {
+            let WorldKey::Interface(id) = key else {
+                continue;
+            };
+            let Some((track, version)) = self.semver_track(*id) else {
+                continue;
+            };
+            let (latest_version, latest) = latest[&track];
+            if latest_version > version {
+                replacements.insert(*id, latest);
+            }
+        }
+
+        self.replace_world_imports(world_id, &replacements)
+    }
+
+    /// Replaces the imports of `world_id` which are keys of `replacements`
+    /// with their value, importing the replacement in the same position if
+    /// it's not already imported.
+    ///
+    /// The dependencies of all other imports and exports are updated to
+    /// refer to the replacements, and then all worlds are re-elaborated.
+    fn replace_world_imports(
+        &mut self,
+        world_id: WorldId,
+        replacements: &HashMap<InterfaceId, InterfaceId>,
+    ) -> Result<Vec<(WorldKey, WorldKey)>> {
+        // Validate that `merge_world_item` succeeds for merging all removed
+        // interfaces with their replacement. This is a double-check that the
+        // semver version is actually correct and all items present in the old
+        // interface are in the new.
+        for (to_replace, replace_with) in replacements.iter() {
+            self.merge_world_item(
+                &WorldItem::Interface {
+                    id: *to_replace,
+                    stability: Default::default(),
+                },
+                &WorldItem::Interface {
+                    id: *replace_with,
+                    stability: Default::default(),
+                },
+            )
+            .with_context(|| {
+                let old_name = self.id_of(*to_replace).unwrap();
+                let new_name = self.id_of(*replace_with).unwrap();
+                format!(
+                    "failed to upgrade `{old_name}` to `{new_name}`, was \
+                     this semver-compatible update not semver compatible?"
+                )
+            })?;
+        }
+
+        for (to_replace, replace_with) in replacements.iter() {
+            log::debug!(
+                "REPLACE {} => {}",
+                self.id_of(*to_replace).unwrap(),
+                self.id_of(*replace_with).unwrap(),
+            );
+        }
+        let merged = self.worlds[world_id]
+            .imports
+            .keys()
+            .filter_map(|key| match key {
+                WorldKey::Interface(id) => replacements
+                    .get(id)
+                    .map(|new| (key.clone(), WorldKey::Interface(*new))),
+                WorldKey::Name(_) => None,
+            })
+            .collect();
+
+        // Finally perform the actual transformation of the imports/exports.
+        // Here all imports are removed if they're replaced and otherwise all
+        // imports have their dependencies updated, possibly transitively, to
+        // point to the new interfaces in `replacements`.
+        //
+        // Afterwards exports are additionally updated, but only their
+        // dependencies on imports which were remapped. Exports themselves are
+        // not deduplicated and/or removed.
+        let imports = mem::take(&mut self.worlds[world_id].imports);
+        let imported = imports.keys().cloned().collect::<HashSet<_>>();
+        for (key, item) in imports {
+            if let WorldItem::Interface { id, stability } = &item {
+                if let Some(new) = replacements.get(id) {
+                    let key = WorldKey::Interface(*new);
+                    if !imported.contains(&key) {
+                        let item = WorldItem::Interface {
+                            id: *new,
+                            stability: stability.clone(),
+                        };
+                        self.update_interface_deps_of_world_item(&item, replacements);
+                        let prev = self.worlds[world_id].imports.insert(key, item);
+                        assert!(prev.is_none());
+                    }
+                    continue;
+                }
+            }
+
+            self.update_interface_deps_of_world_item(&item, replacements);
+
+            let prev = self.worlds[world_id].imports.insert(key, item);
+            assert!(prev.is_none());
+        }
+        for (key, item) in mem::take(&mut self.worlds[world_id].exports) {
+            self.update_interface_deps_of_world_item(&item, replacements);
+            let prev = self.worlds[world_id].exports.insert(key, item);
+            assert!(prev.is_none());
+        }
+
+        // Run through `elaborate_world` to reorder imports as appropriate and
+        // fill anything back in if it's actually required by exports. For now
+        // this doesn't tamper with exports at all. Also note that this is
+        // applied to all worlds in this `Resolve` because interfaces were
+        // modified directly.
+        let ids = self.worlds.iter().map(|(id, _)| id).collect::<Vec<_>>();
+        for world_id in ids {
+            self.elaborate_world(world_id).with_context(|| {
+                let name = &self.worlds[world_id].name;
+                format!(
+                    "failed to elaborate world `{name}` after replacing imports \
+                     based on semver"
+                )
+            })?;
+        }
+
+        #[cfg(debug_assertions)]
+        self.assert_valid();
+
+        Ok(merged)
+    }
+
+    fn update_interface_deps_of_world_item(
+        &mut self,
+        item: &WorldItem,
+        replacements: &HashMap<InterfaceId, InterfaceId>,
+    ) {
+        match *item {
+            WorldItem::Type(t) => self.update_interface_dep_of_type(t, &replacements),
+            WorldItem::Interface { id, .. } => {
+                let types = self.interfaces[id]
+                    .types
+                    .values()
+                    .copied()
+                    .collect::<Vec<_>>();
+                for ty in types {
+                    self.update_interface_dep_of_type(ty, &replacements);
+                }
+            }
+            WorldItem::Function(_) => {}
+        }
+    }
+
+    /// Returns the "semver track" of an interface plus the interface's version.
+    ///
+    /// This function returns `None` if the interface `id` has a package without
+    /// a version. If the version is present, however, the first element of the
+    /// tuple returned is a "semver track" for the specific interface. The
+    /// version listed in `PackageName` will be modified so all
+    /// semver-compatible versions are listed the same way.
+    ///
+    /// The second element in the returned tuple is this interface's package's
+    /// version.
+    fn semver_track(&self, id: InterfaceId) -> Option<((PackageName, String), &Version)> {
+        let iface = &self.interfaces[id];
+        let pkg = &self.packages[iface.package?];
+        let version = pkg.name.version.as_ref()?;
+        let mut name = pkg.name.clone();
+        name.version = Some(PackageName::version_compat_track(version));
+        Some(((name, iface.name.clone()?), version))
+    }
+
+    /// If `ty` is a definition where it's a `use` from another interface, then
+    /// change what interface it's using from according to the pairs in the
+    /// `replacements` map.
+    fn update_interface_dep_of_type(
+        &mut self,
+        ty: TypeId,
+        replacements: &HashMap<InterfaceId, InterfaceId>,
+    ) {
+        let to_replace = match self.type_interface_dep(ty) {
+            Some(id) => id,
+            None => return,
+        };
+        let replace_with = match replacements.get(&to_replace) {
+            Some(id) => id,
+            None => return,
+        };
+        let dep = match self.types[ty].kind {
+            TypeDefKind::Type(Type::Id(id)) => id,
+            _ => return,
+        };
+        let name = self.types[dep].name.as_ref().unwrap();
+        // Note the infallible name indexing happening here. This should be
+        // previously validated with `merge_world_item` to succeed.
+        let replacement_id = self.interfaces[*replace_with].types[name];
+        self.types[ty].kind = TypeDefKind::Type(Type::Id(replacement_id));
+        self.layout = None;
+    }
+
+    /// Returns the core wasm module/field names for the specified `import`.
+    ///
+    /// This function will return the core wasm module/field that can be used to
+    /// use `import` with the name `mangling` scheme specified as well. This can
+    /// be useful for bindings generators, for example, and these names are
+    /// recognized by `wit-component` and `wasm-tools component new`.
+    pub fn wasm_import_name(
+        &self,
+        mangling: ManglingAndAbi,
+        import: WasmImport<'_>,
+    ) -> (String, String) {
+        match mangling {
+            ManglingAndAbi::Standard32 | ManglingAndAbi::Standard64 => {
+                let scheme = match mangling {
+                    ManglingAndAbi::Standard64 => "cm64p2",
+                    _ => "cm32p2",
+                };
+                match import {
+                    WasmImport::Func { interface, func } => {
+                        let module = match interface {
+                            Some(key) => {
+                                format!("{scheme}|{}", self.name_canonicalized_world_key(key))
+                            }
+                            None => scheme.to_string(),
+                        };
+                        (module, func.name.clone())
+                    }
+                    WasmImport::ResourceIntrinsic {
+                        interface,
+                        resource,
+                        intrinsic,
+                    } => {
+                        let name = self.types[resource].name.as_ref().unwrap();
+                        let (prefix, name) = match intrinsic {
+                            ResourceIntrinsic::ImportedDrop => ("", format!("{name}_drop")),
+                            ResourceIntrinsic::ExportedDrop => ("_ex_", format!("{name}_drop")),
+                            ResourceIntrinsic::ExportedNew => ("_ex_", format!("{name}_new")),
+                            ResourceIntrinsic::ExportedRep => ("_ex_", format!("{name}_rep")),
+                        };
+                        let module = match interface {
+                            Some(key) => {
+                                format!(
+                                    "{scheme}|{prefix}{}",
+                                    self.name_canonicalized_world_key(key)
+                                )
+                            }
+                            None => {
+                                assert_eq!(prefix, "");
+                                scheme.to_string()
+                            }
+                        };
+                        (module, name)
+                    }
+                }
+            }
+            ManglingAndAbi::Legacy(abi) => match import {
+                WasmImport::Func { interface, func } => {
+                    let module = match interface {
+                        Some(key) => self.name_world_key(key),
+                        None => format!("$root"),
+                    };
+                    (module, format!("{}{}", abi.import_prefix(), func.name))
+                }
+                WasmImport::ResourceIntrinsic {
+                    interface,
+                    resource,
+                    intrinsic,
+                } => {
+                    let name = self.types[resource].name.as_ref().unwrap();
+                    let (prefix, name) = match intrinsic {
+                        ResourceIntrinsic::ImportedDrop => ("", format!("[resource-drop]{name}")),
+                        ResourceIntrinsic::ExportedDrop => {
+                            ("[export]", format!("[resource-drop]{name}"))
+                        }
+                        ResourceIntrinsic::ExportedNew => {
+                            ("[export]", format!("[resource-new]{name}"))
+                        }
+                        ResourceIntrinsic::ExportedRep => {
+                            ("[export]", format!("[resource-rep]{name}"))
+                        }
+                    };
+                    let module = match interface {
+                        Some(key) => format!("{prefix}{}", self.name_world_key(key)),
+                        None => {
+                            assert_eq!(prefix, "");
+                            format!("$root")
+                        }
+                    };
+                    (module, format!("{}{name}", abi.import_prefix()))
+                }
+            },
+        }
+    }
+
+    /// Returns whether values of the type `id` own a resource which must
+    /// eventually be dropped.
+    ///
+    /// This is true for resources, looking through type aliases, whose drop
+    /// intrinsic is named by [`Resolve::resource_drop_name`].
+    pub fn resource_needs_drop(&self, id: TypeId) -> bool {
+        match self.types[id].kind {
+            TypeDefKind::Resource => true,
+            TypeDefKind::Type(Type::Id(id)) => self.resource_needs_drop(id),
+            _ => false,
+        }
+    }
+
+    /// Returns the core wasm module/field names of the `[resource-drop]`
+    /// intrinsic for the imported `resource`, which is imported from the
+    /// `interface` of a world or from the world itself if it's `None`.
+    ///
+    /// This is shorthand for [`Resolve::wasm_import_name`] with
+    /// [`ResourceIntrinsic::ImportedDrop`].
+    pub fn resource_drop_name(
+        &self,
+        mangling: ManglingAndAbi,
+        interface: Option<&WorldKey>,
+        resource: TypeId,
+    ) -> (String, String) {
+        self.wasm_import_name(
+            mangling,
+            WasmImport::ResourceIntrinsic {
+                interface,
+                resource,
+                intrinsic: ResourceIntrinsic::ImportedDrop,
+            },
+        )
+    }
+
+    /// Returns the core wasm export name for the specified `export`.
+    ///
+    /// This is the same as [`Resolve::wasm_import_name`], except for exports.
+    pub fn wasm_export_name(&self, mangling: ManglingAndAbi, export: WasmExport<'_>) -> String {
+        match mangling {
+            ManglingAndAbi::Standard32 | ManglingAndAbi::Standard64 => {
+                let scheme = match mangling {
+                    ManglingAndAbi::Standard64 => "cm64p2",
+                    _ => "cm32p2",
+                };
+                match export {
+                    WasmExport::Func {
+                        interface,
+                        func,
+                        kind,
+                    } => {
+                        let mut name = format!("{scheme}|");
+                        if let Some(interface) = interface {
+                            let s = self.name_canonicalized_world_key(interface);
+                            name.push_str(&s);
+                        }
+                        name.push_str("|");
+                        name.push_str(&func.name);
+                        match kind {
+                            WasmExportKind::Normal => {}
+                            WasmExportKind::PostReturn => name.push_str("_post"),
+                            WasmExportKind::Callback => todo!(
+                                "not yet supported: \
+                             async callback functions using standard name mangling"
+                            ),
+                        }
+                        name
+                    }
+                    WasmExport::ResourceDtor {
+                        interface,
+                        resource,
+                    } => {
+                        let name = self.types[resource].name.as_ref().unwrap();
+                        let interface = self.name_canonicalized_world_key(interface);
+                        format!("{scheme}|{interface}|{name}_dtor")
+                    }
+                    WasmExport::Memory => format!("{scheme}_memory"),
+                    WasmExport::Initialize => format!("{scheme}_initialize"),
+                    WasmExport::Realloc => format!("{scheme}_realloc"),
+                }
+            }
+            ManglingAndAbi::Legacy(abi) => match export {
+                WasmExport::Func {
+                    interface,
+                    func,
+                    kind,
+                } => {
+                    let mut name = abi.export_prefix().to_string();
+                    match kind {
+                        WasmExportKind::Normal => {}
+                        WasmExportKind::PostReturn => name.push_str("cabi_post_"),
+                        WasmExportKind::Callback => {
+                            assert!(matches!(abi, LiftLowerAbi::AsyncCallback));
+                            name = format!("[callback]{name}")
+                        }
+                    }
+                    if let Some(interface) = interface {
+                        let s = self.name_world_key(interface);
+                        name.push_str(&s);
+                        name.push_str("#");
+                    }
+                    name.push_str(&func.name);
+                    name
+                }
+                WasmExport::ResourceDtor {
+                    interface,
+                    resource,
+                } => {
+                    let name = self.types[resource].name.as_ref().unwrap();
+                    let interface = self.name_world_key(interface);
+                    format!("{}{interface}#[dtor]{name}", abi.export_prefix())
+                }
+                WasmExport::Memory => "memory".to_string(),
+                WasmExport::Initialize => "_initialize".to_string(),
+                WasmExport::Realloc => "cabi_realloc".to_string(),
+            },
+        }
+    }
+}
+
+/// Policy used by [`Resolve::push_with_policy`] when a package with the same
+/// name is already present.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// Return an error, the same behavior as [`Resolve::push`].
+    #[default]
+    Error,
+    /// Reuse the existing package if it's structurally identical to the new
+    /// one, and otherwise return an error.
+    MergeIfIdentical,
+    /// Insert the new package and make it the one found by name.
+    Replace,
+}
+
+/// A reference to an item within a [`Resolve`], as returned by
+/// [`Resolve::items_introduced_in`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ItemRef {
+    /// An interface.
+    Interface(InterfaceId),
+    /// A world.
+    World(WorldId),
+    /// A type defined in an interface or a world.
+    Type(TypeId),
+    /// A function defined in an interface, identified by its name.
+    InterfaceFunction(InterfaceId, String),
+    /// An interface or function imported or exported by a world.
+    WorldItem(WorldId, WorldKey),
+}
+
+/// Which side of a world to look at, as taken by [`Resolve::world_functions`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorldItemKind {
+    /// The imports of the world.
+    Import,
+    /// The exports of the world.
+    Export,
+}
+
+/// An item of an `include`d world which was overridden by the including
+/// world, as returned by [`Resolve::resolve_include_overrides`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Override {
+    /// The name of the overridden interface import or export, such as
+    /// `wasi:io/streams`.
+    pub name: String,
+    /// Whether the overridden item was an import or an export.
+    pub kind: WorldItemKind,
+    /// The included world that the overridden item came from.
+    pub included_world: WorldId,
+}
+
+/// The members of an imported or exported interface, as returned by
+/// [`Resolve::component_import_shape`] and
+/// [`Resolve::component_export_shape`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ItemShape {
+    /// Names of the functions within the interface, including resource
+    /// methods and constructors, for example `[method]input-stream.read`.
+    pub functions: Vec<String>,
+    /// Names of the resources defined within the interface.
+    pub resources: Vec<String>,
+}
+
+/// How a type refers back to itself, as returned by
+/// [`Resolve::type_recursion`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Recursion {
+    /// The type never refers back to itself.
+    Acyclic,
+    /// The type refers directly to itself, for example a resource with a
+    /// method returning `own<self>`.
+    SelfRecursive,
+    /// The type is part of a cycle with other types. All types in the cycle,
+    /// including the original type, are listed here.
+    MutuallyRecursive(Vec<TypeId>),
+}
+
+/// The differences between two definitions of a type, as returned by
+/// [`Resolve::diff_type`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TypeDiff {
+    /// Each change found, in the order the affected items are defined.
+    pub changes: Vec<TypeChange>,
+}
+
+impl TypeDiff {
+    /// Returns whether the two definitions are the same.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Returns whether any of the changes are breaking, see
+    /// [`TypeChange::is_breaking`].
+    pub fn is_breaking(&self) -> bool {
+        self.changes.iter().any(TypeChange::is_breaking)
+    }
+}
+
+/// A single difference between two definitions of a type, see
+/// [`TypeDiff`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeChange {
+    /// The name of the type changed.
+    Renamed {
+        old: Option<String>,
+        new: Option<String>,
+    },
+    /// The type is now a different kind of type, for example a `record`
+    /// which became a `variant`. No further changes are listed.
+    KindChanged {
+        old: &'static str,
+        new: &'static str,
+    },
+    /// A change to the fields of a `record`.
+    Field(ItemChange),
+    /// A change to the cases of a `variant` or `enum`.
+    Case(ItemChange),
+    /// A change to the flags of a `flags`.
+    Flag(ItemChange),
+    /// The definition of any other kind of type changed, such as the
+    /// element type of a `list` or the target of an alias. Each definition
+    /// is rendered in WIT syntax.
+    DefinitionChanged { old: String, new: String },
+}
+
+impl TypeChange {
+    /// Returns whether this change is incompatible with the original
+    /// definition.
+    ///
+    /// Every change is breaking except for reordering the fields of a record,
+    /// since source code using bindings generated from WIT refers to fields
+    /// by name. Reordering the cases of a variant
+    /// or enum is breaking as cases are identified by their discriminant, as
+    /// is reordering flags since each flag is identified by its bit.
+    ///
+    /// Note that the canonical ABI lays out and flattens records by position,
+    /// so reordering fields still changes the ABI of a record and components
+    /// compiled against the old definition won't link against the new one.
+    pub fn is_breaking(&self) -> bool {
+        !matches!(self, TypeChange::Field(ItemChange::Reordered))
+    }
+}
+
+/// A change to one of the named items within a type, such as a field of a
+/// record, see [`TypeChange`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ItemChange {
+    /// An item with this name was added.
+    Added(String),
+    /// The item with this name was removed.
+    Removed(String),
+    /// The type of the item with this name changed. `None` means the item has
+    /// no payload, for example a variant case without a type.
+    TypeChanged {
+        name: String,
+        old: Option<String>,
+        new: Option<String>,
+    },
+    /// Items present in both definitions are in a different order.
+    Reordered,
+}
+
+/// Exports of the same name in two worlds which disagree, as returned by
+/// [`Resolve::cross_world_conflicts`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// The name of the export, such as `foo:bar/baz` or `run`.
+    pub name: String,
+    /// The first world to export `name` and the world it conflicts with.
+    pub worlds: (WorldId, WorldId),
+    /// How the two exports differ.
+    pub kind: ConflictKind,
+}
+
+/// How two exports in a [`Conflict`] differ.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// The exports have different stability attributes.
+    Stability,
+    /// The exports have different signatures, described by the message.
+    Signature(String),
+}
+
+/// An interface imported by a world, as returned by
+/// [`Resolve::capability_manifest`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub struct Capability {
+    /// The name of the interface without its version, such as
+    /// `wasi:filesystem/types`, or the name of the import for interfaces
+    /// defined inline in the world.
+    pub interface: String,
+    /// The version of the interface's package, if any.
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "serialize_optional_version")
+    )]
+    pub version: Option<Version>,
+    /// The kind of access the interface grants, if it's recognized.
+    pub category: Option<CapabilityCategory>,
+}
+
+/// A well-known kind of access granted by an imported interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum CapabilityCategory {
+    /// Access to files and directories, from `wasi:filesystem`.
+    Filesystem,
+    /// Access to the network, from `wasi:sockets` or `wasi:http`.
+    Network,
+    /// Access to wall clock and monotonic time, from `wasi:clocks`.
+    Clocks,
+    /// Access to random numbers, from `wasi:random`.
+    Random,
+}
+
+impl CapabilityCategory {
+    /// Returns the category of interfaces in package `name`, if it's a
+    /// well-known WASI package.
+    fn of_package(name: &PackageName) -> Option<CapabilityCategory> {
+        if name.namespace != "wasi" {
+            return None;
+        }
+        match name.name.as_str() {
+            "filesystem" => Some(CapabilityCategory::Filesystem),
+            "sockets" | "http" => Some(CapabilityCategory::Network),
+            "clocks" => Some(CapabilityCategory::Clocks),
+            "random" => Some(CapabilityCategory::Random),
+            _ => None,
+        }
+    }
+}
+
+/// A naming conflict found by [`Resolve::validate_names`].
+///
+/// The `owner` of each error is the fully qualified name of the interface or
+/// world the conflicting item is defined in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NameError {
+    /// More than one type named `name` is defined in `owner`.
+    DuplicateType { owner: String, name: String },
+    /// More than one function named `name` is defined in `owner`.
+    DuplicateFunction { owner: String, name: String },
+    /// More than one import, or more than one export, of `world` is named
+    /// `name`.
+    DuplicateWorldKey { world: WorldId, name: String },
+}
+
+impl fmt::Display for NameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NameError::DuplicateType { owner, name } => {
+                write!(f, "type `{name}` is defined more than once in `{owner}`")
+            }
+            NameError::DuplicateFunction { owner, name } => {
+                write!(
+                    f,
+                    "function `{name}` is defined more than once in `{owner}`"
+                )
+            }
+            NameError::DuplicateWorldKey { name, .. } => {
+                write!(f, "world item `{name}` conflicts with another item")
+            }
+        }
+    }
+}
+
+impl std::error::Error for NameError {}
+
+/// Possible imports that can be passed to [`Resolve::wasm_import_name`].
+#[derive(Debug)]
+pub enum WasmImport<'a> {
+    /// A WIT function is being imported. Optionally from an interface.
+    Func {
+        /// The name of the interface that the function is being imported from.
+        ///
+        /// If the function is imported directly from the world then this is
+        /// `Noen`.
+        interface: Option<&'a WorldKey>,
+
+        /// The function being imported.
+        func: &'a Function,
+    },
+
+    /// A resource-related intrinsic is being imported.
+    ResourceIntrinsic {
+        /// The optional interface to import from, same as `WasmImport::Func`.
+        interface: Option<&'a WorldKey>,
+
+        /// The resource that's being operated on.
+        resource: TypeId,
+
+        /// The intrinsic that's being imported.
+        intrinsic: ResourceIntrinsic,
+    },
+}
+
+/// Intrinsic definitions to go with [`WasmImport::ResourceIntrinsic`] which
+/// also goes with [`Resolve::wasm_import_name`].
+#[derive(Debug)]
+pub enum ResourceIntrinsic {
+    ImportedDrop,
+    ExportedDrop,
+    ExportedNew,
+    ExportedRep,
+}
+
+/// Indicates whether a function export is a normal export, a post-return
+/// function, or a callback function.
+#[derive(Debug)]
+pub enum WasmExportKind {
+    /// Normal function export.
+    Normal,
+
+    /// Post-return function.
+    PostReturn,
+
+    /// Async callback function.
+    Callback,
+}
+
+/// Different kinds of exports that can be passed to
+/// [`Resolve::wasm_export_name`] to export from core wasm modules.
+#[derive(Debug)]
+pub enum WasmExport<'a> {
+    /// A WIT function is being exported, optionally from an interface.
+    Func {
+        /// An optional interface which owns `func`. Use `None` for top-level
+        /// world function.
+        interface: Option<&'a WorldKey>,
+
+        /// The function being exported.
+        func: &'a Function,
+
+        /// Kind of function (normal, post-return, or callback) being exported.
+        kind: WasmExportKind,
+    },
+
+    /// A destructor for a resource exported from this module.
+    ResourceDtor {
+        /// The interface that owns the resource.
+        interface: &'a WorldKey,
+        /// The resource itself that the destructor is for.
+        resource: TypeId,
+    },
+
+    /// Linear memory, the one that the canonical ABI uses.
+    Memory,
+
+    /// An initialization function (not the core wasm `start`).
+    Initialize,
+
+    /// The general-purpose realloc hook.
+    Realloc,
+}
+
+/// Which package each type and interface merged by
+/// [`Resolve::merge_detailed`] originally came from.
+#[derive(Debug, Clone, Default)]
+pub struct MergeProvenance {
+    /// The package each merged type was defined in.
+    ///
+    /// Types which aren't defined within a package, such as those defined in
+    /// a world outside of a package, aren't present.
+    pub types: IndexMap<TypeId, PackageName>,
+    /// The package each merged interface was defined in.
+    pub interfaces: IndexMap<InterfaceId, PackageName>,
+}
+
+/// Structure returned by [`Resolve::merge`] which contains mappings from
+/// old-ids to new-ids after the merge.
+#[derive(Default)]
+pub struct Remap {
+    pub types: Vec<Option<TypeId>>,
+    pub interfaces: Vec<Option<InterfaceId>>,
+    pub worlds: Vec<Option<WorldId>>,
+    pub packages: Vec<PackageId>,
+
+    /// A cache of anonymous `own<T>` handles for resource types.
+    ///
+    /// The appending operation of `Remap` is the one responsible for
+    /// translating references to `T` where `T` is a resource into `own<T>`
+    /// instead. This map is used to deduplicate the `own<T>` types generated
+    /// to generate as few as possible.
+    ///
+    /// The key of this map is the resource id `T` in the new resolve, and
+    /// the value is the `own<T>` type pointing to `T`.
+    own_handles: HashMap<TypeId, TypeId>,
+
+    type_has_borrow: Vec<Option<bool>>,
+}
+
+fn apply_map<T>(map: &[Option<Id<T>>], id: Id<T>, desc: &str, span: Option<Span>) -> Result<Id<T>> {
+    match map.get(id.index()) {
+        Some(Some(id)) => Ok(*id),
+        Some(None) => {
+            let msg = format!(
+                "found a reference to a {desc} which is excluded \
+                 due to its feature not being activated"
+            );
+            match span {
+                Some(span) => Err(Error::new(span, msg).into()),
+                None => bail!("{msg}"),
+            }
+        }
+        None => panic!("request to remap a {desc} that has not yet been registered"),
+    }
+}
+
+impl Remap {
+    pub fn map_type(&self, id: TypeId, span: Option<Span>) -> Result<TypeId> {
+        apply_map(&self.types, id, "type", span)
+    }
+
+    pub fn map_interface(&self, id: InterfaceId, span: Option<Span>) -> Result<InterfaceId> {
+        apply_map(&self.interfaces, id, "interface", span)
+    }
+
+    pub fn map_world(&self, id: WorldId, span: Option<Span>) -> Result<WorldId> {
+        apply_map(&self.worlds, id, "world", span)
+    }
+
+    /// Maps `item` to its new ids, returning `None` if any of its ids weren't
+    /// carried over.
+    fn map_item_ref(&self, item: &ItemRef) -> Option<ItemRef> {
+        fn get<T: Copy>(map: &[Option<T>], index: usize) -> Option<T> {
+            map.get(index).copied().flatten()
+        }
+        let interface = |id: InterfaceId| get(&self.interfaces, id.index());
+        let world = |id: WorldId| get(&self.worlds, id.index());
+        Some(match item {
+            ItemRef::Interface(id) => ItemRef::Interface(interface(*id)?),
+            ItemRef::World(id) => ItemRef::World(world(*id)?),
+            ItemRef::Type(id) => ItemRef::Type(get(&self.types, id.index())?),
+            ItemRef::InterfaceFunction(id, name) => {
+                ItemRef::InterfaceFunction(interface(*id)?, name.clone())
+            }
+            ItemRef::WorldItem(id, key) => {
+                let key = match key {
+                    WorldKey::Name(name) => WorldKey::Name(name.clone()),
+                    WorldKey::Interface(id) => WorldKey::Interface(interface(*id)?),
+                };
+                ItemRef::WorldItem(world(*id)?, key)
+            }
+        })
+    }
+
+    /// Maps the include overrides of `world` to their new ids, dropping
+    /// those whose included world wasn't carried over.
+    fn map_overrides(
+        &self,
+        world: WorldId,
+        mut overrides: Vec<Override>,
+    ) -> Option<(WorldId, Vec<Override>)> {
+        let map = |id: WorldId| self.worlds.get(id.index()).copied().flatten();
+        overrides.retain_mut(|o| match map(o.included_world) {
+            Some(id) => {
+                o.included_world = id;
+                true
+            }
+            None => false,
+        });
+        Some((map(world)?, overrides))
+    }
+
+    fn append(
+        &mut self,
+        resolve: &mut Resolve,
+        unresolved: UnresolvedPackage,
+        source_map: &Arc<SourceMap>,
+    ) -> Result<PackageId> {
+        let pkgid = resolve.packages.alloc(Package {
+            name: unresolved.name.clone(),
+            docs: unresolved.docs.clone(),
+            leading_comments: unresolved.leading_comments.clone(),
+            interfaces: Default::default(),
+            worlds: Default::default(),
+        });
+        let prev = resolve.package_names.insert(unresolved.name.clone(), pkgid);
+        if let Some(prev) = prev {
+            resolve.package_names.insert(unresolved.name.clone(), prev);
+            bail!(
+                "attempting to re-add package `{}` when it's already present in this `Resolve`",
+                unresolved.name,
+            );
+        }
+
+        self.process_foreign_deps(resolve, pkgid, &unresolved)?;
+
+        let foreign_types = self.types.len();
+        let foreign_interfaces = self.interfaces.len();
+        let foreign_worlds = self.worlds.len();
+
+        // Copy over all types first, updating any intra-type references. Note
+        // that types are sorted topologically which means this iteration
+        // order should be sufficient. Also note though that the interface
+        // owner of a type isn't updated here due to interfaces not being known
+        // yet.
+        assert_eq!(unresolved.types.len(), unresolved.type_spans.len());
+        for ((id, mut ty), span) in unresolved
+            .types
+            .into_iter()
+            .zip(&unresolved.type_spans)
+            .skip(foreign_types)
+        {
+            if !resolve.include_type(&ty, pkgid, *span)? {
+                self.types.push(None);
+                continue;
+            }
+
+            self.update_typedef(resolve, &mut ty, Some(*span))?;
+            let named = ty.name.is_some();
+            let new_id = resolve.types.alloc(ty);
+            assert_eq!(self.types.len(), id.index());
+            if named {
+                resolve
+                    .item_spans
+                    .insert(ItemRef::Type(new_id), (*span, source_map.clone()));
+            }
+
+            let new_id = match resolve.types[new_id] {
+                // If this is an `own<T>` handle then either replace it with a
+                // preexisting `own<T>` handle which may have been generated in
+                // `update_ty`. If that doesn't exist though then insert it into
+                // the `own_handles` cache.
+                TypeDef {
+                    name: None,
+                    owner: TypeOwner::None,
+                    kind: TypeDefKind::Handle(Handle::Own(id)),
+                    docs: _,
+                    stability: _,
+                } => *self.own_handles.entry(id).or_insert(new_id),
+
+                // Everything not-related to `own<T>` doesn't get its ID
+                // modified.
+                _ => new_id,
+            };
+            self.types.push(Some(new_id));
+        }
+
+        // Next transfer all interfaces into `Resolve`, updating type ids
+        // referenced along the way.
+        assert_eq!(
+            unresolved.interfaces.len(),
+            unresolved.interface_spans.len()
+        );
+        for ((id, mut iface), span) in unresolved
+            .interfaces
+            .into_iter()
+            .zip(&unresolved.interface_spans)
+            .skip(foreign_interfaces)
+        {
+            if !resolve
+                .include_stability(&iface.stability, &pkgid, Some(span.span))
+                .with_context(|| {
+                    format!(
+                        "failed to process feature gate for interface [{}] in package [{}]",
+                        iface
+                            .name
+                            .as_ref()
+                            .map(String::as_str)
+                            .unwrap_or("<unknown>"),
+                        resolve.packages[pkgid].name,
+                    )
+                })?
+            {
+                self.interfaces.push(None);
+                continue;
+            }
+            assert!(iface.package.is_none());
+            iface.package = Some(pkgid);
+            let mut func_spans = iface
+                .functions
+                .keys()
+                .cloned()
+                .zip(span.funcs.iter().copied())
+                .collect::<Vec<_>>();
+            self.update_interface(resolve, &mut iface, Some(span))?;
+            func_spans.retain(|(name, _)| iface.functions.contains_key(name));
+            let offsets = func_spans
+                .iter()
+                .map(|(name, span)| (name.clone(), span.start))
+                .collect::<HashMap<_, _>>();
+            let new_id = resolve.interfaces.alloc(iface);
+            resolve
+                .item_spans
+                .insert(ItemRef::Interface(new_id), (span.span, source_map.clone()));
+            for (name, func_span) in func_spans {
+                resolve.item_spans.insert(
+                    ItemRef::InterfaceFunction(new_id, name),
+                    (func_span, source_map.clone()),
+                );
+            }
+            resolve.interface_function_offsets.insert(new_id, offsets);
+            assert_eq!(self.interfaces.len(), id.index());
+            self.interfaces.push(Some(new_id));
+        }
+
+        // Now that interfaces are identified go back through the types and
+        // update their interface owners.
+        for (i, id) in self.types.iter().enumerate().skip(foreign_types) {
+            let id = match id {
+                Some(id) => *id,
+                None => continue,
+            };
+            match &mut resolve.types[id].owner {
+                TypeOwner::Interface(id) => {
+                    let span = unresolved.type_spans[i];
+                    *id = self.map_interface(*id, Some(span))
+                        .with_context(|| {
+                            "this type is not gated by a feature but its interface is gated by a feature"
+                        })?;
+                }
+                TypeOwner::World(_) | TypeOwner::None => {}
+            }
+        }
+
+        // Expand worlds. Note that this does NOT process `include` statements,
+        // that's handled below. Instead this just handles world item updates
+        // and resolves references to types/items within `Resolve`.
+        //
+        // This is done after types/interfaces are fully settled so the
+        // transitive relation between interfaces, through types, is understood
+        // here.
+        assert_eq!(unresolved.worlds.len(), unresolved.world_spans.len());
+        for ((id, mut world), span) in unresolved
+            .worlds
+            .into_iter()
+            .zip(&unresolved.world_spans)
+            .skip(foreign_worlds)
+        {
+            if !resolve
+                .include_stability(&world.stability, &pkgid, Some(span.span))
+                .with_context(|| {
+                    format!(
+                        "failed to process feature gate for world [{}] in package [{}]",
+                        world.name, resolve.packages[pkgid].name,
+                    )
+                })?
+            {
+                self.worlds.push(None);
+                continue;
+            }
+            let item_spans = self.update_world(&mut world, resolve, &pkgid, &span)?;
+
+            let new_id = resolve.worlds.alloc(world);
+            resolve
+                .item_spans
+                .insert(ItemRef::World(new_id), (span.span, source_map.clone()));
+            for (key, item_span) in item_spans {
+                resolve.item_spans.insert(
+                    ItemRef::WorldItem(new_id, key),
+                    (item_span, source_map.clone()),
+                );
+            }
+            assert_eq!(self.worlds.len(), id.index());
+            self.worlds.push(Some(new_id));
+        }
+
+        // As with interfaces, now update the ids of world-owned types.
+        for (i, id) in self.types.iter().enumerate().skip(foreign_types) {
+            let id = match id {
+                Some(id) => *id,
+                None => continue,
+            };
+            match &mut resolve.types[id].owner {
+                TypeOwner::World(id) => {
+                    let span = unresolved.type_spans[i];
+                    *id = self.map_world(*id, Some(span))
+                        .with_context(|| {
+                            "this type is not gated by a feature but its interface is gated by a feature"
+                        })?;
+                }
+                TypeOwner::Interface(_) | TypeOwner::None => {}
+            }
+        }
+
+        // After the above, process `include` statements for worlds and
+        // additionally fully elaborate them. Processing of `include` is
+        // deferred until after the steps above so the fully resolved state of
+        // local types in this package are all available. This is required
+        // because `include` may copy types between worlds when the type is
+        // defined in the world itself.
+        //
+        // This step, after processing `include`, will also use
+        // `elaborate_world` to fully expand the world in terms of
+        // imports/exports and ensure that all necessary imports/exports are all
+        // listed.
+        //
+        // Note that `self.worlds` is already sorted in topological order so if
+        // one world refers to another via `include` then it's guaranteed that
+        // the one we're referring to is already expanded and ready to be
+        // included.
+        assert_eq!(self.worlds.len(), unresolved.world_spans.len());
+        for (id, span) in self
+            .worlds
+            .iter()
+            .zip(unresolved.world_spans.iter())
+            .skip(foreign_worlds)
+        {
+            let Some(id) = *id else {
+                continue;
+            };
+            self.process_world_includes(id, resolve, &pkgid, &span)?;
+
+            resolve.elaborate_world(id).with_context(|| {
+                Error::new(
+                    span.span,
+                    format!(
+                        "failed to elaborate world imports/exports of `{}`",
+                        resolve.worlds[id].name
+                    ),
+                )
+            })?;
+        }
+
+        // Fixup "parent" ids now that everything has been identified
+        for id in self.interfaces.iter().skip(foreign_interfaces) {
+            let id = match id {
+                Some(id) => *id,
+                None => continue,
+            };
+            let iface = &mut resolve.interfaces[id];
+            iface.package = Some(pkgid);
+            if let Some(name) = &iface.name {
+                let prev = resolve.packages[pkgid].interfaces.insert(name.clone(), id);
+                assert!(prev.is_none());
+            }
+        }
+        for id in self.worlds.iter().skip(foreign_worlds) {
+            let id = match id {
+                Some(id) => *id,
+                None => continue,
+            };
+            let world = &mut resolve.worlds[id];
+            world.package = Some(pkgid);
+            let prev = resolve.packages[pkgid]
+                .worlds
+                .insert(world.name.clone(), id);
+            assert!(prev.is_none());
+        }
+        Ok(pkgid)
+    }
+
+    fn process_foreign_deps(
+        &mut self,
+        resolve: &mut Resolve,
+        pkgid: PackageId,
+        unresolved: &UnresolvedPackage,
+    ) -> Result<()> {
+        // Invert the `foreign_deps` map to be keyed by world id to get
+        // used in the loops below.
+        let mut world_to_package = HashMap::new();
+        let mut interface_to_package = HashMap::new();
+        for (i, (pkg_name, worlds_or_ifaces)) in unresolved.foreign_deps.iter().enumerate() {
+            for (name, item) in worlds_or_ifaces {
+                match item {
+                    AstItem::Interface(unresolved_interface_id) => {
+                        let prev = interface_to_package.insert(
+                            *unresolved_interface_id,
+                            (pkg_name, name, unresolved.foreign_dep_spans[i]),
+                        );
+                        assert!(prev.is_none());
+                    }
+                    AstItem::World(unresolved_world_id) => {
+                        let prev = world_to_package.insert(
+                            *unresolved_world_id,
+                            (pkg_name, name, unresolved.foreign_dep_spans[i]),
+                        );
+                        assert!(prev.is_none());
+                    }
+                }
+            }
+        }
+
+        // Connect all interfaces referred to in `interface_to_package`, which
+        // are at the front of `unresolved.interfaces`, to interfaces already
+        // contained within `resolve`.
+        self.process_foreign_interfaces(unresolved, &interface_to_package, resolve)?;
+
+        // Connect all worlds referred to in `world_to_package`, which
+        // are at the front of `unresolved.worlds`, to worlds already
+        // contained within `resolve`.
+        self.process_foreign_worlds(unresolved, &world_to_package, resolve)?;
+
+        // Finally, iterate over all foreign-defined types and determine
+        // what they map to.
+        self.process_foreign_types(unresolved, pkgid, resolve)?;
+
+        for (id, span) in unresolved.required_resource_types.iter() {
+            // Note that errors are ignored here because an error represents a
+            // type that has been configured away. If a type is configured away
+            // then any future use of it will generate an error so there's no
+            // need to validate that it's a resource here.
+            let Ok(mut id) = self.map_type(*id, Some(*span)) else {
+                continue;
+            };
+            loop {
+                match resolve.types[id].kind {
+                    TypeDefKind::Type(Type::Id(i)) => id = i,
+                    TypeDefKind::Resource => break,
+                    ref kind => bail!(Error::new(
+                        *span,
+                        format!(
+                            "type used in a handle must be a resource, found `{}`",
+                            crate::type_def_kind_to_string(&resolve.types, kind),
+                        ),
+                    )),
+                }
+            }
+        }
+
+        #[cfg(debug_assertions)]
+        resolve.assert_valid();
+
+        Ok(())
+    }
+
+    fn process_foreign_interfaces(
+        &mut self,
+        unresolved: &UnresolvedPackage,
+        interface_to_package: &HashMap<InterfaceId, (&PackageName, &String, Span)>,
+        resolve: &mut Resolve,
+    ) -> Result<(), anyhow::Error> {
+        for (unresolved_iface_id, unresolved_iface) in unresolved.interfaces.iter() {
+            let (pkg_name, interface, span) = match interface_to_package.get(&unresolved_iface_id) {
+                Some(items) => *items,
+                // All foreign interfaces are defined first, so the first one
+                // which is defined in a non-foreign document means that all
+                // further interfaces will be non-foreign as well.
+                None => break,
+            };
+            let unversioned = resolve.resolve_unversioned_deps;
+            let pkgid = lookup_package_name(&resolve.package_names, pkg_name, unversioned)
+                .map_err(|msg| Error::new(span, msg))?
+                .copied()
+                .ok_or_else(|| {
+                    PackageNotFoundError::new(
+                        span,
+                        pkg_name.clone(),
+                        resolve.package_names.keys().cloned().collect(),
+                    )
+                })?;
+
+            // Functions can't be imported so this should be empty.
+            assert!(unresolved_iface.functions.is_empty());
+
+            let pkg = &resolve.packages[pkgid];
+            let span = &unresolved.interface_spans[unresolved_iface_id.index()];
+            let iface_id = pkg
+                .interfaces
+                .get(interface)
+                .copied()
+                .ok_or_else(|| Error::new(span.span, "interface not found in package"))?;
+            assert_eq!(self.interfaces.len(), unresolved_iface_id.index());
+            self.interfaces.push(Some(iface_id));
+        }
+        for (id, _) in unresolved.interfaces.iter().skip(self.interfaces.len()) {
+            assert!(
+                interface_to_package.get(&id).is_none(),
+                "found foreign interface after local interface"
+            );
+        }
+        Ok(())
+    }
+
+    fn process_foreign_worlds(
+        &mut self,
+        unresolved: &UnresolvedPackage,
+        world_to_package: &HashMap<WorldId, (&PackageName, &String, Span)>,
+        resolve: &mut Resolve,
+    ) -> Result<(), anyhow::Error> {
+        for (unresolved_world_id, _) in unresolved.worlds.iter() {
+            let (pkg_name, world, span) = match world_to_package.get(&unresolved_world_id) {
+                Some(items) => *items,
+                // Same as above, all worlds are foreign until we find a
+                // non-foreign one.
+                None => break,
+            };
+
+            let unversioned = resolve.resolve_unversioned_deps;
+            let pkgid = lookup_package_name(&resolve.package_names, pkg_name, unversioned)
+                .map_err(|msg| Error::new(span, msg))?
+                .copied()
+                .ok_or_else(|| Error::new(span, "package not found"))?;
+            let pkg = &resolve.packages[pkgid];
+            let span = &unresolved.world_spans[unresolved_world_id.index()];
+            let world_id = pkg
+                .worlds
+                .get(world)
+                .copied()
+                .ok_or_else(|| Error::new(span.span, "world not found in package"))?;
+            assert_eq!(self.worlds.len(), unresolved_world_id.index());
+            self.worlds.push(Some(world_id));
+        }
+        for (id, _) in unresolved.worlds.iter().skip(self.worlds.len()) {
+            assert!(
+                world_to_package.get(&id).is_none(),
+                "found foreign world after local world"
+            );
+        }
+        Ok(())
+    }
+
+    fn process_foreign_types(
+        &mut self,
+        unresolved: &UnresolvedPackage,
+        pkgid: PackageId,
+        resolve: &mut Resolve,
+    ) -> Result<(), anyhow::Error> {
+        for ((unresolved_type_id, unresolved_ty), span) in
+            unresolved.types.iter().zip(&unresolved.type_spans)
+        {
+            // All "Unknown" types should appear first so once we're no longer
+            // in unknown territory it's package-defined types so break out of
+            // this loop.
+            match unresolved_ty.kind {
+                TypeDefKind::Unknown => {}
+                _ => break,
+            }
+
+            if !resolve.include_type(unresolved_ty, pkgid, *span)? {
+                self.types.push(None);
+                continue;
+            }
+
+            let unresolved_iface_id = match unresolved_ty.owner {
+                TypeOwner::Interface(id) => id,
+                _ => unreachable!(),
+            };
+            let iface_id = self.map_interface(unresolved_iface_id, None)?;
+            let name = unresolved_ty.name.as_ref().unwrap();
+            let span = unresolved.unknown_type_spans[unresolved_type_id.index()];
+            let type_id = *resolve.interfaces[iface_id]
+                .types
+                .get(name)
+                .ok_or_else(|| {
+                    Error::new(span, format!("type `{name}` not defined in interface"))
+                })?;
+            assert_eq!(self.types.len(), unresolved_type_id.index());
+            self.types.push(Some(type_id));
+        }
+        for (_, ty) in unresolved.types.iter().skip(self.types.len()) {
+            if let TypeDefKind::Unknown = ty.kind {
+                panic!("unknown type after defined type");
+            }
+        }
+        Ok(())
+    }
+
+    fn update_typedef(
+        &mut self,
+        resolve: &mut Resolve,
+        ty: &mut TypeDef,
+        span: Option<Span>,
+    ) -> Result<()> {
+        // NB: note that `ty.owner` is not updated here since interfaces
+        // haven't been mapped yet and that's done in a separate step.
+        use crate::TypeDefKind::*;
+        match &mut ty.kind {
+            Handle(handle) => match handle {
+                crate::Handle::Own(ty) | crate::Handle::Borrow(ty) => {
+                    self.update_type_id(ty, span)?
+                }
+            },
+            Resource => {}
+            Record(r) => {
+                for field in r.fields.iter_mut() {
+                    self.update_ty(resolve, &mut field.ty, span)
+                        .with_context(|| format!("failed to update field `{}`", field.name))?;
+                }
+            }
+            Tuple(t) => {
+                for ty in t.types.iter_mut() {
+                    self.update_ty(resolve, ty, span)?;
+                }
+            }
+            Variant(v) => {
+                for case in v.cases.iter_mut() {
+                    if let Some(t) = &mut case.ty {
+                        self.update_ty(resolve, t, span)?;
+                    }
+                }
+            }
+            Option(t) | List(t, ..) | FixedSizeList(t, ..) | Future(Some(t)) | Stream(Some(t)) => {
+                self.update_ty(resolve, t, span)?
+            }
+            Result(r) => {
+                if let Some(ty) = &mut r.ok {
+                    self.update_ty(resolve, ty, span)?;
+                }
+                if let Some(ty) = &mut r.err {
+                    self.update_ty(resolve, ty, span)?;
+                }
+            }
+
+            // Note that `update_ty` is specifically not used here as typedefs
+            // because for the `type a = b` form that doesn't force `a` to be a
+            // handle type if `b` is a resource type, instead `a` is
+            // simultaneously usable as a resource and a handle type
+            Type(crate::Type::Id(id)) => self.update_type_id(id, span)?,
+            Type(_) => {}
+
+            // nothing to do for these as they're just names or empty
+            Flags(_) | Enum(_) | Future(None) | Stream(None) => {}
+
+            Unknown => unreachable!(),
+        }
+
+        Ok(())
+    }
+
+    fn update_ty(
+        &mut self,
+        resolve: &mut Resolve,
+        ty: &mut Type,
+        span: Option<Span>,
+    ) -> Result<()> {
+        let id = match ty {
+            Type::Id(id) => id,
+            _ => return Ok(()),
+        };
+        self.update_type_id(id, span)?;
+
+        // If `id` points to a `Resource` type then this means that what was
+        // just discovered was a reference to what will implicitly become an
+        // `own<T>` handle. This `own` handle is implicitly allocated here
+        // and handled during the merging process.
+        let mut cur = *id;
+        let points_to_resource = loop {
+            match resolve.types[cur].kind {
+                TypeDefKind::Type(Type::Id(id)) => cur = id,
+                TypeDefKind::Resource => break true,
+                _ => break false,
+            }
+        };
+
+        if points_to_resource {
+            *id = *self.own_handles.entry(*id).or_insert_with(|| {
+                resolve.types.alloc(TypeDef {
+                    name: None,
+                    owner: TypeOwner::None,
+                    kind: TypeDefKind::Handle(Handle::Own(*id)),
+                    docs: Default::default(),
+                    stability: Default::default(),
+                })
+            });
+        }
+        Ok(())
+    }
+
+    fn update_type_id(&self, id: &mut TypeId, span: Option<Span>) -> Result<()> {
+        *id = self.map_type(*id, span)?;
+        Ok(())
+    }
+
+    fn update_interface(
+        &mut self,
+        resolve: &mut Resolve,
+        iface: &mut Interface,
+        spans: Option<&InterfaceSpan>,
+    ) -> Result<()> {
+        iface.types.retain(|_, ty| self.types[ty.index()].is_some());
+        let iface_pkg_id = iface.package.as_ref().unwrap_or_else(|| {
+            panic!(
+                "unexpectedly missing package on interface [{}]",
+                iface
+                    .name
+                    .as_ref()
+                    .map(String::as_str)
+                    .unwrap_or("<unknown>"),
+            )
+        });
+
+        // NB: note that `iface.doc` is not updated here since interfaces
+        // haven't been mapped yet and that's done in a separate step.
+        for (_name, ty) in iface.types.iter_mut() {
+            self.update_type_id(ty, spans.map(|s| s.span))?;
+        }
+        if let Some(spans) = spans {
+            assert_eq!(iface.functions.len(), spans.funcs.len());
+        }
+        for (i, (func_name, func)) in iface.functions.iter_mut().enumerate() {
+            let span = spans.map(|s| s.funcs[i]);
+            if !resolve
+                .include_stability(&func.stability, iface_pkg_id, span)
+                .with_context(|| {
+                    format!(
+                        "failed to process feature gate for function [{func_name}] in package [{}]",
+                        resolve.packages[*iface_pkg_id].name,
+                    )
+                })?
+            {
+                continue;
+            }
+            self.update_function(resolve, func, span)
+                .with_context(|| format!("failed to update function `{}`", func.name))?;
+        }
+
+        // Filter out all of the existing functions in interface which fail the
+        // `include_stability()` check, as they shouldn't be available.
+        for (name, func) in mem::take(&mut iface.functions) {
+            if resolve.include_stability(&func.stability, iface_pkg_id, None)? {
+                iface.functions.insert(name, func);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn update_function(
+        &mut self,
+        resolve: &mut Resolve,
+        func: &mut Function,
+        span: Option<Span>,
+    ) -> Result<()> {
+        if let Some(id) = func.kind.resource_mut() {
+            self.update_type_id(id, span)?;
+        }
+        for (_, ty) in func.params.iter_mut() {
+            self.update_ty(resolve, ty, span)?;
+        }
+        if let Some(ty) = &mut func.result {
+            self.update_ty(resolve, ty, span)?;
+        }
+
+        if let Some(ty) = &func.result {
+            if self.type_has_borrow(resolve, ty) {
+                match span {
+                    Some(span) => {
+                        bail!(Error::new(
+                            span,
+                            format!(
+                                "function returns a type which contains \
+                                 a `borrow<T>` which is not supported"
+                            )
+                        ))
+                    }
+                    None => unreachable!(),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn update_world(
+        &mut self,
+        world: &mut World,
+        resolve: &mut Resolve,
+        pkg_id: &PackageId,
+        spans: &WorldSpan,
+    ) -> Result<Vec<(WorldKey, Span)>> {
+        assert_eq!(world.imports.len(), spans.imports.len());
+        assert_eq!(world.exports.len(), spans.exports.len());
+
+        // Rewrite imports/exports with their updated versions. Note that this
+        // may involve updating the key of the imports/exports maps so this
+        // starts by emptying them out and then everything is re-inserted.
+        let mut item_spans = Vec::new();
+        let imports = mem::take(&mut world.imports).into_iter();
+        let imports = imports.zip(&spans.imports).map(|p| (p, true));
+        let exports = mem::take(&mut world.exports).into_iter();
+        let exports = exports.zip(&spans.exports).map(|p| (p, false));
+        for (((mut name, mut item), span), import) in imports.chain(exports) {
+            // Update the `id` eagerly here so `item.stability(..)` below
+            // works.
+            if let WorldItem::Type(id) = &mut item {
+                *id = self.map_type(*id, Some(*span))?;
+            }
+            let stability = item.stability(resolve);
+            if !resolve
+                .include_stability(stability, pkg_id, Some(*span))
+                .with_context(|| format!("failed to process world item in `{}`", world.name))?
+            {
+                continue;
+            }
+            self.update_world_key(&mut name, Some(*span))?;
+            match &mut item {
+                WorldItem::Interface { id, .. } => {
+                    *id = self.map_interface(*id, Some(*span))?;
+                }
+                WorldItem::Function(f) => {
+                    self.update_function(resolve, f, Some(*span))?;
+                }
+                WorldItem::Type(_) => {
+                    // already mapped above
+                }
+            }
+
+            let dst = if import {
+                &mut world.imports
+            } else {
+                &mut world.exports
+            };
+            item_spans.push((name.clone(), *span));
+            let prev = dst.insert(name, item);
+            assert!(prev.is_none());
+        }
+
+        Ok(item_spans)
+    }
+
+    fn process_world_includes(
+        &self,
+        id: WorldId,
+        resolve: &mut Resolve,
+        pkg_id: &PackageId,
+        spans: &WorldSpan,
+    ) -> Result<()> {
+        let world = &mut resolve.worlds[id];
+        // Resolve all `include` statements of the world which will add more
+        // entries to the imports/exports list for this world.
+        assert_eq!(world.includes.len(), spans.includes.len());
+        let includes = mem::take(&mut world.includes);
+        let include_names = mem::take(&mut world.include_names);
+        // Items defined by the world itself, which take precedence over
+        // items of the same name from included worlds.
+        let own = (
+            world.imports.keys().cloned().collect::<HashSet<_>>(),
+            world.exports.keys().cloned().collect::<HashSet<_>>(),
+        );
+        for (((stability, include_world), span), names) in includes
+            .into_iter()
+            .zip(&spans.includes)
+            .zip(&include_names)
+        {
+            if !resolve
+                .include_stability(&stability, pkg_id, Some(*span))
+                .with_context(|| {
+                    format!(
+                        "failed to process feature gate for included world [{}] in package [{}]",
+                        resolve.worlds[include_world].name.as_str(),
+                        resolve.packages[*pkg_id].name
+                    )
+                })?
+            {
+                continue;
+            }
+            self.resolve_include(id, include_world, names, &own, *span, pkg_id, resolve)?;
+        }
+
+        Ok(())
+    }
+
+    fn update_world_key(&self, key: &mut WorldKey, span: Option<Span>) -> Result<()> {
+        match key {
+            WorldKey::Name(_) => {}
+            WorldKey::Interface(id) => {
+                *id = self.map_interface(*id, span)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn resolve_include(
+        &self,
+        id: WorldId,
+        include_world_id_orig: WorldId,
+        names: &[IncludeName],
+        (own_imports, own_exports): &(HashSet<WorldKey>, HashSet<WorldKey>),
+        span: Span,
+        pkg_id: &PackageId,
+        resolve: &mut Resolve,
+    ) -> Result<()> {
+        let world = &resolve.worlds[id];
+        let include_world_id = self.map_world(include_world_id_orig, Some(span))?;
+        let include_world = resolve.worlds[include_world_id].clone();
+        let mut names_ = names.to_owned();
+        let is_external_include = world.package != include_world.package;
+
+        // remove all imports and exports that match the names we're including
+        for import in include_world.imports.iter() {
+            self.remove_matching_name(import, &mut names_);
+        }
+        for export in include_world.exports.iter() {
+            self.remove_matching_name(export, &mut names_);
+        }
+        if !names_.is_empty() {
+            bail!(Error::new(
+                span,
+                format!(
+                    "no import or export kebab-name `{}`. Note that an ID does not support renaming",
+                    names_[0].name
+                ),
+            ));
+        }
+
+        let mut cloner = clone::Cloner::new(
+            resolve,
+            TypeOwner::World(if is_external_include {
+                include_world_id
+            } else {
+                include_world_id
+                // include_world_id_orig
+            }),
+            TypeOwner::World(id),
+        );
+        cloner.new_package = Some(*pkg_id);
+
+        // copy the imports and exports from the included world into the current world
+        let mut overrides = Vec::new();
+        for import in include_world.imports.iter() {
+            let overridden = self.resolve_include_item(
+                &mut cloner,
+                names,
+                |resolve| &mut resolve.worlds[id].imports,
+                own_imports,
+                import,
+                span,
+                "import",
+                is_external_include,
+            )?;
+            if let Some(name) = overridden {
+                overrides.push((name, WorldItemKind::Import));
+            }
+        }
+
+        for export in include_world.exports.iter() {
+            let overridden = self.resolve_include_item(
+                &mut cloner,
+                names,
+                |resolve| &mut resolve.worlds[id].exports,
+                own_exports,
+                export,
+                span,
+                "export",
+                is_external_include,
+            )?;
+            if let Some(name) = overridden {
+                overrides.push((name, WorldItemKind::Export));
+            }
+        }
+
+        if !overrides.is_empty() {
+            resolve
+                .include_overrides
+                .entry(id)
+                .or_default()
+                .extend(overrides.into_iter().map(|(name, kind)| Override {
+                    name,
+                    kind,
+                    included_world: include_world_id,
+                }));
+        }
+        Ok(())
+    }
+
+    fn resolve_include_item(
+        &self,
+        cloner: &mut clone::Cloner<'_>,
+        names: &[IncludeName],
+        get_items: impl Fn(&mut Resolve) -> &mut IndexMap<WorldKey, WorldItem>,
+        own: &HashSet<WorldKey>,
+        item: (&WorldKey, &WorldItem),
+        span: Span,
+        item_type: &str,
+        is_external_include: bool,
+    ) -> Result<Option<String>> {
+        match item.0 {
+            WorldKey::Name(n) => {
+                let n = if let Some(found) = names
+                    .into_iter()
+                    .find(|include_name| include_name.name == n.clone())
+                {
+                    found.as_.clone()
+                } else {
+                    n.clone()
+                };
+
+                // When the `with` option to the `include` directive is
+                // specified and is used to rename a function that means that
+                // the function's own original name needs to be updated, so
+                // reflect the change not only in the world key but additionally
+                // in the function itself.
+                let mut new_item = item.1.clone();
+                let key = WorldKey::Name(n.clone());
+                cloner.world_item(&key, &mut new_item);
+                match &mut new_item {
+                    WorldItem::Function(f) => f.name = n.clone(),
+                    WorldItem::Type(id) => cloner.resolve.types[*id].name = Some(n.clone()),
+                    WorldItem::Interface { .. } => {}
+                }
+
+                let prev = get_items(cloner.resolve).insert(key, new_item);
+                if prev.is_some() {
+                    bail!(Error::new(
+                        span,
+                        format!("{item_type} of `{n}` shadows previously {item_type}ed items"),
+                    ))
+                }
+            }
+            key @ WorldKey::Interface(_) => {
+                // The including world's own import or export of an interface
+                // takes precedence over the included one, which is dropped.
+                let overridden = own.contains(key);
+                let prev = get_items(cloner.resolve)
+                    .entry(key.clone())
+                    .or_insert(item.1.clone());
+                match (&item.1, prev) {
+                    (
+                        WorldItem::Interface {
+                            id: aid,
+                            stability: astability,
+                        },
+                        WorldItem::Interface {
+                            id: bid,
+                            stability: bstability,
+                        },
+                    ) => {
+                        assert_eq!(*aid, *bid);
+                        merge_include_stability(astability, bstability, is_external_include)?;
+                    }
+                    (WorldItem::Interface { .. }, _) => unreachable!(),
+                    (WorldItem::Function(_), _) => unreachable!(),
+                    (WorldItem::Type(_), _) => unreachable!(),
+                }
+                if overridden {
+                    return Ok(Some(cloner.resolve.name_world_key(key)));
+                }
+            }
+        };
+
+        Ok(None)
+    }
+
+    fn remove_matching_name(&self, item: (&WorldKey, &WorldItem), names: &mut Vec<IncludeName>) {
+        match item.0 {
+            WorldKey::Name(n) => {
+                names.retain(|name| name.name != n.clone());
+            }
+            _ => {}
+        }
+    }
+
+    fn type_has_borrow(&mut self, resolve: &Resolve, ty: &Type) -> bool {
+        let id = match ty {
+            Type::Id(id) => *id,
+            _ => return false,
+        };
+
+        if let Some(Some(has_borrow)) = self.type_has_borrow.get(id.index()) {
+            return *has_borrow;
+        }
+
+        let result = self.typedef_has_borrow(resolve, &resolve.types[id]);
+        if self.type_has_borrow.len() <= id.index() {
+            self.type_has_borrow.resize(id.index() + 1, None);
+        }
+        self.type_has_borrow[id.index()] = Some(result);
+        result
+    }
+
+    fn typedef_has_borrow(&mut self, resolve: &Resolve, ty: &TypeDef) -> bool {
+        match &ty.kind {
+            TypeDefKind::Type(t) => self.type_has_borrow(resolve, t),
+            TypeDefKind::Variant(v) => v
+                .cases
+                .iter()
+                .filter_map(|case| case.ty.as_ref())
+                .any(|ty| self.type_has_borrow(resolve, ty)),
+            TypeDefKind::Handle(Handle::Borrow(_)) => true,
+            TypeDefKind::Handle(Handle::Own(_)) => false,
+            TypeDefKind::Resource => false,
+            TypeDefKind::Record(r) => r
+                .fields
+                .iter()
+                .any(|case| self.type_has_borrow(resolve, &case.ty)),
+            TypeDefKind::Flags(_) => false,
+            TypeDefKind::Tuple(t) => t.types.iter().any(|t| self.type_has_borrow(resolve, t)),
+            TypeDefKind::Enum(_) => false,
+            TypeDefKind::List(ty)
+            | TypeDefKind::FixedSizeList(ty, ..)
+            | TypeDefKind::Future(Some(ty))
+            | TypeDefKind::Stream(Some(ty))
+            | TypeDefKind::Option(ty) => self.type_has_borrow(resolve, ty),
+            TypeDefKind::Result(r) => [&r.ok, &r.err]
+                .iter()
+                .filter_map(|t| t.as_ref())
+                .any(|t| self.type_has_borrow(resolve, t)),
+            TypeDefKind::Future(None) | TypeDefKind::Stream(None) => false,
+            TypeDefKind::Unknown => unreachable!(),
+        }
+    }
+}
+
+struct MergeMap<'a> {
+    /// A map of package ids in `from` to those in `into` for those that are
+    /// found to be equivalent.
+    package_map: HashMap<PackageId, PackageId>,
+
+    /// A map of interface ids in `from` to those in `into` for those that are
+    /// found to be equivalent.
+    interface_map: HashMap<InterfaceId, InterfaceId>,
+
+    /// A map of type ids in `from` to those in `into` for those that are
+    /// found to be equivalent.
+    type_map: HashMap<TypeId, TypeId>,
+
+    /// A map of world ids in `from` to those in `into` for those that are
+    /// found to be equivalent.
+    world_map: HashMap<WorldId, WorldId>,
+
+    /// A list of documents that need to be added to packages in `into`.
+    ///
+    /// The elements here are:
+    ///
+    /// * The name of the interface/world
+    /// * The ID within `into` of the package being added to
+    /// * The ID within `from` of the item being added.
+    interfaces_to_add: Vec<(String, PackageId, InterfaceId)>,
+    worlds_to_add: Vec<(String, PackageId, WorldId)>,
+
+    /// Which `Resolve` is being merged from.
+    from: &'a Resolve,
+
+    /// Which `Resolve` is being merged into.
+    into: &'a Resolve,
+}
+
+impl<'a> MergeMap<'a> {
+    fn new(from: &'a Resolve, into: &'a Resolve) -> MergeMap<'a> {
+        MergeMap {
+            package_map: Default::default(),
+            interface_map: Default::default(),
+            type_map: Default::default(),
+            world_map: Default::default(),
+            interfaces_to_add: Default::default(),
+            worlds_to_add: Default::default(),
+            from,
+            into,
+        }
+    }
+
+    fn build(&mut self) -> Result<()> {
+        for from_id in self.from.topological_packages() {
+            let from = &self.from.packages[from_id];
+            let into_id = match self.into.package_names.get(&from.name) {
+                Some(id) => *id,
+
+                // This package, according to its name and url, is not present
+                // in `self` so it needs to get added below.
+                None => {
+                    log::trace!("adding unique package {}", from.name);
+                    continue;
+                }
+            };
+            log::trace!("merging duplicate package {}", from.name);
+
+            self.build_package(from_id, into_id).with_context(|| {
+                format!("failed to merge package `{}` into existing copy", from.name)
+            })?;
+        }
+
+        Ok(())
+    }
+
+    fn build_package(&mut self, from_id: PackageId, into_id: PackageId) -> Result<()> {
+        let prev = self.package_map.insert(from_id, into_id);
+        assert!(prev.is_none());
+
+        let from = &self.from.packages[from_id];
+        let into = &self.into.packages[into_id];
+
+        // If an interface is present in `from_id` but not present in `into_id`
+        // then it can be copied over wholesale. That copy is scheduled to
+        // happen within the `self.interfaces_to_add` list.
+        for (name, from_interface_id) in from.interfaces.iter() {
+            let into_interface_id = match into.interfaces.get(name) {
+                Some(id) => *id,
+                None => {
+                    log::trace!("adding unique interface {name}");
+                    self.interfaces_to_add
+                        .push((name.clone(), into_id, *from_interface_id));
+                    continue;
+                }
+            };
+
+            log::trace!("merging duplicate interfaces {name}");
+            self.build_interface(*from_interface_id, into_interface_id)
+                .with_context(|| format!("failed to merge interface `{name}`"))?;
+        }
+
+        for (name, from_world_id) in from.worlds.iter() {
+            let into_world_id = match into.worlds.get(name) {
+                Some(id) => *id,
+                None => {
+                    log::trace!("adding unique world {name}");
+                    self.worlds_to_add
+                        .push((name.clone(), into_id, *from_world_id));
+                    continue;
+                }
+            };
+
+            log::trace!("merging duplicate worlds {name}");
+            self.build_world(*from_world_id, into_world_id)
+                .with_context(|| format!("failed to merge world `{name}`"))?;
+        }
+
+        Ok(())
+    }
+
+    fn build_interface(&mut self, from_id: InterfaceId, into_id: InterfaceId) -> Result<()> {
+        let prev = self.interface_map.insert(from_id, into_id);
+        assert!(prev.is_none());
+
+        let from_interface = &self.from.interfaces[from_id];
+        let into_interface = &self.into.interfaces[into_id];
+
+        // Unlike documents/interfaces above if an interface in `from`
+        // differs from the interface in `into` then that's considered an
+        // error. Changing interfaces can reflect changes in imports/exports
+        // which may not be expected so it's currently required that all
+        // interfaces, when merged, exactly match.
+        //
+        // One case to consider here, for example, is that if a world in
+        // `into` exports the interface `into_id` then if `from_id` were to
+        // add more items into `into` then it would unexpectedly require more
+        // items to be exported which may not work. In an import context this
+        // might work since it's "just more items available for import", but
+        // for now a conservative route of "interfaces must match" is taken.
+
+        for (name, from_type_id) in from_interface.types.iter() {
+            let into_type_id = *into_interface
+                .types
+                .get(name)
+                .ok_or_else(|| anyhow!("expected type `{name}` to be present"))?;
+            let prev = self.type_map.insert(*from_type_id, into_type_id);
+            assert!(prev.is_none());
+
+            self.build_type_id(*from_type_id, into_type_id)
+                .with_context(|| format!("mismatch in type `{name}`"))?;
+        }
+
+        for (name, from_func) in from_interface.functions.iter() {
+            let into_func = match into_interface.functions.get(name) {
+                Some(func) => func,
+                None => bail!("expected function `{name}` to be present"),
+            };
+            self.build_function(from_func, into_func)
+                .with_context(|| format!("mismatch in function `{name}`"))?;
+        }
+
+        Ok(())
+    }
+
+    fn build_type_id(&mut self, from_id: TypeId, into_id: TypeId) -> Result<()> {
+        // FIXME: ideally the types should be "structurally
+        // equal" but that's not trivial to do in the face of
+        // resources.
+        let _ = from_id;
+        let _ = into_id;
+        Ok(())
+    }
+
+    fn build_type(&mut self, from_ty: &Type, into_ty: &Type) -> Result<()> {
+        match (from_ty, into_ty) {
+            (Type::Id(from), Type::Id(into)) => {
+                self.build_type_id(*from, *into)?;
+            }
+            (from, into) if from != into => bail!(
+                "different kinds of types: expected `{}`, found `{}`",
+                self.into.type_to_string(into),
+                self.from.type_to_string(from),
+            ),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn build_function(&mut self, from_func: &Function, into_func: &Function) -> Result<()> {
+        if from_func.name != into_func.name {
+            bail!(
+                "different function names `{}` and `{}`",
+                from_func.name,
+                into_func.name
+            );
+        }
+        match (&from_func.kind, &into_func.kind) {
+            (FunctionKind::Freestanding, FunctionKind::Freestanding) => {}
+            (FunctionKind::AsyncFreestanding, FunctionKind::AsyncFreestanding) => {}
+
+            (FunctionKind::Method(from), FunctionKind::Method(into))
+            | (FunctionKind::Static(from), FunctionKind::Static(into))
+            | (FunctionKind::AsyncMethod(from), FunctionKind::AsyncMethod(into))
+            | (FunctionKind::AsyncStatic(from), FunctionKind::AsyncStatic(into))
+            | (FunctionKind::Constructor(from), FunctionKind::Constructor(into)) => {
+                self.build_type_id(*from, *into)
+                    .context("different function kind types")?;
+            }
+
+            (FunctionKind::Method(_), _)
+            | (FunctionKind::Constructor(_), _)
+            | (FunctionKind::Static(_), _)
+            | (FunctionKind::Freestanding, _)
+            | (FunctionKind::AsyncFreestanding, _)
+            | (FunctionKind::AsyncMethod(_), _)
+            | (FunctionKind::AsyncStatic(_), _) => {
+                bail!("different function kind types")
+            }
+        }
+
+        if from_func.params.len() != into_func.params.len() {
+            bail!("different number of function parameters");
+        }
+        for ((from_name, from_ty), (into_name, into_ty)) in
+            from_func.params.iter().zip(&into_func.params)
+        {
+            if from_name != into_name {
+                bail!("different function parameter names: {from_name} != {into_name}");
+            }
+            self.build_type(from_ty, into_ty)
+                .with_context(|| format!("different function parameter types for `{from_name}`"))?;
+        }
+        match (&from_func.result, &into_func.result) {
+            (Some(from_ty), Some(into_ty)) => {
+                self.build_type(from_ty, into_ty)
+                    .context("different function result types")?;
+            }
+            (None, None) => {}
+            (Some(_), None) | (None, Some(_)) => bail!("different number of function results"),
+        }
+        Ok(())
+    }
+
+    fn build_world(&mut self, from_id: WorldId, into_id: WorldId) -> Result<()> {
+        let prev = self.world_map.insert(from_id, into_id);
+        assert!(prev.is_none());
+
+        let from_world = &self.from.worlds[from_id];
+        let into_world = &self.into.worlds[into_id];
+
+        // Same as interfaces worlds are expected to exactly match to avoid
+        // unexpectedly changing a particular component's view of imports and
+        // exports.
+        //
+        // FIXME: this should probably share functionality with
+        // `Resolve::merge_worlds` to support adding imports but not changing
+        // exports.
+
+        if from_world.imports.len() != into_world.imports.len() {
+            bail!("world contains different number of imports than expected");
+        }
+        if from_world.exports.len() != into_world.exports.len() {
+            bail!("world contains different number of exports than expected");
+        }
+
+        for (from_name, from) in from_world.imports.iter() {
+            let into_name = MergeMap::map_name(from_name, &self.interface_map);
+            let name_str = self.from.name_world_key(from_name);
+            let into = into_world
+                .imports
+                .get(&into_name)
+                .ok_or_else(|| anyhow!("import `{name_str}` not found in target world"))?;
+            self.match_world_item(from, into)
+                .with_context(|| format!("import `{name_str}` didn't match target world"))?;
+        }
+
+        for (from_name, from) in from_world.exports.iter() {
+            let into_name = MergeMap::map_name(from_name, &self.interface_map);
+            let name_str = self.from.name_world_key(from_name);
+            let into = into_world
+                .exports
+                .get(&into_name)
+                .ok_or_else(|| anyhow!("export `{name_str}` not found in target world"))?;
+            self.match_world_item(from, into)
+                .with_context(|| format!("export `{name_str}` didn't match target world"))?;
+        }
+
+        Ok(())
+    }
+
+    fn map_name(
+        from_name: &WorldKey,
+        interface_map: &HashMap<InterfaceId, InterfaceId>,
+    ) -> WorldKey {
+        match from_name {
+            WorldKey::Name(s) => WorldKey::Name(s.clone()),
+            WorldKey::Interface(id) => {
+                WorldKey::Interface(interface_map.get(id).copied().unwrap_or(*id))
+            }
+        }
+    }
+
+    fn match_world_item(&mut self, from: &WorldItem, into: &WorldItem) -> Result<()> {
+        match (from, into) {
+            (WorldItem::Interface { id: from, .. }, WorldItem::Interface { id: into, .. }) => {
+                match (
+                    &self.from.interfaces[*from].name,
+                    &self.into.interfaces[*into].name,
+                ) {
+                    // If one interface is unnamed then they must both be
+                    // unnamed and they must both have the same structure for
+                    // now.
+                    (None, None) => self.build_interface(*from, *into)?,
+
+                    // Otherwise both interfaces must be named and they must
+                    // have been previously found to be equivalent. Note that
+                    // if either is unnamed it won't be present in
+                    // `interface_map` so this'll return an error.
+                    _ => {
+                        if self.interface_map.get(&from) != Some(&into) {
+                            bail!("interfaces are not the same");
+                        }
+                    }
+                }
+            }
+            (WorldItem::Function(from), WorldItem::Function(into)) => {
+                let _ = (from, into);
+                // FIXME: should assert an check that `from` structurally
+                // matches `into`
+            }
+            (WorldItem::Type(from), WorldItem::Type(into)) => {
+                // FIXME: should assert an check that `from` structurally
+                // matches `into`
+                let prev = self.type_map.insert(*from, *into);
+                assert!(prev.is_none());
+            }
+
+            (WorldItem::Interface { .. }, _)
+            | (WorldItem::Function(_), _)
+            | (WorldItem::Type(_), _) => {
+                bail!("world items do not have the same type")
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns `map` with the key `old` renamed to `new`, keeping its position.
+fn rename_key<T>(map: IndexMap<String, T>, old: &str, new: &str) -> IndexMap<String, T> {
+    map.into_iter()
+        .map(|(name, value)| {
+            if name == old {
+                (new.to_string(), value)
+            } else {
+                (name, value)
+            }
+        })
+        .collect()
+}
+
+/// Updates stability annotations when merging `from` into `into`.
+///
+/// This is done to keep up-to-date stability information if possible.
+/// Components for example don't carry stability information but WIT does so
+/// this tries to move from "unknown" to stable/unstable if possible.
+fn update_stability(from: &Stability, into: &mut Stability) -> Result<()> {
+    // If `from` is unknown or the two stability annotations are equal then
+    // there's nothing to do here.
+    if from == into || from.is_unknown() {
+        return Ok(());
+    }
+    // Otherwise if `into` is unknown then inherit the stability listed in
+    // `from`.
+    if into.is_unknown() {
+        *into = from.clone();
+        return Ok(());
+    }
+
+    // Failing all that this means that the two attributes are different so
+    // generate an error.
+    bail!("mismatch in stability from '{:?}' to '{:?}'", from, into)
+}
+
+fn merge_include_stability(
+    from: &Stability,
+    into: &mut Stability,
+    is_external_include: bool,
+) -> Result<()> {
+    if is_external_include && from.is_stable() {
+        log::trace!("dropped stability from external package");
+        *into = Stability::Unknown;
+        return Ok(());
+    }
+
+    return update_stability(from, into);
+}
+
+/// An error that can be returned during "world elaboration" during various
+/// [`Resolve`] operations.
+///
+/// Methods on [`Resolve`] which mutate its internals, such as
+/// [`Resolve::push_dir`] or [`Resolve::importize`] can fail if `world` imports
+/// in WIT packages are invalid. This error indicates one of these situations
+/// where an invalid dependency graph between imports and exports are detected.
+///
+/// Note that at this time this error is subtle and not easy to understand, and
+/// work needs to be done to explain this better and additionally provide a
+/// better error message. For now though this type enables callers to test for
+/// the exact kind of error emitted.
+#[derive(Debug, Clone)]
+pub struct InvalidTransitiveDependency(String);
+
+impl fmt::Display for InvalidTransitiveDependency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "interface `{}` transitively depends on an interface in \
+             incompatible ways",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for InvalidTransitiveDependency {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Capability, CapabilityCategory, ConflictKind, DuplicatePolicy, FunctionKind, ItemChange,
+        ItemRef, ItemShape, LiftLowerAbi, Mangling, ManglingAndAbi, NameError, Override, PackageId,
+        PackageName, Recursion, Resolve, SizeAlign, Stability, Type, TypeChange, TypeDefKind,
+        TypeId, TypeOwner, UnresolvedPackageGroup, World, WorldItem, WorldItemKind, WorldKey,
+        WorldNotFoundError,
+    };
+    use anyhow::Result;
+    use indexmap::IndexMap;
+    use semver::Version;
+    use std::borrow::Cow;
+
+    #[test]
+    fn select_world() -> Result<()> {
+        let mut resolve = Resolve::default();
+        resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar@0.1.0;
+
+                world foo {}
+            "#,
+        )?;
+        resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:baz@0.1.0;
+
+                world foo {}
+            "#,
+        )?;
+        resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:baz@0.2.0;
+
+                world foo {}
+            "#,
+        )?;
+
+        let dummy = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:dummy;
+
+                world foo {}
+            "#,
+        )?;
+
+        assert!(resolve.select_world(dummy, None).is_ok());
+        assert!(resolve.select_world(dummy, Some("xx")).is_err());
+        assert!(resolve.select_world(dummy, Some("")).is_err());
+        assert!(resolve.select_world(dummy, Some("foo:bar/foo")).is_ok());
+        assert!(
+            resolve
+                .select_world(dummy, Some("foo:bar/foo@0.1.0"))
+                .is_ok()
+        );
+        assert!(resolve.select_world(dummy, Some("foo:baz/foo")).is_err());
+        assert!(
+            resolve
+                .select_world(dummy, Some("foo:baz/foo@0.1.0"))
+                .is_ok()
+        );
+        assert!(
+            resolve
+                .select_world(dummy, Some("foo:baz/foo@0.2.0"))
+                .is_ok()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn push_with_policy() -> Result<()> {
+        let wit = r#"
+            package foo:bar@0.1.0;
+
+            interface a {
+                type t = u32;
+                f: func(x: t) -> string;
+            }
+
+            world w {
+                import a;
+            }
+        "#;
+        let conflicting = r#"
+            package foo:bar@0.1.0;
+
+            interface a {
+                type t = u32;
+                f: func(x: t);
             }
-            true
-        }
+
+            world w {
+                import a;
+            }
+        "#;
+
+        let mut resolve = Resolve::default();
+        let original = resolve.push_str("test.wit", wit)?;
+        let push = |resolve: &mut Resolve, wit: &str, policy: DuplicatePolicy| {
+            let group = UnresolvedPackageGroup::parse("test.wit", wit)?;
+            resolve.push_with_policy(group.main, &group.source_map, policy)
+        };
+
+        assert!(push(&mut resolve, wit, DuplicatePolicy::Error).is_err());
+
+        let packages = resolve.packages.len();
+        let id = push(&mut resolve, wit, DuplicatePolicy::MergeIfIdentical)?;
+        assert_eq!(id, original);
+        assert_eq!(resolve.packages.len(), packages);
+
+        let err = push(&mut resolve, conflicting, DuplicatePolicy::MergeIfIdentical).unwrap_err();
+        assert!(format!("{err:?}").contains("function `f`"), "{err:?}");
+        assert_eq!(resolve.packages.len(), packages);
+
+        let replaced = push(&mut resolve, conflicting, DuplicatePolicy::Replace)?;
+        assert_ne!(replaced, original);
+        assert_eq!(
+            resolve.package_names.values().copied().collect::<Vec<_>>(),
+            [replaced]
+        );
+        Ok(())
     }
 
-    /// Remove duplicate imports from a world if they import from the same
-    /// interface with semver-compatible versions.
-    ///
-    /// This will merge duplicate interfaces present at multiple versions in
-    /// both a world by selecting the larger version of the two interfaces. This
-    /// requires that the interfaces are indeed semver-compatible and it means
-    /// that some imports might be removed and replaced. Note that this is only
-    /// done within a single semver track, for example the world imports 0.2.0
-    /// and 0.2.1 then the result afterwards will be that it imports
-    /// 0.2.1. If, however, 0.3.0 where imported then the final result would
-    /// import both 0.2.0 and 0.3.0.
-    pub fn merge_world_imports_based_on_semver(&mut self, world_id: WorldId) -> Result<()> {
-        let world = &self.worlds[world_id];
+    #[test]
+    fn union_worlds() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
 
-        // The first pass here is to build a map of "semver tracks" where they
-        // key is per-interface and the value is the maximal version found in
-        // that semver-compatible-track plus the interface which is the maximal
-        // version.
-        //
-        // At the same time a `to_remove` set is maintained to remember what
-        // interfaces are being removed from `from` and `into`. All of
-        // `to_remove` are placed with a known other version.
-        let mut semver_tracks = HashMap::new();
-        let mut to_remove = HashSet::new();
-        for (key, _) in world.imports.iter() {
-            let iface_id = match key {
-                WorldKey::Interface(id) => *id,
-                WorldKey::Name(_) => continue,
-            };
-            let (track, version) = match self.semver_track(iface_id) {
-                Some(track) => track,
-                None => continue,
-            };
-            log::debug!(
-                "{} is on track {}/{}",
-                self.id_of(iface_id).unwrap(),
-                track.0,
-                track.1,
-            );
-            match semver_tracks.entry(track.clone()) {
-                hash_map::Entry::Vacant(e) => {
-                    e.insert((version, iface_id));
+                interface x {
+                    type t = u32;
                 }
-                hash_map::Entry::Occupied(mut e) => match version.cmp(&e.get().0) {
-                    Ordering::Greater => {
-                        to_remove.insert(e.get().1);
-                        e.insert((version, iface_id));
+                interface y {}
+
+                world a {
+                    import x;
+                    export f: func(a: u32);
+                }
+
+                world b {
+                    import x;
+                    import y;
+                    export f: func(a: u32);
+                    export g: func();
+                }
+
+                world c {
+                    export f: func(a: string);
+                }
+            "#,
+        )?;
+        let world = |resolve: &Resolve, name: &str| resolve.packages[pkg].worlds[name];
+        let (a, b, c) = (
+            world(&resolve, "a"),
+            world(&resolve, "b"),
+            world(&resolve, "c"),
+        );
+
+        let ab = resolve.union_worlds(a, b, "ab")?;
+        assert_eq!(world(&resolve, "ab"), ab);
+        let names = |items: &IndexMap<WorldKey, WorldItem>| {
+            items
+                .keys()
+                .map(|key| resolve.name_world_key(key))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(&resolve.worlds[ab].imports),
+            ["foo:bar/x", "foo:bar/y"]
+        );
+        assert_eq!(names(&resolve.worlds[ab].exports), ["f", "g"]);
+        assert_eq!(resolve.worlds[a].exports.len(), 1);
+
+        let err = resolve.union_worlds(a, c, "ac").unwrap_err();
+        assert!(
+            format!("{err:?}").contains("failed to merge world export f"),
+            "{err:?}"
+        );
+        assert!(!resolve.packages[pkg].worlds.contains_key("ac"));
+
+        assert!(resolve.union_worlds(a, b, "ab").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn interface_functions_in_source_order() -> Result<()> {
+        let mut resolve = Resolve::default();
+        resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
+
+                interface i {
+                    b: func();
+                    resource r {
+                        m: func();
                     }
-                    Ordering::Equal => {}
-                    Ordering::Less => {
-                        to_remove.insert(iface_id);
+                    a: func();
+                }
+            "#,
+        )?;
+        let (id, _) = resolve.interfaces.iter().next().unwrap();
+        let names = |resolve: &Resolve, id| {
+            resolve
+                .interface_functions_in_source_order(id)
+                .iter()
+                .map(|f| f.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&resolve, id), ["b", "[method]r.m", "a"]);
+
+        // Renamed and copied functions keep their position.
+        resolve.rename_function(id, "a", "z")?;
+        assert_eq!(names(&resolve, id), ["b", "[method]r.m", "z"]);
+        let subset = resolve.subset_interface(id, &["z", "b"])?;
+        resolve.interfaces[subset].functions.reverse();
+        assert_eq!(names(&resolve, subset), ["b", "z"]);
+
+        let mut extra = resolve.interfaces[id].functions["z"].clone();
+        extra.name = "c".to_string();
+        let functions = &mut resolve.interfaces[id].functions;
+        functions.reverse();
+        functions.insert_before(0, "c".to_string(), extra);
+        assert_eq!(names(&resolve, id), ["b", "[method]r.m", "z", "c"]);
+        Ok(())
+    }
+
+    #[test]
+    fn normalize_char_lists() -> Result<()> {
+        let mut resolve = Resolve::default();
+        resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
+
+                interface i {
+                    type chars = list<char>;
+                    record r {
+                        a: list<char>,
+                        b: string,
+                        c: list<u8>,
                     }
-                },
-            }
-        }
+                    f: func(x: list<char>, y: chars) -> option<list<char>>;
+                }
+            "#,
+        )?;
+        let (_, iface) = resolve.interfaces.iter().next().unwrap();
+        let f = iface.functions["f"].clone();
+        let r = iface.types["r"];
+        let chars = iface.types["chars"];
+        assert!(resolve.is_stringlike(&f.params[0].1));
+        assert!(resolve.is_stringlike(&f.params[1].1));
+        assert!(resolve.is_stringlike(&Type::String));
+        assert!(!resolve.is_stringlike(&Type::Char));
+
+        resolve.normalize_char_lists();
+
+        let f = &resolve.interfaces.iter().next().unwrap().1.functions["f"];
+        assert_eq!(f.params[0].1, Type::String);
+        assert_eq!(f.params[1].1, Type::Id(chars));
+        assert_eq!(resolve.types[chars].kind, TypeDefKind::Type(Type::String));
+        let Some(Type::Id(result)) = f.result else {
+            panic!()
+        };
+        assert_eq!(
+            resolve.types[result].kind,
+            TypeDefKind::Option(Type::String)
+        );
+        let TypeDefKind::Record(r) = &resolve.types[r].kind else {
+            panic!()
+        };
+        assert_eq!(r.fields[0].ty, Type::String);
+        assert_eq!(r.fields[1].ty, Type::String);
+        assert!(!resolve.is_stringlike(&r.fields[2].ty));
+        Ok(())
+    }
 
-        // Build a map of "this interface is replaced with this interface" using
-        // the results of the loop above.
-        let mut replacements = HashMap::new();
-        for id in to_remove {
-            let (track, _) = self.semver_track(id).unwrap();
-            let (_, latest) = semver_tracks[&track];
-            let prev = replacements.insert(id, latest);
-            assert!(prev.is_none());
-        }
+    #[test]
+    fn unused_types() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
+
+                interface types {
+                    type used-by-other = u32;
+                    type used-by-func = u8;
+                    record used-by-field { x: used-by-other }
+                    type dead = string;
+                    resource r;
+                }
 
-        // Validate that `merge_world_item` succeeds for merging all removed
-        // interfaces with their replacement. This is a double-check that the
-        // semver version is actually correct and all items present in the old
-        // interface are in the new.
-        for (to_replace, replace_with) in replacements.iter() {
-            self.merge_world_item(
-                &WorldItem::Interface {
-                    id: *to_replace,
-                    stability: Default::default(),
-                },
-                &WorldItem::Interface {
-                    id: *replace_with,
-                    stability: Default::default(),
-                },
-            )
-            .with_context(|| {
-                let old_name = self.id_of(*to_replace).unwrap();
-                let new_name = self.id_of(*replace_with).unwrap();
-                format!(
-                    "failed to upgrade `{old_name}` to `{new_name}`, was \
-                     this semver-compatible update not semver compatible?"
-                )
-            })?;
-        }
+                interface user {
+                    use types.{used-by-field};
+                    f: func(a: used-by-field);
+                }
 
-        for (to_replace, replace_with) in replacements.iter() {
-            log::debug!(
-                "REPLACE {} => {}",
-                self.id_of(*to_replace).unwrap(),
-                self.id_of(*replace_with).unwrap(),
-            );
-        }
+                world w {
+                    use types.{used-by-func};
+                    import g: func() -> used-by-func;
+                }
+            "#,
+        )?;
+        let names = resolve
+            .unused_types(pkg)
+            .into_iter()
+            .map(|id| resolve.types[id].name.clone().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["dead"]);
+        Ok(())
+    }
 
-        // Finally perform the actual transformation of the imports/exports.
-        // Here all imports are removed if they're replaced and otherwise all
-        // imports have their dependencies updated, possibly transitively, to
-        // point to the new interfaces in `replacements`.
-        //
-        // Afterwards exports are additionally updated, but only their
-        // dependencies on imports which were remapped. Exports themselves are
-        // not deduplicated and/or removed.
-        for (key, item) in mem::take(&mut self.worlds[world_id].imports) {
-            if let WorldItem::Interface { id, .. } = item {
-                if replacements.contains_key(&id) {
-                    continue;
+    #[test]
+    fn items_introduced_in() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar@0.2.0;
+
+                @since(version = 0.1.0)
+                interface a {
+                    @since(version = 0.1.0)
+                    type t = u32;
+                    @since(version = 0.2.0)
+                    type u = u64;
+                    @since(version = 0.2.0)
+                    f: func();
+                    g: func();
                 }
-            }
 
-            self.update_interface_deps_of_world_item(&item, &replacements);
+                @since(version = 0.2.0)
+                interface b {}
+
+                @since(version = 0.1.0)
+                world w {
+                    @since(version = 0.1.0)
+                    import a;
+                    @since(version = 0.2.0)
+                    import b;
+                    @since(version = 0.2.0)
+                    export h: func();
+                }
+            "#,
+        )?;
+        let a = resolve.packages[pkg].interfaces["a"];
+        let b = resolve.packages[pkg].interfaces["b"];
+        let w = resolve.packages[pkg].worlds["w"];
+        let t = resolve.interfaces[a].types["t"];
+        let u = resolve.interfaces[a].types["u"];
 
-            let prev = self.worlds[world_id].imports.insert(key, item);
-            assert!(prev.is_none());
-        }
-        for (key, item) in mem::take(&mut self.worlds[world_id].exports) {
-            self.update_interface_deps_of_world_item(&item, &replacements);
-            let prev = self.worlds[world_id].exports.insert(key, item);
-            assert!(prev.is_none());
-        }
+        assert_eq!(
+            resolve.items_introduced_in(pkg, &Version::new(0, 1, 0)),
+            [
+                ItemRef::Interface(a),
+                ItemRef::Type(t),
+                ItemRef::World(w),
+                ItemRef::WorldItem(w, WorldKey::Interface(a)),
+            ]
+        );
+        assert_eq!(
+            resolve.items_introduced_in(pkg, &Version::new(0, 2, 0)),
+            [
+                ItemRef::Type(u),
+                ItemRef::InterfaceFunction(a, "f".to_string()),
+                ItemRef::Interface(b),
+                ItemRef::WorldItem(w, WorldKey::Interface(b)),
+                ItemRef::WorldItem(w, WorldKey::Name("h".to_string())),
+            ]
+        );
+        assert!(
+            resolve
+                .items_introduced_in(pkg, &Version::new(0, 3, 0))
+                .is_empty()
+        );
+        Ok(())
+    }
 
-        // Run through `elaborate_world` to reorder imports as appropriate and
-        // fill anything back in if it's actually required by exports. For now
-        // this doesn't tamper with exports at all. Also note that this is
-        // applied to all worlds in this `Resolve` because interfaces were
-        // modified directly.
-        let ids = self.worlds.iter().map(|(id, _)| id).collect::<Vec<_>>();
-        for world_id in ids {
-            self.elaborate_world(world_id).with_context(|| {
-                let name = &self.worlds[world_id].name;
-                format!(
-                    "failed to elaborate world `{name}` after deduplicating imports \
-                     based on semver"
-                )
-            })?;
-        }
+    #[test]
+    fn generate_version_adapter_world() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let old = resolve.push_str(
+            "old.wit",
+            r#"
+                package foo:bar@0.2.0;
+
+                interface types {
+                    type t = u32;
+                }
+
+                interface i {
+                    use types.{t};
+                    f: func(x: t) -> string;
+                }
+            "#,
+        )?;
+        let new = resolve.push_str(
+            "new.wit",
+            r#"
+                package foo:bar@0.2.1;
+
+                interface types {
+                    type t = u32;
+                }
+
+                interface i {
+                    use types.{t};
+                    f: func(x: t) -> string;
+                    g: func();
+                }
+
+                interface incompatible {
+                    f: func(x: u64) -> string;
+                }
+            "#,
+        )?;
+        let old_i = resolve.packages[old].interfaces["i"];
+        let old_types = resolve.packages[old].interfaces["types"];
+        let new_types = resolve.packages[new].interfaces["types"];
+        let new_i = resolve.packages[new].interfaces["i"];
+
+        let id = resolve.generate_version_adapter_world(old_i, new_i)?;
+        assert_eq!(resolve.packages[new].worlds["i-adapter"], id);
+        let world = &resolve.worlds[id];
+        assert_eq!(world.package, Some(new));
+        assert_eq!(
+            world.imports.keys().collect::<Vec<_>>(),
+            [
+                &WorldKey::Interface(old_types),
+                &WorldKey::Interface(old_i),
+                &WorldKey::Interface(new_types),
+            ]
+        );
+        assert_eq!(
+            world.exports.keys().collect::<Vec<_>>(),
+            [&WorldKey::Interface(new_i)]
+        );
+
+        let incompatible = resolve.packages[new].interfaces["incompatible"];
+        let err = resolve
+            .generate_version_adapter_world(old_i, incompatible)
+            .unwrap_err();
+        assert!(
+            format!("{err:?}").contains("function `f` differs"),
+            "{err:?}"
+        );
+        assert!(
+            !resolve.packages[new]
+                .worlds
+                .contains_key("incompatible-adapter")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn type_recursion() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
+
+                interface i {
+                    resource node {
+                        constructor();
+                        next: func() -> option<own<node>>;
+                    }
 
-        #[cfg(debug_assertions)]
-        self.assert_valid();
+                    resource leaf {
+                        constructor();
+                        get: func() -> u32;
+                    }
+
+                    resource a {
+                        to-b: func() -> b;
+                    }
+                    resource b {
+                        to-a: func() -> list<a>;
+                    }
+                    record uses-a {
+                        a: a,
+                    }
+                }
+            "#,
+        )?;
+        let i = resolve.packages[pkg].interfaces["i"];
+        let ty = |name: &str| resolve.interfaces[i].types[name];
 
+        assert_eq!(resolve.type_recursion(ty("node")), Recursion::SelfRecursive);
+        assert_eq!(resolve.type_recursion(ty("leaf")), Recursion::Acyclic);
+        assert_eq!(
+            resolve.type_recursion(ty("a")),
+            Recursion::MutuallyRecursive(vec![ty("a"), ty("b")])
+        );
+        assert_eq!(
+            resolve.type_recursion(ty("b")),
+            Recursion::MutuallyRecursive(vec![ty("a"), ty("b")])
+        );
+        assert_eq!(resolve.type_recursion(ty("uses-a")), Recursion::Acyclic);
         Ok(())
     }
 
-    fn update_interface_deps_of_world_item(
-        &mut self,
-        item: &WorldItem,
-        replacements: &HashMap<InterfaceId, InterfaceId>,
-    ) {
-        match *item {
-            WorldItem::Type(t) => self.update_interface_dep_of_type(t, &replacements),
-            WorldItem::Interface { id, .. } => {
-                let types = self.interfaces[id]
-                    .types
-                    .values()
-                    .copied()
-                    .collect::<Vec<_>>();
-                for ty in types {
-                    self.update_interface_dep_of_type(ty, &replacements);
+    #[test]
+    fn component_import_shape() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar@0.1.0;
+
+                interface streams {
+                    resource input-stream {
+                        read: func() -> list<u8>;
+                    }
+                    type t = u32;
+                    open: func() -> input-stream;
+                }
+
+                world w {
+                    import streams;
+                    import f: func();
+                    export g: func();
                 }
+            "#,
+        )?;
+        let world = resolve.select_world(pkg, None)?;
+        let shape = resolve.component_import_shape(world);
+        assert_eq!(shape.len(), 1);
+        assert_eq!(
+            shape["foo:bar/streams@0.1.0"],
+            ItemShape {
+                functions: vec!["[method]input-stream.read".to_string(), "open".to_string()],
+                resources: vec!["input-stream".to_string()],
             }
-            WorldItem::Function(_) => {}
-        }
+        );
+        Ok(())
     }
 
-    /// Returns the "semver track" of an interface plus the interface's version.
-    ///
-    /// This function returns `None` if the interface `id` has a package without
-    /// a version. If the version is present, however, the first element of the
-    /// tuple returned is a "semver track" for the specific interface. The
-    /// version listed in `PackageName` will be modified so all
-    /// semver-compatible versions are listed the same way.
-    ///
-    /// The second element in the returned tuple is this interface's package's
-    /// version.
-    fn semver_track(&self, id: InterfaceId) -> Option<((PackageName, String), &Version)> {
-        let iface = &self.interfaces[id];
-        let pkg = &self.packages[iface.package?];
-        let version = pkg.name.version.as_ref()?;
-        let mut name = pkg.name.clone();
-        name.version = Some(PackageName::version_compat_track(version));
-        Some(((name, iface.name.clone()?), version))
-    }
+    #[test]
+    fn world_named_imports() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
 
-    /// If `ty` is a definition where it's a `use` from another interface, then
-    /// change what interface it's using from according to the pairs in the
-    /// `replacements` map.
-    fn update_interface_dep_of_type(
-        &mut self,
-        ty: TypeId,
-        replacements: &HashMap<InterfaceId, InterfaceId>,
-    ) {
-        let to_replace = match self.type_interface_dep(ty) {
-            Some(id) => id,
-            None => return,
-        };
-        let replace_with = match replacements.get(&to_replace) {
-            Some(id) => id,
-            None => return,
-        };
-        let dep = match self.types[ty].kind {
-            TypeDefKind::Type(Type::Id(id)) => id,
-            _ => return,
-        };
-        let name = self.types[dep].name.as_ref().unwrap();
-        // Note the infallible name indexing happening here. This should be
-        // previously validated with `merge_world_item` to succeed.
-        let replacement_id = self.interfaces[*replace_with].types[name];
-        self.types[ty].kind = TypeDefKind::Type(Type::Id(replacement_id));
-    }
+                interface i {
+                    f: func();
+                }
 
-    /// Returns the core wasm module/field names for the specified `import`.
-    ///
-    /// This function will return the core wasm module/field that can be used to
-    /// use `import` with the name `mangling` scheme specified as well. This can
-    /// be useful for bindings generators, for example, and these names are
-    /// recognized by `wit-component` and `wasm-tools component new`.
-    pub fn wasm_import_name(
-        &self,
-        mangling: ManglingAndAbi,
-        import: WasmImport<'_>,
-    ) -> (String, String) {
-        match mangling {
-            ManglingAndAbi::Standard32 => match import {
-                WasmImport::Func { interface, func } => {
-                    let module = match interface {
-                        Some(key) => format!("cm32p2|{}", self.name_canonicalized_world_key(key)),
-                        None => format!("cm32p2"),
-                    };
-                    (module, func.name.clone())
+                world w {
+                    import i;
+                    import g: func();
+                    export h: func();
                 }
-                WasmImport::ResourceIntrinsic {
-                    interface,
-                    resource,
-                    intrinsic,
-                } => {
-                    let name = self.types[resource].name.as_ref().unwrap();
-                    let (prefix, name) = match intrinsic {
-                        ResourceIntrinsic::ImportedDrop => ("", format!("{name}_drop")),
-                        ResourceIntrinsic::ExportedDrop => ("_ex_", format!("{name}_drop")),
-                        ResourceIntrinsic::ExportedNew => ("_ex_", format!("{name}_new")),
-                        ResourceIntrinsic::ExportedRep => ("_ex_", format!("{name}_rep")),
-                    };
-                    let module = match interface {
-                        Some(key) => {
-                            format!("cm32p2|{prefix}{}", self.name_canonicalized_world_key(key))
-                        }
-                        None => {
-                            assert_eq!(prefix, "");
-                            format!("cm32p2")
-                        }
-                    };
-                    (module, name)
+            "#,
+        )?;
+        let world = resolve.select_world(pkg, None)?;
+        let imports = resolve.world_named_imports(world).collect::<Vec<_>>();
+        assert_eq!(imports.len(), 1);
+        assert_eq!(imports[0].0, "g");
+        assert!(matches!(imports[0].1, WorldItem::Function(f) if f.name == "g"));
+        Ok(())
+    }
+
+    #[test]
+    fn world_imports_subset_of() -> Result<()> {
+        let mut resolve = Resolve::default();
+        resolve.push_str(
+            "streams-0.2.0.wit",
+            r#"
+                package wasi:io@0.2.0;
+
+                interface streams {
+                    read: func() -> list<u8>;
                 }
-            },
-            ManglingAndAbi::Legacy(abi) => match import {
-                WasmImport::Func { interface, func } => {
-                    let module = match interface {
-                        Some(key) => self.name_world_key(key),
-                        None => format!("$root"),
-                    };
-                    (module, format!("{}{}", abi.import_prefix(), func.name))
+            "#,
+        )?;
+        resolve.push_str(
+            "streams-0.2.1.wit",
+            r#"
+                package wasi:io@0.2.1;
+
+                interface streams {
+                    read: func() -> list<u8>;
                 }
-                WasmImport::ResourceIntrinsic {
-                    interface,
-                    resource,
-                    intrinsic,
-                } => {
-                    let name = self.types[resource].name.as_ref().unwrap();
-                    let (prefix, name) = match intrinsic {
-                        ResourceIntrinsic::ImportedDrop => ("", format!("[resource-drop]{name}")),
-                        ResourceIntrinsic::ExportedDrop => {
-                            ("[export]", format!("[resource-drop]{name}"))
-                        }
-                        ResourceIntrinsic::ExportedNew => {
-                            ("[export]", format!("[resource-new]{name}"))
-                        }
-                        ResourceIntrinsic::ExportedRep => {
-                            ("[export]", format!("[resource-rep]{name}"))
-                        }
-                    };
-                    let module = match interface {
-                        Some(key) => format!("{prefix}{}", self.name_world_key(key)),
-                        None => {
-                            assert_eq!(prefix, "");
-                            format!("$root")
-                        }
-                    };
-                    (module, format!("{}{name}", abi.import_prefix()))
+            "#,
+        )?;
+        resolve.push_str(
+            "streams-0.3.0.wit",
+            r#"
+                package wasi:io@0.3.0;
+
+                interface streams {
+                    read: func() -> list<u8>;
                 }
-            },
-        }
-    }
+            "#,
+        )?;
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
 
-    /// Returns the core wasm export name for the specified `export`.
-    ///
-    /// This is the same as [`Resolve::wasm_import_name`], except for exports.
-    pub fn wasm_export_name(&self, mangling: ManglingAndAbi, export: WasmExport<'_>) -> String {
-        match mangling {
-            ManglingAndAbi::Standard32 => match export {
-                WasmExport::Func {
-                    interface,
-                    func,
-                    kind,
-                } => {
-                    let mut name = String::from("cm32p2|");
-                    if let Some(interface) = interface {
-                        let s = self.name_canonicalized_world_key(interface);
-                        name.push_str(&s);
-                    }
-                    name.push_str("|");
-                    name.push_str(&func.name);
-                    match kind {
-                        WasmExportKind::Normal => {}
-                        WasmExportKind::PostReturn => name.push_str("_post"),
-                        WasmExportKind::Callback => todo!(
-                            "not yet supported: \
-                             async callback functions using standard name mangling"
-                        ),
-                    }
-                    name
+                interface secrets {
+                    get: func() -> string;
                 }
-                WasmExport::ResourceDtor {
-                    interface,
-                    resource,
-                } => {
-                    let name = self.types[resource].name.as_ref().unwrap();
-                    let interface = self.name_canonicalized_world_key(interface);
-                    format!("cm32p2|{interface}|{name}_dtor")
+
+                world policy {
+                    import wasi:io/streams@0.2.0;
+                    import log: func(msg: string);
                 }
-                WasmExport::Memory => "cm32p2_memory".to_string(),
-                WasmExport::Initialize => "cm32p2_initialize".to_string(),
-                WasmExport::Realloc => "cm32p2_realloc".to_string(),
-            },
-            ManglingAndAbi::Legacy(abi) => match export {
-                WasmExport::Func {
-                    interface,
-                    func,
-                    kind,
-                } => {
-                    let mut name = abi.export_prefix().to_string();
-                    match kind {
-                        WasmExportKind::Normal => {}
-                        WasmExportKind::PostReturn => name.push_str("cabi_post_"),
-                        WasmExportKind::Callback => {
-                            assert!(matches!(abi, LiftLowerAbi::AsyncCallback));
-                            name = format!("[callback]{name}")
-                        }
-                    }
-                    if let Some(interface) = interface {
-                        let s = self.name_world_key(interface);
-                        name.push_str(&s);
-                        name.push_str("#");
-                    }
-                    name.push_str(&func.name);
-                    name
+
+                world compliant {
+                    import wasi:io/streams@0.2.1;
+                    import log: func(msg: string);
+                    export run: func();
                 }
-                WasmExport::ResourceDtor {
-                    interface,
-                    resource,
-                } => {
-                    let name = self.types[resource].name.as_ref().unwrap();
-                    let interface = self.name_world_key(interface);
-                    format!("{}{interface}#[dtor]{name}", abi.export_prefix())
+
+                world violating {
+                    import wasi:io/streams@0.3.0;
+                    import secrets;
+                    import log: func(msg: string, level: u32);
                 }
-                WasmExport::Memory => "memory".to_string(),
-                WasmExport::Initialize => "_initialize".to_string(),
-                WasmExport::Realloc => "cabi_realloc".to_string(),
-            },
-        }
-    }
-}
+            "#,
+        )?;
+        let world = |name: &str| resolve.packages[pkg].worlds[name];
+
+        assert_eq!(
+            resolve.world_imports_subset_of(world("compliant"), world("policy")),
+            Ok(())
+        );
 
-/// Possible imports that can be passed to [`Resolve::wasm_import_name`].
-#[derive(Debug)]
-pub enum WasmImport<'a> {
-    /// A WIT function is being imported. Optionally from an interface.
-    Func {
-        /// The name of the interface that the function is being imported from.
-        ///
-        /// If the function is imported directly from the world then this is
-        /// `Noen`.
-        interface: Option<&'a WorldKey>,
+        let denied = resolve
+            .world_imports_subset_of(world("violating"), world("policy"))
+            .unwrap_err();
+        let denied = denied
+            .iter()
+            .map(|key| resolve.name_world_key(key))
+            .collect::<Vec<_>>();
+        assert_eq!(denied, ["wasi:io/streams@0.3.0", "foo:bar/secrets", "log"]);
+        Ok(())
+    }
 
-        /// The function being imported.
-        func: &'a Function,
-    },
+    #[test]
+    fn function_owned_resources() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
 
-    /// A resource-related intrinsic is being imported.
-    ResourceIntrinsic {
-        /// The optional interface to import from, same as `WasmImport::Func`.
-        interface: Option<&'a WorldKey>,
+                interface i {
+                    resource r;
+                    type alias = r;
 
-        /// The resource that's being operated on.
-        resource: TypeId,
+                    open: func(path: string) -> r;
+                    open-alias: func() -> alias;
+                    close: func(r: r);
+                    peek: func(r: borrow<r>) -> u32;
+                    maybe-open: func() -> option<r>;
+                }
+            "#,
+        )?;
+        let i = resolve.packages[pkg].interfaces["i"];
+        let r = resolve.interfaces[i].types["r"];
+        let func = |name: &str| &resolve.interfaces[i].functions[name];
 
-        /// The intrinsic that's being imported.
-        intrinsic: ResourceIntrinsic,
-    },
-}
+        assert_eq!(
+            resolve.function_returns_owned_resource(func("open")),
+            Some(r)
+        );
+        assert_eq!(
+            resolve.function_returns_owned_resource(func("open-alias")),
+            Some(r)
+        );
+        assert_eq!(resolve.function_returns_owned_resource(func("peek")), None);
+        assert_eq!(
+            resolve.function_returns_owned_resource(func("maybe-open")),
+            None
+        );
+        assert_eq!(resolve.function_returns_owned_resource(func("close")), None);
 
-/// Intrinsic definitions to go with [`WasmImport::ResourceIntrinsic`] which
-/// also goes with [`Resolve::wasm_import_name`].
-#[derive(Debug)]
-pub enum ResourceIntrinsic {
-    ImportedDrop,
-    ExportedDrop,
-    ExportedNew,
-    ExportedRep,
-}
+        assert_eq!(
+            resolve.function_consumes_owned_resource(func("close")),
+            Some(r)
+        );
+        assert_eq!(resolve.function_consumes_owned_resource(func("peek")), None);
+        assert_eq!(resolve.function_consumes_owned_resource(func("open")), None);
+        Ok(())
+    }
 
-/// Indicates whether a function export is a normal export, a post-return
-/// function, or a callback function.
-#[derive(Debug)]
-pub enum WasmExportKind {
-    /// Normal function export.
-    Normal,
+    #[test]
+    fn merge_world_imports_based_on_semver_reported() -> Result<()> {
+        let mut resolve = Resolve::default();
+        for version in ["0.2.0", "0.2.1"] {
+            resolve.push_str(
+                "clocks.wit",
+                &format!(
+                    r#"
+                        package wasi:clocks@{version};
+
+                        interface wall-clock {{
+                            now: func() -> u64;
+                        }}
+                    "#
+                ),
+            )?;
+        }
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
 
-    /// Post-return function.
-    PostReturn,
+                world w {
+                    import wasi:clocks/wall-clock@0.2.0;
+                    import wasi:clocks/wall-clock@0.2.1;
+                    import f: func();
+                }
+            "#,
+        )?;
+        let world = resolve.select_world(pkg, None)?;
 
-    /// Async callback function.
-    Callback,
-}
+        let merged = resolve.merge_world_imports_based_on_semver_reported(world)?;
+        let merged = merged
+            .iter()
+            .map(|(old, new)| (resolve.name_world_key(old), resolve.name_world_key(new)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            merged,
+            [(
+                "wasi:clocks/wall-clock@0.2.0".to_string(),
+                "wasi:clocks/wall-clock@0.2.1".to_string()
+            )]
+        );
+        assert_eq!(resolve.worlds[world].imports.len(), 2);
 
-/// Different kinds of exports that can be passed to
-/// [`Resolve::wasm_export_name`] to export from core wasm modules.
-#[derive(Debug)]
-pub enum WasmExport<'a> {
-    /// A WIT function is being exported, optionally from an interface.
-    Func {
-        /// An optional interface which owns `func`. Use `None` for top-level
-        /// world function.
-        interface: Option<&'a WorldKey>,
+        assert!(
+            resolve
+                .merge_world_imports_based_on_semver_reported(world)?
+                .is_empty()
+        );
+        Ok(())
+    }
 
-        /// The function being exported.
-        func: &'a Function,
+    #[test]
+    fn types_equivalent() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
+
+                interface i {
+                    type int = u32;
+                    type int2 = int;
+                    type bytes = list<u8>;
+                    resource r;
+                    type r2 = r;
+                    record a { x: u32 }
+                    record b { x: u32 }
+                    type a2 = a;
+
+                    f: func(
+                        x: list<int>,
+                        y: list<u32>,
+                        z: list<u64>,
+                        h1: own<r>,
+                        h2: own<r2>,
+                        h3: borrow<r>,
+                        t1: tuple<int2, bytes>,
+                        t2: tuple<u32, list<u8>>,
+                        l: list<u8>,
+                    );
+                }
+            "#,
+        )?;
+        let i = resolve.packages[pkg].interfaces["i"];
+        let ty = |name: &str| Type::Id(resolve.interfaces[i].types[name]);
+        let param = |name: &str| {
+            resolve.interfaces[i].functions["f"]
+                .params
+                .iter()
+                .find(|(n, _)| n == name)
+                .unwrap()
+                .1
+        };
 
-        /// Kind of function (normal, post-return, or callback) being exported.
-        kind: WasmExportKind,
-    },
+        assert!(resolve.types_equivalent(Type::U32, Type::U32));
+        assert!(!resolve.types_equivalent(Type::U32, Type::U64));
+        assert!(resolve.types_equivalent(ty("int"), Type::U32));
+        assert!(resolve.types_equivalent(Type::U32, ty("int2")));
+        assert!(resolve.types_equivalent(ty("int"), ty("int2")));
+        assert!(!resolve.types_equivalent(ty("int"), Type::S32));
+
+        assert!(resolve.types_equivalent(param("x"), param("y")));
+        assert!(!resolve.types_equivalent(param("x"), param("z")));
+        assert!(resolve.types_equivalent(param("h1"), param("h2")));
+        assert!(!resolve.types_equivalent(param("h1"), param("h3")));
+        assert!(resolve.types_equivalent(param("t1"), param("t2")));
+        assert!(resolve.types_equivalent(ty("bytes"), param("l")));
+
+        assert!(resolve.types_equivalent(ty("r"), ty("r2")));
+        assert!(resolve.types_equivalent(ty("a"), ty("a2")));
+        assert!(!resolve.types_equivalent(ty("a"), ty("b")));
+        Ok(())
+    }
 
-    /// A destructor for a resource exported from this module.
-    ResourceDtor {
-        /// The interface that owns the resource.
-        interface: &'a WorldKey,
-        /// The resource itself that the destructor is for.
-        resource: TypeId,
-    },
+    #[test]
+    fn collapse_type_aliases() -> Result<()> {
+        let mut resolve = Resolve::default();
+        resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
 
-    /// Linear memory, the one that the canonical ABI uses.
-    Memory,
+                interface types {
+                    type t = u8;
+                }
 
-    /// An initialization function (not the core wasm `start`).
-    Initialize,
+                interface foo {
+                    use types.{t};
+
+                    /// A 32-bit number.
+                    type b = u32;
+                    /// Another name.
+                    type c = b;
+                    type a = c;
+                    /// Unsigned, as in: A 32-bit number.
+                    type d = c;
+                    type u = t;
+                }
+            "#,
+        )?;
+        resolve.collapse_type_aliases();
 
-    /// The general-purpose realloc hook.
-    Realloc,
-}
+        let (foo, _) = resolve
+            .interfaces
+            .iter()
+            .find(|(_, i)| i.name.as_deref() == Some("foo"))
+            .unwrap();
+        let def = |name: &str| &resolve.types[resolve.interfaces[foo].types[name]];
 
-/// Structure returned by [`Resolve::merge`] which contains mappings from
-/// old-ids to new-ids after the merge.
-#[derive(Default)]
-pub struct Remap {
-    pub types: Vec<Option<TypeId>>,
-    pub interfaces: Vec<Option<InterfaceId>>,
-    pub worlds: Vec<Option<WorldId>>,
-    pub packages: Vec<PackageId>,
+        let a = def("a");
+        assert_eq!(a.kind, TypeDefKind::Type(Type::U32));
+        assert_eq!(
+            a.docs.contents.as_deref(),
+            Some("Another name.\n\nA 32-bit number.")
+        );
+        let c = def("c");
+        assert_eq!(c.kind, TypeDefKind::Type(Type::U32));
+        assert_eq!(
+            c.docs.contents.as_deref(),
+            Some("Another name.\n\nA 32-bit number.")
+        );
+        // Docs are only deduplicated by whole paragraphs.
+        assert_eq!(
+            def("d").docs.contents.as_deref(),
+            Some("Unsigned, as in: A 32-bit number.\n\nAnother name.\n\nA 32-bit number.")
+        );
 
-    /// A cache of anonymous `own<T>` handles for resource types.
-    ///
-    /// The appending operation of `Remap` is the one responsible for
-    /// translating references to `T` where `T` is a resource into `own<T>`
-    /// instead. This map is used to deduplicate the `own<T>` types generated
-    /// to generate as few as possible.
-    ///
-    /// The key of this map is the resource id `T` in the new resolve, and
-    /// the value is the `own<T>` type pointing to `T`.
-    own_handles: HashMap<TypeId, TypeId>,
+        // The `use`'d `t` still refers to its original definition.
+        let t = resolve.interfaces[foo].types["t"];
+        assert_eq!(def("u").kind, TypeDefKind::Type(Type::Id(t)));
+        assert!(matches!(
+            resolve.types[t].kind,
+            TypeDefKind::Type(Type::Id(_))
+        ));
+        Ok(())
+    }
 
-    type_has_borrow: Vec<Option<bool>>,
-}
+    #[test]
+    fn validate_names() -> Result<()> {
+        let wit = r#"
+            package foo:bar;
+
+            interface i {
+                type a = u32;
+                type b = u32;
+                f: func();
+                g: func();
+            }
 
-fn apply_map<T>(map: &[Option<Id<T>>], id: Id<T>, desc: &str, span: Option<Span>) -> Result<Id<T>> {
-    match map.get(id.index()) {
-        Some(Some(id)) => Ok(*id),
-        Some(None) => {
-            let msg = format!(
-                "found a reference to a {desc} which is excluded \
-                 due to its feature not being activated"
-            );
-            match span {
-                Some(span) => Err(Error::new(span, msg).into()),
-                None => bail!("{msg}"),
+            world w {
+                import i;
             }
-        }
-        None => panic!("request to remap a {desc} that has not yet been registered"),
-    }
-}
+        "#;
+        let parse = || -> Result<_> {
+            let mut resolve = Resolve::default();
+            let pkg = resolve.push_str("test.wit", wit)?;
+            let i = resolve.packages[pkg].interfaces["i"];
+            let w = resolve.packages[pkg].worlds["w"];
+            Ok((resolve, i, w))
+        };
+        let (resolve, _, _) = parse()?;
+        assert_eq!(resolve.validate_names(), Ok(()));
 
-impl Remap {
-    pub fn map_type(&self, id: TypeId, span: Option<Span>) -> Result<TypeId> {
-        apply_map(&self.types, id, "type", span)
-    }
+        let (mut resolve, i, _) = parse()?;
+        let b = resolve.interfaces[i].types["b"];
+        resolve.types[b].name = Some("a".to_string());
+        assert_eq!(
+            resolve.validate_names(),
+            Err(vec![NameError::DuplicateType {
+                owner: "foo:bar/i".to_string(),
+                name: "a".to_string(),
+            }])
+        );
 
-    pub fn map_interface(&self, id: InterfaceId, span: Option<Span>) -> Result<InterfaceId> {
-        apply_map(&self.interfaces, id, "interface", span)
-    }
+        let (mut resolve, i, _) = parse()?;
+        resolve.interfaces[i].functions["g"].name = "[async]f".to_string();
+        resolve.interfaces[i].functions["g"].kind = FunctionKind::AsyncFreestanding;
+        assert_eq!(
+            resolve.validate_names(),
+            Err(vec![NameError::DuplicateFunction {
+                owner: "foo:bar/i".to_string(),
+                name: "[async]f".to_string(),
+            }])
+        );
 
-    pub fn map_world(&self, id: WorldId, span: Option<Span>) -> Result<WorldId> {
-        apply_map(&self.worlds, id, "world", span)
+        let (mut resolve, i, w) = parse()?;
+        resolve.worlds[w].imports.insert(
+            WorldKey::Name("foo:bar/i".to_string()),
+            WorldItem::Interface {
+                id: i,
+                stability: Default::default(),
+            },
+        );
+        assert_eq!(
+            resolve.validate_names(),
+            Err(vec![NameError::DuplicateWorldKey {
+                world: w,
+                name: "foo:bar/i".to_string(),
+            }])
+        );
+
+        let mut resolve = Resolve::default();
+        resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
+
+                interface i {
+                    record %record {
+                        %type: u32,
+                    }
+                    f: func(%list: u32);
+                }
+            "#,
+        )?;
+        // Keywords escaped with `%` are valid identifiers.
+        assert_eq!(resolve.validate_names(), Ok(()));
+        Ok(())
     }
 
-    fn append(
-        &mut self,
-        resolve: &mut Resolve,
-        unresolved: UnresolvedPackage,
-    ) -> Result<PackageId> {
-        let pkgid = resolve.packages.alloc(Package {
-            name: unresolved.name.clone(),
-            docs: unresolved.docs.clone(),
-            interfaces: Default::default(),
-            worlds: Default::default(),
-        });
-        let prev = resolve.package_names.insert(unresolved.name.clone(), pkgid);
-        if let Some(prev) = prev {
-            resolve.package_names.insert(unresolved.name.clone(), prev);
-            bail!(
-                "attempting to re-add package `{}` when it's already present in this `Resolve`",
-                unresolved.name,
-            );
-        }
+    #[test]
+    fn replace_interface() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
 
-        self.process_foreign_deps(resolve, pkgid, &unresolved)?;
+                interface mock {
+                    resource r {
+                        get: func() -> u32;
+                    }
+                    ping: func() -> string;
+                }
 
-        let foreign_types = self.types.len();
-        let foreign_interfaces = self.interfaces.len();
-        let foreign_worlds = self.worlds.len();
+                interface real {
+                    resource r {
+                        get: func() -> u32;
+                        put: func(x: u32);
+                    }
+                    ping: func() -> string;
+                    extra: func();
+                }
 
-        // Copy over all types first, updating any intra-type references. Note
-        // that types are sorted topologically which means this iteration
-        // order should be sufficient. Also note though that the interface
-        // owner of a type isn't updated here due to interfaces not being known
-        // yet.
-        assert_eq!(unresolved.types.len(), unresolved.type_spans.len());
-        for ((id, mut ty), span) in unresolved
-            .types
-            .into_iter()
-            .zip(&unresolved.type_spans)
-            .skip(foreign_types)
-        {
-            if !resolve.include_type(&ty, pkgid, *span)? {
-                self.types.push(None);
-                continue;
-            }
+                interface wrong {
+                    resource r {
+                        get: func() -> u32;
+                    }
+                    ping: func() -> u32;
+                }
 
-            self.update_typedef(resolve, &mut ty, Some(*span))?;
-            let new_id = resolve.types.alloc(ty);
-            assert_eq!(self.types.len(), id.index());
+                interface user {
+                    use mock.{r};
+                    f: func(x: borrow<r>);
+                }
 
-            let new_id = match resolve.types[new_id] {
-                // If this is an `own<T>` handle then either replace it with a
-                // preexisting `own<T>` handle which may have been generated in
-                // `update_ty`. If that doesn't exist though then insert it into
-                // the `own_handles` cache.
-                TypeDef {
-                    name: None,
-                    owner: TypeOwner::None,
-                    kind: TypeDefKind::Handle(Handle::Own(id)),
-                    docs: _,
-                    stability: _,
-                } => *self.own_handles.entry(id).or_insert(new_id),
+                world importer {
+                    import user;
+                }
 
-                // Everything not-related to `own<T>` doesn't get its ID
-                // modified.
-                _ => new_id,
-            };
-            self.types.push(Some(new_id));
-        }
+                world exporter {
+                    export mock;
+                }
+            "#,
+        )?;
+        let interfaces = &resolve.packages[pkg].interfaces;
+        let (mock, real, wrong, user) = (
+            interfaces["mock"],
+            interfaces["real"],
+            interfaces["wrong"],
+            interfaces["user"],
+        );
 
-        // Next transfer all interfaces into `Resolve`, updating type ids
-        // referenced along the way.
+        let err = resolve.replace_interface(real, mock).unwrap_err();
         assert_eq!(
-            unresolved.interfaces.len(),
-            unresolved.interface_spans.len()
+            err.to_string(),
+            "interface `foo:bar/mock` cannot replace `foo:bar/real` as it's missing: \
+             function `[method]r.put`, function `extra`"
         );
-        for ((id, mut iface), span) in unresolved
-            .interfaces
-            .into_iter()
-            .zip(&unresolved.interface_spans)
-            .skip(foreign_interfaces)
-        {
-            if !resolve
-                .include_stability(&iface.stability, &pkgid, Some(span.span))
-                .with_context(|| {
-                    format!(
-                        "failed to process feature gate for interface [{}] in package [{}]",
-                        iface
-                            .name
-                            .as_ref()
-                            .map(String::as_str)
-                            .unwrap_or("<unknown>"),
-                        resolve.packages[pkgid].name,
-                    )
-                })?
-            {
-                self.interfaces.push(None);
-                continue;
-            }
-            assert!(iface.package.is_none());
-            iface.package = Some(pkgid);
-            self.update_interface(resolve, &mut iface, Some(span))?;
-            let new_id = resolve.interfaces.alloc(iface);
-            assert_eq!(self.interfaces.len(), id.index());
-            self.interfaces.push(Some(new_id));
-        }
 
-        // Now that interfaces are identified go back through the types and
-        // update their interface owners.
-        for (i, id) in self.types.iter().enumerate().skip(foreign_types) {
-            let id = match id {
-                Some(id) => *id,
-                None => continue,
-            };
-            match &mut resolve.types[id].owner {
-                TypeOwner::Interface(id) => {
-                    let span = unresolved.type_spans[i];
-                    *id = self.map_interface(*id, Some(span))
-                        .with_context(|| {
-                            "this type is not gated by a feature but its interface is gated by a feature"
-                        })?;
+        let err = resolve.replace_interface(mock, wrong).unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "interface `foo:bar/wrong` cannot replace `foo:bar/mock`: \
+             mismatch in function `ping`: different function result types: \
+             different kinds of types: expected `u32`, found `string`"
+        );
+
+        resolve.replace_interface(mock, real)?;
+
+        let r = resolve.interfaces[user].types["r"];
+        let real_r = resolve.interfaces[real].types["r"];
+        assert_eq!(resolve.types[r].kind, TypeDefKind::Type(Type::Id(real_r)));
+
+        let importer = &resolve.worlds[resolve.packages[pkg].worlds["importer"]];
+        assert_eq!(
+            importer.imports.keys().collect::<Vec<_>>(),
+            [&WorldKey::Interface(real), &WorldKey::Interface(user)]
+        );
+        let exporter = &resolve.worlds[resolve.packages[pkg].worlds["exporter"]];
+        assert_eq!(
+            exporter.exports.keys().collect::<Vec<_>>(),
+            [&WorldKey::Interface(real)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn world_contains_async() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
+
+                interface sync {
+                    resource r {
+                        get: func() -> list<u32>;
+                    }
                 }
-                TypeOwner::World(_) | TypeOwner::None => {}
-            }
-        }
 
-        // Expand worlds. Note that this does NOT process `include` statements,
-        // that's handled below. Instead this just handles world item updates
-        // and resolves references to types/items within `Resolve`.
-        //
-        // This is done after types/interfaces are fully settled so the
-        // transitive relation between interfaces, through types, is understood
-        // here.
-        assert_eq!(unresolved.worlds.len(), unresolved.world_spans.len());
-        for ((id, mut world), span) in unresolved
-            .worlds
-            .into_iter()
-            .zip(&unresolved.world_spans)
-            .skip(foreign_worlds)
-        {
-            if !resolve
-                .include_stability(&world.stability, &pkgid, Some(span.span))
-                .with_context(|| {
-                    format!(
-                        "failed to process feature gate for world [{}] in package [{}]",
-                        world.name, resolve.packages[pkgid].name,
-                    )
-                })?
-            {
-                self.worlds.push(None);
-                continue;
-            }
-            self.update_world(&mut world, resolve, &pkgid, &span)?;
+                interface with-async {
+                    resource r {
+                        get: async func() -> u32;
+                    }
+                }
 
-            let new_id = resolve.worlds.alloc(world);
-            assert_eq!(self.worlds.len(), id.index());
-            self.worlds.push(Some(new_id));
-        }
+                interface with-stream {
+                    record data {
+                        bytes: option<stream<u8>>,
+                    }
+                    f: func() -> data;
+                }
 
-        // As with interfaces, now update the ids of world-owned types.
-        for (i, id) in self.types.iter().enumerate().skip(foreign_types) {
-            let id = match id {
-                Some(id) => *id,
-                None => continue,
-            };
-            match &mut resolve.types[id].owner {
-                TypeOwner::World(id) => {
-                    let span = unresolved.type_spans[i];
-                    *id = self.map_world(*id, Some(span))
-                        .with_context(|| {
-                            "this type is not gated by a feature but its interface is gated by a feature"
-                        })?;
+                world sync-world {
+                    import sync;
+                    export f: func(x: u32) -> string;
                 }
-                TypeOwner::Interface(_) | TypeOwner::None => {}
-            }
-        }
 
-        // After the above, process `include` statements for worlds and
-        // additionally fully elaborate them. Processing of `include` is
-        // deferred until after the steps above so the fully resolved state of
-        // local types in this package are all available. This is required
-        // because `include` may copy types between worlds when the type is
-        // defined in the world itself.
-        //
-        // This step, after processing `include`, will also use
-        // `elaborate_world` to fully expand the world in terms of
-        // imports/exports and ensure that all necessary imports/exports are all
-        // listed.
-        //
-        // Note that `self.worlds` is already sorted in topological order so if
-        // one world refers to another via `include` then it's guaranteed that
-        // the one we're referring to is already expanded and ready to be
-        // included.
-        assert_eq!(self.worlds.len(), unresolved.world_spans.len());
-        for (id, span) in self
-            .worlds
-            .iter()
-            .zip(unresolved.world_spans.iter())
-            .skip(foreign_worlds)
-        {
-            let Some(id) = *id else {
-                continue;
-            };
-            self.process_world_includes(id, resolve, &pkgid, &span)?;
+                world async-world {
+                    import sync;
+                    export with-async;
+                }
 
-            resolve.elaborate_world(id).with_context(|| {
-                Error::new(
-                    span.span,
-                    format!(
-                        "failed to elaborate world imports/exports of `{}`",
-                        resolve.worlds[id].name
-                    ),
-                )
-            })?;
-        }
+                world stream-world {
+                    import with-stream;
+                }
 
-        // Fixup "parent" ids now that everything has been identified
-        for id in self.interfaces.iter().skip(foreign_interfaces) {
-            let id = match id {
-                Some(id) => *id,
-                None => continue,
-            };
-            let iface = &mut resolve.interfaces[id];
-            iface.package = Some(pkgid);
-            if let Some(name) = &iface.name {
-                let prev = resolve.packages[pkgid].interfaces.insert(name.clone(), id);
-                assert!(prev.is_none());
-            }
-        }
-        for id in self.worlds.iter().skip(foreign_worlds) {
-            let id = match id {
-                Some(id) => *id,
-                None => continue,
-            };
-            let world = &mut resolve.worlds[id];
-            world.package = Some(pkgid);
-            let prev = resolve.packages[pkgid]
-                .worlds
-                .insert(world.name.clone(), id);
-            assert!(prev.is_none());
-        }
-        Ok(pkgid)
+                world error-context-world {
+                    export f: func() -> result<_, error-context>;
+                }
+            "#,
+        )?;
+        let worlds = &resolve.packages[pkg].worlds;
+        assert!(!resolve.world_contains_async(worlds["sync-world"]));
+        assert!(resolve.world_contains_async(worlds["async-world"]));
+        assert!(resolve.world_contains_async(worlds["stream-world"]));
+        assert!(resolve.world_contains_async(worlds["error-context-world"]));
+        Ok(())
     }
 
-    fn process_foreign_deps(
-        &mut self,
-        resolve: &mut Resolve,
-        pkgid: PackageId,
-        unresolved: &UnresolvedPackage,
-    ) -> Result<()> {
-        // Invert the `foreign_deps` map to be keyed by world id to get
-        // used in the loops below.
-        let mut world_to_package = HashMap::new();
-        let mut interface_to_package = HashMap::new();
-        for (i, (pkg_name, worlds_or_ifaces)) in unresolved.foreign_deps.iter().enumerate() {
-            for (name, item) in worlds_or_ifaces {
-                match item {
-                    AstItem::Interface(unresolved_interface_id) => {
-                        let prev = interface_to_package.insert(
-                            *unresolved_interface_id,
-                            (pkg_name, name, unresolved.foreign_dep_spans[i]),
-                        );
-                        assert!(prev.is_none());
-                    }
-                    AstItem::World(unresolved_world_id) => {
-                        let prev = world_to_package.insert(
-                            *unresolved_world_id,
-                            (pkg_name, name, unresolved.foreign_dep_spans[i]),
-                        );
-                        assert!(prev.is_none());
+    #[test]
+    fn merge_detailed() -> Result<()> {
+        let mut resolve = Resolve::default();
+        resolve.push_str(
+            "a.wit",
+            r#"
+                package foo:a;
+
+                interface types {
+                    type t = u32;
+                }
+            "#,
+        )?;
+
+        let mut other = Resolve::default();
+        other.push_str(
+            "a.wit",
+            r#"
+                package foo:a;
+
+                interface types {
+                    type t = u32;
+                }
+            "#,
+        )?;
+        other.push_str(
+            "b.wit",
+            r#"
+                package foo:b;
+
+                interface types {
+                    use foo:a/types.{t};
+                    record r {
+                        x: t,
                     }
                 }
-            }
-        }
+            "#,
+        )?;
 
-        // Connect all interfaces referred to in `interface_to_package`, which
-        // are at the front of `unresolved.interfaces`, to interfaces already
-        // contained within `resolve`.
-        self.process_foreign_interfaces(unresolved, &interface_to_package, resolve)?;
+        let (remap, provenance) = resolve.merge_detailed(other)?;
+        assert_eq!(provenance.interfaces.len(), 2);
+        assert_eq!(provenance.types.len(), 3);
+
+        let a_types = remap.interfaces[0].unwrap();
+        let b_types = remap.interfaces[1].unwrap();
+        assert_eq!(provenance.interfaces[&a_types].to_string(), "foo:a");
+        assert_eq!(provenance.interfaces[&b_types].to_string(), "foo:b");
+
+        let a_t = resolve.interfaces[a_types].types["t"];
+        let b_t = resolve.interfaces[b_types].types["t"];
+        let b_r = resolve.interfaces[b_types].types["r"];
+        assert_eq!(provenance.types[&a_t].to_string(), "foo:a");
+        assert_eq!(provenance.types[&b_t].to_string(), "foo:b");
+        assert_eq!(provenance.types[&b_r].to_string(), "foo:b");
+        Ok(())
+    }
 
-        // Connect all worlds referred to in `world_to_package`, which
-        // are at the front of `unresolved.worlds`, to worlds already
-        // contained within `resolve`.
-        self.process_foreign_worlds(unresolved, &world_to_package, resolve)?;
+    #[test]
+    fn world_exported_interfaces() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar@1.0.0;
 
-        // Finally, iterate over all foreign-defined types and determine
-        // what they map to.
-        self.process_foreign_types(unresolved, pkgid, resolve)?;
+                interface handler {
+                    handle: func();
+                }
 
-        for (id, span) in unresolved.required_resource_types.iter() {
-            // Note that errors are ignored here because an error represents a
-            // type that has been configured away. If a type is configured away
-            // then any future use of it will generate an error so there's no
-            // need to validate that it's a resource here.
-            let Ok(mut id) = self.map_type(*id, Some(*span)) else {
-                continue;
-            };
-            loop {
-                match resolve.types[id].kind {
-                    TypeDefKind::Type(Type::Id(i)) => id = i,
-                    TypeDefKind::Resource => break,
-                    _ => bail!(Error::new(
-                        *span,
-                        format!("type used in a handle must be a resource"),
-                    )),
+                world w {
+                    @since(version = 1.0.0)
+                    export handler;
+                    export run: func();
                 }
+            "#,
+        )?;
+        let handler = resolve.packages[pkg].interfaces["handler"];
+        let world = resolve.packages[pkg].worlds["w"];
+
+        let exports = resolve.world_exported_interfaces(world);
+        assert_eq!(exports.len(), 1);
+        let (key, id, stability) = &exports[0];
+        assert_eq!(*key, WorldKey::Interface(handler));
+        assert_eq!(*id, handler);
+        assert_eq!(
+            **stability,
+            Stability::Stable {
+                since: Version::new(1, 0, 0),
+                deprecated: None,
             }
-        }
+        );
+        Ok(())
+    }
 
-        #[cfg(debug_assertions)]
-        resolve.assert_valid();
+    #[test]
+    fn resolve_method() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
 
+                interface i {
+                    resource a {
+                        constructor();
+                        foo: func() -> u32;
+                    }
+                    resource b {
+                        foo: static func() -> string;
+                    }
+                    foo: func();
+                }
+            "#,
+        )?;
+        let i = resolve.packages[pkg].interfaces["i"];
+        let a = resolve.interfaces[i].types["a"];
+        let b = resolve.interfaces[i].types["b"];
+
+        let foo = resolve.resolve_method(i, a, "foo").unwrap();
+        assert_eq!(foo.name, "[method]a.foo");
+        assert_eq!(foo.kind, FunctionKind::Method(a));
+        let foo = resolve.resolve_method(i, b, "foo").unwrap();
+        assert_eq!(foo.name, "[static]b.foo");
+        let ctor = resolve.resolve_method(i, a, "constructor").unwrap();
+        assert_eq!(ctor.name, "[constructor]a");
+        assert!(resolve.resolve_method(i, b, "constructor").is_none());
+        assert!(resolve.resolve_method(i, a, "bar").is_none());
         Ok(())
     }
 
-    fn process_foreign_interfaces(
-        &mut self,
-        unresolved: &UnresolvedPackage,
-        interface_to_package: &HashMap<InterfaceId, (&PackageName, &String, Span)>,
-        resolve: &mut Resolve,
-    ) -> Result<(), anyhow::Error> {
-        for (unresolved_iface_id, unresolved_iface) in unresolved.interfaces.iter() {
-            let (pkg_name, interface, span) = match interface_to_package.get(&unresolved_iface_id) {
-                Some(items) => *items,
-                // All foreign interfaces are defined first, so the first one
-                // which is defined in a non-foreign document means that all
-                // further interfaces will be non-foreign as well.
-                None => break,
-            };
-            let pkgid = resolve
-                .package_names
-                .get(pkg_name)
-                .copied()
-                .ok_or_else(|| {
-                    PackageNotFoundError::new(
-                        span,
-                        pkg_name.clone(),
-                        resolve.package_names.keys().cloned().collect(),
-                    )
-                })?;
+    #[test]
+    fn to_stable_json() -> Result<()> {
+        let wit = r#"
+            package foo:bar;
 
-            // Functions can't be imported so this should be empty.
-            assert!(unresolved_iface.functions.is_empty());
+            interface a {}
+            interface b {}
 
-            let pkg = &resolve.packages[pkgid];
-            let span = &unresolved.interface_spans[unresolved_iface_id.index()];
-            let iface_id = pkg
-                .interfaces
-                .get(interface)
-                .copied()
-                .ok_or_else(|| Error::new(span.span, "interface not found in package"))?;
-            assert_eq!(self.interfaces.len(), unresolved_iface_id.index());
-            self.interfaces.push(Some(iface_id));
-        }
-        for (id, _) in unresolved.interfaces.iter().skip(self.interfaces.len()) {
-            assert!(
-                interface_to_package.get(&id).is_none(),
-                "found foreign interface after local interface"
-            );
-        }
+            world w {
+                import a;
+                import anon: interface {}
+                export b;
+            }
+        "#;
+
+        let mut resolve = Resolve::default();
+        resolve.push_str("test.wit", wit)?;
+        let json = resolve.to_stable_json();
+
+        let mut other = Resolve::default();
+        other.push_str("test.wit", wit)?;
+        assert_eq!(other.to_stable_json(), json);
+
+        let world = &json["worlds"][0];
+        assert!(world["imports"]["foo:bar/a"].is_object());
+        assert!(world["imports"]["anon"].is_object());
+        assert!(world["exports"]["foo:bar/b"].is_object());
+
+        // Keys don't depend on where the interface is in the arena.
+        let mut shifted = Resolve::default();
+        shifted.push_str(
+            "other.wit",
+            "package foo:other; interface x {} interface y {}",
+        )?;
+        shifted.push_str("test.wit", wit)?;
+        let shifted = shifted.to_stable_json();
+        let keys = |json: &serde_json::Value, world: usize, field: &str| {
+            json["worlds"][world][field]
+                .as_object()
+                .unwrap()
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(keys(&shifted, 0, "imports"), keys(&json, 0, "imports"));
+        assert_eq!(keys(&shifted, 0, "exports"), keys(&json, 0, "exports"));
         Ok(())
     }
 
-    fn process_foreign_worlds(
-        &mut self,
-        unresolved: &UnresolvedPackage,
-        world_to_package: &HashMap<WorldId, (&PackageName, &String, Span)>,
-        resolve: &mut Resolve,
-    ) -> Result<(), anyhow::Error> {
-        for (unresolved_world_id, _) in unresolved.worlds.iter() {
-            let (pkg_name, world, span) = match world_to_package.get(&unresolved_world_id) {
-                Some(items) => *items,
-                // Same as above, all worlds are foreign until we find a
-                // non-foreign one.
-                None => break,
-            };
+    #[test]
+    fn cross_world_conflicts() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar@1.0.0;
 
-            let pkgid = resolve
-                .package_names
-                .get(pkg_name)
-                .copied()
-                .ok_or_else(|| Error::new(span, "package not found"))?;
-            let pkg = &resolve.packages[pkgid];
-            let span = &unresolved.world_spans[unresolved_world_id.index()];
-            let world_id = pkg
-                .worlds
-                .get(world)
-                .copied()
-                .ok_or_else(|| Error::new(span.span, "world not found in package"))?;
-            assert_eq!(self.worlds.len(), unresolved_world_id.index());
-            self.worlds.push(Some(world_id));
-        }
-        for (id, _) in unresolved.worlds.iter().skip(self.worlds.len()) {
-            assert!(
-                world_to_package.get(&id).is_none(),
-                "found foreign world after local world"
-            );
-        }
+                interface api {}
+
+                world a {
+                    export api;
+                    export run: func(x: u32);
+                    export same: func();
+                }
+
+                world b {
+                    @since(version = 1.0.0)
+                    export api;
+                    export run: func(x: string);
+                    export same: func();
+                }
+            "#,
+        )?;
+        let a = resolve.packages[pkg].worlds["a"];
+        let b = resolve.packages[pkg].worlds["b"];
+
+        let conflicts = resolve.cross_world_conflicts(pkg);
+        assert_eq!(conflicts.len(), 2);
+        assert_eq!(conflicts[0].name, "run");
+        assert_eq!(conflicts[0].worlds, (a, b));
+        assert!(matches!(
+            &conflicts[0].kind,
+            ConflictKind::Signature(msg) if msg.contains("different function parameter types"),
+        ));
+        assert_eq!(conflicts[1].name, "foo:bar/api@1.0.0");
+        assert_eq!(conflicts[1].worlds, (a, b));
+        assert_eq!(conflicts[1].kind, ConflictKind::Stability);
         Ok(())
     }
 
-    fn process_foreign_types(
-        &mut self,
-        unresolved: &UnresolvedPackage,
-        pkgid: PackageId,
-        resolve: &mut Resolve,
-    ) -> Result<(), anyhow::Error> {
-        for ((unresolved_type_id, unresolved_ty), span) in
-            unresolved.types.iter().zip(&unresolved.type_spans)
-        {
-            // All "Unknown" types should appear first so once we're no longer
-            // in unknown territory it's package-defined types so break out of
-            // this loop.
-            match unresolved_ty.kind {
-                TypeDefKind::Unknown => {}
-                _ => break,
-            }
+    #[test]
+    fn item_span() -> Result<()> {
+        let wit = r#"
+            package foo:bar;
 
-            if !resolve.include_type(unresolved_ty, pkgid, *span)? {
-                self.types.push(None);
-                continue;
+            interface i {
+                type t = u32;
+                f: func(x: t);
             }
 
-            let unresolved_iface_id = match unresolved_ty.owner {
-                TypeOwner::Interface(id) => id,
-                _ => unreachable!(),
-            };
-            let iface_id = self.map_interface(unresolved_iface_id, None)?;
-            let name = unresolved_ty.name.as_ref().unwrap();
-            let span = unresolved.unknown_type_spans[unresolved_type_id.index()];
-            let type_id = *resolve.interfaces[iface_id]
-                .types
-                .get(name)
-                .ok_or_else(|| {
-                    Error::new(span, format!("type `{name}` not defined in interface"))
-                })?;
-            assert_eq!(self.types.len(), unresolved_type_id.index());
-            self.types.push(Some(type_id));
-        }
-        for (_, ty) in unresolved.types.iter().skip(self.types.len()) {
-            if let TypeDefKind::Unknown = ty.kind {
-                panic!("unknown type after defined type");
+            world w {
+                import i;
             }
-        }
+        "#;
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str("test.wit", wit)?;
+        let i = resolve.packages[pkg].interfaces["i"];
+        let w = resolve.packages[pkg].worlds["w"];
+        let t = resolve.interfaces[i].types["t"];
+
+        let text = |item| {
+            let (span, _) = resolve.item_span(item).unwrap();
+            &wit[span.start as usize..span.end as usize]
+        };
+        assert_eq!(text(ItemRef::Type(t)), "t");
+        let (span, _) = resolve.item_span(ItemRef::Type(t)).unwrap();
+        assert_eq!(
+            span.start as usize,
+            wit.find("type t").unwrap() + "type ".len()
+        );
+        assert_eq!(text(ItemRef::Interface(i)), "i");
+        assert_eq!(text(ItemRef::World(w)), "w");
+        assert_eq!(text(ItemRef::InterfaceFunction(i, "f".to_string())), "f");
+        assert_eq!(text(ItemRef::WorldItem(w, WorldKey::Interface(i))), "i");
+
+        // Spans of packages pushed separately are rendered with their own
+        // source map.
+        let pkg2 = resolve.push_str("other.wit", "package foo:baz;\n\ninterface j {}\n")?;
+        let j = resolve.packages[pkg2].interfaces["j"];
+        let location = |item| {
+            let (span, source_map) = resolve.item_span(item).unwrap();
+            source_map.render_location(span)
+        };
+        assert_eq!(location(ItemRef::Interface(j)), "other.wit:3:11");
+        assert_eq!(location(ItemRef::Interface(i)), "test.wit:4:23");
+
+        // Packages pushed together share a single source map.
+        let pkg3 = resolve.push_str(
+            "nested.wit",
+            "package foo:outer;\ninterface k {}\npackage foo:inner { interface l {} }\n",
+        )?;
+        let k = resolve.packages[pkg3].interfaces["k"];
+        let inner = resolve.package_names[&"foo:inner".parse::<PackageName>()?];
+        let l = resolve.packages[inner].interfaces["l"];
+        let (_, k_map) = resolve.item_span(ItemRef::Interface(k)).unwrap();
+        let (_, l_map) = resolve.item_span(ItemRef::Interface(l)).unwrap();
+        assert!(std::ptr::eq(k_map, l_map));
+
+        // Spans survive merging into another `Resolve`.
+        let mut merged = Resolve::default();
+        let remap = merged.merge(resolve.clone())?;
+        let t2 = remap.types[t.index()].unwrap();
+        assert_eq!(
+            merged.item_span(ItemRef::Type(t2)).map(|(s, _)| s),
+            Some(span)
+        );
         Ok(())
     }
 
-    fn update_typedef(
-        &mut self,
-        resolve: &mut Resolve,
-        ty: &mut TypeDef,
-        span: Option<Span>,
-    ) -> Result<()> {
-        // NB: note that `ty.owner` is not updated here since interfaces
-        // haven't been mapped yet and that's done in a separate step.
-        use crate::TypeDefKind::*;
-        match &mut ty.kind {
-            Handle(handle) => match handle {
-                crate::Handle::Own(ty) | crate::Handle::Borrow(ty) => {
-                    self.update_type_id(ty, span)?
+    #[test]
+    fn resources_without_methods() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
+
+                interface i {
+                    resource empty;
+                    resource full {
+                        get: func() -> u32;
+                    }
+                    type t = u32;
                 }
-            },
-            Resource => {}
-            Record(r) => {
-                for field in r.fields.iter_mut() {
-                    self.update_ty(resolve, &mut field.ty, span)
-                        .with_context(|| format!("failed to update field `{}`", field.name))?;
+
+                world w {
+                    resource local {
+                        constructor();
+                    }
+                    import f: func(x: local);
                 }
-            }
-            Tuple(t) => {
-                for ty in t.types.iter_mut() {
-                    self.update_ty(resolve, ty, span)?;
+            "#,
+        )?;
+        let i = resolve.packages[pkg].interfaces["i"];
+        let w = resolve.packages[pkg].worlds["w"];
+        let empty = resolve.interfaces[i].types["empty"];
+        let full = resolve.interfaces[i].types["full"];
+        let t = resolve.interfaces[i].types["t"];
+        let WorldItem::Type(local) =
+            resolve.worlds[w].imports[&WorldKey::Name("local".to_string())]
+        else {
+            panic!("expected a type");
+        };
+
+        assert!(!resolve.resource_has_methods(empty));
+        assert!(resolve.resource_has_methods(full));
+        assert!(resolve.resource_has_methods(local));
+        assert!(!resolve.resource_has_methods(t));
+        assert_eq!(resolve.resources_without_methods(pkg), [empty]);
+        Ok(())
+    }
+
+    #[test]
+    fn coalesce_params_to_record() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
+
+                interface i {
+                    resource r {
+                        m: func(a: u32);
+                        n: func();
+                    }
+                    open: func(path: string, %flags: u8, mode: u32) -> bool;
+                    close: func();
                 }
-            }
-            Variant(v) => {
-                for case in v.cases.iter_mut() {
-                    if let Some(t) = &mut case.ty {
-                        self.update_ty(resolve, t, span)?;
+
+                world w {
+                    export run: func(a: u32, b: string);
+                }
+            "#,
+        )?;
+        let i = resolve.packages[pkg].interfaces["i"];
+        let w = resolve.packages[pkg].worlds["w"];
+
+        resolve.coalesce_params_to_record(
+            ItemRef::InterfaceFunction(i, "open".to_string()),
+            "open-options",
+        )?;
+        let open = &resolve.interfaces[i].functions["open"];
+        let options = resolve.interfaces[i].types["open-options"];
+        assert_eq!(
+            open.params,
+            [("open-options".to_string(), Type::Id(options))]
+        );
+        assert_eq!(open.result, Some(Type::Bool));
+        let TypeDefKind::Record(record) = &resolve.types[options].kind else {
+            panic!("expected a record");
+        };
+        let fields = record
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.ty))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fields,
+            [
+                ("path", Type::String),
+                ("flags", Type::U8),
+                ("mode", Type::U32)
+            ]
+        );
+
+        // Methods keep their `self` parameter.
+        resolve.coalesce_params_to_record(
+            ItemRef::InterfaceFunction(i, "[method]r.m".to_string()),
+            "m-args",
+        )?;
+        let m = &resolve.interfaces[i].functions["[method]r.m"];
+        assert_eq!(
+            m.params.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>(),
+            ["self", "m-args"]
+        );
+
+        // World functions get a record defined in the world.
+        let run = WorldKey::Name("run".to_string());
+        resolve.coalesce_params_to_record(ItemRef::WorldItem(w, run.clone()), "run-args")?;
+        let WorldItem::Type(args) =
+            resolve.worlds[w].imports[&WorldKey::Name("run-args".to_string())]
+        else {
+            panic!("expected a type");
+        };
+        let WorldItem::Function(run) = &resolve.worlds[w].exports[&run] else {
+            panic!("expected a function");
+        };
+        assert_eq!(run.params, [("run-args".to_string(), Type::Id(args))]);
+
+        let err = resolve
+            .coalesce_params_to_record(
+                ItemRef::InterfaceFunction(i, "open".to_string()),
+                "open-options",
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("already in use"), "{err}");
+
+        // Functions and world items share the namespace of the record.
+        let err = resolve
+            .coalesce_params_to_record(
+                ItemRef::InterfaceFunction(i, "[method]r.m".to_string()),
+                "close",
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("already in use"), "{err}");
+        let err = resolve
+            .coalesce_params_to_record(
+                ItemRef::WorldItem(w, WorldKey::Name("run".to_string())),
+                "run",
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("already in use"), "{err}");
+
+        for name in ["close", "[method]r.n"] {
+            let err = resolve
+                .coalesce_params_to_record(ItemRef::InterfaceFunction(i, name.to_string()), "args")
+                .unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("function `{name}` has no parameters to coalesce")
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn type_declaration() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
+
+                interface i {
+                    record point {
+                        x: f32,
+                        y: f32,
                     }
+                    record %record {
+                        %type: u8,
+                        %list: list<point>,
+                    }
+                    enum color { red, green }
+                    resource r {
+                        get: func() -> point;
+                    }
+                    variant shape { dot(point), none }
+                    type points = list<point>;
+                    type records = list<%record>;
+                    f: func() -> option<point>;
                 }
-            }
-            Option(t) | List(t, ..) | FixedSizeList(t, ..) | Future(Some(t)) | Stream(Some(t)) => {
-                self.update_ty(resolve, t, span)?
-            }
-            Result(r) => {
-                if let Some(ty) = &mut r.ok {
-                    self.update_ty(resolve, ty, span)?;
-                }
-                if let Some(ty) = &mut r.err {
-                    self.update_ty(resolve, ty, span)?;
-                }
-            }
+            "#,
+        )?;
+        let i = &resolve.interfaces[resolve.packages[pkg].interfaces["i"]];
+        let decl = |name: &str| resolve.type_declaration(i.types[name]).unwrap();
+        assert_eq!(decl("point"), "record point { x: f32, y: f32 }");
+        assert_eq!(
+            decl("record"),
+            "record %record { %type: u8, %list: list<point> }"
+        );
+        assert_eq!(decl("color"), "enum color { red, green }");
+        assert_eq!(decl("r"), "resource r");
+        assert_eq!(decl("shape"), "variant shape { dot(point), none }");
+        assert_eq!(decl("points"), "type points = list<point>");
+        assert_eq!(decl("records"), "type records = list<%record>");
+
+        let Some(Type::Id(anon)) = i.functions["f"].result else {
+            panic!("expected a type id");
+        };
+        assert_eq!(resolve.type_declaration(anon), None);
+        Ok(())
+    }
 
-            // Note that `update_ty` is specifically not used here as typedefs
-            // because for the `type a = b` form that doesn't force `a` to be a
-            // handle type if `b` is a resource type, instead `a` is
-            // simultaneously usable as a resource and a handle type
-            Type(crate::Type::Id(id)) => self.update_type_id(id, span)?,
-            Type(_) => {}
+    #[test]
+    fn imports_required_for_export() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
 
-            // nothing to do for these as they're just names or empty
-            Flags(_) | Enum(_) | Future(None) | Stream(None) => {}
+                interface types {
+                    record config { verbose: bool }
+                }
 
-            Unknown => unreachable!(),
-        }
+                interface logging {
+                    log: func(msg: string);
+                }
+
+                interface app {
+                    use types.{config};
+                    configure: func(c: config);
+                }
 
+                world w {
+                    import logging;
+                    import types;
+                    type id = u32;
+                    type unused = u64;
+                    export app;
+                    export run: func(x: id);
+                    export noop: func();
+                }
+            "#,
+        )?;
+        let w = resolve.packages[pkg].worlds["w"];
+        let iface = |name: &str| WorldKey::Interface(resolve.packages[pkg].interfaces[name]);
+        let name = |name: &str| WorldKey::Name(name.to_string());
+
+        let required = resolve.imports_required_for_export(w, &iface("app"));
+        assert_eq!(required.into_iter().collect::<Vec<_>>(), [iface("types")]);
+        let required = resolve.imports_required_for_export(w, &name("run"));
+        assert_eq!(required.into_iter().collect::<Vec<_>>(), [name("id")]);
+        assert!(
+            resolve
+                .imports_required_for_export(w, &name("noop"))
+                .is_empty()
+        );
+        assert!(
+            resolve
+                .imports_required_for_export(w, &iface("logging"))
+                .is_empty()
+        );
         Ok(())
     }
 
-    fn update_ty(
-        &mut self,
-        resolve: &mut Resolve,
-        ty: &mut Type,
-        span: Option<Span>,
-    ) -> Result<()> {
-        let id = match ty {
-            Type::Id(id) => id,
-            _ => return Ok(()),
-        };
-        self.update_type_id(id, span)?;
+    #[test]
+    fn rename_function() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
+
+                interface i {
+                    resource r {
+                        constructor();
+                        get: func() -> u32;
+                        set: func(x: u32);
+                        make: static func() -> r;
+                    }
+                    type t = u32;
+                    first: func();
+                    second: func();
+                    third: async func();
+                }
+            "#,
+        )?;
+        let i = resolve.packages[pkg].interfaces["i"];
 
-        // If `id` points to a `Resource` type then this means that what was
-        // just discovered was a reference to what will implicitly become an
-        // `own<T>` handle. This `own` handle is implicitly allocated here
-        // and handled during the merging process.
-        let mut cur = *id;
-        let points_to_resource = loop {
-            match resolve.types[cur].kind {
-                TypeDefKind::Type(Type::Id(id)) => cur = id,
-                TypeDefKind::Resource => break true,
-                _ => break false,
-            }
-        };
+        resolve.rename_function(i, "first", "start")?;
+        resolve.rename_function(i, "[method]r.get", "fetch")?;
+        let names = resolve.interfaces[i]
+            .functions
+            .iter()
+            .map(|(key, func)| {
+                assert_eq!(*key, func.name);
+                key.as_str()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "[constructor]r",
+                "[method]r.fetch",
+                "[method]r.set",
+                "[static]r.make",
+                "start",
+                "second",
+                "[async]third"
+            ]
+        );
+        let fetch = &resolve.interfaces[i].functions["[method]r.fetch"];
+        assert_eq!(fetch.item_name(), "fetch");
+        assert!(matches!(fetch.kind, FunctionKind::Method(_)));
+        assert!(
+            resolve
+                .item_span(ItemRef::InterfaceFunction(i, "start".to_string()))
+                .is_some()
+        );
 
-        if points_to_resource {
-            *id = *self.own_handles.entry(*id).or_insert_with(|| {
-                resolve.types.alloc(TypeDef {
-                    name: None,
-                    owner: TypeOwner::None,
-                    kind: TypeDefKind::Handle(Handle::Own(*id)),
-                    docs: Default::default(),
-                    stability: Default::default(),
-                })
-            });
-        }
+        assert!(resolve.rename_function(i, "second", "start").is_err());
+        assert!(resolve.rename_function(i, "second", "third").is_err());
+        assert!(resolve.rename_function(i, "second", "t").is_err());
+        let err = resolve
+            .rename_function(i, "[method]r.set", "make")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot rename `[method]r.set` as function `[static]r.make` already exists"
+        );
+        resolve.rename_function(i, "[method]r.set", "t")?;
+        assert!(resolve.rename_function(i, "second", "Not_Kebab").is_err());
+        assert!(resolve.rename_function(i, "[constructor]r", "new").is_err());
+        assert!(resolve.rename_function(i, "missing", "other").is_err());
         Ok(())
     }
 
-    fn update_type_id(&self, id: &mut TypeId, span: Option<Span>) -> Result<()> {
-        *id = self.map_type(*id, span)?;
+    #[test]
+    fn capability_manifest() -> Result<()> {
+        let mut resolve = Resolve::default();
+        resolve.push_str(
+            "deps.wit",
+            r#"
+                package wasi:filesystem@0.2.0;
+
+                interface types {
+                    read: func() -> list<u8>;
+                }
+            "#,
+        )?;
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
+
+                interface logging {
+                    log: func(msg: string);
+                }
+
+                world w {
+                    import wasi:filesystem/types@0.2.0;
+                    import logging;
+                    import inline: interface {
+                        f: func();
+                    }
+                    import direct: func();
+                }
+            "#,
+        )?;
+        let w = resolve.packages[pkg].worlds["w"];
+        assert_eq!(
+            resolve.capability_manifest(w),
+            [
+                Capability {
+                    interface: "wasi:filesystem/types".to_string(),
+                    version: Some(Version::new(0, 2, 0)),
+                    category: Some(CapabilityCategory::Filesystem),
+                },
+                Capability {
+                    interface: "foo:bar/logging".to_string(),
+                    version: None,
+                    category: None,
+                },
+                Capability {
+                    interface: "inline".to_string(),
+                    version: None,
+                    category: None,
+                },
+            ]
+        );
         Ok(())
     }
 
-    fn update_interface(
-        &mut self,
-        resolve: &mut Resolve,
-        iface: &mut Interface,
-        spans: Option<&InterfaceSpan>,
-    ) -> Result<()> {
-        iface.types.retain(|_, ty| self.types[ty.index()].is_some());
-        let iface_pkg_id = iface.package.as_ref().unwrap_or_else(|| {
-            panic!(
-                "unexpectedly missing package on interface [{}]",
-                iface
-                    .name
-                    .as_ref()
-                    .map(String::as_str)
-                    .unwrap_or("<unknown>"),
-            )
-        });
+    #[test]
+    fn enums_and_variants() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
+
+                interface i {
+                    /// Colors.
+                    enum color {
+                        red,
+                        /// Green.
+                        green,
+                        blue,
+                    }
+                    variant mode { fast, slow }
+                    variant value { none, some(u32) }
+                    f: func(c: color, m: mode, v: value);
+                }
 
-        // NB: note that `iface.doc` is not updated here since interfaces
-        // haven't been mapped yet and that's done in a separate step.
-        for (_name, ty) in iface.types.iter_mut() {
-            self.update_type_id(ty, spans.map(|s| s.span))?;
-        }
-        if let Some(spans) = spans {
-            assert_eq!(iface.functions.len(), spans.funcs.len());
-        }
-        for (i, (func_name, func)) in iface.functions.iter_mut().enumerate() {
-            let span = spans.map(|s| s.funcs[i]);
-            if !resolve
-                .include_stability(&func.stability, iface_pkg_id, span)
-                .with_context(|| {
-                    format!(
-                        "failed to process feature gate for function [{func_name}] in package [{}]",
-                        resolve.packages[*iface_pkg_id].name,
-                    )
-                })?
-            {
-                continue;
-            }
-            self.update_function(resolve, func, span)
-                .with_context(|| format!("failed to update function `{}`", func.name))?;
-        }
+                world w {
+                    import i;
+                }
+            "#,
+        )?;
+        let i = resolve.packages[pkg].interfaces["i"];
+        let w = resolve.packages[pkg].worlds["w"];
+        let ty = |resolve: &Resolve, name: &str| resolve.interfaces[i].types[name];
+        let cases = |resolve: &Resolve, name: &str| match &resolve.types[ty(resolve, name)].kind {
+            TypeDefKind::Variant(v) => v
+                .cases
+                .iter()
+                .map(|c| (c.name.clone(), c.ty.is_some()))
+                .collect::<Vec<_>>(),
+            TypeDefKind::Enum(e) => e
+                .cases
+                .iter()
+                .map(|c| (c.name.clone(), false))
+                .collect::<Vec<_>>(),
+            _ => panic!("unexpected kind"),
+        };
+        let is_enum = |resolve: &Resolve, name: &str| {
+            matches!(resolve.types[ty(resolve, name)].kind, TypeDefKind::Enum(_))
+        };
+        let expected = |names: &[&str]| {
+            names
+                .iter()
+                .map(|n| (n.to_string(), false))
+                .collect::<Vec<_>>()
+        };
 
-        // Filter out all of the existing functions in interface which fail the
-        // `include_stability()` check, as they shouldn't be available.
-        for (name, func) in mem::take(&mut iface.functions) {
-            if resolve.include_stability(&func.stability, iface_pkg_id, None)? {
-                iface.functions.insert(name, func);
-            }
-        }
+        resolve.enums_as_variants(w);
+        assert!(!is_enum(&resolve, "color"));
+        assert_eq!(
+            cases(&resolve, "color"),
+            expected(&["red", "green", "blue"])
+        );
+        let TypeDefKind::Variant(color) = &resolve.types[ty(&resolve, "color")].kind else {
+            unreachable!()
+        };
+        assert_eq!(color.cases[1].docs.contents.as_deref(), Some("Green."));
+        assert_eq!(
+            resolve.types[ty(&resolve, "color")]
+                .docs
+                .contents
+                .as_deref(),
+            Some("Colors.")
+        );
 
+        resolve.variants_as_enums(w);
+        assert!(is_enum(&resolve, "color"));
+        assert!(is_enum(&resolve, "mode"));
+        assert_eq!(cases(&resolve, "mode"), expected(&["fast", "slow"]));
+        assert!(!is_enum(&resolve, "value"));
+        assert_eq!(
+            cases(&resolve, "value"),
+            [("none".to_string(), false), ("some".to_string(), true)]
+        );
         Ok(())
     }
 
-    fn update_function(
-        &mut self,
-        resolve: &mut Resolve,
-        func: &mut Function,
-        span: Option<Span>,
-    ) -> Result<()> {
-        if let Some(id) = func.kind.resource_mut() {
-            self.update_type_id(id, span)?;
-        }
-        for (_, ty) in func.params.iter_mut() {
-            self.update_ty(resolve, ty, span)?;
-        }
-        if let Some(ty) = &mut func.result {
-            self.update_ty(resolve, ty, span)?;
-        }
+    #[test]
+    fn precompute_layout() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
 
-        if let Some(ty) = &func.result {
-            if self.type_has_borrow(resolve, ty) {
-                match span {
-                    Some(span) => {
-                        bail!(Error::new(
-                            span,
-                            format!(
-                                "function returns a type which contains \
-                                 a `borrow<T>` which is not supported"
-                            )
-                        ))
-                    }
-                    None => unreachable!(),
+                interface i {
+                    record point { x: u8, y: u64 }
+                    variant v { a(point), b(string) }
+                    type l = list<v>;
                 }
-            }
-        }
+            "#,
+        )?;
+        let i = resolve.packages[pkg].interfaces["i"];
+        let types = resolve.interfaces[i]
+            .types
+            .values()
+            .map(|id| Type::Id(*id))
+            .collect::<Vec<_>>();
+        let query = |layout: &SizeAlign| {
+            types
+                .iter()
+                .map(|ty| {
+                    (
+                        layout.size(ty).size_wasm32(),
+                        layout.align(ty).align_wasm32(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let expected = query(&resolve.layout());
+        assert_eq!(expected, [(16, 8), (24, 8), (8, 4)]);
+        assert!(matches!(resolve.layout(), Cow::Owned(_)));
+
+        // Queries after precomputing reuse the same layout and agree with
+        // computing it from scratch.
+        resolve.precompute_layout();
+        let layout = resolve.layout();
+        assert!(matches!(layout, Cow::Borrowed(_)));
+        assert_eq!(query(&layout), expected);
+
+        // Rewriting types discards the cache.
+        resolve.normalize_char_lists();
+        assert!(matches!(resolve.layout(), Cow::Owned(_)));
+        assert_eq!(query(&resolve.layout()), expected);
+
+        // Adding types invalidates the cache too.
+        resolve.precompute_layout();
+        resolve.push_str(
+            "other.wit",
+            r#"
+                package foo:baz;
 
+                interface j {
+                    type t = u32;
+                }
+            "#,
+        )?;
+        assert!(matches!(resolve.layout(), Cow::Owned(_)));
+        assert_eq!(query(&resolve.layout()), expected);
         Ok(())
     }
 
-    fn update_world(
-        &mut self,
-        world: &mut World,
-        resolve: &mut Resolve,
-        pkg_id: &PackageId,
-        spans: &WorldSpan,
-    ) -> Result<()> {
-        assert_eq!(world.imports.len(), spans.imports.len());
-        assert_eq!(world.exports.len(), spans.exports.len());
+    #[test]
+    fn validate_async_types() -> Result<()> {
+        use crate::{LiftLowerAbi, ManglingAndAbi};
 
-        // Rewrite imports/exports with their updated versions. Note that this
-        // may involve updating the key of the imports/exports maps so this
-        // starts by emptying them out and then everything is re-inserted.
-        let imports = mem::take(&mut world.imports).into_iter();
-        let imports = imports.zip(&spans.imports).map(|p| (p, true));
-        let exports = mem::take(&mut world.exports).into_iter();
-        let exports = exports.zip(&spans.exports).map(|p| (p, false));
-        for (((mut name, mut item), span), import) in imports.chain(exports) {
-            // Update the `id` eagerly here so `item.stability(..)` below
-            // works.
-            if let WorldItem::Type(id) = &mut item {
-                *id = self.map_type(*id, Some(*span))?;
-            }
-            let stability = item.stability(resolve);
-            if !resolve
-                .include_stability(stability, pkg_id, Some(*span))
-                .with_context(|| format!("failed to process world item in `{}`", world.name))?
-            {
-                continue;
-            }
-            self.update_world_key(&mut name, Some(*span))?;
-            match &mut item {
-                WorldItem::Interface { id, .. } => {
-                    *id = self.map_interface(*id, Some(*span))?;
-                }
-                WorldItem::Function(f) => {
-                    self.update_function(resolve, f, Some(*span))?;
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
+
+                interface i {
+                    f: func();
+                    read: func(s: stream<u8>);
                 }
-                WorldItem::Type(_) => {
-                    // already mapped above
+
+                world sync {
+                    export run: func(x: u32);
                 }
-            }
 
-            let dst = if import {
-                &mut world.imports
-            } else {
-                &mut world.exports
-            };
-            let prev = dst.insert(name, item);
-            assert!(prev.is_none());
-        }
+                world streams {
+                    import i;
+                }
 
+                world errors {
+                    export run: func() -> result<_, error-context>;
+                }
+            "#,
+        )?;
+        let world = |name: &str| resolve.packages[pkg].worlds[name];
+        let sync_abis = [
+            ManglingAndAbi::Standard32,
+            ManglingAndAbi::Legacy(LiftLowerAbi::Sync),
+        ];
+        for abi in sync_abis {
+            resolve.validate_async_types(world("sync"), abi)?;
+            let err = resolve
+                .validate_async_types(world("streams"), abi)
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains("`read` in `foo:bar/i`"), "{err}");
+            assert!(err.contains("`stream<u8>`"), "{err}");
+            let err = resolve
+                .validate_async_types(world("errors"), abi)
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains("`run` uses `error-context`"), "{err}");
+        }
+        let async_abi = ManglingAndAbi::Legacy(LiftLowerAbi::AsyncCallback);
+        resolve.validate_async_types(world("streams"), async_abi)?;
+        resolve.validate_async_types(world("errors"), async_abi)?;
         Ok(())
     }
 
-    fn process_world_includes(
-        &self,
-        id: WorldId,
-        resolve: &mut Resolve,
-        pkg_id: &PackageId,
-        spans: &WorldSpan,
-    ) -> Result<()> {
-        let world = &mut resolve.worlds[id];
-        // Resolve all `include` statements of the world which will add more
-        // entries to the imports/exports list for this world.
-        assert_eq!(world.includes.len(), spans.includes.len());
-        let includes = mem::take(&mut world.includes);
-        let include_names = mem::take(&mut world.include_names);
-        for (((stability, include_world), span), names) in includes
-            .into_iter()
-            .zip(&spans.includes)
-            .zip(&include_names)
-        {
-            if !resolve
-                .include_stability(&stability, pkg_id, Some(*span))
-                .with_context(|| {
-                    format!(
-                        "failed to process feature gate for included world [{}] in package [{}]",
-                        resolve.worlds[include_world].name.as_str(),
-                        resolve.packages[*pkg_id].name
-                    )
-                })?
-            {
-                continue;
-            }
-            self.resolve_include(id, include_world, names, *span, pkg_id, resolve)?;
-        }
+    #[test]
+    fn prune_empty_interfaces() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar@1.0.0;
 
-        Ok(())
-    }
+                interface shiny {
+                    @unstable(feature = shiny)
+                    polish: func();
+                }
 
-    fn update_world_key(&self, key: &mut WorldKey, span: Option<Span>) -> Result<()> {
-        match key {
-            WorldKey::Name(_) => {}
-            WorldKey::Interface(id) => {
-                *id = self.map_interface(*id, span)?;
-            }
-        }
+                interface plain {
+                    f: func();
+                }
+
+                world w {
+                    import shiny;
+                    import plain;
+                    export shiny;
+                }
+
+                world only-shiny {
+                    import shiny;
+                }
+            "#,
+        )?;
+        let shiny = resolve.packages[pkg].interfaces["shiny"];
+        let plain = resolve.packages[pkg].interfaces["plain"];
+        let w = resolve.packages[pkg].worlds["w"];
+        let only_shiny = resolve.packages[pkg].worlds["only-shiny"];
+        assert!(resolve.interface_is_empty(shiny));
+        assert!(!resolve.interface_is_empty(plain));
+        assert!(!resolve.world_is_empty(only_shiny));
+
+        assert_eq!(resolve.prune_empty_interfaces(), [shiny]);
+        let world = &resolve.worlds[w];
+        assert_eq!(
+            world.imports.keys().collect::<Vec<_>>(),
+            [&WorldKey::Interface(plain)]
+        );
+        assert!(world.exports.is_empty());
+        assert!(resolve.world_is_empty(only_shiny));
+        assert!(resolve.prune_empty_interfaces().is_empty());
         Ok(())
     }
 
-    fn resolve_include(
-        &self,
-        id: WorldId,
-        include_world_id_orig: WorldId,
-        names: &[IncludeName],
-        span: Span,
-        pkg_id: &PackageId,
-        resolve: &mut Resolve,
-    ) -> Result<()> {
-        let world = &resolve.worlds[id];
-        let include_world_id = self.map_world(include_world_id_orig, Some(span))?;
-        let include_world = resolve.worlds[include_world_id].clone();
-        let mut names_ = names.to_owned();
-        let is_external_include = world.package != include_world.package;
+    #[test]
+    fn select_world_not_found() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
 
-        // remove all imports and exports that match the names we're including
-        for import in include_world.imports.iter() {
-            self.remove_matching_name(import, &mut names_);
-        }
-        for export in include_world.exports.iter() {
-            self.remove_matching_name(export, &mut names_);
-        }
-        if !names_.is_empty() {
-            bail!(Error::new(
-                span,
-                format!(
-                    "no import or export kebab-name `{}`. Note that an ID does not support renaming",
-                    names_[0].name
-                ),
-            ));
-        }
+                world proxy {}
+                world command {}
+            "#,
+        )?;
 
-        let mut cloner = clone::Cloner::new(
-            resolve,
-            TypeOwner::World(if is_external_include {
-                include_world_id
-            } else {
-                include_world_id
-                // include_world_id_orig
-            }),
-            TypeOwner::World(id),
+        let err = resolve.select_world(pkg, Some("proxi")).unwrap_err();
+        let err = err.downcast_ref::<WorldNotFoundError>().unwrap();
+        assert_eq!(err.requested(), "proxi");
+        assert_eq!(err.package().to_string(), "foo:bar");
+        assert_eq!(err.known(), ["proxy", "command"]);
+        assert_eq!(err.suggestion(), Some("proxy"));
+        assert_eq!(
+            err.to_string(),
+            "no world named `proxi` in package `foo:bar`. did you mean `proxy`? \
+             known worlds:\n    proxy\n    command\n"
         );
-        cloner.new_package = Some(*pkg_id);
-
-        // copy the imports and exports from the included world into the current world
-        for import in include_world.imports.iter() {
-            self.resolve_include_item(
-                &mut cloner,
-                names,
-                |resolve| &mut resolve.worlds[id].imports,
-                import,
-                span,
-                "import",
-                is_external_include,
-            )?;
-        }
 
-        for export in include_world.exports.iter() {
-            self.resolve_include_item(
-                &mut cloner,
-                names,
-                |resolve| &mut resolve.worlds[id].exports,
-                export,
-                span,
-                "export",
-                is_external_include,
-            )?;
-        }
+        let err = resolve
+            .select_world(pkg, Some("foo:bar/unrelated"))
+            .unwrap_err();
+        let err = err.downcast_ref::<WorldNotFoundError>().unwrap();
+        assert_eq!(err.suggestion(), None);
         Ok(())
     }
 
-    fn resolve_include_item(
-        &self,
-        cloner: &mut clone::Cloner<'_>,
-        names: &[IncludeName],
-        get_items: impl Fn(&mut Resolve) -> &mut IndexMap<WorldKey, WorldItem>,
-        item: (&WorldKey, &WorldItem),
-        span: Span,
-        item_type: &str,
-        is_external_include: bool,
-    ) -> Result<()> {
-        match item.0 {
-            WorldKey::Name(n) => {
-                let n = if let Some(found) = names
-                    .into_iter()
-                    .find(|include_name| include_name.name == n.clone())
-                {
-                    found.as_.clone()
-                } else {
-                    n.clone()
-                };
+    #[test]
+    fn component_export_shape() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar@0.1.0;
 
-                // When the `with` option to the `include` directive is
-                // specified and is used to rename a function that means that
-                // the function's own original name needs to be updated, so
-                // reflect the change not only in the world key but additionally
-                // in the function itself.
-                let mut new_item = item.1.clone();
-                let key = WorldKey::Name(n.clone());
-                cloner.world_item(&key, &mut new_item);
-                match &mut new_item {
-                    WorldItem::Function(f) => f.name = n.clone(),
-                    WorldItem::Type(id) => cloner.resolve.types[*id].name = Some(n.clone()),
-                    WorldItem::Interface { .. } => {}
+                interface handler {
+                    resource request {
+                        constructor();
+                        body: func() -> list<u8>;
+                    }
+                    handle: func(r: request);
                 }
 
-                let prev = get_items(cloner.resolve).insert(key, new_item);
-                if prev.is_some() {
-                    bail!(Error::new(
-                        span,
-                        format!("{item_type} of `{n}` shadows previously {item_type}ed items"),
-                    ))
+                interface logging {
+                    log: func(msg: string);
                 }
+
+                world w {
+                    import logging;
+                    export handler;
+                    export run: func();
+                }
+            "#,
+        )?;
+        let world = resolve.select_world(pkg, None)?;
+        let shape = resolve.component_export_shape(world);
+        assert_eq!(shape.len(), 1);
+        assert_eq!(
+            shape["foo:bar/handler@0.1.0"],
+            ItemShape {
+                functions: vec![
+                    "[constructor]request".to_string(),
+                    "[method]request.body".to_string(),
+                    "handle".to_string(),
+                ],
+                resources: vec!["request".to_string()],
             }
-            key @ WorldKey::Interface(_) => {
-                let prev = get_items(cloner.resolve)
-                    .entry(key.clone())
-                    .or_insert(item.1.clone());
-                match (&item.1, prev) {
-                    (
-                        WorldItem::Interface {
-                            id: aid,
-                            stability: astability,
-                        },
-                        WorldItem::Interface {
-                            id: bid,
-                            stability: bstability,
-                        },
-                    ) => {
-                        assert_eq!(*aid, *bid);
-                        merge_include_stability(astability, bstability, is_external_include)?;
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn world_functions() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
+
+                interface i {
+                    resource r {
+                        m: func();
                     }
-                    (WorldItem::Interface { .. }, _) => unreachable!(),
-                    (WorldItem::Function(_), _) => unreachable!(),
-                    (WorldItem::Type(_), _) => unreachable!(),
+                    f: func();
                 }
-            }
-        };
 
+                interface e {
+                    g: func();
+                }
+
+                world w {
+                    import i;
+                    import h: func();
+                    import t: interface {
+                        type u = u32;
+                    }
+                    export e;
+                    export run: func();
+                }
+            "#,
+        )?;
+        let world = resolve.select_world(pkg, None)?;
+        let names = |direction| {
+            resolve
+                .world_functions(world, direction)
+                .map(|(key, func)| (resolve.name_world_key(key), func.name.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(WorldItemKind::Import),
+            [
+                ("foo:bar/i".to_string(), "[method]r.m".to_string()),
+                ("foo:bar/i".to_string(), "f".to_string()),
+                ("h".to_string(), "h".to_string()),
+            ]
+        );
+        assert_eq!(
+            names(WorldItemKind::Export),
+            [
+                ("run".to_string(), "run".to_string()),
+                ("foo:bar/e".to_string(), "g".to_string()),
+            ]
+        );
         Ok(())
     }
 
-    fn remove_matching_name(&self, item: (&WorldKey, &WorldItem), names: &mut Vec<IncludeName>) {
-        match item.0 {
-            WorldKey::Name(n) => {
-                names.retain(|name| name.name != n.clone());
-            }
-            _ => {}
-        }
-    }
+    #[test]
+    fn resolve_include_overrides() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar@0.1.0;
 
-    fn type_has_borrow(&mut self, resolve: &Resolve, ty: &Type) -> bool {
-        let id = match ty {
-            Type::Id(id) => *id,
-            _ => return false,
-        };
+                interface streams {}
+                interface clocks {}
+                interface handler {}
 
-        if let Some(Some(has_borrow)) = self.type_has_borrow.get(id.index()) {
-            return *has_borrow;
-        }
+                world base {
+                    import streams;
+                    import clocks;
+                    export handler;
+                    export run: func();
+                }
 
-        let result = self.typedef_has_borrow(resolve, &resolve.types[id]);
-        if self.type_has_borrow.len() <= id.index() {
-            self.type_has_borrow.resize(id.index() + 1, None);
-        }
-        self.type_has_borrow[id.index()] = Some(result);
-        result
-    }
+                world app {
+                    include base;
+                    @since(version = 0.1.0)
+                    import streams;
+                    import log: func(msg: string);
+                }
+            "#,
+        )?;
+        let base = resolve.packages[pkg].worlds["base"];
+        let app = resolve.packages[pkg].worlds["app"];
+        assert_eq!(
+            resolve.resolve_include_overrides(app),
+            [Override {
+                name: "foo:bar/streams@0.1.0".to_string(),
+                kind: WorldItemKind::Import,
+                included_world: base,
+            }]
+        );
+        assert!(resolve.resolve_include_overrides(base).is_empty());
+
+        let world = &resolve.worlds[app];
+        assert_eq!(world.imports.len(), 3);
+        assert_eq!(world.exports.len(), 2);
+        let streams = resolve.packages[pkg].interfaces["streams"];
+        let WorldItem::Interface { stability, .. } = &world.imports[&WorldKey::Interface(streams)]
+        else {
+            panic!("expected an interface");
+        };
+        assert!(matches!(stability, Stability::Stable { .. }));
 
-    fn typedef_has_borrow(&mut self, resolve: &Resolve, ty: &TypeDef) -> bool {
-        match &ty.kind {
-            TypeDefKind::Type(t) => self.type_has_borrow(resolve, t),
-            TypeDefKind::Variant(v) => v
-                .cases
-                .iter()
-                .filter_map(|case| case.ty.as_ref())
-                .any(|ty| self.type_has_borrow(resolve, ty)),
-            TypeDefKind::Handle(Handle::Borrow(_)) => true,
-            TypeDefKind::Handle(Handle::Own(_)) => false,
-            TypeDefKind::Resource => false,
-            TypeDefKind::Record(r) => r
-                .fields
-                .iter()
-                .any(|case| self.type_has_borrow(resolve, &case.ty)),
-            TypeDefKind::Flags(_) => false,
-            TypeDefKind::Tuple(t) => t.types.iter().any(|t| self.type_has_borrow(resolve, t)),
-            TypeDefKind::Enum(_) => false,
-            TypeDefKind::List(ty)
-            | TypeDefKind::FixedSizeList(ty, ..)
-            | TypeDefKind::Future(Some(ty))
-            | TypeDefKind::Stream(Some(ty))
-            | TypeDefKind::Option(ty) => self.type_has_borrow(resolve, ty),
-            TypeDefKind::Result(r) => [&r.ok, &r.err]
-                .iter()
-                .filter_map(|t| t.as_ref())
-                .any(|t| self.type_has_borrow(resolve, t)),
-            TypeDefKind::Future(None) | TypeDefKind::Stream(None) => false,
-            TypeDefKind::Unknown => unreachable!(),
-        }
+        // Overrides are carried over when merging.
+        let mut merged = Resolve::default();
+        let remap = merged.merge(resolve)?;
+        let app = remap.worlds[app.index()].unwrap();
+        assert_eq!(merged.resolve_include_overrides(app).len(), 1);
+        Ok(())
     }
-}
 
-struct MergeMap<'a> {
-    /// A map of package ids in `from` to those in `into` for those that are
-    /// found to be equivalent.
-    package_map: HashMap<PackageId, PackageId>,
+    #[test]
+    fn subset_interface() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
 
-    /// A map of interface ids in `from` to those in `into` for those that are
-    /// found to be equivalent.
-    interface_map: HashMap<InterfaceId, InterfaceId>,
+                interface types {
+                    type id = u64;
+                }
 
-    /// A map of type ids in `from` to those in `into` for those that are
-    /// found to be equivalent.
-    type_map: HashMap<TypeId, TypeId>,
+                interface store {
+                    use types.{id};
+                    record entry {
+                        key: id,
+                        value: list<u8>,
+                    }
+                    record summary {
+                        count: u32,
+                    }
+                    get: func(key: id) -> option<entry>;
+                    put: func(e: entry);
+                    stats: func() -> summary;
+                }
+            "#,
+        )?;
+        let store = resolve.packages[pkg].interfaces["store"];
+        let facade = resolve.subset_interface(store, &["get"])?;
+
+        let iface = &resolve.interfaces[facade];
+        assert_eq!(iface.name, None);
+        assert_eq!(iface.package, Some(pkg));
+        assert_eq!(iface.functions.keys().collect::<Vec<_>>(), ["get"]);
+        assert_eq!(iface.types.keys().collect::<Vec<_>>(), ["id", "entry"]);
+        for ty in iface.types.values() {
+            assert_eq!(resolve.types[*ty].owner, TypeOwner::Interface(facade));
+        }
+        assert_eq!(resolve.interfaces[store].functions.len(), 3);
+        assert_eq!(resolve.interfaces[store].types.len(), 3);
+
+        let world = resolve.worlds.alloc(World {
+            name: "facade".to_string(),
+            imports: Default::default(),
+            exports: [(
+                WorldKey::Name("store".to_string()),
+                WorldItem::Interface {
+                    id: facade,
+                    stability: Default::default(),
+                },
+            )]
+            .into_iter()
+            .collect(),
+            package: Some(pkg),
+            docs: Default::default(),
+            stability: Default::default(),
+            includes: Default::default(),
+            include_names: Default::default(),
+        });
+        resolve.packages[pkg]
+            .worlds
+            .insert("facade".to_string(), world);
+        resolve.elaborate_world(world)?;
+        assert_eq!(resolve.worlds[world].imports.len(), 1);
 
-    /// A map of world ids in `from` to those in `into` for those that are
-    /// found to be equivalent.
-    world_map: HashMap<WorldId, WorldId>,
+        let err = resolve
+            .subset_interface(store, &["get", "delete"])
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "function `delete` not found in interface `foo:bar/store`"
+        );
+        Ok(())
+    }
 
-    /// A list of documents that need to be added to packages in `into`.
-    ///
-    /// The elements here are:
-    ///
-    /// * The name of the interface/world
-    /// * The ID within `into` of the package being added to
-    /// * The ID within `from` of the item being added.
-    interfaces_to_add: Vec<(String, PackageId, InterfaceId)>,
-    worlds_to_add: Vec<(String, PackageId, WorldId)>,
+    #[test]
+    fn resource_constructor() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
 
-    /// Which `Resolve` is being merged from.
-    from: &'a Resolve,
+                interface i {
+                    resource built {
+                        constructor(x: u32);
+                        get: func() -> u32;
+                    }
+                    resource handle {
+                        get: func() -> u32;
+                        open: static func() -> handle;
+                    }
+                }
+            "#,
+        )?;
+        let i = resolve.packages[pkg].interfaces["i"];
+        let built = resolve.interfaces[i].types["built"];
+        let handle = resolve.interfaces[i].types["handle"];
+        let ctor = resolve.resource_constructor(i, built).unwrap();
+        assert_eq!(ctor.name, "[constructor]built");
+        assert_eq!(ctor.params.len(), 1);
+        assert!(resolve.resource_constructor(i, handle).is_none());
+        Ok(())
+    }
 
-    /// Which `Resolve` is being merged into.
-    into: &'a Resolve,
-}
+    #[test]
+    fn visit_type_deps() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
 
-impl<'a> MergeMap<'a> {
-    fn new(from: &'a Resolve, into: &'a Resolve) -> MergeMap<'a> {
-        MergeMap {
-            package_map: Default::default(),
-            interface_map: Default::default(),
-            type_map: Default::default(),
-            world_map: Default::default(),
-            interfaces_to_add: Default::default(),
-            worlds_to_add: Default::default(),
-            from,
-            into,
-        }
+                interface i {
+                    resource r;
+                    record point {
+                        x: u32,
+                    }
+                    f: func(x: tuple<list<point>, option<own<r>>>);
+                }
+            "#,
+        )?;
+        let i = resolve.packages[pkg].interfaces["i"];
+        let ty = resolve.interfaces[i].functions["f"].params[0].1;
+        let mut visited = Vec::new();
+        resolve.visit_type_deps(ty, &mut |id| visited.push(id));
+        let visited = visited
+            .iter()
+            .map(|id| resolve.type_to_string(&Type::Id(*id)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            visited,
+            [
+                "point",
+                "list<point>",
+                "r",
+                "own<r>",
+                "option<own<r>>",
+                "tuple<list<point>, option<own<r>>>",
+            ]
+        );
+
+        let mut visited = Vec::new();
+        resolve.visit_type_deps(Type::U32, &mut |id| visited.push(id));
+        assert!(visited.is_empty());
+        Ok(())
     }
 
-    fn build(&mut self) -> Result<()> {
-        for from_id in self.from.topological_packages() {
-            let from = &self.from.packages[from_id];
-            let into_id = match self.into.package_names.get(&from.name) {
-                Some(id) => *id,
+    #[test]
+    fn upgrade_world_imports_to_latest() -> Result<()> {
+        let mut resolve = Resolve::default();
+        for (version, extra) in [("0.2.0", ""), ("0.2.1", "g: func();"), ("0.3.0", "")] {
+            resolve.push_str(
+                "dep.wit",
+                &format!(
+                    "package foo:dep@{version};
+                     interface i {{
+                         type t = u32;
+                         f: func();
+                         {extra}
+                     }}"
+                ),
+            )?;
+        }
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:app;
 
-                // This package, according to its name and url, is not present
-                // in `self` so it needs to get added below.
-                None => {
-                    log::trace!("adding unique package {}", from.name);
-                    continue;
+                interface e {
+                    use foo:dep/i@0.2.0.{t};
+                    run: func(x: t);
                 }
-            };
-            log::trace!("merging duplicate package {}", from.name);
 
-            self.build_package(from_id, into_id).with_context(|| {
-                format!("failed to merge package `{}` into existing copy", from.name)
-            })?;
-        }
+                world w {
+                    import foo:dep/i@0.2.0;
+                    export e;
+                }
+            "#,
+        )?;
+        let world = resolve.select_world(pkg, None)?;
+        let dep = |version: &str| {
+            let name = format!("foo:dep@{version}").parse::<PackageName>().unwrap();
+            resolve.packages[resolve.package_names[&name]].interfaces["i"]
+        };
+        let (old, new) = (dep("0.2.0"), dep("0.2.1"));
+
+        let upgraded = resolve.upgrade_world_imports_to_latest(world)?;
+        assert_eq!(
+            upgraded,
+            [(WorldKey::Interface(old), WorldKey::Interface(new))]
+        );
+        let imports = resolve.worlds[world].imports.keys().collect::<Vec<_>>();
+        assert_eq!(imports, [&WorldKey::Interface(new)]);
+
+        // The export now uses the type from the upgraded interface.
+        let e = resolve.packages[pkg].interfaces["e"];
+        let TypeDefKind::Type(Type::Id(t)) = resolve.types[resolve.interfaces[e].types["t"]].kind
+        else {
+            panic!("expected an alias");
+        };
+        assert_eq!(resolve.types[t].owner, TypeOwner::Interface(new));
 
+        assert!(resolve.upgrade_world_imports_to_latest(world)?.is_empty());
         Ok(())
     }
 
-    fn build_package(&mut self, from_id: PackageId, into_id: PackageId) -> Result<()> {
-        let prev = self.package_map.insert(from_id, into_id);
-        assert!(prev.is_none());
-
-        let from = &self.from.packages[from_id];
-        let into = &self.into.packages[into_id];
+    #[test]
+    fn validate_named_exports() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
 
-        // If an interface is present in `from_id` but not present in `into_id`
-        // then it can be copied over wholesale. That copy is scheduled to
-        // happen within the `self.interfaces_to_add` list.
-        for (name, from_interface_id) in from.interfaces.iter() {
-            let into_interface_id = match into.interfaces.get(name) {
-                Some(id) => *id,
-                None => {
-                    log::trace!("adding unique interface {name}");
-                    self.interfaces_to_add
-                        .push((name.clone(), into_id, *from_interface_id));
-                    continue;
+                interface named {
+                    f: func();
                 }
-            };
 
-            log::trace!("merging duplicate interfaces {name}");
-            self.build_interface(*from_interface_id, into_interface_id)
-                .with_context(|| format!("failed to merge interface `{name}`"))?;
-        }
+                world good {
+                    import inline: interface {
+                        g: func();
+                    }
+                    export named;
+                    export run: func();
+                }
 
-        for (name, from_world_id) in from.worlds.iter() {
-            let into_world_id = match into.worlds.get(name) {
-                Some(id) => *id,
-                None => {
-                    log::trace!("adding unique world {name}");
-                    self.worlds_to_add
-                        .push((name.clone(), into_id, *from_world_id));
-                    continue;
+                world bad {
+                    export named;
+                    export inline: interface {
+                        g: func();
+                    }
                 }
-            };
+            "#,
+        )?;
+        let good = resolve.packages[pkg].worlds["good"];
+        let bad = resolve.packages[pkg].worlds["bad"];
+        assert_eq!(resolve.validate_named_exports(good), Ok(()));
+        assert_eq!(
+            resolve.validate_named_exports(bad),
+            Err(vec![WorldKey::Name("inline".to_string())])
+        );
+        Ok(())
+    }
 
-            log::trace!("merging duplicate worlds {name}");
-            self.build_world(*from_world_id, into_world_id)
-                .with_context(|| format!("failed to merge world `{name}`"))?;
+    #[test]
+    fn interface_by_name() -> Result<()> {
+        let mut resolve = Resolve::default();
+        for version in ["0.2.0", "0.2.3", "1.0.0", "1.0.3"] {
+            resolve.push_str(
+                "test.wit",
+                &format!("package wasi:io@{version}; interface streams {{}}"),
+            )?;
         }
+        resolve.push_str("test.wit", "package foo:bar; interface i {}")?;
+        let get = |version: &str| {
+            let name = format!("wasi:io@{version}").parse::<PackageName>().unwrap();
+            resolve.packages[resolve.package_names[&name]].interfaces["streams"]
+        };
+
+        let lookup = |id| resolve.interface_by_name(id);
+        assert_eq!(lookup("wasi:io/streams@0.2.0"), Some(get("0.2.0")));
+        assert_eq!(lookup("wasi:io/streams@0.2.3"), Some(get("0.2.3")));
+        assert_eq!(lookup("wasi:io/streams@0.2"), Some(get("0.2.3")));
+        assert_eq!(lookup("wasi:io/streams@1.0"), Some(get("1.0.3")));
+        assert_eq!(lookup("wasi:io/streams@1"), Some(get("1.0.3")));
+        assert_eq!(lookup("wasi:io/streams@1.0.0"), Some(get("1.0.0")));
+        assert_eq!(lookup("wasi:io/streams@0"), Some(get("0.2.3")));
+        assert_eq!(lookup("wasi:io/streams@1.x"), None);
+        assert_eq!(lookup("wasi:io/streams@0.2.1"), None);
+        assert_eq!(lookup("wasi:io/streams@0.3"), None);
+        // Ambiguous without a version.
+        assert_eq!(lookup("wasi:io/streams"), None);
+        assert_eq!(
+            lookup("foo:bar/i"),
+            Some(
+                resolve.packages[resolve.package_names[&"foo:bar".parse::<PackageName>()?]]
+                    .interfaces["i"]
+            )
+        );
+        assert_eq!(lookup("foo:bar/missing"), None);
+        assert_eq!(lookup("not a name"), None);
+        Ok(())
+    }
 
+    #[test]
+    fn world_slug() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let cli = resolve.push_str("test.wit", "package wasi:cli@0.2.0; world command {}")?;
+        let build = resolve.push_str("test.wit", "package foo:bar@1.0.0-rc.1+abc; world w {}")?;
+        let plain = resolve.push_str("test.wit", "package foo:baz; world proxy {}")?;
+
+        let command = resolve.packages[cli].worlds["command"];
+        assert_eq!(resolve.world_slug(command), "wasi-cli-command-0.2.0");
+        assert_eq!(resolve.world_slug(command), resolve.world_slug(command));
+        let w = resolve.packages[build].worlds["w"];
+        assert_eq!(resolve.world_slug(w), "foo-bar-w-1.0.0-rc.1-abc");
+        let proxy = resolve.packages[plain].worlds["proxy"];
+        assert_eq!(resolve.world_slug(proxy), "foo-baz-proxy");
+
+        // The slug doesn't depend on the order packages were added in.
+        let mut other = Resolve::default();
+        let plain = other.push_str("test.wit", "package foo:baz; world proxy {}")?;
+        let cli = other.push_str("test.wit", "package wasi:cli@0.2.0; world command {}")?;
+        assert_eq!(
+            other.world_slug(other.packages[cli].worlds["command"]),
+            "wasi-cli-command-0.2.0"
+        );
+        assert_eq!(
+            other.world_slug(other.packages[plain].worlds["proxy"]),
+            "foo-baz-proxy"
+        );
         Ok(())
     }
 
-    fn build_interface(&mut self, from_id: InterfaceId, into_id: InterfaceId) -> Result<()> {
-        let prev = self.interface_map.insert(from_id, into_id);
-        assert!(prev.is_none());
+    fn diff_type(old: &str, new: &str) -> Result<Vec<TypeChange>> {
+        let ty = |contents: &str| -> Result<(Resolve, TypeId)> {
+            let mut resolve = Resolve::default();
+            let pkg = resolve.push_str(
+                "test.wit",
+                &format!("package foo:bar; interface i {{ {contents} }}"),
+            )?;
+            let i = resolve.packages[pkg].interfaces["i"];
+            let t = resolve.interfaces[i].types["t"];
+            Ok((resolve, t))
+        };
+        let (old, old_id) = ty(old)?;
+        let (new, new_id) = ty(new)?;
+        Ok(Resolve::diff_type(&old, old_id, &new, new_id).changes)
+    }
 
-        let from_interface = &self.from.interfaces[from_id];
-        let into_interface = &self.into.interfaces[into_id];
+    #[test]
+    fn diff_type_records() -> Result<()> {
+        let old = "record t { a: u32, b: string, c: u8 }";
+        assert!(diff_type(old, old)?.is_empty());
 
-        // Unlike documents/interfaces above if an interface in `from`
-        // differs from the interface in `into` then that's considered an
-        // error. Changing interfaces can reflect changes in imports/exports
-        // which may not be expected so it's currently required that all
-        // interfaces, when merged, exactly match.
-        //
-        // One case to consider here, for example, is that if a world in
-        // `into` exports the interface `into_id` then if `from_id` were to
-        // add more items into `into` then it would unexpectedly require more
-        // items to be exported which may not work. In an import context this
-        // might work since it's "just more items available for import", but
-        // for now a conservative route of "interfaces must match" is taken.
+        let changes = diff_type(old, "record t { a: u64, c: u8, d: bool }")?;
+        assert_eq!(
+            changes,
+            [
+                TypeChange::Field(ItemChange::TypeChanged {
+                    name: "a".to_string(),
+                    old: Some("u32".to_string()),
+                    new: Some("u64".to_string()),
+                }),
+                TypeChange::Field(ItemChange::Removed("b".to_string())),
+                TypeChange::Field(ItemChange::Added("d".to_string())),
+            ]
+        );
+        assert!(changes.iter().all(TypeChange::is_breaking));
 
-        for (name, from_type_id) in from_interface.types.iter() {
-            let into_type_id = *into_interface
-                .types
-                .get(name)
-                .ok_or_else(|| anyhow!("expected type `{name}` to be present"))?;
-            let prev = self.type_map.insert(*from_type_id, into_type_id);
-            assert!(prev.is_none());
+        let changes = diff_type(old, "record t { c: u8, a: u32, b: string }")?;
+        assert_eq!(changes, [TypeChange::Field(ItemChange::Reordered)]);
+        assert!(!changes[0].is_breaking());
+        Ok(())
+    }
 
-            self.build_type_id(*from_type_id, into_type_id)
-                .with_context(|| format!("mismatch in type `{name}`"))?;
-        }
+    #[test]
+    fn diff_type_variants_and_enums() -> Result<()> {
+        let changes = diff_type(
+            "variant t { a(u32), b, c(string) }",
+            "variant t { a(u32), b(u8), d }",
+        )?;
+        assert_eq!(
+            changes,
+            [
+                TypeChange::Case(ItemChange::TypeChanged {
+                    name: "b".to_string(),
+                    old: None,
+                    new: Some("u8".to_string()),
+                }),
+                TypeChange::Case(ItemChange::Removed("c".to_string())),
+                TypeChange::Case(ItemChange::Added("d".to_string())),
+            ]
+        );
 
-        for (name, from_func) in from_interface.functions.iter() {
-            let into_func = match into_interface.functions.get(name) {
-                Some(func) => func,
-                None => bail!("expected function `{name}` to be present"),
-            };
-            self.build_function(from_func, into_func)
-                .with_context(|| format!("mismatch in function `{name}`"))?;
-        }
+        let changes = diff_type("variant t { a(u32), b }", "variant t { b, a(u32) }")?;
+        assert_eq!(changes, [TypeChange::Case(ItemChange::Reordered)]);
+        assert!(changes[0].is_breaking());
+
+        let changes = diff_type("enum t { a, b }", "enum t { b, a, c }")?;
+        assert_eq!(
+            changes,
+            [
+                TypeChange::Case(ItemChange::Added("c".to_string())),
+                TypeChange::Case(ItemChange::Reordered),
+            ]
+        );
 
+        let changes = diff_type("flags t { a, b }", "flags t { b, a }")?;
+        assert_eq!(changes, [TypeChange::Flag(ItemChange::Reordered)]);
+        assert!(changes[0].is_breaking());
         Ok(())
     }
 
-    fn build_type_id(&mut self, from_id: TypeId, into_id: TypeId) -> Result<()> {
-        // FIXME: ideally the types should be "structurally
-        // equal" but that's not trivial to do in the face of
-        // resources.
-        let _ = from_id;
-        let _ = into_id;
+    #[test]
+    fn diff_type_other_kinds() -> Result<()> {
+        assert_eq!(
+            diff_type("type t = list<u8>;", "type t = list<u16>;")?,
+            [TypeChange::DefinitionChanged {
+                old: "list<u8>".to_string(),
+                new: "list<u16>".to_string(),
+            }]
+        );
+        assert_eq!(
+            diff_type("record t { a: u8 }", "enum t { a }")?,
+            [TypeChange::KindChanged {
+                old: "record",
+                new: "enum",
+            }]
+        );
+        assert!(diff_type("type t = option<u8>;", "type t = option<u8>;")?.is_empty());
+
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            "package foo:bar; interface i { type t = u8; type u = u8; }",
+        )?;
+        let i = &resolve.interfaces[resolve.packages[pkg].interfaces["i"]];
+        let diff = Resolve::diff_type(&resolve, i.types["t"], &resolve, i.types["u"]);
+        assert_eq!(
+            diff.changes,
+            [TypeChange::Renamed {
+                old: Some("t".to_string()),
+                new: Some("u".to_string()),
+            }]
+        );
+        assert!(diff.is_breaking());
         Ok(())
     }
 
-    fn build_type(&mut self, from_ty: &Type, into_ty: &Type) -> Result<()> {
-        match (from_ty, into_ty) {
-            (Type::Id(from), Type::Id(into)) => {
-                self.build_type_id(*from, *into)?;
-            }
-            (from, into) if from != into => bail!("different kinds of types"),
-            _ => {}
-        }
+    #[test]
+    fn create_world_importing() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
+
+                interface types {
+                    type t = u32;
+                }
+                interface uses {
+                    use types.{t};
+                    f: func() -> t;
+                }
+                interface unrelated {
+                    g: func();
+                }
+            "#,
+        )?;
+        let uses = resolve.packages[pkg].interfaces["uses"];
+        let types = resolve.packages[pkg].interfaces["types"];
+        let world = resolve.create_world_importing(uses, "just-uses")?;
+        resolve.assert_valid();
+
+        assert_eq!(resolve.packages[pkg].worlds["just-uses"], world);
+        assert_eq!(resolve.worlds[world].package, Some(pkg));
+        let imports = resolve.worlds[world].imports.keys().collect::<Vec<_>>();
+        assert_eq!(
+            imports,
+            [&WorldKey::Interface(types), &WorldKey::Interface(uses)]
+        );
+        assert!(resolve.worlds[world].exports.is_empty());
+
+        let err = resolve
+            .create_world_importing(uses, "just-uses")
+            .unwrap_err();
+        assert!(err.to_string().contains("already exists"), "{err}");
         Ok(())
     }
 
-    fn build_function(&mut self, from_func: &Function, into_func: &Function) -> Result<()> {
-        if from_func.name != into_func.name {
-            bail!(
-                "different function names `{}` and `{}`",
-                from_func.name,
-                into_func.name
-            );
-        }
-        match (&from_func.kind, &into_func.kind) {
-            (FunctionKind::Freestanding, FunctionKind::Freestanding) => {}
-            (FunctionKind::AsyncFreestanding, FunctionKind::AsyncFreestanding) => {}
+    #[test]
+    fn feature_enabled() -> Result<()> {
+        let mut resolve = Resolve::default();
+        resolve.features.insert("b".to_string());
+        resolve.features.insert("a".to_string());
+        assert!(resolve.feature_enabled("a"));
+        assert!(!resolve.feature_enabled("c"));
+        assert_eq!(resolve.enabled_features().collect::<Vec<_>>(), ["b", "a"]);
 
-            (FunctionKind::Method(from), FunctionKind::Method(into))
-            | (FunctionKind::Static(from), FunctionKind::Static(into))
-            | (FunctionKind::AsyncMethod(from), FunctionKind::AsyncMethod(into))
-            | (FunctionKind::AsyncStatic(from), FunctionKind::AsyncStatic(into))
-            | (FunctionKind::Constructor(from), FunctionKind::Constructor(into)) => {
-                self.build_type_id(*from, *into)
-                    .context("different function kind types")?;
-            }
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
 
-            (FunctionKind::Method(_), _)
-            | (FunctionKind::Constructor(_), _)
-            | (FunctionKind::Static(_), _)
-            | (FunctionKind::Freestanding, _)
-            | (FunctionKind::AsyncFreestanding, _)
-            | (FunctionKind::AsyncMethod(_), _)
-            | (FunctionKind::AsyncStatic(_), _) => {
-                bail!("different function kind types")
-            }
-        }
+                interface i {
+                    @unstable(feature = a)
+                    f: func();
+                    @unstable(feature = c)
+                    g: func();
+                }
+            "#,
+        )?;
+        let i = &resolve.interfaces[resolve.packages[pkg].interfaces["i"]];
+        assert!(i.functions.contains_key("f"));
+        assert!(!i.functions.contains_key("g"));
 
-        if from_func.params.len() != into_func.params.len() {
-            bail!("different number of function parameters");
-        }
-        for ((from_name, from_ty), (into_name, into_ty)) in
-            from_func.params.iter().zip(&into_func.params)
-        {
-            if from_name != into_name {
-                bail!("different function parameter names: {from_name} != {into_name}");
-            }
-            self.build_type(from_ty, into_ty)
-                .with_context(|| format!("different function parameter types for `{from_name}`"))?;
-        }
-        match (&from_func.result, &into_func.result) {
-            (Some(from_ty), Some(into_ty)) => {
-                self.build_type(from_ty, into_ty)
-                    .context("different function result types")?;
-            }
-            (None, None) => {}
-            (Some(_), None) | (None, Some(_)) => bail!("different number of function results"),
-        }
+        resolve.all_features = true;
+        assert!(resolve.feature_enabled("c"));
+        assert_eq!(resolve.enabled_features().count(), 2);
         Ok(())
     }
 
-    fn build_world(&mut self, from_id: WorldId, into_id: WorldId) -> Result<()> {
-        let prev = self.world_map.insert(from_id, into_id);
-        assert!(prev.is_none());
+    #[test]
+    fn validate_mangleable() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
 
-        let from_world = &self.from.worlds[from_id];
-        let into_world = &self.into.worlds[into_id];
+                interface i {
+                    resource r {
+                        get: func();
+                    }
+                    f: func();
+                }
 
-        // Same as interfaces worlds are expected to exactly match to avoid
-        // unexpectedly changing a particular component's view of imports and
-        // exports.
-        //
-        // FIXME: this should probably share functionality with
-        // `Resolve::merge_worlds` to support adding imports but not changing
-        // exports.
+                world w {
+                    import i;
+                    export run: func();
+                }
+            "#,
+        )?;
+        let world = resolve.select_world(pkg, Some("w"))?;
+        assert!(resolve.validate_mangleable(world, Mangling::Legacy).is_ok());
+        assert!(
+            resolve
+                .validate_mangleable(world, Mangling::Standard32)
+                .is_ok()
+        );
 
-        if from_world.imports.len() != into_world.imports.len() {
-            bail!("world contains different number of imports than expected");
-        }
-        if from_world.exports.len() != into_world.exports.len() {
-            bail!("world contains different number of exports than expected");
-        }
+        let i = resolve.packages[pkg].interfaces["i"];
+        resolve.interfaces[i].functions["f"].name = "f#g".to_string();
+        let WorldItem::Function(run) = &mut resolve.worlds[world].exports[0] else {
+            unreachable!()
+        };
+        run.name = "run_post".to_string();
 
-        for (from_name, from) in from_world.imports.iter() {
-            let into_name = MergeMap::map_name(from_name, &self.interface_map);
-            let name_str = self.from.name_world_key(from_name);
-            let into = into_world
-                .imports
-                .get(&into_name)
-                .ok_or_else(|| anyhow!("import `{name_str}` not found in target world"))?;
-            self.match_world_item(from, into)
-                .with_context(|| format!("import `{name_str}` didn't match target world"))?;
-        }
+        assert_eq!(
+            resolve.validate_mangleable(world, Mangling::Legacy),
+            Err(vec!["f#g".to_string()])
+        );
+        assert_eq!(
+            resolve.validate_mangleable(world, Mangling::Standard32),
+            Err(vec!["run_post".to_string()])
+        );
+        Ok(())
+    }
 
-        for (from_name, from) in from_world.exports.iter() {
-            let into_name = MergeMap::map_name(from_name, &self.interface_map);
-            let name_str = self.from.name_world_key(from_name);
-            let into = into_world
-                .exports
-                .get(&into_name)
-                .ok_or_else(|| anyhow!("export `{name_str}` not found in target world"))?;
-            self.match_world_item(from, into)
-                .with_context(|| format!("export `{name_str}` didn't match target world"))?;
-        }
+    #[test]
+    fn copy_interface() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let dest = resolve.push_str("dest.wit", "package my:pkg; interface existing {}")?;
+        let src = resolve.push_str(
+            "src.wit",
+            r#"
+                package foo:bar;
+
+                interface types {
+                    type id = u32;
+                    record range {
+                        start: id,
+                        end: list<id>,
+                    }
+                }
+                interface api {
+                    use types.{id, range as span};
+                    record entry {
+                        key: id,
+                        value: string,
+                    }
+                    get: func(key: id) -> option<entry>;
+                }
+            "#,
+        )?;
+        let api = resolve.packages[src].interfaces["api"];
+        let copy = resolve.copy_interface(api, dest, Some("vendored"))?;
+
+        assert_eq!(resolve.packages[dest].interfaces["vendored"], copy);
+        assert_eq!(resolve.interfaces[copy].package, Some(dest));
+        let entry = resolve.interfaces[copy].types["entry"];
+        assert_ne!(entry, resolve.interfaces[api].types["entry"]);
+        assert_eq!(resolve.types[entry].owner, TypeOwner::Interface(copy));
+        let Some(Type::Id(result)) = resolve.interfaces[copy].functions["get"].result else {
+            panic!()
+        };
+        let TypeDefKind::Option(Type::Id(inner)) = resolve.types[result].kind else {
+            panic!()
+        };
+        assert_eq!(inner, entry);
+
+        // Modifying the copy leaves the original untouched.
+        let TypeDefKind::Record(r) = &mut resolve.types[entry].kind else {
+            panic!()
+        };
+        r.fields.pop();
+        let original = resolve.interfaces[api].types["entry"];
+        let TypeDefKind::Record(r) = &resolve.types[original].kind else {
+            panic!()
+        };
+        assert_eq!(r.fields.len(), 2);
+
+        // Types used from `types` are copied too, including the types their
+        // definitions refer to.
+        let id = resolve.interfaces[copy].types["id"];
+        assert_eq!(resolve.types[id].owner, TypeOwner::Interface(copy));
+        assert_eq!(resolve.types[id].kind, TypeDefKind::Type(Type::U32));
+        assert_eq!(resolve.type_interface_dep(id), None);
+        let span = resolve.interfaces[copy].types["span"];
+        let TypeDefKind::Record(r) = &resolve.types[span].kind else {
+            panic!()
+        };
+        assert_eq!(r.fields[0].ty, Type::Id(id));
+        let Type::Id(list) = r.fields[1].ty else {
+            panic!()
+        };
+        assert_eq!(resolve.types[list].kind, TypeDefKind::List(Type::Id(id)));
+        assert_eq!(resolve.interface_direct_deps(copy).count(), 0);
+        assert_eq!(resolve.package_direct_deps(dest).count(), 0);
+
+        let err = resolve
+            .copy_interface(api, dest, Some("existing"))
+            .unwrap_err();
+        assert!(err.to_string().contains("already exists"), "{err}");
+
+        // A type that's only referred to indirectly keeps its name, which
+        // can't clash with the types of the copy.
+        let clash = resolve.push_str(
+            "clash.wit",
+            r#"
+                package baz:qux;
+
+                interface types {
+                    type id = u32;
+                    type ids = list<id>;
+                }
+                interface api {
+                    use types.{ids};
+                    type id = string;
+                }
+            "#,
+        )?;
+        let clash = resolve.packages[clash].interfaces["api"];
+        let err = resolve.copy_interface(clash, dest, None).unwrap_err();
+        assert!(err.to_string().contains("two types named `id`"), "{err}");
+        Ok(())
+    }
 
+    #[test]
+    fn interface_function_names() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
+
+                interface files {
+                    open: func(path: string) -> file;
+                    resource file {
+                        constructor();
+                        read: func(len: u32) -> list<u8>;
+                        create: static func() -> file;
+                    }
+                    list-dir: async func(path: string) -> list<string>;
+                }
+            "#,
+        )?;
+        let files = resolve.packages[pkg].interfaces["files"];
+        assert_eq!(
+            resolve.interface_function_names(files),
+            [
+                "open",
+                "file.constructor",
+                "file.read",
+                "file.create",
+                "list-dir"
+            ]
+        );
         Ok(())
     }
 
-    fn map_name(
-        from_name: &WorldKey,
-        interface_map: &HashMap<InterfaceId, InterfaceId>,
-    ) -> WorldKey {
-        match from_name {
-            WorldKey::Name(s) => WorldKey::Name(s.clone()),
-            WorldKey::Interface(id) => {
-                WorldKey::Interface(interface_map.get(id).copied().unwrap_or(*id))
-            }
-        }
+    #[test]
+    fn rename_world_and_interface() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
+
+                interface a {}
+                interface b {}
+                world w1 { import a; }
+                world w2 {}
+            "#,
+        )?;
+        let a = resolve.packages[pkg].interfaces["a"];
+        let w1 = resolve.packages[pkg].worlds["w1"];
+
+        resolve.rename_interface(a, "c")?;
+        assert_eq!(resolve.interfaces[a].name.as_deref(), Some("c"));
+        let names = resolve.packages[pkg].interfaces.keys().collect::<Vec<_>>();
+        assert_eq!(names, ["c", "b"]);
+        assert_eq!(resolve.packages[pkg].interfaces["c"], a);
+        assert!(
+            resolve.worlds[w1]
+                .imports
+                .contains_key(&WorldKey::Interface(a))
+        );
+
+        resolve.rename_world(w1, "w3")?;
+        assert_eq!(resolve.worlds[w1].name, "w3");
+        let names = resolve.packages[pkg].worlds.keys().collect::<Vec<_>>();
+        assert_eq!(names, ["w3", "w2"]);
+        assert_eq!(resolve.select_world(pkg, Some("w3"))?, w1);
+
+        let err = resolve.rename_interface(a, "b").unwrap_err();
+        assert!(err.to_string().contains("already exists"), "{err}");
+        let err = resolve.rename_world(w1, "w2").unwrap_err();
+        assert!(err.to_string().contains("already exists"), "{err}");
+        assert!(resolve.rename_world(w1, "NotKebab").is_err());
+        assert_eq!(resolve.worlds[w1].name, "w3");
+        Ok(())
     }
 
-    fn match_world_item(&mut self, from: &WorldItem, into: &WorldItem) -> Result<()> {
-        match (from, into) {
-            (WorldItem::Interface { id: from, .. }, WorldItem::Interface { id: into, .. }) => {
-                match (
-                    &self.from.interfaces[*from].name,
-                    &self.into.interfaces[*into].name,
-                ) {
-                    // If one interface is unnamed then they must both be
-                    // unnamed and they must both have the same structure for
-                    // now.
-                    (None, None) => self.build_interface(*from, *into)?,
+    #[test]
+    fn split_interface() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
 
-                    // Otherwise both interfaces must be named and they must
-                    // have been previously found to be equivalent. Note that
-                    // if either is unnamed it won't be present in
-                    // `interface_map` so this'll return an error.
-                    _ => {
-                        if self.interface_map.get(&from) != Some(&into) {
-                            bail!("interfaces are not the same");
-                        }
+                interface big {
+                    type id = u32;
+                    record user {
+                        id: id,
+                        name: string,
                     }
+                    resource conn {
+                        send: func(data: list<u8>);
+                    }
+                    get-user: func(id: id) -> option<user>;
+                    list-users: func() -> list<user>;
+                    connect: func(user: id) -> conn;
+                    ping: func() -> id;
                 }
-            }
-            (WorldItem::Function(from), WorldItem::Function(into)) => {
-                let _ = (from, into);
-                // FIXME: should assert an check that `from` structurally
-                // matches `into`
-            }
-            (WorldItem::Type(from), WorldItem::Type(into)) => {
-                // FIXME: should assert an check that `from` structurally
-                // matches `into`
-                let prev = self.type_map.insert(*from, *into);
-                assert!(prev.is_none());
-            }
 
-            (WorldItem::Interface { .. }, _)
-            | (WorldItem::Function(_), _)
-            | (WorldItem::Type(_), _) => {
-                bail!("world items do not have the same type")
-            }
-        }
-        Ok(())
-    }
-}
+                interface other {
+                    use big.{id};
+                }
 
-/// Updates stability annotations when merging `from` into `into`.
-///
-/// This is done to keep up-to-date stability information if possible.
-/// Components for example don't carry stability information but WIT does so
-/// this tries to move from "unknown" to stable/unstable if possible.
-fn update_stability(from: &Stability, into: &mut Stability) -> Result<()> {
-    // If `from` is unknown or the two stability annotations are equal then
-    // there's nothing to do here.
-    if from == into || from.is_unknown() {
-        return Ok(());
-    }
-    // Otherwise if `into` is unknown then inherit the stability listed in
-    // `from`.
-    if into.is_unknown() {
-        *into = from.clone();
-        return Ok(());
-    }
+                world importer {
+                    import big;
+                    import other;
+                }
 
-    // Failing all that this means that the two attributes are different so
-    // generate an error.
-    bail!("mismatch in stability from '{:?}' to '{:?}'", from, into)
-}
+                world exporter {
+                    export big;
+                }
+            "#,
+        )?;
+        let big = resolve.packages[pkg].interfaces["big"];
+        let users: &[&str] = &["get-user", "list-users"];
+        let net: &[&str] = &["connect", "[method]conn.send"];
+        let split = resolve.split_interface(big, &[("users", users), ("net", net)])?;
+        let [users, net] = split[..] else { panic!() };
+
+        let names = resolve.packages[pkg].interfaces.keys().collect::<Vec<_>>();
+        assert_eq!(names, ["big", "other", "users", "net"]);
+
+        // `ping` stays, and `id` is still used from `other` so it stays too.
+        let big_iface = &resolve.interfaces[big];
+        assert_eq!(big_iface.functions.keys().collect::<Vec<_>>(), ["ping"]);
+        assert_eq!(big_iface.types.keys().collect::<Vec<_>>(), ["id"]);
+        let id = big_iface.types["id"];
+
+        // `user` is only used by the `users` group so it moves there.
+        let users_iface = &resolve.interfaces[users];
+        assert_eq!(
+            users_iface.functions.keys().collect::<Vec<_>>(),
+            ["get-user", "list-users"]
+        );
+        assert_eq!(users_iface.types.keys().collect::<Vec<_>>(), ["id", "user"]);
+        let user = users_iface.types["user"];
+        assert_eq!(resolve.types[user].owner, TypeOwner::Interface(users));
+        let alias = users_iface.types["id"];
+        assert_eq!(resolve.types[alias].kind, TypeDefKind::Type(Type::Id(id)));
+        let TypeDefKind::Record(r) = &resolve.types[user].kind else {
+            panic!()
+        };
+        assert_eq!(r.fields[0].ty, Type::Id(alias));
 
-fn merge_include_stability(
-    from: &Stability,
-    into: &mut Stability,
-    is_external_include: bool,
-) -> Result<()> {
-    if is_external_include && from.is_stable() {
-        log::trace!("dropped stability from external package");
-        *into = Stability::Unknown;
-        return Ok(());
-    }
+        let net_iface = &resolve.interfaces[net];
+        assert_eq!(
+            net_iface.functions.keys().collect::<Vec<_>>(),
+            ["[method]conn.send", "connect"]
+        );
+        assert_eq!(net_iface.types.keys().collect::<Vec<_>>(), ["id", "conn"]);
+        assert_eq!(
+            resolve.interfaces[net].functions["[method]conn.send"].kind,
+            FunctionKind::Method(resolve.interfaces[net].types["conn"])
+        );
 
-    return update_stability(from, into);
-}
+        let err = resolve
+            .split_interface(big, &[("a", &["missing"])])
+            .unwrap_err();
+        assert!(err.to_string().contains("not found"), "{err}");
+        let err = resolve
+            .split_interface(
+                users,
+                &[("a", &["get-user"]), ("b", &["get-user", "list-users"])],
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("both"), "{err}");
+        let err = resolve
+            .split_interface(net, &[("a", &["[method]conn.send"])])
+            .unwrap_err();
+        assert!(err.to_string().contains("resource"), "{err}");
+        let err = resolve
+            .split_interface(users, &[("a", &["get-user"]), ("a", &["list-users"])])
+            .unwrap_err();
+        assert_eq!(err.to_string(), "interface `a` is listed more than once");
+
+        // Worlds keep every function of the original interface.
+        let other = resolve.packages[pkg].interfaces["other"];
+        let importer = &resolve.worlds[resolve.packages[pkg].worlds["importer"]];
+        assert_eq!(
+            importer.imports.keys().collect::<Vec<_>>(),
+            [
+                &WorldKey::Interface(big),
+                &WorldKey::Interface(users),
+                &WorldKey::Interface(net),
+                &WorldKey::Interface(other),
+            ]
+        );
+        let exporter = &resolve.worlds[resolve.packages[pkg].worlds["exporter"]];
+        assert_eq!(
+            exporter.exports.keys().collect::<Vec<_>>(),
+            [
+                &WorldKey::Interface(big),
+                &WorldKey::Interface(users),
+                &WorldKey::Interface(net),
+            ]
+        );
 
-/// An error that can be returned during "world elaboration" during various
-/// [`Resolve`] operations.
-///
-/// Methods on [`Resolve`] which mutate its internals, such as
-/// [`Resolve::push_dir`] or [`Resolve::importize`] can fail if `world` imports
-/// in WIT packages are invalid. This error indicates one of these situations
-/// where an invalid dependency graph between imports and exports are detected.
-///
-/// Note that at this time this error is subtle and not easy to understand, and
-/// work needs to be done to explain this better and additionally provide a
-/// better error message. For now though this type enables callers to test for
-/// the exact kind of error emitted.
-#[derive(Debug, Clone)]
-pub struct InvalidTransitiveDependency(String);
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
 
-impl fmt::Display for InvalidTransitiveDependency {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "interface `{}` transitively depends on an interface in \
-             incompatible ways",
-            self.0
-        )
+                interface i {
+                    f: func();
+                }
+
+                world w {}
+            "#,
+        )?;
+        let i = resolve.packages[pkg].interfaces["i"];
+        let w = resolve.packages[pkg].worlds["w"];
+        resolve.worlds[w].imports.insert(
+            WorldKey::Name("named".to_string()),
+            WorldItem::Interface {
+                id: i,
+                stability: Stability::Unknown,
+            },
+        );
+        let err = resolve.split_interface(i, &[("a", &["f"])]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot split interface `foo:bar/i` as world `w` refers to it as `named`"
+        );
+        Ok(())
     }
-}
 
-impl std::error::Error for InvalidTransitiveDependency {}
+    #[test]
+    fn record_padding() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
+            "test.wit",
+            r#"
+                package foo:bar;
 
-#[cfg(test)]
-mod tests {
-    use crate::Resolve;
-    use anyhow::Result;
+                interface i {
+                    record loose { a: u8, b: u32, c: u8 }
+                    record packed { b: u32, a: u8, c: u8, d: u16 }
+                    record nested { x: u8, y: packed, z: string }
+                    record empty {}
+                }
+            "#,
+        )?;
+        let padding = |name: &str| {
+            let iface = resolve.packages[pkg].interfaces["i"];
+            let id = resolve.interfaces[iface].types[name];
+            let TypeDefKind::Record(r) = &resolve.types[id].kind else {
+                panic!()
+            };
+            resolve.record_padding(r)
+        };
+        // 3 bytes to align `b`, then 3 trailing bytes to reach a size of 12.
+        assert_eq!(padding("loose"), 6);
+        assert_eq!(padding("packed"), 0);
+        // 3 bytes to align `y`, nothing within `z` or at the end.
+        assert_eq!(padding("nested"), 3);
+        assert_eq!(padding("empty"), 0);
+        Ok(())
+    }
 
     #[test]
-    fn select_world() -> Result<()> {
+    fn type_name() -> Result<()> {
         let mut resolve = Resolve::default();
-        resolve.push_str(
+        let pkg = resolve.push_str(
             "test.wit",
             r#"
-                package foo:bar@0.1.0;
-
-                world foo {}
+                package foo:bar;
+
+                interface i {
+                    resource file;
+                    record point { x: u32, y: u32 }
+                    f: func(
+                        a: list<u8>,
+                        b: option<string>,
+                        c: tuple<point, list<option<u32>>>,
+                        d: borrow<file>,
+                    ) -> file;
+                }
             "#,
         )?;
-        resolve.push_str(
+        let iface = resolve.packages[pkg].interfaces["i"];
+        let iface = &resolve.interfaces[iface];
+        assert_eq!(resolve.type_name(iface.types["point"]), "point");
+        let func = &iface.functions["f"];
+        let names = func
+            .params
+            .iter()
+            .map(|(_, ty)| ty)
+            .chain(&func.result)
+            .map(|ty| match ty {
+                Type::Id(id) => resolve.type_name(*id),
+                _ => panic!(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "list<u8>",
+                "option<string>",
+                "tuple<point, list<option<u32>>>",
+                "borrow<file>",
+                "own<file>",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn merge_worlds_keeps_more_stable() -> Result<()> {
+        let mut resolve = Resolve::default();
+        resolve.all_features = true;
+        let pkg = resolve.push_str(
             "test.wit",
             r#"
-                package foo:baz@0.1.0;
+                package foo:bar@1.0.0;
+
+                interface i {}
+                interface j {}
+
+                world unstable {
+                    @unstable(feature = fancy)
+                    import i;
+                    @since(version = 1.0.0)
+                    import j;
+                    @unstable(feature = fancy)
+                    export run: func();
+                    @unstable(feature = fancy)
+                    export extra: func();
+                }
 
-                world foo {}
+                world stable {
+                    @since(version = 1.0.0)
+                    import i;
+                    import j;
+                    @since(version = 1.0.0)
+                    export run: func();
+                    export extra: func();
+                    export other: func();
+                }
+
+                world conflict {
+                    export run: func(x: u32);
+                }
             "#,
         )?;
-        resolve.push_str(
+        let unstable = resolve.packages[pkg].worlds["unstable"];
+        let stable = resolve.packages[pkg].worlds["stable"];
+        let i = resolve.packages[pkg].interfaces["i"];
+        let j = resolve.packages[pkg].interfaces["j"];
+        let before = resolve.worlds[stable].clone();
+        resolve.merge_worlds(stable, unstable)?;
+
+        let stability = |import: bool, key: WorldKey| {
+            let world = &resolve.worlds[unstable];
+            let items = if import {
+                &world.imports
+            } else {
+                &world.exports
+            };
+            match &items[&key] {
+                WorldItem::Interface { stability, .. } => stability.clone(),
+                WorldItem::Function(f) => f.stability.clone(),
+                WorldItem::Type(_) => unreachable!(),
+            }
+        };
+        let since = Stability::Stable {
+            since: semver::Version::new(1, 0, 0),
+            deprecated: None,
+        };
+        assert_eq!(stability(true, WorldKey::Interface(i)), since);
+        assert_eq!(stability(true, WorldKey::Interface(j)), since);
+        let run = WorldKey::Name("run".to_string());
+        assert_eq!(stability(false, run.clone()), since);
+        // An unannotated item in `from` doesn't remove the feature gate.
+        assert_eq!(
+            stability(false, WorldKey::Name("extra".to_string())),
+            Stability::Unstable {
+                feature: "fancy".to_string(),
+                deprecated: None,
+            }
+        );
+        let exports = resolve.worlds[unstable].exports.len();
+        assert_eq!(exports, 3);
+
+        // `from` is untouched.
+        assert_eq!(resolve.worlds[stable].imports, before.imports);
+        assert_eq!(resolve.worlds[stable].exports, before.exports);
+
+        let conflict = resolve.packages[pkg].worlds["conflict"];
+        let err = resolve.merge_worlds(conflict, unstable).unwrap_err();
+        assert!(format!("{err:#}").contains("world export run"), "{err:#}");
+        Ok(())
+    }
+
+    #[test]
+    fn importize_preserves_stability() -> Result<()> {
+        let mut resolve = Resolve::default();
+        resolve.all_features = true;
+        let pkg = resolve.push_str(
             "test.wit",
             r#"
-                package foo:baz@0.2.0;
-
-                world foo {}
+                package foo:bar@1.0.0;
+
+                interface i {}
+                interface j {}
+
+                world w {
+                    @unstable(feature = fancy)
+                    export i;
+                    @since(version = 1.0.0)
+                    export j;
+                    @unstable(feature = fancy)
+                    export run: func();
+                }
             "#,
         )?;
+        let world = resolve.packages[pkg].worlds["w"];
+        let i = resolve.packages[pkg].interfaces["i"];
+        let j = resolve.packages[pkg].interfaces["j"];
+        resolve.importize(world, None)?;
+
+        let world = &resolve.worlds[world];
+        assert!(world.exports.is_empty());
+        let fancy = Stability::Unstable {
+            feature: "fancy".to_string(),
+            deprecated: None,
+        };
+        let WorldItem::Interface { stability, .. } = &world.imports[&WorldKey::Interface(i)] else {
+            panic!()
+        };
+        assert_eq!(*stability, fancy);
+        let WorldItem::Interface { stability, .. } = &world.imports[&WorldKey::Interface(j)] else {
+            panic!()
+        };
+        assert!(stability.is_stable());
+        let WorldItem::Function(run) = &world.imports[&WorldKey::Name("run".to_string())] else {
+            panic!()
+        };
+        assert_eq!(run.stability, fancy);
+        Ok(())
+    }
 
-        let dummy = resolve.push_str(
+    #[test]
+    fn resource_drop_name() -> Result<()> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str(
             "test.wit",
             r#"
-                package foo:dummy;
+                package foo:bar@1.0.0;
 
-                world foo {}
+                interface files {
+                    resource file;
+                    type handle = file;
+                    type size = u64;
+                }
+
+                world w {
+                    import files;
+                    resource logger;
+                }
             "#,
         )?;
+        let files = resolve.packages[pkg].interfaces["files"];
+        let types = &resolve.interfaces[files].types;
+        let file = types["file"];
+        assert!(resolve.resource_needs_drop(file));
+        assert!(resolve.resource_needs_drop(types["handle"]));
+        assert!(!resolve.resource_needs_drop(types["size"]));
+
+        let key = WorldKey::Interface(files);
+        assert_eq!(
+            resolve.resource_drop_name(
+                ManglingAndAbi::Legacy(LiftLowerAbi::Sync),
+                Some(&key),
+                file
+            ),
+            (
+                "foo:bar/files@1.0.0".to_string(),
+                "[resource-drop]file".to_string()
+            )
+        );
+        assert_eq!(
+            resolve.resource_drop_name(ManglingAndAbi::Standard32, Some(&key), file),
+            (
+                "cm32p2|foo:bar/files@1".to_string(),
+                "file_drop".to_string()
+            )
+        );
 
-        assert!(resolve.select_world(dummy, None).is_ok());
-        assert!(resolve.select_world(dummy, Some("xx")).is_err());
-        assert!(resolve.select_world(dummy, Some("")).is_err());
-        assert!(resolve.select_world(dummy, Some("foo:bar/foo")).is_ok());
-        assert!(
-            resolve
-                .select_world(dummy, Some("foo:bar/foo@0.1.0"))
-                .is_ok()
+        let world = resolve.packages[pkg].worlds["w"];
+        let WorldItem::Type(logger) =
+            resolve.worlds[world].imports[&WorldKey::Name("logger".to_string())]
+        else {
+            panic!()
+        };
+        assert_eq!(
+            resolve.resource_drop_name(ManglingAndAbi::Legacy(LiftLowerAbi::Sync), None, logger),
+            ("$root".to_string(), "[resource-drop]logger".to_string())
         );
-        assert!(resolve.select_world(dummy, Some("foo:baz/foo")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_unversioned_deps() -> Result<()> {
+        let dep = |version: &str| {
+            format!("package foo:dep@{version} {{ interface types {{ type t = u32; }} }}")
+        };
+        let main = r#"
+            package foo:root;
+
+            interface i {
+                use foo:dep/types.{t};
+            }
+        "#;
+        let push = |resolve: &mut Resolve, versions: &[&str]| -> Result<PackageId> {
+            let wit = versions
+                .iter()
+                .map(|v| dep(v))
+                .collect::<Vec<_>>()
+                .join("\n");
+            resolve.push_str("test.wit", &format!("{main}{wit}"))
+        };
+
+        // Versions must be given by default.
+        let mut resolve = Resolve::default();
+        let err = push(&mut resolve, &["0.2.0"]).unwrap_err();
         assert!(
-            resolve
-                .select_world(dummy, Some("foo:baz/foo@0.1.0"))
-                .is_ok()
+            format!("{err:?}").contains("package 'foo:dep' not found"),
+            "{err:?}"
         );
+
+        let mut resolve = Resolve::default();
+        resolve.resolve_unversioned_deps = true;
+        let pkg = push(&mut resolve, &["0.2.0"])?;
+        let i = resolve.packages[pkg].interfaces["i"];
+        let t = resolve.interfaces[i].types["t"];
+        let TypeDefKind::Type(Type::Id(dep)) = resolve.types[t].kind else {
+            panic!()
+        };
+        let TypeOwner::Interface(types) = resolve.types[dep].owner else {
+            panic!()
+        };
+        assert_eq!(resolve.id_of(types).as_deref(), Some("foo:dep/types@0.2.0"));
+
+        let mut resolve = Resolve::default();
+        resolve.resolve_unversioned_deps = true;
+        let err = push(&mut resolve, &["0.2.0", "0.3.0"]).unwrap_err();
         assert!(
-            resolve
-                .select_world(dummy, Some("foo:baz/foo@0.2.0"))
-                .is_ok()
+            format!("{err:?}").contains(
+                "package `foo:dep` is ambiguous as multiple versions are present: \
+                 foo:dep@0.2.0, foo:dep@0.3.0"
+            ),
+            "{err:?}"
         );
         Ok(())
     }
@@ -64,6 +64,12 @@ impl<'a> Cloner<'a> {
         }
     }
 
+    /// Records that references to `from` should be replaced with `to` instead
+    /// of cloning `from`.
+    pub fn register_type(&mut self, from: TypeId, to: TypeId) {
+        self.types.insert(from, to);
+    }
+
     pub fn world_item(&mut self, key: &WorldKey, item: &mut WorldItem) {
         match key {
             WorldKey::Name(_) => {}
@@ -93,7 +99,7 @@ impl<'a> Cloner<'a> {
         *ty = self.types[ty];
     }
 
-    fn type_def(&mut self, def: &mut TypeDef) {
+    pub fn type_def(&mut self, def: &mut TypeDef) {
         if def.owner != TypeOwner::None {
             assert_eq!(def.owner, self.prev_owner);
             def.owner = self.new_owner;
@@ -159,7 +165,7 @@ impl<'a> Cloner<'a> {
         }
     }
 
-    fn function(&mut self, func: &mut Function) {
+    pub fn function(&mut self, func: &mut Function) {
         if let Some(id) = func.kind.resource_mut() {
             self.type_id(id);
         }
@@ -173,10 +179,25 @@ impl<'a> Cloner<'a> {
 
     fn interface(&mut self, id: &mut InterfaceId) {
         let mut new = self.resolve.interfaces[*id].clone();
-        let next_id = self.resolve.interfaces.next_id();
+        new.package = Some(self.new_package.unwrap_or_else(|| match self.new_owner {
+            TypeOwner::Interface(id) => self.resolve.interfaces[id].package.unwrap(),
+            TypeOwner::World(id) => self.resolve.worlds[id].package.unwrap(),
+            TypeOwner::None => unreachable!(),
+        }));
+        *id = Cloner::alloc_interface(self.resolve, *id, new);
+    }
+
+    /// Allocates `new`, a copy of some or all of the interface `prev`, as a
+    /// new interface with its own copies of the types it contains.
+    pub fn alloc_interface(
+        resolve: &mut Resolve,
+        prev: InterfaceId,
+        mut new: Interface,
+    ) -> InterfaceId {
+        let next_id = resolve.interfaces.next_id();
         let mut clone = Cloner::new(
-            self.resolve,
-            TypeOwner::Interface(*id),
+            resolve,
+            TypeOwner::Interface(prev),
             TypeOwner::Interface(next_id),
         );
         for id in new.types.values_mut() {
@@ -185,12 +206,17 @@ impl<'a> Cloner<'a> {
         for func in new.functions.values_mut() {
             clone.function(func);
         }
-        new.package = Some(self.new_package.unwrap_or_else(|| match self.new_owner {
-            TypeOwner::Interface(id) => self.resolve.interfaces[id].package.unwrap(),
-            TypeOwner::World(id) => self.resolve.worlds[id].package.unwrap(),
-            TypeOwner::None => unreachable!(),
-        }));
-        *id = self.resolve.interfaces.alloc(new);
-        assert_eq!(*id, next_id);
+        // Copied functions keep their position in the source of `prev`.
+        if let Some(offsets) = resolve.interface_function_offsets.get(&prev) {
+            let offsets = offsets
+                .iter()
+                .filter(|(name, _)| new.functions.contains_key(*name))
+                .map(|(name, offset)| (name.clone(), *offset))
+                .collect();
+            resolve.interface_function_offsets.insert(next_id, offsets);
+        }
+        let id = resolve.interfaces.alloc(new);
+        assert_eq!(id, next_id);
+        id
     }
 }
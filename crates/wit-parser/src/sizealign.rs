@@ -221,7 +221,7 @@ impl ArchitectureSize {
 }
 
 /// Information per structure element
-#[derive(Default)]
+#[derive(Default, Clone, Debug)]
 pub struct ElementInfo {
     pub size: ArchitectureSize,
     pub align: Alignment,
@@ -243,12 +243,18 @@ impl ElementInfo {
 }
 
 /// Collect size and alignment for sub-elements of a structure
-#[derive(Default)]
+#[derive(Default, Clone, Debug)]
 pub struct SizeAlign {
     map: Vec<ElementInfo>,
 }
 
 impl SizeAlign {
+    /// Returns whether this was filled with every type in `resolve`, which
+    /// is a cheap check that nothing was added since.
+    pub(crate) fn covers(&self, resolve: &Resolve) -> bool {
+        self.map.len() == resolve.types.len()
+    }
+
     pub fn fill(&mut self, resolve: &Resolve) {
         self.map = Vec::new();
         for (_, ty) in resolve.types.iter() {
@@ -381,6 +381,11 @@ impl EmbedOpts {
                         }
                         ManglingAndAbi::Standard32
                     }
+                    // Components can't yet be created from modules using this
+                    // mangling, so don't generate such modules either.
+                    Mangling::Standard64 => {
+                        bail!("the `standard64` mangling is not yet supported by `wasm-tools`")
+                    }
                     Mangling::Legacy => ManglingAndAbi::Legacy(if self.async_callback {
                         LiftLowerAbi::AsyncCallback
                     } else if self.async_stackful {